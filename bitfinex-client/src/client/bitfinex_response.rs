@@ -0,0 +1,254 @@
+use rust_decimal::Decimal;
+use serde_json::Value;
+
+use crate::BitfinexClientError;
+
+/// Bitfinex v2 responds with positional arrays, this helper
+/// gives named access to the fields we are interested in
+pub(super) struct ResponseArray<'a>(&'a [Value]);
+
+impl<'a> ResponseArray<'a> {
+    pub(super) fn new(value: &'a Value) -> Result<Self, BitfinexClientError> {
+        value
+            .as_array()
+            .map(|arr| Self(arr.as_slice()))
+            .ok_or_else(|| BitfinexClientError::NonParsableResponseData(value.to_string()))
+    }
+
+    pub(super) fn value(&self, idx: usize) -> &'a Value {
+        self.0.get(idx).unwrap_or(&Value::Null)
+    }
+
+    pub(super) fn string(&self, idx: usize) -> String {
+        match self.value(idx) {
+            Value::String(s) => s.clone(),
+            Value::Null => String::new(),
+            v => v.to_string(),
+        }
+    }
+
+    pub(super) fn decimal(&self, idx: usize) -> Option<Decimal> {
+        match self.value(idx) {
+            Value::Number(n) => n.to_string().parse::<Decimal>().ok().or_else(|| {
+                n.as_f64()
+                    .and_then(|f| Decimal::try_from(f).ok())
+                    .map(|d| d.normalize())
+            }),
+            Value::String(s) => s.parse::<Decimal>().ok(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct WalletData {
+    pub wallet_type: String,
+    pub currency: String,
+    pub balance: Decimal,
+    pub available_balance: Decimal,
+}
+
+impl TryFrom<&Value> for WalletData {
+    type Error = BitfinexClientError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        let arr = ResponseArray::new(value)?;
+        Ok(Self {
+            wallet_type: arr.string(0),
+            currency: arr.string(1),
+            balance: arr.decimal(2).unwrap_or(Decimal::ZERO),
+            available_balance: arr.decimal(4).unwrap_or(Decimal::ZERO),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct PositionData {
+    pub symbol: String,
+    pub status: String,
+    pub amount: Decimal,
+}
+
+impl TryFrom<&Value> for PositionData {
+    type Error = BitfinexClientError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        let arr = ResponseArray::new(value)?;
+        Ok(Self {
+            symbol: arr.string(0),
+            status: arr.string(1),
+            amount: arr
+                .decimal(2)
+                .ok_or_else(|| BitfinexClientError::NonParsableResponseData(value.to_string()))?,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct OrderData {
+    pub id: String,
+    pub symbol: String,
+    pub status: String,
+    pub price_avg: Option<Decimal>,
+}
+
+impl TryFrom<&Value> for OrderData {
+    type Error = BitfinexClientError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        let arr = ResponseArray::new(value)?;
+        Ok(Self {
+            id: arr.string(0),
+            symbol: arr.string(3),
+            status: arr.string(13),
+            price_avg: arr.decimal(17).filter(|p| !p.is_zero()),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct TradeData {
+    pub order_id: String,
+    pub fee: Decimal,
+}
+
+impl TryFrom<&Value> for TradeData {
+    type Error = BitfinexClientError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        let arr = ResponseArray::new(value)?;
+        Ok(Self {
+            order_id: arr.string(3),
+            fee: arr.decimal(9).unwrap_or(Decimal::ZERO),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct MovementData {
+    pub id: String,
+    pub status: String,
+    pub amount: Decimal,
+    pub fees: Decimal,
+    pub destination_address: String,
+    pub transaction_id: String,
+}
+
+impl TryFrom<&Value> for MovementData {
+    type Error = BitfinexClientError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        let arr = ResponseArray::new(value)?;
+        Ok(Self {
+            id: arr.string(0),
+            status: arr.string(9),
+            amount: arr.decimal(12).unwrap_or(Decimal::ZERO),
+            fees: arr.decimal(13).unwrap_or(Decimal::ZERO),
+            destination_address: arr.string(16),
+            transaction_id: arr.string(20),
+        })
+    }
+}
+
+/// Write endpoints answer with a notification wrapping the actual payload:
+/// [MTS, TYPE, MESSAGE_ID, null, DATA, CODE, STATUS, TEXT]
+#[derive(Debug)]
+pub struct NotificationData {
+    pub data: Value,
+    pub code: String,
+    pub status: String,
+    pub text: String,
+}
+
+impl TryFrom<&Value> for NotificationData {
+    type Error = BitfinexClientError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        let arr = ResponseArray::new(value)?;
+        Ok(Self {
+            data: arr.value(4).clone(),
+            code: arr.string(5),
+            status: arr.string(6),
+            text: arr.string(7),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn parse_position() {
+        let raw = json!([
+            "tBTCF0:USTF0",
+            "ACTIVE",
+            -0.25,
+            30000.5,
+            0,
+            0,
+            null,
+            null,
+            null,
+            null
+        ]);
+        let position = PositionData::try_from(&raw).unwrap();
+        assert_eq!(position.symbol, "tBTCF0:USTF0");
+        assert_eq!(position.amount, dec!(-0.25));
+    }
+
+    #[test]
+    fn parse_movement() {
+        let raw = json!([
+            13105603,
+            "BTC",
+            "BITCOIN",
+            null,
+            null,
+            1569348774000_i64,
+            1569348774000_i64,
+            null,
+            null,
+            "COMPLETED",
+            null,
+            null,
+            0.01,
+            -0.0004,
+            null,
+            null,
+            "bc1qaddress",
+            null,
+            null,
+            null,
+            "txid",
+            null
+        ]);
+        let movement = MovementData::try_from(&raw).unwrap();
+        assert_eq!(movement.id, "13105603");
+        assert_eq!(movement.status, "COMPLETED");
+        assert_eq!(movement.amount, dec!(0.01));
+        assert_eq!(movement.fees, dec!(-0.0004));
+        assert_eq!(movement.destination_address, "bc1qaddress");
+        assert_eq!(movement.transaction_id, "txid");
+    }
+
+    #[test]
+    fn parse_error_notification() {
+        let raw = json!([
+            1568711312683_i64,
+            "on-req",
+            null,
+            null,
+            [],
+            null,
+            "ERROR",
+            "Invalid order: not enough margin"
+        ]);
+        let notification = NotificationData::try_from(&raw).unwrap();
+        assert_eq!(notification.status, "ERROR");
+        assert_eq!(notification.text, "Invalid order: not enough margin");
+    }
+}
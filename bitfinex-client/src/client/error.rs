@@ -0,0 +1,36 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum BitfinexClientError {
+    #[error("BitfinexClientError - Reqwest: {0}")]
+    Reqwest(#[from] reqwest::Error),
+    #[error("BitfinexClientError - SerdeJson: {0}")]
+    Deserialization(#[from] serde_json::Error),
+    #[error("BitfinexClientError - InvalidHeaderValue: {0}")]
+    Header(#[from] reqwest::header::InvalidHeaderValue),
+    #[error("BitfinexClientError - UnexpectedResponse: {code:?} - {msg:?}")]
+    UnexpectedResponse { msg: String, code: String },
+    #[error("BitfinexClientError - ServiceUnavailable: {code:?} - {msg:?}")]
+    ServiceUnavailable { msg: String, code: String },
+    #[error("BitfinexClientError - OrderDoesNotExist")]
+    OrderDoesNotExist,
+    #[error("BitfinexClientError - MovementDoesNotExist")]
+    MovementDoesNotExist,
+    #[error("BitfinexClientError - NoDepositAddressFound")]
+    NoDepositAddressFound,
+    #[error("BitfinexClientError - NoLastPriceAvailable")]
+    NoLastPriceAvailable,
+    #[error("BitfinexClientError - NonParsableResponseData: {0}")]
+    NonParsableResponseData(String),
+    #[error("BitfinexClientError - DecimalConversion: {0}")]
+    DecimalConversion(#[from] rust_decimal::Error),
+}
+
+impl From<(String, String)> for BitfinexClientError {
+    fn from((msg, code): (String, String)) -> Self {
+        match code.as_str() {
+            "20060" => BitfinexClientError::ServiceUnavailable { msg, code },
+            _ => BitfinexClientError::UnexpectedResponse { msg, code },
+        }
+    }
+}
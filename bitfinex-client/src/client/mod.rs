@@ -0,0 +1,512 @@
+mod bitfinex_response;
+mod error;
+mod primitives;
+
+use data_encoding::HEXLOWER;
+use reqwest::{
+    header::{HeaderMap, HeaderValue, CONTENT_TYPE},
+    Client as ReqwestClient,
+};
+use ring::hmac;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::instrument;
+
+use std::time::Duration;
+
+use bitfinex_response::*;
+pub use error::*;
+pub use primitives::*;
+
+use governor::{
+    clock::DefaultClock, state::keyed::DefaultKeyedStateStore, Jitter, Quota, RateLimiter,
+};
+use std::num::NonZeroU32;
+
+lazy_static::lazy_static! {
+    static ref LIMITER: RateLimiter<&'static str, DefaultKeyedStateStore<&'static str>, DefaultClock>  = RateLimiter::keyed(Quota::per_second(NonZeroU32::new(1).unwrap()));
+}
+
+const TESTNET_BURNER_ADDRESS: &str = "tb1qfqh7ksqcrhjgq35clnf06l5d9s6tk2ke46ecrj";
+const BITFINEX_API_URL: &str = "https://api.bitfinex.com";
+const BITFINEX_PUBLIC_API_URL: &str = "https://api-pub.bitfinex.com";
+const BITFINEX_REDUCE_ONLY_FLAG: u32 = 1024;
+pub const BITFINEX_WITHDRAWAL_FEE: Decimal = dec!(0.0004);
+pub const BITFINEX_MINIMUM_WITHDRAWAL_AMOUNT: Decimal = dec!(0.0006);
+pub const BITFINEX_MINIMUM_ORDER_SIZE_BTC: Decimal = dec!(0.0001);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BitfinexClientConfig {
+    #[serde(default)]
+    pub api_key: String,
+    #[serde(default)]
+    pub secret_key: String,
+    #[serde(default)]
+    pub simulated: bool,
+}
+
+#[derive(Clone)]
+pub struct BitfinexClient {
+    client: ReqwestClient,
+    config: BitfinexClientConfig,
+}
+
+impl BitfinexClient {
+    pub async fn new(config: BitfinexClientConfig) -> Result<Self, BitfinexClientError> {
+        let client = Self {
+            client: ReqwestClient::builder().use_rustls_tls().build()?,
+            config,
+        };
+        client.post_auth("v2/auth/r/info/user", json!({})).await?;
+        Ok(client)
+    }
+
+    pub fn is_simulated(&self) -> bool {
+        self.config.simulated
+    }
+
+    pub fn instrument_id(&self) -> BitfinexInstrumentId {
+        if self.config.simulated {
+            BitfinexInstrumentId::TestBtcUsdPerp
+        } else {
+            BitfinexInstrumentId::BtcUsdPerp
+        }
+    }
+
+    pub async fn rate_limit_client(&self, key: &'static str) -> &ReqwestClient {
+        let jitter = Jitter::new(Duration::from_secs(1), Duration::from_secs(1));
+        LIMITER.until_key_ready_with_jitter(&key, jitter).await;
+        &self.client
+    }
+
+    #[instrument(name = "bitfinex_client.get_funding_deposit_address", skip(self), err)]
+    pub async fn get_funding_deposit_address(&self) -> Result<DepositAddress, BitfinexClientError> {
+        if self.config.simulated {
+            return Ok(DepositAddress {
+                value: TESTNET_BURNER_ADDRESS.to_string(),
+            });
+        }
+
+        let body = json!({
+            "wallet": Wallet::Exchange.to_string(),
+            "method": "bitcoin",
+            "op_renew": 0,
+        });
+        let notification = self
+            .post_notification("v2/auth/w/deposit/address", body)
+            .await?;
+        let address = ResponseArray::new(&notification.data)?.string(4);
+        if address.is_empty() {
+            return Err(BitfinexClientError::NoDepositAddressFound);
+        }
+        Ok(DepositAddress { value: address })
+    }
+
+    #[instrument(name = "bitfinex_client.transfer_funding_to_trading", skip(self), err)]
+    pub async fn transfer_funding_to_trading(
+        &self,
+        amt: Decimal,
+    ) -> Result<TransferId, BitfinexClientError> {
+        self.transfer(Wallet::Exchange, Wallet::Margin, amt).await
+    }
+
+    #[instrument(name = "bitfinex_client.transfer_trading_to_funding", skip(self), err)]
+    pub async fn transfer_trading_to_funding(
+        &self,
+        amt: Decimal,
+    ) -> Result<TransferId, BitfinexClientError> {
+        self.transfer(Wallet::Margin, Wallet::Exchange, amt).await
+    }
+
+    async fn transfer(
+        &self,
+        from: Wallet,
+        to: Wallet,
+        amt: Decimal,
+    ) -> Result<TransferId, BitfinexClientError> {
+        let body = json!({
+            "from": from.to_string(),
+            "to": to.to_string(),
+            "currency": "BTC",
+            "amount": amt.to_string(),
+        });
+        let notification = self.post_notification("v2/auth/w/transfer", body).await?;
+        Ok(TransferId {
+            value: ResponseArray::new(&notification.data)?.string(0),
+        })
+    }
+
+    #[instrument(name = "bitfinex_client.funding_account_balance", skip(self), err)]
+    pub async fn funding_account_balance(&self) -> Result<AvailableBalance, BitfinexClientError> {
+        self.wallet_balance(Wallet::Exchange).await
+    }
+
+    #[instrument(name = "bitfinex_client.trading_account_balance", skip(self), err)]
+    pub async fn trading_account_balance(&self) -> Result<AvailableBalance, BitfinexClientError> {
+        self.wallet_balance(Wallet::Margin).await
+    }
+
+    async fn wallet_balance(
+        &self,
+        wallet: Wallet,
+    ) -> Result<AvailableBalance, BitfinexClientError> {
+        let response = self.post_auth("v2/auth/r/wallets", json!({})).await?;
+        let wallet_type = wallet.to_string();
+        let wallet = Self::parse_array::<WalletData>(&response)?
+            .into_iter()
+            .find(|w| w.wallet_type == wallet_type && w.currency == "BTC");
+
+        Ok(match wallet {
+            Some(w) => AvailableBalance {
+                free_amt_in_btc: w.available_balance,
+                used_amt_in_btc: w.balance - w.available_balance,
+                total_amt_in_btc: w.balance,
+            },
+            None => AvailableBalance {
+                free_amt_in_btc: Decimal::ZERO,
+                used_amt_in_btc: Decimal::ZERO,
+                total_amt_in_btc: Decimal::ZERO,
+            },
+        })
+    }
+
+    #[instrument(name = "bitfinex_client.withdraw_btc_onchain", skip(self), err)]
+    pub async fn withdraw_btc_onchain(
+        &self,
+        amt: Decimal,
+        btc_address: String,
+    ) -> Result<WithdrawId, BitfinexClientError> {
+        let body = json!({
+            "wallet": Wallet::Exchange.to_string(),
+            "method": "bitcoin",
+            "amount": amt.to_string(),
+            "address": btc_address,
+        });
+        let notification = self.post_notification("v2/auth/w/withdraw", body).await?;
+        Ok(WithdrawId {
+            value: ResponseArray::new(&notification.data)?.string(0),
+        })
+    }
+
+    /// https://docs.bitfinex.com/reference/rest-auth-movements
+    #[instrument(
+        name = "bitfinex_client.fetch_deposit",
+        fields(deposit_found, bitfinex_deposit_state),
+        skip(self),
+        err
+    )]
+    pub async fn fetch_deposit(
+        &self,
+        depo_addr: String,
+        amt_in_btc: Decimal,
+    ) -> Result<MovementStatus, BitfinexClientError> {
+        let deposit = self
+            .movements()
+            .await?
+            .into_iter()
+            .find(|m| m.destination_address == depo_addr && m.amount == amt_in_btc);
+
+        if let Some(deposit) = deposit {
+            tracing::Span::current().record("deposit_found", true);
+            tracing::Span::current().record("bitfinex_deposit_state", &deposit.status);
+            Ok(Self::movement_status(deposit))
+        } else {
+            Err(BitfinexClientError::MovementDoesNotExist)
+        }
+    }
+
+    #[instrument(name = "bitfinex_client.fetch_withdrawal", skip(self), err)]
+    pub async fn fetch_withdrawal(
+        &self,
+        withdraw_id: String,
+    ) -> Result<MovementStatus, BitfinexClientError> {
+        self.movements()
+            .await?
+            .into_iter()
+            .find(|m| m.id == withdraw_id)
+            .map(Self::movement_status)
+            .ok_or(BitfinexClientError::MovementDoesNotExist)
+    }
+
+    async fn movements(&self) -> Result<Vec<MovementData>, BitfinexClientError> {
+        let response = self
+            .post_auth("v2/auth/r/movements/BTC/hist", json!({}))
+            .await?;
+        Self::parse_array::<MovementData>(&response)
+    }
+
+    fn movement_status(movement: MovementData) -> MovementStatus {
+        MovementStatus {
+            state: match &movement.status[..] {
+                "COMPLETED" => "success".to_string(),
+                "CANCELED" | "FAILED" | "UNCONFIRMED" => "failed".to_string(),
+                _ => "pending".to_string(),
+            },
+            id: movement.id,
+            fee: movement.fees.abs(),
+            transaction_id: movement.transaction_id,
+        }
+    }
+
+    #[instrument(name = "bitfinex_client.place_order", skip(self), err)]
+    pub async fn place_order(
+        &self,
+        id: ClientOrderId,
+        side: BitfinexOrderSide,
+        amount_in_btc: Decimal,
+    ) -> Result<OrderId, BitfinexClientError> {
+        let signed_amount = match side {
+            BitfinexOrderSide::Buy => amount_in_btc.abs(),
+            BitfinexOrderSide::Sell => -amount_in_btc.abs(),
+        };
+        let body = json!({
+            "type": "MARKET",
+            "symbol": self.instrument_id().to_string(),
+            "amount": signed_amount.to_string(),
+            "cid": id.cid(),
+        });
+        self.submit_order(body).await
+    }
+
+    #[instrument(name = "bitfinex_client.close_positions", skip(self), err)]
+    pub async fn close_positions(
+        &self,
+        id: ClientOrderId,
+    ) -> Result<Option<OrderId>, BitfinexClientError> {
+        let position = self.position_amount_in_btc().await?;
+        if position.is_zero() {
+            return Ok(None);
+        }
+        let body = json!({
+            "type": "MARKET",
+            "symbol": self.instrument_id().to_string(),
+            "amount": (-position).to_string(),
+            "cid": id.cid(),
+            "flags": BITFINEX_REDUCE_ONLY_FLAG,
+        });
+        Ok(Some(self.submit_order(body).await?))
+    }
+
+    async fn submit_order(&self, body: Value) -> Result<OrderId, BitfinexClientError> {
+        let notification = self
+            .post_notification("v2/auth/w/order/submit", body)
+            .await?;
+        let order = notification
+            .data
+            .as_array()
+            .and_then(|orders| orders.first())
+            .map(OrderData::try_from)
+            .transpose()?
+            .ok_or_else(|| BitfinexClientError::UnexpectedResponse {
+                msg: notification.text,
+                code: notification.code,
+            })?;
+        Ok(OrderId { value: order.id })
+    }
+
+    #[instrument(name = "bitfinex_client.order_details", skip(self), err)]
+    pub async fn order_details(
+        &self,
+        id: ClientOrderId,
+        order_id: String,
+    ) -> Result<OrderDetails, BitfinexClientError> {
+        let order_ids: Vec<i64> = order_id.parse().into_iter().collect();
+        let mut order = None;
+        for path in ["v2/auth/r/orders", "v2/auth/r/orders/hist"] {
+            let response = self.post_auth(path, json!({ "id": order_ids })).await?;
+            order = Self::parse_array::<OrderData>(&response)?
+                .into_iter()
+                .find(|o| o.id == order_id);
+            if order.is_some() {
+                break;
+            }
+        }
+        let order = order.ok_or(BitfinexClientError::OrderDoesNotExist)?;
+
+        let trades_path = format!("v2/auth/r/order/{}:{}/trades", order.symbol, order.id);
+        let response = self
+            .post_auth_with_key("v2/auth/r/order/trades", &trades_path, json!({}))
+            .await?;
+        let fee = Self::parse_array::<TradeData>(&response)?
+            .into_iter()
+            .filter(|t| t.order_id == order.id)
+            .map(|t| t.fee)
+            .sum::<Decimal>();
+
+        let complete = order.status.starts_with("EXECUTED") || order.status.starts_with("CANCELED");
+        Ok(OrderDetails {
+            order_id: order.id,
+            cl_ord_id: id,
+            avg_px: order.price_avg,
+            fee,
+            state: order
+                .status
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_lowercase(),
+            complete,
+        })
+    }
+
+    pub async fn get_last_price_in_usd_cents(&self) -> Result<LastPrice, BitfinexClientError> {
+        let request_path = format!("v2/ticker/{}", self.instrument_id());
+        let response = self
+            .rate_limit_client("v2/ticker")
+            .await
+            .get(format!("{BITFINEX_PUBLIC_API_URL}/{request_path}"))
+            .send()
+            .await?;
+        let response = Self::extract_response(response).await?;
+        match ResponseArray::new(&response)?.decimal(6) {
+            Some(last) => Ok(LastPrice {
+                usd_cents: last * Decimal::ONE_HUNDRED,
+            }),
+            None => Err(BitfinexClientError::NoLastPriceAvailable),
+        }
+    }
+
+    async fn position_amount_in_btc(&self) -> Result<Decimal, BitfinexClientError> {
+        let response = self.post_auth("v2/auth/r/positions", json!({})).await?;
+        let symbol = self.instrument_id().to_string();
+        Ok(Self::parse_array::<PositionData>(&response)?
+            .into_iter()
+            .filter(|p| p.symbol == symbol && p.status == "ACTIVE")
+            .map(|p| p.amount)
+            .sum())
+    }
+
+    #[instrument(
+        name = "bitfinex_client.get_position_in_signed_usd_cents",
+        skip_all,
+        fields(position_in_btc, last_price),
+        err
+    )]
+    pub async fn get_position_in_signed_usd_cents(
+        &self,
+    ) -> Result<PositionSize, BitfinexClientError> {
+        let position = self.position_amount_in_btc().await?;
+        let span = tracing::Span::current();
+        span.record("position_in_btc", tracing::field::display(&position));
+
+        if position.is_zero() {
+            return Ok(PositionSize {
+                instrument_id: self.instrument_id(),
+                usd_cents: Decimal::ZERO,
+                last_price_in_usd_cents: Decimal::ZERO,
+            });
+        }
+        let last_price_in_usd_cents = self.get_last_price_in_usd_cents().await?.usd_cents;
+        span.record(
+            "last_price",
+            tracing::field::display(&last_price_in_usd_cents),
+        );
+        Ok(PositionSize {
+            instrument_id: self.instrument_id(),
+            usd_cents: position * last_price_in_usd_cents,
+            last_price_in_usd_cents,
+        })
+    }
+
+    async fn post_notification(
+        &self,
+        request_path: &'static str,
+        body: Value,
+    ) -> Result<NotificationData, BitfinexClientError> {
+        let response = self.post_auth(request_path, body).await?;
+        let notification = NotificationData::try_from(&response)?;
+        if notification.status != "SUCCESS" {
+            return Err(BitfinexClientError::from((
+                notification.text,
+                notification.code,
+            )));
+        }
+        Ok(notification)
+    }
+
+    async fn post_auth(
+        &self,
+        request_path: &'static str,
+        body: Value,
+    ) -> Result<Value, BitfinexClientError> {
+        self.post_auth_with_key(request_path, request_path, body)
+            .await
+    }
+
+    async fn post_auth_with_key(
+        &self,
+        rate_limit_key: &'static str,
+        request_path: &str,
+        body: Value,
+    ) -> Result<Value, BitfinexClientError> {
+        let request_body = serde_json::to_string(&body)?;
+        let headers = self.request_headers(request_path, &request_body)?;
+        let response = self
+            .rate_limit_client(rate_limit_key)
+            .await
+            .post(Self::url_for_path(request_path))
+            .headers(headers)
+            .body(request_body)
+            .send()
+            .await?;
+        Self::extract_response(response).await
+    }
+
+    /// Errors are returned as ["error", CODE, MESSAGE]
+    async fn extract_response(response: reqwest::Response) -> Result<Value, BitfinexClientError> {
+        let response_text = response.text().await?;
+        let value = serde_json::from_str::<Value>(&response_text)?;
+        if let Some(arr) = value.as_array() {
+            if arr.first().and_then(Value::as_str) == Some("error") {
+                let arr = ResponseArray::new(&value)?;
+                return Err(BitfinexClientError::from((arr.string(2), arr.string(1))));
+            }
+        }
+        Ok(value)
+    }
+
+    fn parse_array<T>(value: &Value) -> Result<Vec<T>, BitfinexClientError>
+    where
+        T: for<'a> TryFrom<&'a Value, Error = BitfinexClientError>,
+    {
+        value
+            .as_array()
+            .ok_or_else(|| BitfinexClientError::NonParsableResponseData(value.to_string()))?
+            .iter()
+            .map(T::try_from)
+            .collect()
+    }
+
+    fn sign_bitfinex_request(&self, pre_hash: String) -> String {
+        let key = hmac::Key::new(hmac::HMAC_SHA384, self.config.secret_key.as_bytes());
+        let signature = hmac::sign(&key, pre_hash.as_bytes());
+        HEXLOWER.encode(signature.as_ref())
+    }
+
+    fn url_for_path(path: &str) -> String {
+        format!("{BITFINEX_API_URL}/{path}")
+    }
+
+    fn request_headers(
+        &self,
+        request_path: &str,
+        request_body: &str,
+    ) -> Result<HeaderMap, BitfinexClientError> {
+        let nonce = (chrono::Utc::now().timestamp_micros()).to_string();
+        let pre_hash = format!("/api/{request_path}{nonce}{request_body}");
+        let signature = self.sign_bitfinex_request(pre_hash);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_str("application/json")?);
+        headers.insert("bfx-nonce", HeaderValue::from_str(nonce.as_str())?);
+        headers.insert(
+            "bfx-apikey",
+            HeaderValue::from_str(self.config.api_key.as_str())?,
+        );
+        headers.insert("bfx-signature", HeaderValue::from_str(signature.as_str())?);
+
+        Ok(headers)
+    }
+}
@@ -0,0 +1,171 @@
+use rust_decimal::Decimal;
+use std::fmt::Display;
+
+#[derive(serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct ClientOrderId(pub(super) String);
+impl ClientOrderId {
+    pub fn new() -> Self {
+        use rand::Rng;
+        // Bitfinex expects an integer cid that is unique within the (UTC) day
+        let millis_of_day = chrono::Utc::now().timestamp_millis() % 86_400_000;
+        let suffix: u32 = rand::thread_rng().gen_range(0..100_000);
+        Self(format!("{millis_of_day}{suffix:05}"))
+    }
+
+    pub fn cid(&self) -> i64 {
+        self.0.parse().unwrap_or_default()
+    }
+}
+impl From<String> for ClientOrderId {
+    fn from(s: String) -> Self {
+        Self(s)
+    }
+}
+impl From<ClientOrderId> for String {
+    fn from(id: ClientOrderId) -> Self {
+        id.0
+    }
+}
+impl Default for ClientOrderId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(transparent)]
+pub struct ClientTransferId(pub(super) String);
+impl ClientTransferId {
+    pub fn new() -> Self {
+        use rand::distributions::{Alphanumeric, DistString};
+        Self(Alphanumeric.sample_string(&mut rand::thread_rng(), 32))
+    }
+}
+impl From<String> for ClientTransferId {
+    fn from(s: String) -> Self {
+        Self(s)
+    }
+}
+impl From<ClientTransferId> for String {
+    fn from(id: ClientTransferId) -> Self {
+        id.0
+    }
+}
+impl Default for ClientTransferId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitfinexInstrumentId {
+    BtcUsdPerp,
+    TestBtcUsdPerp,
+}
+
+impl Display for BitfinexInstrumentId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            BitfinexInstrumentId::BtcUsdPerp => write!(f, "tBTCF0:USTF0"),
+            BitfinexInstrumentId::TestBtcUsdPerp => write!(f, "tTESTBTCF0:TESTUSDTF0"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wallet {
+    Exchange,
+    Margin,
+}
+
+impl Display for Wallet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Wallet::Exchange => write!(f, "exchange"),
+            Wallet::Margin => write!(f, "margin"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitfinexOrderSide {
+    Buy,
+    Sell,
+}
+
+impl Display for BitfinexOrderSide {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            BitfinexOrderSide::Buy => write!(f, "buy"),
+            BitfinexOrderSide::Sell => write!(f, "sell"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct DepositAddress {
+    pub value: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct TransferId {
+    pub value: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct WithdrawId {
+    pub value: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct OrderId {
+    pub value: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct LastPrice {
+    pub usd_cents: Decimal,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct PositionSize {
+    pub instrument_id: BitfinexInstrumentId,
+    pub usd_cents: Decimal,
+    pub last_price_in_usd_cents: Decimal,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct AvailableBalance {
+    pub free_amt_in_btc: Decimal,
+    pub used_amt_in_btc: Decimal,
+    pub total_amt_in_btc: Decimal,
+}
+
+impl Display for AvailableBalance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "free_amt_in_btc={}, used_amt_in_btc={}, total_amt_in_btc={}",
+            self.free_amt_in_btc, self.used_amt_in_btc, self.total_amt_in_btc
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct MovementStatus {
+    pub id: String,
+    pub state: String,
+    pub fee: Decimal,
+    pub transaction_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderDetails {
+    pub order_id: String,
+    pub cl_ord_id: ClientOrderId,
+    pub avg_px: Option<Decimal>,
+    pub fee: Decimal,
+    pub state: String,
+    pub complete: bool,
+}
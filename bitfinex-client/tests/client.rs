@@ -0,0 +1,81 @@
+use rust_decimal::Decimal;
+use serial_test::serial;
+
+use std::env;
+
+use bitfinex_client::*;
+
+async fn configured_bitfinex_client() -> anyhow::Result<BitfinexClient> {
+    let api_key = env::var("BITFINEX_API_KEY").expect("BITFINEX_API_KEY not set");
+    let secret_key = env::var("BITFINEX_SECRET_KEY").expect("BITFINEX_SECRET_KEY not set");
+
+    let client = BitfinexClient::new(BitfinexClientConfig {
+        api_key,
+        secret_key,
+        simulated: true,
+    })
+    .await?;
+
+    Ok(client)
+}
+
+#[tokio::test]
+#[serial]
+#[ignore = "avoid rate limit"]
+async fn get_deposit_address_data() -> anyhow::Result<()> {
+    let client = configured_bitfinex_client().await?;
+    let address = client.get_funding_deposit_address().await?;
+    assert!(address.value.len() > 10);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+#[ignore = "avoid rate limit"]
+async fn client_is_missing_header() -> anyhow::Result<()> {
+    let client = BitfinexClient::new(BitfinexClientConfig {
+        api_key: "".to_string(),
+        secret_key: "".to_string(),
+        simulated: true,
+    })
+    .await;
+    assert!(client.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+#[ignore = "avoid rate limit"]
+async fn last_price() -> anyhow::Result<()> {
+    let client = configured_bitfinex_client().await?;
+    let last_price = client.get_last_price_in_usd_cents().await?;
+    assert!(last_price.usd_cents > Decimal::ZERO);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+#[ignore = "avoid rate limit"]
+async fn wallet_balances() -> anyhow::Result<()> {
+    let client = configured_bitfinex_client().await?;
+    let funding = client.funding_account_balance().await?;
+    assert!(funding.total_amt_in_btc >= Decimal::ZERO);
+    let trading = client.trading_account_balance().await?;
+    assert!(trading.total_amt_in_btc >= Decimal::ZERO);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+#[ignore = "avoid rate limit"]
+async fn get_position() -> anyhow::Result<()> {
+    let client = configured_bitfinex_client().await?;
+    let position = client.get_position_in_signed_usd_cents().await?;
+    assert!(position.usd_cents <= Decimal::ZERO);
+
+    Ok(())
+}
@@ -5,6 +5,7 @@ mod proto {
 
 use std::collections::HashMap;
 
+use futures::{Stream, StreamExt};
 use opentelemetry::propagation::TextMapPropagator;
 use opentelemetry_sdk::propagation::TraceContextPropagator;
 use tracing::instrument;
@@ -21,6 +22,71 @@ pub struct OnchainAddress {
     pub address: String,
 }
 
+#[derive(Debug)]
+pub struct WalletBalance {
+    pub effective_settled_sats: u64,
+    pub effective_pending_income_sats: u64,
+    pub effective_pending_outgoing_sats: u64,
+}
+
+#[derive(Debug)]
+pub struct Payout {
+    pub id: String,
+    pub external_id: String,
+    pub cancelled: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayoutEventKind {
+    Committed,
+    Broadcast,
+    Settled,
+    Cancelled,
+}
+
+/// Lifecycle event of a payout, correlated through the external id
+/// it was submitted with
+#[derive(Debug, Clone)]
+pub struct PayoutEvent {
+    pub sequence: u64,
+    pub kind: PayoutEventKind,
+    pub payout_id: String,
+    pub external_id: Option<String>,
+    pub tx_id: Option<String>,
+}
+
+impl PayoutEvent {
+    fn from_event(event: proto::BriaEvent) -> Option<Self> {
+        use proto::bria_event::Payload;
+
+        let external_id = event
+            .augmentation
+            .and_then(|augmentation| augmentation.payout_info)
+            .map(|payout| payout.external_id)
+            .filter(|external_id| !external_id.is_empty());
+        let (kind, payout_id, tx_id) = match event.payload? {
+            Payload::PayoutCommitted(payout) => {
+                (PayoutEventKind::Committed, payout.id, Some(payout.tx_id))
+            }
+            Payload::PayoutBroadcast(payout) => {
+                (PayoutEventKind::Broadcast, payout.id, Some(payout.tx_id))
+            }
+            Payload::PayoutSettled(payout) => {
+                (PayoutEventKind::Settled, payout.id, Some(payout.tx_id))
+            }
+            Payload::PayoutCancelled(payout) => (PayoutEventKind::Cancelled, payout.id, None),
+            _ => return None,
+        };
+        Some(Self {
+            sequence: event.sequence,
+            kind,
+            payout_id,
+            external_id,
+            tx_id,
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct BriaClient {
     config: BriaClientConfig,
@@ -37,6 +103,15 @@ impl BriaClient {
         })
     }
 
+    /// Defers connecting until the first call
+    pub fn connect_lazy(config: BriaClientConfig) -> Result<Self, BriaClientError> {
+        let channel = tonic::transport::Endpoint::from_shared(config.url.clone())?.connect_lazy();
+        Ok(Self {
+            config,
+            proto_client: ProtoClient::new(channel),
+        })
+    }
+
     pub fn inject_headers<T>(
         &self,
         mut request: tonic::Request<T>,
@@ -137,4 +212,73 @@ impl BriaClient {
             .await?;
         Ok(response.into_inner().id)
     }
+
+    /// The payout submitted with `external_id`, if bria has accepted one
+    #[instrument(name = "bria_client.find_payout", skip(self), err)]
+    pub async fn find_payout(
+        &mut self,
+        external_id: String,
+    ) -> Result<Option<Payout>, BriaClientError> {
+        let request = tonic::Request::new(proto::GetPayoutRequest {
+            identifier: Some(proto::get_payout_request::Identifier::ExternalId(
+                external_id,
+            )),
+        });
+
+        match self
+            .proto_client
+            .get_payout(self.inject_headers(request)?)
+            .await
+        {
+            Ok(response) => Ok(response.into_inner().payout.map(|payout| Payout {
+                id: payout.id,
+                external_id: payout.external_id,
+                cancelled: payout.cancelled,
+            })),
+            Err(status) if status.code() == tonic::Code::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    #[instrument(name = "bria_client.wallet_balance", skip(self), err)]
+    pub async fn wallet_balance(&mut self) -> Result<WalletBalance, BriaClientError> {
+        let request = tonic::Request::new(proto::GetWalletBalanceSummaryRequest {
+            wallet_name: self.config.wallet_name.clone(),
+        });
+
+        let response = self
+            .proto_client
+            .get_wallet_balance_summary(self.inject_headers(request)?)
+            .await?
+            .into_inner();
+        Ok(WalletBalance {
+            effective_settled_sats: response.effective_settled,
+            effective_pending_income_sats: response.effective_pending_income,
+            effective_pending_outgoing_sats: response.effective_pending_outgoing,
+        })
+    }
+
+    /// Payout events recorded after the given sequence, other events are skipped
+    #[instrument(name = "bria_client.subscribe_payouts", skip(self), err)]
+    pub async fn subscribe_payouts(
+        &mut self,
+        after_sequence: Option<u64>,
+    ) -> Result<impl Stream<Item = Result<PayoutEvent, BriaClientError>>, BriaClientError> {
+        let request = tonic::Request::new(proto::SubscribeAllRequest {
+            after_sequence,
+            augment: Some(true),
+        });
+
+        let stream = self
+            .proto_client
+            .subscribe_all(self.inject_headers(request)?)
+            .await?
+            .into_inner();
+        Ok(stream.filter_map(|event| async move {
+            match event {
+                Ok(event) => PayoutEvent::from_event(event).map(Ok),
+                Err(status) => Some(Err(status.into())),
+            }
+        }))
+    }
 }
@@ -45,3 +45,38 @@ async fn send_onchain_payment() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn wallet_balance() -> anyhow::Result<()> {
+    let config = client_configuration();
+    let mut client = BriaClient::connect(config).await?;
+
+    let balance = client.wallet_balance().await?;
+    assert!(balance.effective_settled_sats > 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn find_payout() -> anyhow::Result<()> {
+    let config = client_configuration();
+    let mut client = BriaClient::connect(config).await?;
+    let destination = "bcrt1q5cwegu66cf344du3ffrvnwjz9u246xlydqezsa".to_string();
+    let satoshis = rust_decimal::Decimal::from(5000);
+
+    use rand::distributions::{Alphanumeric, DistString};
+    let external_id = Alphanumeric.sample_string(&mut rand::thread_rng(), 32);
+    assert!(client.find_payout(external_id.clone()).await?.is_none());
+
+    let id = client
+        .send_onchain_payment(destination, satoshis, external_id.clone())
+        .await?;
+    let payout = client
+        .find_payout(external_id.clone())
+        .await?
+        .expect("payout not found");
+    assert_eq!(payout.id, id);
+    assert_eq!(payout.external_id, external_id);
+
+    Ok(())
+}
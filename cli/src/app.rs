@@ -1,11 +1,11 @@
 use anyhow::Context;
-use chrono::Duration;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use clap::{Parser, Subcommand};
 use rust_decimal::Decimal;
 use std::{collections::HashMap, path::PathBuf};
 use url::Url;
 
-use super::{config::*, price_client::*, quotes_client::*};
+use super::{config::*, hedging_admin_client::*, price_client::*, quotes_client::*};
 use shared::pubsub::memory;
 
 #[derive(Parser)]
@@ -44,6 +44,9 @@ enum Command {
         /// Okex passphrase
         #[clap(env = "OKEX_PASSPHRASE", default_value = "")]
         okex_passphrase: String,
+        /// Bitfinex secret key
+        #[clap(env = "BITFINEX_SECRET_KEY", default_value = "")]
+        bitfinex_secret_key: String,
         /// Bria profile api key
         #[clap(env = "BRIA_PROFILE_API_KEY", default_value = "")]
         bria_profile_api_key: String,
@@ -82,6 +85,145 @@ enum Command {
         #[clap(short, long)]
         id: String,
     },
+
+    /// Resumes hedging after the circuit breaker has tripped
+    ResetCircuitBreaker {
+        /// Connection string for the stablesats database
+        #[clap(env = "PG_CON", default_value = "")]
+        pg_con: String,
+        /// Why it is safe to resume hedging
+        #[clap(short, long)]
+        reason: String,
+    },
+
+    /// Prints recorded hedging and funding decisions as JSON lines
+    ExportHedgingDecisions {
+        /// Connection string for the stablesats database
+        #[clap(env = "PG_CON", default_value = "")]
+        pg_con: String,
+        /// Only decisions made at or after this time (RFC 3339)
+        #[clap(long)]
+        since: Option<DateTime<Utc>>,
+        /// Only decisions made before this time (RFC 3339)
+        #[clap(long)]
+        until: Option<DateTime<Utc>>,
+        #[clap(long, value_enum)]
+        process: Option<HedgingProcess>,
+        /// Only decisions for this exchange (okex, bitfinex)
+        #[clap(long)]
+        exchange: Option<String>,
+        #[clap(long)]
+        correlation_id: Option<uuid::Uuid>,
+        /// Skip decisions that ended in doing nothing
+        #[clap(long)]
+        action_required_only: bool,
+        #[clap(long)]
+        limit: Option<i64>,
+    },
+
+    /// Replays a liability and price series through the configured okex hedging and funding bounds
+    Backtest {
+        /// CSV file with a header row, or a .json array, of liability_usd, btc_price_usd
+        /// and optionally timestamp and funding_rate
+        series: PathBuf,
+        /// Print the report as JSON
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// Inspects or steers a running hedging process
+    Hedging {
+        /// hedging admin server URL
+        #[clap(short, long, action, value_parser, env = "HEDGING_ADMIN_URL")]
+        url: Option<Url>,
+        #[clap(subcommand)]
+        command: HedgingCommand,
+    },
+
+    /// Reports on the stablesats ledger as CSV
+    Ledger {
+        /// Connection string for the stablesats database
+        #[clap(long, env = "PG_CON", default_value = "")]
+        pg_con: String,
+        #[clap(subcommand)]
+        command: LedgerCommand,
+    },
+
+    /// Shows how the okex account differs from what hedging expects
+    OkexAccountSetup {
+        /// Okex secret key
+        #[clap(env = "OKEX_SECRET_KEY", default_value = "")]
+        okex_secret_key: String,
+        /// Okex passphrase
+        #[clap(env = "OKEX_PASSPHRASE", default_value = "")]
+        okex_passphrase: String,
+        /// Change the account settings instead of only listing them
+        #[clap(long)]
+        apply: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum HedgingCommand {
+    /// Shows target liability, exposure, pauses and the last actions taken
+    Status,
+    /// Lists the okex orders that are not complete
+    OpenOrders,
+    /// Lists the okex transfers that have not settled
+    PendingTransfers,
+    /// Stops a process from taking new actions
+    Pause {
+        #[clap(value_enum)]
+        process: HedgingProcess,
+        /// Why the process is being paused
+        #[clap(short, long)]
+        reason: String,
+    },
+    /// Lets a paused process take actions again
+    Resume {
+        #[clap(value_enum)]
+        process: HedgingProcess,
+        /// Why it is safe to resume
+        #[clap(short, long)]
+        reason: String,
+    },
+    /// Runs adjust_hedge now
+    AdjustHedge {
+        /// Correlation id to find the run in traces, generated when omitted
+        #[clap(long)]
+        correlation_id: Option<String>,
+    },
+    /// Runs adjust_funding now
+    AdjustFunding {
+        /// Correlation id to find the run in traces, generated when omitted
+        #[clap(long)]
+        correlation_id: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum LedgerCommand {
+    /// Lists the entries posted in a date range with their template and meta
+    Statement {
+        /// Entries effective on or after this date (YYYY-MM-DD)
+        #[clap(long)]
+        from: NaiveDate,
+        /// Entries effective before this date (YYYY-MM-DD)
+        #[clap(long)]
+        until: NaiveDate,
+        /// Only entries on this account code
+        #[clap(long)]
+        account: Option<String>,
+        /// Entries fetched per query
+        #[clap(long, default_value = "1000", value_parser = clap::value_parser!(i64).range(1..))]
+        page_size: i64,
+    },
+    /// Lists the balance of every account as of a point in time
+    Balances {
+        /// Defaults to now (RFC 3339)
+        #[clap(long)]
+        at: Option<DateTime<Utc>>,
+    },
 }
 
 pub async fn run() -> anyhow::Result<()> {
@@ -93,6 +235,7 @@ pub async fn run() -> anyhow::Result<()> {
             galoy_phone_code,
             okex_passphrase,
             okex_secret_key,
+            bitfinex_secret_key,
             pg_con,
             bria_profile_api_key,
         } => {
@@ -102,6 +245,7 @@ pub async fn run() -> anyhow::Result<()> {
                     galoy_phone_code,
                     okex_passphrase,
                     okex_secret_key,
+                    bitfinex_secret_key,
                     pg_con,
                     bria_profile_api_key,
                 },
@@ -139,6 +283,256 @@ pub async fn run() -> anyhow::Result<()> {
             let client = get_quotes_client(url).await;
             client.accept_quote(id).await?;
         }
+        Command::ResetCircuitBreaker { pg_con, reason } => {
+            let config = Config::from_path(
+                cli.config,
+                EnvOverride {
+                    pg_con,
+                    ..Default::default()
+                },
+            )?;
+            reset_circuit_breaker_cmd(config, reason).await?
+        }
+        Command::ExportHedgingDecisions {
+            pg_con,
+            since,
+            until,
+            process,
+            exchange,
+            correlation_id,
+            action_required_only,
+            limit,
+        } => {
+            let config = Config::from_path(
+                cli.config,
+                EnvOverride {
+                    pg_con,
+                    ..Default::default()
+                },
+            )?;
+            let filter = hedging::HedgingDecisionFilter {
+                since,
+                until,
+                process: process.map(Into::into),
+                exchange,
+                correlation_id,
+                action_required_only,
+                limit,
+            };
+            export_hedging_decisions_cmd(config, filter).await?
+        }
+        Command::Backtest { series, json } => {
+            let config = Config::from_path(cli.config, EnvOverride::default())?;
+            backtest_cmd(config, series, json).await?
+        }
+        Command::Hedging { url, command } => hedging_cmd(url, command).await?,
+        Command::Ledger { pg_con, command } => {
+            let config = Config::from_path(
+                cli.config,
+                EnvOverride {
+                    pg_con,
+                    ..Default::default()
+                },
+            )?;
+            ledger_cmd(config, command).await?
+        }
+        Command::OkexAccountSetup {
+            okex_secret_key,
+            okex_passphrase,
+            apply,
+        } => {
+            let config = Config::from_path(
+                cli.config,
+                EnvOverride {
+                    okex_secret_key,
+                    okex_passphrase,
+                    ..Default::default()
+                },
+            )?;
+            okex_account_setup_cmd(config, apply).await?
+        }
+    }
+    Ok(())
+}
+
+async fn hedging_cmd(url: Option<Url>, command: HedgingCommand) -> anyhow::Result<()> {
+    let client = HedgingAdminClient::new(
+        url.map(|url| HedgingAdminClientConfig { url })
+            .unwrap_or_default(),
+    );
+    match command {
+        HedgingCommand::Status => client.status().await,
+        HedgingCommand::OpenOrders => client.open_orders().await,
+        HedgingCommand::PendingTransfers => client.pending_transfers().await,
+        HedgingCommand::Pause { process, reason } => client.pause(process, reason).await,
+        HedgingCommand::Resume { process, reason } => client.resume(process, reason).await,
+        HedgingCommand::AdjustHedge { correlation_id } => client.adjust_hedge(correlation_id).await,
+        HedgingCommand::AdjustFunding { correlation_id } => {
+            client.adjust_funding(correlation_id).await
+        }
+    }
+}
+
+async fn ledger_cmd(config: Config, command: LedgerCommand) -> anyhow::Result<()> {
+    let pool = crate::db::init_pool(&config.db).await?;
+    let ledger = ledger::LedgerReader::new(&pool);
+    match command {
+        LedgerCommand::Statement {
+            from,
+            until,
+            account,
+            page_size,
+        } => {
+            println!("created_at,effective,journal,template_code,account_code,entry_type,layer,direction,currency,units,btc_tx_id,usd_tx_id,transaction_id,entry_id,meta");
+            let mut query = ledger::StatementQuery {
+                from,
+                until,
+                account_code: account,
+                after: None,
+                limit: page_size,
+            };
+            loop {
+                let page = ledger.statements().entries(&query).await?;
+                for entry in page.entries {
+                    let meta = entry.meta.map(|meta| meta.to_string()).unwrap_or_default();
+                    println!(
+                        "{}",
+                        [
+                            entry.created_at.to_rfc3339(),
+                            entry.effective.to_string(),
+                            entry.journal,
+                            entry.template_code,
+                            entry.account_code,
+                            entry.entry_type,
+                            entry.layer,
+                            entry.direction,
+                            entry.currency,
+                            entry.units.to_string(),
+                            entry.btc_tx_id.unwrap_or_default(),
+                            entry.usd_tx_id.unwrap_or_default(),
+                            entry.transaction_id.to_string(),
+                            entry.entry_id.to_string(),
+                            meta,
+                        ]
+                        .iter()
+                        .map(|field| csv_field(field))
+                        .collect::<Vec<_>>()
+                        .join(",")
+                    );
+                }
+                match page.next {
+                    Some(next) => query.after = Some(next),
+                    None => break,
+                }
+            }
+        }
+        LedgerCommand::Balances { at } => {
+            println!("journal,account_code,currency,settled,pending,encumbered,account_id");
+            let balances = ledger
+                .statements()
+                .balances_at(at.unwrap_or_else(Utc::now))
+                .await?;
+            for balance in balances {
+                println!(
+                    "{}",
+                    [
+                        balance.journal,
+                        balance.account_code,
+                        balance.currency,
+                        balance.settled.to_string(),
+                        balance.pending.to_string(),
+                        balance.encumbered.to_string(),
+                        balance.account_id.to_string(),
+                    ]
+                    .iter()
+                    .map(|field| csv_field(field))
+                    .collect::<Vec<_>>()
+                    .join(",")
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Quotes a field that would otherwise break the row
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+async fn okex_account_setup_cmd(config: Config, apply: bool) -> anyhow::Result<()> {
+    let okex = config
+        .exchanges
+        .okex
+        .context("No okex exchange configured")?
+        .config;
+    let changes = if apply {
+        hedging::setup_okex_account(&okex).await?
+    } else {
+        hedging::okex_account_changes(&okex).await?
+    };
+    if changes.is_empty() {
+        println!("Okex account is set up for hedging");
+        return Ok(());
+    }
+    for change in changes {
+        println!("{change}");
+    }
+    if apply {
+        println!("Okex account updated");
+    } else {
+        println!("Run again with --apply to update the okex account");
+    }
+    Ok(())
+}
+
+async fn backtest_cmd(config: Config, series: PathBuf, json: bool) -> anyhow::Result<()> {
+    let contents = std::fs::read_to_string(&series).context("Couldn't read series file")?;
+    let samples = if series.extension().is_some_and(|ext| ext == "json") {
+        hedging::parse_json_series(&contents)?
+    } else {
+        hedging::parse_csv_series(&contents)?
+    };
+    let okex = config
+        .exchanges
+        .okex
+        .map(|okex| okex.config)
+        .unwrap_or_default();
+    let report = hedging::Backtest::new(okex.hedging, okex.funding)
+        .run(&samples)
+        .await?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("{report}");
+    }
+    Ok(())
+}
+
+async fn export_hedging_decisions_cmd(
+    config: Config,
+    filter: hedging::HedgingDecisionFilter,
+) -> anyhow::Result<()> {
+    let pool = crate::db::init_pool(&config.db).await?;
+    let decisions = hedging::HedgingDecisions::new(pool).list(&filter).await?;
+    for decision in decisions {
+        println!("{}", serde_json::to_string(&decision)?);
+    }
+    Ok(())
+}
+
+async fn reset_circuit_breaker_cmd(config: Config, reason: String) -> anyhow::Result<()> {
+    let pool = crate::db::init_pool(&config.db).await?;
+    match hedging::reset_circuit_breaker(&pool, reason).await? {
+        Some(trip) => println!(
+            "Circuit breaker reset (tripped at {}: {})",
+            trip.tripped_at, trip.reason
+        ),
+        None => println!("Circuit breaker was not tripped"),
     }
     Ok(())
 }
@@ -230,11 +624,11 @@ async fn run_cmd(
         let price = price_recv.resubscribe();
         checkers.insert("hedging", snd);
 
-        if let Some(okex_cfg) = exchanges.okex.as_ref() {
+        if exchanges.okex.is_some() || exchanges.bitfinex.is_some() {
             pool = Some(crate::db::init_pool(&db).await?);
             ledger = Some(ledger::Ledger::init(pool.as_ref().unwrap()).await?);
 
-            let okex_config = okex_cfg.config.clone();
+            let exchanges = exchanges.clone();
             let pool = pool.clone();
             let ledger = ledger.clone();
             handles.push(tokio::spawn(async move {
@@ -243,7 +637,7 @@ async fn run_cmd(
                         pool.as_ref().unwrap().clone(),
                         recv,
                         hedging.config,
-                        okex_config,
+                        exchanges,
                         galoy,
                         bria,
                         price,
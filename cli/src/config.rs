@@ -38,10 +38,12 @@ pub struct Config {
     pub quotes_server: QuotesServerWrapper,
 }
 
+#[derive(Default)]
 pub struct EnvOverride {
     pub pg_con: String,
     pub okex_secret_key: String,
     pub okex_passphrase: String,
+    pub bitfinex_secret_key: String,
     pub galoy_phone_code: String,
     pub bria_profile_api_key: String,
 }
@@ -53,6 +55,7 @@ impl Config {
             galoy_phone_code,
             okex_passphrase,
             okex_secret_key,
+            bitfinex_secret_key,
             pg_con: stablesats_pg_con,
             bria_profile_api_key,
         }: EnvOverride,
@@ -68,6 +71,10 @@ impl Config {
             okex.config.client.passphrase = okex_passphrase;
         };
 
+        if let Some(bitfinex) = config.exchanges.bitfinex.as_mut() {
+            bitfinex.config.client.secret_key = bitfinex_secret_key;
+        };
+
         config.db.pg_con = stablesats_pg_con;
 
         if config.hedging.enabled {
@@ -0,0 +1,135 @@
+use clap::ValueEnum;
+use tonic::transport::channel::Channel;
+use url::Url;
+
+use hedging::admin_proto as proto;
+type ProtoClient = proto::hedging_admin_service_client::HedgingAdminServiceClient<Channel>;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum HedgingProcess {
+    Hedging,
+    Funding,
+}
+
+impl From<HedgingProcess> for proto::Process {
+    fn from(process: HedgingProcess) -> Self {
+        match process {
+            HedgingProcess::Hedging => proto::Process::Hedging,
+            HedgingProcess::Funding => proto::Process::Funding,
+        }
+    }
+}
+
+impl From<HedgingProcess> for hedging::HedgingProcess {
+    fn from(process: HedgingProcess) -> Self {
+        match process {
+            HedgingProcess::Hedging => hedging::HedgingProcess::Hedging,
+            HedgingProcess::Funding => hedging::HedgingProcess::Funding,
+        }
+    }
+}
+
+pub struct HedgingAdminClientConfig {
+    pub url: Url,
+}
+
+impl Default for HedgingAdminClientConfig {
+    fn default() -> Self {
+        Self {
+            url: Url::parse("http://localhost:3327").unwrap(),
+        }
+    }
+}
+
+pub struct HedgingAdminClient {
+    config: HedgingAdminClientConfig,
+}
+
+impl HedgingAdminClient {
+    pub fn new(config: HedgingAdminClientConfig) -> Self {
+        Self { config }
+    }
+
+    async fn connect(&self) -> anyhow::Result<ProtoClient> {
+        match ProtoClient::connect(self.config.url.to_string()).await {
+            Ok(client) => Ok(client),
+            Err(err) => {
+                eprintln!(
+                    "Couldn't connect to hedging admin server\nAre you sure its running on {}?\n",
+                    self.config.url
+                );
+                Err(anyhow::anyhow!(err))
+            }
+        }
+    }
+
+    pub async fn status(&self) -> anyhow::Result<()> {
+        let mut client = self.connect().await?;
+        let request = tonic::Request::new(proto::GetHedgingStatusRequest {});
+        let response = client.get_hedging_status(request).await?;
+        output_json(response)
+    }
+
+    pub async fn open_orders(&self) -> anyhow::Result<()> {
+        let mut client = self.connect().await?;
+        let request = tonic::Request::new(proto::ListOpenOrdersRequest {});
+        let response = client.list_open_orders(request).await?;
+        output_json(response)
+    }
+
+    pub async fn pending_transfers(&self) -> anyhow::Result<()> {
+        let mut client = self.connect().await?;
+        let request = tonic::Request::new(proto::ListPendingTransfersRequest {});
+        let response = client.list_pending_transfers(request).await?;
+        output_json(response)
+    }
+
+    pub async fn pause(&self, process: HedgingProcess, reason: String) -> anyhow::Result<()> {
+        let mut client = self.connect().await?;
+        let request = tonic::Request::new(proto::PauseProcessRequest {
+            process: proto::Process::from(process).into(),
+            reason,
+        });
+        let response = client.pause_process(request).await?.into_inner();
+        if response.already_paused {
+            println!("{process:?} was already paused");
+        } else {
+            println!("{process:?} paused");
+        }
+        Ok(())
+    }
+
+    pub async fn resume(&self, process: HedgingProcess, reason: String) -> anyhow::Result<()> {
+        let mut client = self.connect().await?;
+        let request = tonic::Request::new(proto::ResumeProcessRequest {
+            process: proto::Process::from(process).into(),
+            reason,
+        });
+        let response = client.resume_process(request).await?.into_inner();
+        if response.was_paused {
+            println!("{process:?} resumed");
+        } else {
+            println!("{process:?} was not paused");
+        }
+        Ok(())
+    }
+
+    pub async fn adjust_hedge(&self, correlation_id: Option<String>) -> anyhow::Result<()> {
+        let mut client = self.connect().await?;
+        let request = tonic::Request::new(proto::TriggerAdjustHedgeRequest { correlation_id });
+        let response = client.trigger_adjust_hedge(request).await?;
+        output_json(response)
+    }
+
+    pub async fn adjust_funding(&self, correlation_id: Option<String>) -> anyhow::Result<()> {
+        let mut client = self.connect().await?;
+        let request = tonic::Request::new(proto::TriggerAdjustFundingRequest { correlation_id });
+        let response = client.trigger_adjust_funding(request).await?;
+        output_json(response)
+    }
+}
+
+fn output_json<T: serde::Serialize>(response: tonic::Response<T>) -> anyhow::Result<()> {
+    println!("{}", serde_json::to_string_pretty(&response.into_inner())?);
+    Ok(())
+}
@@ -7,5 +7,6 @@ mod health;
 mod tracing;
 
 mod db;
+mod hedging_admin_client;
 mod price_client;
 mod quotes_client;
@@ -8,4 +8,7 @@ pub struct GaloyClientConfig {
     pub auth_code: String,
     #[serde(default)]
     pub phone_number: String,
+    /// BTC wallet used to pay and receive lightning invoices
+    #[serde(default)]
+    pub btc_wallet_id: String,
 }
@@ -16,8 +16,9 @@ pub use self::convert::PathString;
 use crate::error::*;
 use queries::*;
 pub use queries::{
-    stablesats_transactions_list::WalletCurrency as SettlementCurrency, StablesatsAuthToken,
-    WalletId,
+    stablesats_ln_invoice_payment_send::PaymentSendResult,
+    stablesats_transactions_list::WalletCurrency as SettlementCurrency, LnInvoice,
+    StablesatsAuthToken, WalletId,
 };
 
 pub use config::*;
@@ -126,6 +127,74 @@ impl GaloyClient {
         GaloyTransactions::try_from(result)
     }
 
+    #[instrument(name = "galoy_client.create_ln_invoice", skip(self), err)]
+    pub async fn create_ln_invoice(
+        &self,
+        sats: u64,
+        memo: String,
+    ) -> Result<LnInvoice, GaloyClientError> {
+        let variables = stablesats_ln_invoice_create::Variables {
+            input: stablesats_ln_invoice_create::LnInvoiceCreateInput {
+                amount: sats,
+                memo: Some(memo),
+                wallet_id: self.config.btc_wallet_id.clone(),
+            },
+        };
+
+        let response = GaloyClient::traced_gql_request::<StablesatsLnInvoiceCreate, _>(
+            &self.client,
+            &self.config.api,
+            variables,
+        )
+        .await?;
+        let result = Self::extract_response_data(response)?;
+        LnInvoice::try_from(result)
+    }
+
+    #[instrument(name = "galoy_client.pay_ln_invoice", skip(self, payment_request), err)]
+    pub async fn pay_ln_invoice(
+        &self,
+        payment_request: String,
+        memo: String,
+    ) -> Result<PaymentSendResult, GaloyClientError> {
+        let variables = stablesats_ln_invoice_payment_send::Variables {
+            input: stablesats_ln_invoice_payment_send::LnInvoicePaymentInput {
+                memo: Some(memo),
+                payment_request,
+                wallet_id: self.config.btc_wallet_id.clone(),
+            },
+        };
+
+        let response = GaloyClient::traced_gql_request::<StablesatsLnInvoicePaymentSend, _>(
+            &self.client,
+            &self.config.api,
+            variables,
+        )
+        .await?;
+        let result = Self::extract_response_data(response)?;
+        PaymentSendResult::try_from(result)
+    }
+
+    fn extract_response_data<D>(response: Response<D>) -> Result<D, GaloyClientError> {
+        if let Some(errors) = response.errors {
+            let zeroth_error = errors[0].clone();
+
+            return Err(GaloyClientError::GraphQLTopLevel {
+                message: zeroth_error.message,
+                path: zeroth_error.path.into(),
+                locations: zeroth_error.locations,
+                extensions: zeroth_error.extensions,
+            });
+        }
+
+        response
+            .data
+            .ok_or_else(|| GaloyClientError::GraphQLNested {
+                message: "Empty `data` in response".to_string(),
+                path: None,
+            })
+    }
+
     async fn traced_gql_request<Q: GraphQLQuery, U: reqwest::IntoUrl>(
         client: &ReqwestClient,
         url: U,
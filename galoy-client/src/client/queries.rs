@@ -44,6 +44,77 @@ impl TryFrom<stablesats_user_login::ResponseData> for StablesatsAuthToken {
     }
 }
 
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "src/client/graphql/schema.graphql",
+    query_path = "src/client/graphql/mutations/ln_invoice_create.graphql",
+    response_derives = "Debug, PartialEq, Eq, Clone"
+)]
+pub struct StablesatsLnInvoiceCreate;
+pub type SatAmount = u64;
+pub type PaymentHash = String;
+pub type LnPaymentRequest = String;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LnInvoice {
+    pub payment_hash: String,
+    pub payment_request: String,
+}
+impl TryFrom<stablesats_ln_invoice_create::ResponseData> for LnInvoice {
+    type Error = GaloyClientError;
+
+    fn try_from(response: stablesats_ln_invoice_create::ResponseData) -> Result<Self, Self::Error> {
+        let ln_invoice_create = response.ln_invoice_create;
+        if let Some(error) = ln_invoice_create.errors.into_iter().next() {
+            return Err(GaloyClientError::GraphQLNested {
+                message: error.message,
+                path: error.path,
+            });
+        }
+        let invoice = ln_invoice_create
+            .invoice
+            .ok_or_else(|| GaloyClientError::GraphQLNested {
+                message: "Empty `invoice` in response data".to_string(),
+                path: None,
+            })?;
+        Ok(LnInvoice {
+            payment_hash: invoice.payment_hash,
+            payment_request: invoice.payment_request,
+        })
+    }
+}
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "src/client/graphql/schema.graphql",
+    query_path = "src/client/graphql/mutations/ln_invoice_payment_send.graphql",
+    response_derives = "Debug, PartialEq, Eq, Clone"
+)]
+pub struct StablesatsLnInvoicePaymentSend;
+impl TryFrom<stablesats_ln_invoice_payment_send::ResponseData>
+    for stablesats_ln_invoice_payment_send::PaymentSendResult
+{
+    type Error = GaloyClientError;
+
+    fn try_from(
+        response: stablesats_ln_invoice_payment_send::ResponseData,
+    ) -> Result<Self, Self::Error> {
+        let payment_send = response.ln_invoice_payment_send;
+        if let Some(error) = payment_send.errors.into_iter().next() {
+            return Err(GaloyClientError::GraphQLNested {
+                message: error.message,
+                path: error.path,
+            });
+        }
+        payment_send
+            .status
+            .ok_or_else(|| GaloyClientError::GraphQLNested {
+                message: "Empty `status` in response data".to_string(),
+                path: None,
+            })
+    }
+}
+
 #[derive(GraphQLQuery)]
 #[graphql(
     schema_path = "src/client/graphql/schema.graphql",
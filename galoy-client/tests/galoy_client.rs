@@ -11,6 +11,7 @@ async fn configured_client() -> anyhow::Result<GaloyClient> {
         api,
         phone_number,
         auth_code: code,
+        ..Default::default()
     })
     .await?;
 
@@ -1,5 +1,14 @@
-// generated by `sqlx migrate build-script`
-fn main() {
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     // trigger recompilation when a new migration is added
     println!("cargo:rerun-if-changed=migrations");
+    std::env::set_var("PROTOC", protobuf_src::protoc());
+
+    tonic_build::configure()
+        .type_attribute(".", "#[derive(serde::Serialize)]")
+        .type_attribute(".", "#[serde(rename_all = \"camelCase\")]")
+        .compile(
+            &["../proto/hedging/hedging_admin_service.proto"],
+            &["../proto"],
+        )?;
+    Ok(())
 }
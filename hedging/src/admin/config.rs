@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HedgingAdminServerConfig {
+    #[serde(default = "default_port")]
+    pub listen_port: u16,
+}
+impl Default for HedgingAdminServerConfig {
+    fn default() -> Self {
+        Self {
+            listen_port: default_port(),
+        }
+    }
+}
+
+fn default_port() -> u16 {
+    3327
+}
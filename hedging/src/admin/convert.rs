@@ -0,0 +1,85 @@
+use super::proto;
+use crate::{
+    control::{HedgingProcess, ProcessAction},
+    error::HedgingError,
+    okex::{InFlightTransfer, OpenOrder},
+};
+
+impl TryFrom<i32> for HedgingProcess {
+    type Error = tonic::Status;
+
+    fn try_from(process: i32) -> Result<Self, Self::Error> {
+        match proto::Process::try_from(process) {
+            Ok(proto::Process::Hedging) => Ok(HedgingProcess::Hedging),
+            Ok(proto::Process::Funding) => Ok(HedgingProcess::Funding),
+            _ => Err(tonic::Status::invalid_argument("unknown process")),
+        }
+    }
+}
+
+impl From<ProcessAction> for proto::LastAction {
+    fn from(action: ProcessAction) -> Self {
+        Self {
+            action: action.action,
+            correlation_id: action.correlation_id.to_string(),
+            recorded_at: action.recorded_at.timestamp(),
+        }
+    }
+}
+
+impl From<OpenOrder> for proto::OpenOrder {
+    fn from(order: OpenOrder) -> Self {
+        Self {
+            client_order_id: String::from(order.id),
+            correlation_id: order.correlation_id.to_string(),
+            instrument: order.instrument,
+            action: order.action_type,
+            order_type: order.order_type,
+            size: order.size.map(|size| size.to_string()),
+            size_usd_value: order.size_usd_value.map(|value| value.to_string()),
+            is_parent: order.is_parent,
+            created_at: order.created_at.timestamp(),
+        }
+    }
+}
+
+impl From<InFlightTransfer> for proto::PendingTransfer {
+    fn from(transfer: InFlightTransfer) -> Self {
+        Self {
+            client_transfer_id: String::from(transfer.client_transfer_id),
+            correlation_id: transfer.correlation_id.to_string(),
+            kind: transfer.kind.to_string(),
+            status: transfer.status.to_string(),
+            amount: transfer.amount.to_string(),
+            attempts: u32::try_from(transfer.attempts).unwrap_or_default(),
+            last_error: transfer.last_error,
+            created_at: transfer.created_at.timestamp(),
+            updated_at: transfer.updated_at.timestamp(),
+        }
+    }
+}
+
+impl From<HedgingError> for tonic::Status {
+    fn from(err: HedgingError) -> Self {
+        tonic::Status::internal(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unspecified_process_is_rejected() {
+        assert_eq!(
+            HedgingProcess::try_from(proto::Process::Hedging as i32).unwrap(),
+            HedgingProcess::Hedging
+        );
+        assert_eq!(
+            HedgingProcess::try_from(proto::Process::Funding as i32).unwrap(),
+            HedgingProcess::Funding
+        );
+        assert!(HedgingProcess::try_from(proto::Process::Unspecified as i32).is_err());
+        assert!(HedgingProcess::try_from(42).is_err());
+    }
+}
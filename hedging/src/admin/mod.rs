@@ -0,0 +1,280 @@
+#![allow(clippy::blocks_in_conditions)]
+mod config;
+mod convert;
+
+#[allow(clippy::all)]
+pub mod proto {
+    tonic::include_proto!("services.hedging.v1");
+}
+
+use opentelemetry::propagation::{Extractor, TextMapPropagator};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use proto::{hedging_admin_service_server::HedgingAdminService, *};
+use tonic::{transport::Server, Request, Response, Status};
+use tracing::instrument;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use uuid::Uuid;
+
+use std::sync::Arc;
+
+use shared::pubsub::CorrelationId;
+
+use crate::{control::*, error::*, okex::OkexEngine, risk::RiskGuard};
+
+pub use config::*;
+
+/// Lets operators inspect and steer hedging without going to the database.
+pub struct HedgingAdmin {
+    ledger: ledger::Ledger,
+    risk_guard: RiskGuard,
+    process_control: ProcessControl,
+    okex_engine: Option<Arc<OkexEngine>>,
+}
+
+impl HedgingAdmin {
+    pub(crate) fn new(
+        ledger: ledger::Ledger,
+        risk_guard: RiskGuard,
+        process_control: ProcessControl,
+        okex_engine: Option<Arc<OkexEngine>>,
+    ) -> Self {
+        Self {
+            ledger,
+            risk_guard,
+            process_control,
+            okex_engine,
+        }
+    }
+
+    fn okex(&self) -> Result<&OkexEngine, Status> {
+        self.okex_engine
+            .as_deref()
+            .ok_or_else(|| Status::failed_precondition("okex is not configured"))
+    }
+
+    async fn process_status(&self, process: HedgingProcess) -> Result<ProcessStatus, Status> {
+        let pause = self.process_control.paused(process).await?;
+        Ok(ProcessStatus {
+            paused: pause.is_some(),
+            paused_at: pause.as_ref().map(|pause| pause.paused_at.timestamp()),
+            pause_reason: pause.map(|pause| pause.reason),
+            last_action: self.process_control.last_action(process).map(Into::into),
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl HedgingAdminService for HedgingAdmin {
+    #[instrument(name = "hedging.admin.get_hedging_status", skip_all,
+        fields(error, error.level, error.message),
+        err
+    )]
+    async fn get_hedging_status(
+        &self,
+        request: Request<GetHedgingStatusRequest>,
+    ) -> Result<Response<GetHedgingStatusResponse>, Status> {
+        shared::tracing::record_error(tracing::Level::ERROR, || async move {
+            extract_tracing(&request);
+            let target_liability = self
+                .ledger
+                .balances()
+                .usd_liability_balances()
+                .await
+                .map_err(HedgingError::from)?
+                .okex_allocation;
+            let position = match self.okex_engine {
+                Some(ref engine) => Some(engine.current_position().await?),
+                None => None,
+            };
+            let trip = self.risk_guard.halted().await?;
+            Ok(Response::new(GetHedgingStatusResponse {
+                target_liability_in_cents: target_liability.to_string(),
+                exposure_in_cents: position
+                    .as_ref()
+                    .map(|position| position.usd_cents.to_string()),
+                instrument_id: position.map(|position| position.instrument_id),
+                circuit_breaker_reason: trip.map(|trip| trip.reason),
+                hedging: Some(self.process_status(HedgingProcess::Hedging).await?),
+                funding: Some(self.process_status(HedgingProcess::Funding).await?),
+            }))
+        })
+        .await
+    }
+
+    #[instrument(name = "hedging.admin.list_open_orders", skip_all,
+        fields(error, error.level, error.message),
+        err
+    )]
+    async fn list_open_orders(
+        &self,
+        request: Request<ListOpenOrdersRequest>,
+    ) -> Result<Response<ListOpenOrdersResponse>, Status> {
+        shared::tracing::record_error(tracing::Level::ERROR, || async move {
+            extract_tracing(&request);
+            let orders = self.okex()?.open_orders().await?;
+            Ok(Response::new(ListOpenOrdersResponse {
+                orders: orders.into_iter().map(Into::into).collect(),
+            }))
+        })
+        .await
+    }
+
+    #[instrument(name = "hedging.admin.list_pending_transfers", skip_all,
+        fields(error, error.level, error.message),
+        err
+    )]
+    async fn list_pending_transfers(
+        &self,
+        request: Request<ListPendingTransfersRequest>,
+    ) -> Result<Response<ListPendingTransfersResponse>, Status> {
+        shared::tracing::record_error(tracing::Level::ERROR, || async move {
+            extract_tracing(&request);
+            let transfers = self.okex()?.in_flight_transfers().await?;
+            Ok(Response::new(ListPendingTransfersResponse {
+                transfers: transfers.into_iter().map(Into::into).collect(),
+            }))
+        })
+        .await
+    }
+
+    #[instrument(name = "hedging.admin.pause_process", skip_all,
+        fields(process, error, error.level, error.message),
+        err
+    )]
+    async fn pause_process(
+        &self,
+        request: Request<PauseProcessRequest>,
+    ) -> Result<Response<PauseProcessResponse>, Status> {
+        shared::tracing::record_error(tracing::Level::ERROR, || async move {
+            extract_tracing(&request);
+            let req = request.into_inner();
+            let process = HedgingProcess::try_from(req.process)?;
+            tracing::Span::current().record("process", tracing::field::display(process));
+            let reason = required_reason(req.reason)?;
+            let paused = self.process_control.pause(process, reason).await?;
+            Ok(Response::new(PauseProcessResponse {
+                already_paused: !paused,
+            }))
+        })
+        .await
+    }
+
+    #[instrument(name = "hedging.admin.resume_process", skip_all,
+        fields(process, error, error.level, error.message),
+        err
+    )]
+    async fn resume_process(
+        &self,
+        request: Request<ResumeProcessRequest>,
+    ) -> Result<Response<ResumeProcessResponse>, Status> {
+        shared::tracing::record_error(tracing::Level::ERROR, || async move {
+            extract_tracing(&request);
+            let req = request.into_inner();
+            let process = HedgingProcess::try_from(req.process)?;
+            tracing::Span::current().record("process", tracing::field::display(process));
+            let reason = required_reason(req.reason)?;
+            let pause = self.process_control.resume(process, reason).await?;
+            Ok(Response::new(ResumeProcessResponse {
+                was_paused: pause.is_some(),
+            }))
+        })
+        .await
+    }
+
+    #[instrument(name = "hedging.admin.trigger_adjust_hedge", skip_all,
+        fields(correlation_id, error, error.level, error.message),
+        err
+    )]
+    async fn trigger_adjust_hedge(
+        &self,
+        request: Request<TriggerAdjustHedgeRequest>,
+    ) -> Result<Response<TriggerAdjustHedgeResponse>, Status> {
+        shared::tracing::record_error(tracing::Level::ERROR, || async move {
+            extract_tracing(&request);
+            let correlation_id = correlation_id(request.into_inner().correlation_id)?;
+            self.okex()?.trigger_adjust_hedge(correlation_id).await?;
+            Ok(Response::new(TriggerAdjustHedgeResponse {
+                correlation_id: correlation_id.to_string(),
+            }))
+        })
+        .await
+    }
+
+    #[instrument(name = "hedging.admin.trigger_adjust_funding", skip_all,
+        fields(correlation_id, error, error.level, error.message),
+        err
+    )]
+    async fn trigger_adjust_funding(
+        &self,
+        request: Request<TriggerAdjustFundingRequest>,
+    ) -> Result<Response<TriggerAdjustFundingResponse>, Status> {
+        shared::tracing::record_error(tracing::Level::ERROR, || async move {
+            extract_tracing(&request);
+            let correlation_id = correlation_id(request.into_inner().correlation_id)?;
+            self.okex()?.trigger_adjust_funding(correlation_id).await?;
+            Ok(Response::new(TriggerAdjustFundingResponse {
+                correlation_id: correlation_id.to_string(),
+            }))
+        })
+        .await
+    }
+}
+
+fn required_reason(reason: String) -> Result<String, Status> {
+    if reason.trim().is_empty() {
+        return Err(Status::invalid_argument("a reason is required"));
+    }
+    Ok(reason)
+}
+
+/// Uses the caller's correlation id so the triggered job can be found in traces
+fn correlation_id(requested: Option<String>) -> Result<CorrelationId, Status> {
+    let correlation_id = match requested {
+        Some(id) => CorrelationId::from(
+            id.parse::<Uuid>()
+                .map_err(|_| Status::invalid_argument("correlation id must be a uuid"))?,
+        ),
+        None => CorrelationId::new(),
+    };
+    tracing::Span::current().record("correlation_id", tracing::field::display(correlation_id));
+    Ok(correlation_id)
+}
+
+pub(crate) async fn start(
+    server_config: HedgingAdminServerConfig,
+    admin: HedgingAdmin,
+) -> Result<(), HedgingError> {
+    Server::builder()
+        .add_service(hedging_admin_service_server::HedgingAdminServiceServer::new(admin))
+        .serve(([0, 0, 0, 0], server_config.listen_port).into())
+        .await?;
+    Ok(())
+}
+
+pub fn extract_tracing<T>(request: &Request<T>) {
+    let propagator = TraceContextPropagator::new();
+    let parent_cx = propagator.extract(&RequestContextExtractor(request));
+    tracing::Span::current().set_parent(parent_cx)
+}
+
+struct RequestContextExtractor<'a, T>(&'a Request<T>);
+
+impl<'a, T> Extractor for RequestContextExtractor<'a, T> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.metadata().get(key).and_then(|s| s.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0
+            .metadata()
+            .keys()
+            .filter_map(|k| {
+                if let tonic::metadata::KeyRef::Ascii(key) = k {
+                    Some(key.as_str())
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,70 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use crate::error::HedgingError;
+
+/// Last successful poll per exchange, kept in the db so that the liability
+/// listener sees polls made by job runners in other processes.
+#[derive(Debug, Clone)]
+pub struct ExchangeHealth {
+    pool: PgPool,
+    started_at: DateTime<Utc>,
+}
+
+impl ExchangeHealth {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            started_at: Utc::now(),
+        }
+    }
+
+    pub async fn record_successful_poll(&self, exchange_id: &str) -> Result<(), HedgingError> {
+        sqlx::query!(
+            r#"INSERT INTO exchange_polls (exchange, last_successful_poll_at)
+               VALUES ($1, NOW())
+               ON CONFLICT (exchange) DO UPDATE SET last_successful_poll_at = NOW()"#,
+            exchange_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// An exchange that was never polled gets the grace period from when this process started
+    pub async fn is_healthy(
+        &self,
+        exchange_id: &str,
+        unhealthy_after: chrono::Duration,
+    ) -> Result<bool, HedgingError> {
+        let last_poll = sqlx::query_scalar!(
+            "SELECT last_successful_poll_at FROM exchange_polls WHERE exchange = $1",
+            exchange_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(is_recent(
+            last_poll.unwrap_or(self.started_at),
+            unhealthy_after,
+        ))
+    }
+}
+
+fn is_recent(last_poll: DateTime<Utc>, unhealthy_after: chrono::Duration) -> bool {
+    Utc::now() - last_poll < unhealthy_after
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_polls_are_unhealthy() {
+        let after = chrono::Duration::seconds(60);
+        assert!(is_recent(Utc::now() - chrono::Duration::seconds(10), after));
+        assert!(!is_recent(
+            Utc::now() - chrono::Duration::seconds(61),
+            after
+        ));
+    }
+}
@@ -0,0 +1,265 @@
+mod health;
+
+use rust_decimal::Decimal;
+
+use ledger::{constants::CENTS_PER_USD, LiabilityAllocations};
+
+use crate::config::*;
+
+pub use health::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllocationAdjustment {
+    pub okex_cents: Decimal,
+    pub bitfinex_cents: Decimal,
+}
+
+#[derive(Debug, Clone)]
+pub struct AllocationPolicy {
+    okex_weight: Decimal,
+    bitfinex_weight: Decimal,
+    rebalance_threshold_cents: Decimal,
+}
+
+impl AllocationPolicy {
+    pub fn new(exchanges: &ExchangesConfig, config: &AllocationConfig) -> Self {
+        Self {
+            okex_weight: exchanges
+                .okex
+                .as_ref()
+                .map(|c| c.weight)
+                .unwrap_or(Decimal::ZERO),
+            bitfinex_weight: exchanges
+                .bitfinex
+                .as_ref()
+                .map(|c| c.weight)
+                .unwrap_or(Decimal::ZERO),
+            rebalance_threshold_cents: config.rebalance_threshold_cents,
+        }
+    }
+
+    pub fn determine_adjustment(
+        &self,
+        balances: &LiabilityAllocations,
+        okex_healthy: bool,
+        bitfinex_healthy: bool,
+    ) -> Option<AllocationAdjustment> {
+        let unallocated_cents = balances.unallocated_usd * CENTS_PER_USD;
+        let current = [
+            Decimal::from(balances.okex_allocation),
+            Decimal::from(balances.bitfinex_allocation),
+        ];
+        let total: Decimal = unallocated_cents + current.iter().sum::<Decimal>();
+
+        let configured = [self.okex_weight, self.bitfinex_weight].map(|w| w.max(Decimal::ZERO));
+        let healthy = [
+            if okex_healthy {
+                configured[0]
+            } else {
+                Decimal::ZERO
+            },
+            if bitfinex_healthy {
+                configured[1]
+            } else {
+                Decimal::ZERO
+            },
+        ];
+        if healthy.iter().all(|w| w.is_zero()) {
+            // Nowhere healthy to move existing allocations to, only place what is unallocated
+            if unallocated_cents.is_zero() {
+                return None;
+            }
+            let [okex_cents, bitfinex_cents] = split_by_weights(unallocated_cents, &configured)?;
+            return Some(AllocationAdjustment {
+                okex_cents,
+                bitfinex_cents,
+            });
+        }
+        let targets = split_by_weights(total, &healthy)?;
+
+        let okex_cents = targets[0] - current[0];
+        let bitfinex_cents = targets[1] - current[1];
+        if okex_cents.is_zero() && bitfinex_cents.is_zero() {
+            return None;
+        }
+        if unallocated_cents.is_zero()
+            && okex_cents.abs() < self.rebalance_threshold_cents
+            && bitfinex_cents.abs() < self.rebalance_threshold_cents
+        {
+            return None;
+        }
+
+        Some(AllocationAdjustment {
+            okex_cents,
+            bitfinex_cents,
+        })
+    }
+}
+
+fn split_by_weights<const N: usize>(
+    total: Decimal,
+    weights: &[Decimal; N],
+) -> Option<[Decimal; N]> {
+    let weight_sum: Decimal = weights.iter().sum();
+    if weight_sum.is_zero() {
+        return None;
+    }
+    let last = weights.iter().rposition(|w| !w.is_zero())?;
+    let mut ret = [Decimal::ZERO; N];
+    let mut remaining = total;
+    for (idx, weight) in weights.iter().enumerate() {
+        if idx == last {
+            ret[idx] = remaining;
+            break;
+        }
+        let share = (total * weight / weight_sum).round_dp(0);
+        ret[idx] = share;
+        remaining -= share;
+    }
+    Some(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn policy(okex_weight: Decimal, bitfinex_weight: Decimal) -> AllocationPolicy {
+        AllocationPolicy {
+            okex_weight,
+            bitfinex_weight,
+            rebalance_threshold_cents: dec!(10_000),
+        }
+    }
+
+    fn balances(
+        unallocated_usd: Decimal,
+        okex_cents: Decimal,
+        bitfinex_cents: Decimal,
+    ) -> LiabilityAllocations {
+        LiabilityAllocations {
+            unallocated_usd,
+            okex_allocation: okex_cents.try_into().unwrap(),
+            bitfinex_allocation: bitfinex_cents.try_into().unwrap(),
+            total_liability: (unallocated_usd * CENTS_PER_USD + okex_cents + bitfinex_cents)
+                .try_into()
+                .unwrap(),
+        }
+    }
+
+    #[test]
+    fn splits_unallocated_by_weight() {
+        let adjustment = policy(dec!(0.7), dec!(0.3)).determine_adjustment(
+            &balances(dec!(1000), dec!(0), dec!(0)),
+            true,
+            true,
+        );
+        assert_eq!(
+            adjustment,
+            Some(AllocationAdjustment {
+                okex_cents: dec!(70_000),
+                bitfinex_cents: dec!(30_000),
+            })
+        );
+    }
+
+    #[test]
+    fn rounding_remainder_goes_to_last_exchange() {
+        let adjustment = policy(dec!(1), dec!(2))
+            .determine_adjustment(&balances(dec!(1), dec!(0), dec!(0)), true, true)
+            .unwrap();
+        assert_eq!(adjustment.okex_cents, dec!(33));
+        assert_eq!(adjustment.bitfinex_cents, dec!(67));
+    }
+
+    #[test]
+    fn rebalances_existing_allocation() {
+        let adjustment = policy(dec!(0.5), dec!(0.5)).determine_adjustment(
+            &balances(dec!(0), dec!(100_000), dec!(0)),
+            true,
+            true,
+        );
+        assert_eq!(
+            adjustment,
+            Some(AllocationAdjustment {
+                okex_cents: dec!(-50_000),
+                bitfinex_cents: dec!(50_000),
+            })
+        );
+    }
+
+    #[test]
+    fn ignores_drift_below_threshold() {
+        let adjustment = policy(dec!(0.5), dec!(0.5)).determine_adjustment(
+            &balances(dec!(0), dec!(52_000), dec!(48_000)),
+            true,
+            true,
+        );
+        assert_eq!(adjustment, None);
+    }
+
+    #[test]
+    fn moves_allocation_away_from_unhealthy_exchange() {
+        let adjustment = policy(dec!(0.5), dec!(0.5)).determine_adjustment(
+            &balances(dec!(10), dec!(50_000), dec!(50_000)),
+            true,
+            false,
+        );
+        assert_eq!(
+            adjustment,
+            Some(AllocationAdjustment {
+                okex_cents: dec!(51_000),
+                bitfinex_cents: dec!(-50_000),
+            })
+        );
+    }
+
+    #[test]
+    fn moves_allocation_away_from_disabled_exchange() {
+        let adjustment = policy(dec!(1), dec!(0)).determine_adjustment(
+            &balances(dec!(0), dec!(0), dec!(50_000)),
+            true,
+            true,
+        );
+        assert_eq!(
+            adjustment,
+            Some(AllocationAdjustment {
+                okex_cents: dec!(50_000),
+                bitfinex_cents: dec!(-50_000),
+            })
+        );
+    }
+
+    #[test]
+    fn keeps_allocation_when_no_exchange_is_healthy() {
+        let policy = policy(dec!(0.5), dec!(0.5));
+        assert_eq!(
+            policy.determine_adjustment(&balances(dec!(0), dec!(0), dec!(100_000)), false, false),
+            None
+        );
+        assert_eq!(
+            policy.determine_adjustment(&balances(dec!(10), dec!(0), dec!(100_000)), false, false),
+            Some(AllocationAdjustment {
+                okex_cents: dec!(500),
+                bitfinex_cents: dec!(500),
+            })
+        );
+    }
+
+    #[test]
+    fn releases_liability_on_user_sells() {
+        let adjustment = policy(dec!(0.5), dec!(0.5)).determine_adjustment(
+            &balances(dec!(-100), dec!(50_000), dec!(50_000)),
+            true,
+            true,
+        );
+        assert_eq!(
+            adjustment,
+            Some(AllocationAdjustment {
+                okex_cents: dec!(-5_000),
+                bitfinex_cents: dec!(-5_000),
+            })
+        );
+    }
+}
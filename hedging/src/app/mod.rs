@@ -1,13 +1,17 @@
 use bria_client::{BriaClient, BriaClientConfig};
 use futures::stream::StreamExt;
-use rust_decimal::Decimal;
 use sqlxmq::JobRunnerHandle;
 use tracing::instrument;
 
+use std::sync::Arc;
+
 use galoy_client::*;
 use shared::{health::HealthCheckTrigger, payload::PriceStreamPayload, pubsub::memory};
 
-use crate::{config::*, error::*, okex::*};
+use crate::{
+    admin::*, allocation::*, bitfinex::*, config::*, control::*, decisions::*, error::*,
+    liability_watermark::*, lightning::*, okex::*, risk::*,
+};
 
 pub struct HedgingApp {
     _job_runner_handle: JobRunnerHandle,
@@ -20,41 +24,78 @@ impl HedgingApp {
         pool: sqlx::PgPool,
         health_check_trigger: HealthCheckTrigger,
         HedgingAppConfig {
-            health: health_cfg, ..
+            health: health_cfg,
+            allocation: allocation_cfg,
+            risk: risk_cfg,
+            admin: admin_cfg,
         }: HedgingAppConfig,
-        okex_config: OkexConfig,
+        exchanges: ExchangesConfig,
         galoy_client_cfg: GaloyClientConfig,
         bria_client_cfg: BriaClientConfig,
         price_receiver: memory::Subscriber<PriceStreamPayload>,
         ledger: ledger::Ledger,
     ) -> Result<Self, HedgingError> {
         let (mut jobs, mut channels) = (Vec::new(), Vec::new());
-        OkexEngine::register_jobs(&mut jobs, &mut channels);
+        if exchanges.okex.is_some() {
+            OkexEngine::register_jobs(&mut jobs, &mut channels);
+        }
+        if exchanges.bitfinex.is_some() {
+            BitfinexEngine::register_jobs(&mut jobs, &mut channels);
+        }
         let mut job_registry = sqlxmq::JobRegistry::new(&jobs);
 
+        let allocation_policy = AllocationPolicy::new(&exchanges, &allocation_cfg);
+        let exchange_health = ExchangeHealth::new(pool.clone());
+        let liability_watermark =
+            LiabilityWatermarkCheck::new(pool.clone(), health_cfg.max_liability_watermark_age)
+                .allow_missing(health_cfg.allow_missing_liability_watermark);
+
         job_registry.set_context(ledger.clone());
-        job_registry.set_context(
-            shared::tracing::record_error(tracing::Level::ERROR, || async move {
-                GaloyClient::connect(galoy_client_cfg).await
-            })
-            .await?,
-        );
-        job_registry.set_context(
-            shared::tracing::record_error(tracing::Level::ERROR, || async move {
-                BriaClient::connect(bria_client_cfg).await
-            })
-            .await?,
-        );
+        job_registry.set_context(exchange_health.clone());
+        let risk_guard = RiskGuard::new(pool.clone(), risk_cfg);
 
-        let okex_engine = OkexEngine::run(
-            pool.clone(),
-            okex_config,
-            ledger.clone(),
-            price_receiver.resubscribe(),
-        )
+        job_registry.set_context(liability_watermark.clone());
+        job_registry.set_context(risk_guard.clone());
+        let process_control = ProcessControl::new(pool.clone());
+        job_registry.set_context(process_control.clone());
+        job_registry.set_context(HedgingDecisions::new(pool.clone()));
+        let galoy = shared::tracing::record_error(tracing::Level::ERROR, || async move {
+            GaloyClient::connect(galoy_client_cfg).await
+        })
         .await?;
+        job_registry.set_context(galoy.clone());
+        let bria = shared::tracing::record_error(tracing::Level::ERROR, || async move {
+            BriaClient::connect(bria_client_cfg).await
+        })
+        .await?;
+        job_registry.set_context(bria.clone());
 
-        okex_engine.add_context_to_job_registry(&mut job_registry);
+        let mut okex_engine = None;
+        if let Some(okex_cfg) = exchanges.okex {
+            let lightning_payer =
+                okex_cfg.config.lightning.enabled.then(|| {
+                    Arc::new(GaloyLightningPayer::new(galoy.clone())) as SharedLightningPayer
+                });
+            let engine = OkexEngine::run(
+                pool.clone(),
+                okex_cfg.config,
+                ledger.clone(),
+                bria,
+                lightning_payer,
+                price_receiver.resubscribe(),
+            )
+            .await?;
+            engine.add_context_to_job_registry(&mut job_registry);
+            okex_engine = Some(engine);
+        }
+
+        let mut bitfinex_engine = None;
+        if let Some(bitfinex_cfg) = exchanges.bitfinex {
+            let engine =
+                BitfinexEngine::run(pool.clone(), bitfinex_cfg.config, ledger.clone()).await?;
+            engine.add_context_to_job_registry(&mut job_registry);
+            bitfinex_engine = Some(engine);
+        }
 
         let job_runner_handle = job_registry
             .runner(&pool)
@@ -62,11 +103,28 @@ impl HedgingApp {
             .run()
             .await?;
 
-        let _ = Self::spawn_global_liability_listener(pool.clone(), ledger).await;
-        Self::spawn_health_checker(health_check_trigger, health_cfg, price_receiver).await;
+        let _ = Self::spawn_global_liability_listener(
+            pool.clone(),
+            ledger.clone(),
+            allocation_policy,
+            exchange_health,
+            risk_guard.clone(),
+            allocation_cfg,
+        )
+        .await;
+        let admin = HedgingAdmin::new(ledger, risk_guard, process_control, okex_engine.clone());
+        tokio::spawn(Self::spawn_health_checker(
+            health_check_trigger,
+            health_cfg,
+            price_receiver,
+            liability_watermark,
+            okex_engine,
+            bitfinex_engine,
+        ));
         let app = HedgingApp {
             _job_runner_handle: job_runner_handle,
         };
+        crate::admin::start(admin_cfg, admin).await?;
         Ok(app)
     }
 
@@ -74,12 +132,23 @@ impl HedgingApp {
         mut health_check_trigger: HealthCheckTrigger,
         health_cfg: HedgingAppHealthConfig,
         price_sub: memory::Subscriber<PriceStreamPayload>,
+        liability_watermark: LiabilityWatermarkCheck,
+        okex_engine: Option<Arc<OkexEngine>>,
+        bitfinex_engine: Option<Arc<BitfinexEngine>>,
     ) {
         while let Some(check) = health_check_trigger.next().await {
             match price_sub
                 .healthy(health_cfg.unhealthy_msg_interval_price)
                 .await
-            {
+                .and(liability_watermark.healthy().await)
+                .and(match okex_engine {
+                    Some(ref engine) => engine.healthy().await,
+                    None => Ok(()),
+                })
+                .and(match bitfinex_engine {
+                    Some(ref engine) => engine.healthy().await,
+                    None => Ok(()),
+                }) {
                 Err(e) => {
                     let _ = check.send(Err(e));
                 }
@@ -93,19 +162,45 @@ impl HedgingApp {
     async fn spawn_global_liability_listener(
         pool: sqlx::PgPool,
         ledger: ledger::Ledger,
+        policy: AllocationPolicy,
+        exchange_health: ExchangeHealth,
+        risk_guard: RiskGuard,
+        allocation_cfg: AllocationConfig,
     ) -> Result<(), HedgingError> {
         let mut events = ledger.usd_omnibus_balance_events().await?;
         tokio::spawn(async move {
+            let mut rebalance_interval = tokio::time::interval(allocation_cfg.rebalance_interval);
             loop {
-                match events.recv().await {
-                    Ok(received) => {
-                        if let ledger::LedgerEventData::BalanceUpdated(_data) = received.data {
-                            let _ = adjust_exchange_allocation(&pool, &ledger).await;
+                tokio::select! {
+                    received = events.recv() => match received {
+                        Ok(received) => {
+                            if let ledger::LedgerEventData::BalanceUpdated(_data) = received.data {
+                                let _ = adjust_exchange_allocation(
+                                    &pool,
+                                    &ledger,
+                                    &policy,
+                                    &exchange_health,
+                                    &risk_guard,
+                                    &allocation_cfg,
+                                )
+                                .await;
+                            }
                         }
-                    }
-                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => (),
-                    _ => {
-                        break;
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => (),
+                        _ => {
+                            break;
+                        }
+                    },
+                    _ = rebalance_interval.tick() => {
+                        let _ = adjust_exchange_allocation(
+                            &pool,
+                            &ledger,
+                            &policy,
+                            &exchange_health,
+                            &risk_guard,
+                            &allocation_cfg,
+                        )
+                        .await;
                     }
                 }
             }
@@ -117,14 +212,31 @@ impl HedgingApp {
 #[instrument(
     name = "hedging.adjust_exchange_allocation",
     skip_all,
-    fields(execute_adjustment, unallocated_usd, okex, bitfinex, omnibus),
+    fields(
+        execute_adjustment,
+        unallocated_usd,
+        okex,
+        bitfinex,
+        omnibus,
+        okex_healthy,
+        bitfinex_healthy,
+        okex_adjustment,
+        bitfinex_adjustment
+    ),
     err
 )]
 async fn adjust_exchange_allocation(
     pool: &sqlx::PgPool,
     ledger: &ledger::Ledger,
-) -> Result<(), ledger::LedgerError> {
+    policy: &AllocationPolicy,
+    exchange_health: &ExchangeHealth,
+    risk_guard: &RiskGuard,
+    allocation_cfg: &AllocationConfig,
+) -> Result<(), HedgingError> {
     let liability_balances = ledger.balances().usd_liability_balances().await?;
+    risk_guard
+        .record_liability(liability_balances.total_liability.into())
+        .await?;
     let span = tracing::Span::current();
     span.record(
         "unallocated_usd",
@@ -143,20 +255,43 @@ async fn adjust_exchange_allocation(
         &tracing::field::display(liability_balances.total_liability),
     );
     span.record("execute_adjustment", false);
-    let tx = pool.begin().await?;
-    let unallocated_usd = liability_balances.unallocated_usd;
-    if unallocated_usd != Decimal::ZERO {
+
+    let okex_healthy = exchange_health
+        .is_healthy(
+            shared::payload::OKEX_EXCHANGE_ID,
+            allocation_cfg.unhealthy_exchange_after,
+        )
+        .await?;
+    let bitfinex_healthy = exchange_health
+        .is_healthy(
+            shared::payload::BITFINEX_EXCHANGE_ID,
+            allocation_cfg.unhealthy_exchange_after,
+        )
+        .await?;
+    span.record("okex_healthy", okex_healthy);
+    span.record("bitfinex_healthy", bitfinex_healthy);
+
+    if let Some(adjustment) =
+        policy.determine_adjustment(&liability_balances, okex_healthy, bitfinex_healthy)
+    {
         span.record("execute_adjustment", true);
+        span.record(
+            "okex_adjustment",
+            tracing::field::display(adjustment.okex_cents),
+        );
+        span.record(
+            "bitfinex_adjustment",
+            tracing::field::display(adjustment.bitfinex_cents),
+        );
         let adjustment_params = ledger::AdjustExchangeAllocationParams {
-            okex_allocation_adjustment_usd_cents_amount: unallocated_usd
-                * ledger::constants::CENTS_PER_USD,
-            bitfinex_allocation_adjustment_usd_cents_amount: Decimal::ZERO,
+            okex_allocation_adjustment_usd_cents_amount: adjustment.okex_cents,
+            bitfinex_allocation_adjustment_usd_cents_amount: adjustment.bitfinex_cents,
             meta: ledger::AdjustExchangeAllocationMeta {
                 timestamp: chrono::Utc::now(),
             },
         };
         ledger
-            .adjust_exchange_allocation(tx, adjustment_params)
+            .adjust_exchange_allocation(pool.begin().await?, adjustment_params)
             .await?;
     }
     Ok(())
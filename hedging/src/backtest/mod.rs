@@ -0,0 +1,325 @@
+mod series;
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use okex_client::{ClientOrderId, ClientTransferId};
+use shared::payload::SyntheticCentLiability;
+
+use crate::{error::HedgingError, okex::*, venue::*};
+
+pub use series::*;
+
+/// adjust_hedge and adjust_funding trigger each other through position and balance
+/// updates, so each sample gets a few rounds to settle
+const MAX_ROUNDS_PER_SAMPLE: usize = 5;
+const BACKTEST_WITHDRAWAL_ADDRESS: &str = "backtest";
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BacktestReport {
+    pub samples: usize,
+    pub orders: usize,
+    pub transfers: usize,
+    pub deposits: usize,
+    pub withdrawals: usize,
+    pub rejected: usize,
+    pub deposited_btc: Decimal,
+    pub withdrawn_btc: Decimal,
+    pub trading_fees_btc: Decimal,
+    pub withdrawal_fees_btc: Decimal,
+    pub funding_paid_btc: Decimal,
+    pub max_drift_usd: Decimal,
+    pub max_drift_at: Option<String>,
+    pub worst_leverage: Decimal,
+    pub worst_leverage_at: Option<String>,
+    pub collateral_exhausted: bool,
+}
+
+impl BacktestReport {
+    fn observe_drift(&mut self, liability_usd: Decimal, position: &VenuePosition, at: &str) {
+        let drift = (liability_usd - position.usd_cents.abs() / Decimal::ONE_HUNDRED).abs();
+        if drift > self.max_drift_usd {
+            self.max_drift_usd = drift;
+            self.max_drift_at = Some(at.to_string());
+        }
+    }
+
+    fn observe_leverage(&mut self, position: &VenuePosition, trading: &VenueBalance, at: &str) {
+        if position.usd_cents.is_zero() {
+            return;
+        }
+        if trading.total_amt_in_btc <= Decimal::ZERO {
+            self.collateral_exhausted = true;
+            return;
+        }
+        let leverage =
+            position.usd_cents.abs() / position.last_price_in_usd_cents / trading.total_amt_in_btc;
+        if leverage > self.worst_leverage {
+            self.worst_leverage = leverage.round_dp(4);
+            self.worst_leverage_at = Some(at.to_string());
+        }
+    }
+
+    fn settled(&mut self, result: Result<(), HedgingError>) -> Result<bool, HedgingError> {
+        match result {
+            Ok(()) => Ok(true),
+            Err(HedgingError::VenueRejected(_)) => {
+                self.rejected += 1;
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl std::fmt::Display for BacktestReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let at = |at: &Option<String>| at.as_deref().map(|at| format!(" at {at}"));
+        writeln!(f, "samples:             {}", self.samples)?;
+        writeln!(f, "orders:              {}", self.orders)?;
+        writeln!(f, "transfers:           {}", self.transfers)?;
+        writeln!(
+            f,
+            "deposits:            {} ({} BTC)",
+            self.deposits, self.deposited_btc
+        )?;
+        writeln!(
+            f,
+            "withdrawals:         {} ({} BTC)",
+            self.withdrawals, self.withdrawn_btc
+        )?;
+        writeln!(f, "rejected:            {}", self.rejected)?;
+        writeln!(f, "trading fees:        {} BTC", self.trading_fees_btc)?;
+        writeln!(f, "withdrawal fees:     {} BTC", self.withdrawal_fees_btc)?;
+        writeln!(f, "funding paid:        {} BTC", self.funding_paid_btc)?;
+        writeln!(
+            f,
+            "max drift:           {} USD{}",
+            self.max_drift_usd,
+            at(&self.max_drift_at).unwrap_or_default()
+        )?;
+        write!(
+            f,
+            "worst leverage:      {}{}",
+            self.worst_leverage,
+            at(&self.worst_leverage_at).unwrap_or_default()
+        )?;
+        if self.collateral_exhausted {
+            write!(f, "\ncollateral was exhausted at least once")?;
+        }
+        Ok(())
+    }
+}
+
+/// Replays a liability and price series through the okex hedging and funding policies
+/// against a `PaperVenue`. Orders fill at market, deposits and withdrawals settle on the spot.
+pub struct Backtest {
+    hedging_adjustment: HedgingAdjustment,
+    funding_adjustment: FundingAdjustment,
+    leverage: Decimal,
+}
+
+impl Backtest {
+    pub fn new(hedging: OkexHedgingConfig, funding: OkexFundingConfig) -> Self {
+        Self {
+            leverage: funding.high_bound_ratio_leverage,
+            funding_adjustment: FundingAdjustment::new(funding, hedging.clone()),
+            hedging_adjustment: HedgingAdjustment::new(hedging),
+        }
+    }
+
+    pub async fn run(&self, samples: &[BacktestSample]) -> Result<BacktestReport, HedgingError> {
+        let mut report = BacktestReport::default();
+        let first_price = match samples.first() {
+            Some(sample) => sample.btc_price_usd * Decimal::ONE_HUNDRED,
+            None => return Ok(report),
+        };
+        let venue = PaperVenue::new(first_price, self.leverage);
+        for (idx, sample) in samples.iter().enumerate() {
+            let at = sample
+                .timestamp
+                .map(|timestamp| timestamp.to_rfc3339())
+                .unwrap_or_else(|| format!("sample {}", idx + 1));
+            venue.set_last_price(sample.btc_price_usd * Decimal::ONE_HUNDRED);
+            if let Some(rate) = sample.funding_rate {
+                report.funding_paid_btc += venue.apply_funding_rate(rate);
+            }
+            report.observe_leverage(
+                &venue.position().await?,
+                &venue.trading_balance().await?,
+                &at,
+            );
+
+            let liability =
+                SyntheticCentLiability::try_from(sample.liability_usd * Decimal::ONE_HUNDRED)
+                    .map_err(|e| HedgingError::InvalidBacktestSeries(e.to_string()))?;
+            for _ in 0..MAX_ROUNDS_PER_SAMPLE {
+                let funded = self.adjust_funding(&venue, liability, &mut report).await?;
+                let hedged = self.adjust_hedge(&venue, liability, &mut report).await?;
+                if !funded && !hedged {
+                    break;
+                }
+            }
+
+            let position = venue.position().await?;
+            report.observe_drift(sample.liability_usd, &position, &at);
+            report.observe_leverage(&position, &venue.trading_balance().await?, &at);
+            report.samples += 1;
+        }
+        report.trading_fees_btc = venue.trading_fees_paid().round_dp(8);
+        report.funding_paid_btc = report.funding_paid_btc.round_dp(8);
+        Ok(report)
+    }
+
+    /// Returns whether an order was filled
+    async fn adjust_hedge(
+        &self,
+        venue: &PaperVenue,
+        liability: SyntheticCentLiability,
+        report: &mut BacktestReport,
+    ) -> Result<bool, HedgingError> {
+        let action = self.hedging_adjustment.determine_action(
+            liability,
+            venue.position().await?.usd_cents.into(),
+            venue.contract_size_cents().await?,
+        );
+        let result = match action {
+            OkexHedgeAdjustment::DoNothing => return Ok(false),
+            OkexHedgeAdjustment::ClosePosition => venue.close_position(ClientOrderId::new()).await,
+            OkexHedgeAdjustment::Sell(ref contracts) => {
+                venue
+                    .place_market_order(ClientOrderId::new(), OrderSide::Sell, contracts)
+                    .await
+            }
+            OkexHedgeAdjustment::Buy(ref contracts) => {
+                venue
+                    .place_market_order(ClientOrderId::new(), OrderSide::Buy, contracts)
+                    .await
+            }
+        };
+        let filled = report.settled(result)?;
+        report.orders += usize::from(filled);
+        Ok(filled)
+    }
+
+    /// Returns whether funds were moved
+    async fn adjust_funding(
+        &self,
+        venue: &PaperVenue,
+        liability: SyntheticCentLiability,
+        report: &mut BacktestReport,
+    ) -> Result<bool, HedgingError> {
+        let position = venue.position().await?;
+        let action = self.funding_adjustment.determine_action(
+            liability,
+            position.usd_cents.into(),
+            venue.trading_balance().await?.total_amt_in_btc,
+            position.last_price_in_usd_cents,
+            venue.funding_balance().await?.total_amt_in_btc,
+            venue.contract_size_cents().await?,
+            venue.margin_currency(&position.instrument_id)?,
+        );
+        let moved = match action {
+            OkexFundingAdjustment::DoNothing => return Ok(false),
+            OkexFundingAdjustment::TransferTradingToFunding(amount) => {
+                let result = venue
+                    .transfer_trading_to_funding(ClientTransferId::new(), amount)
+                    .await;
+                let moved = report.settled(result)?;
+                report.transfers += usize::from(moved);
+                moved
+            }
+            OkexFundingAdjustment::TransferFundingToTrading(amount) => {
+                let result = venue
+                    .transfer_funding_to_trading(ClientTransferId::new(), amount)
+                    .await;
+                let moved = report.settled(result)?;
+                report.transfers += usize::from(moved);
+                moved
+            }
+            OkexFundingAdjustment::OnchainDeposit(amount) => {
+                venue.credit_deposit(amount);
+                report.deposits += 1;
+                report.deposited_btc += amount;
+                true
+            }
+            OkexFundingAdjustment::OnchainWithdraw(amount) => {
+                let fee = venue.withdrawal_fee().await?;
+                let result = venue
+                    .withdraw_btc_onchain(
+                        ClientTransferId::new(),
+                        amount,
+                        fee,
+                        BACKTEST_WITHDRAWAL_ADDRESS.to_string(),
+                    )
+                    .await;
+                let moved = report.settled(result)?;
+                if moved {
+                    report.withdrawals += 1;
+                    report.withdrawn_btc += amount;
+                    report.withdrawal_fees_btc += fee;
+                }
+                moved
+            }
+        };
+        Ok(moved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn sample(liability_usd: Decimal, btc_price_usd: Decimal) -> BacktestSample {
+        BacktestSample {
+            timestamp: None,
+            liability_usd,
+            btc_price_usd,
+            funding_rate: None,
+        }
+    }
+
+    fn backtest() -> Backtest {
+        Backtest::new(OkexHedgingConfig::default(), OkexFundingConfig::default())
+    }
+
+    #[tokio::test]
+    async fn funds_and_hedges_a_growing_liability() {
+        let report = backtest()
+            .run(&[
+                sample(dec!(5_000), dec!(40_000)),
+                sample(dec!(5_000), dec!(41_000)),
+                sample(dec!(20_000), dec!(41_000)),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(report.samples, 3);
+        assert!(report.deposits >= 1);
+        assert!(report.transfers >= 1);
+        assert!(report.orders >= 2);
+        assert!(report.trading_fees_btc > Decimal::ZERO);
+        assert!(report.max_drift_usd < dec!(20_000) * dec!(0.05));
+        assert!(report.worst_leverage > Decimal::ZERO);
+        assert!(!report.collateral_exhausted);
+    }
+
+    #[tokio::test]
+    async fn shorts_receive_positive_funding() {
+        let mut samples = vec![sample(dec!(10_000), dec!(40_000))];
+        samples.push(BacktestSample {
+            funding_rate: Some(dec!(0.0001)),
+            ..sample(dec!(10_000), dec!(40_000))
+        });
+        let report = backtest().run(&samples).await.unwrap();
+        assert!(report.funding_paid_btc < Decimal::ZERO);
+    }
+
+    #[tokio::test]
+    async fn empty_series() {
+        let report = backtest().run(&[]).await.unwrap();
+        assert_eq!(report.samples, 0);
+        assert_eq!(report.orders, 0);
+    }
+}
@@ -0,0 +1,151 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use std::collections::HashMap;
+
+use crate::error::HedgingError;
+
+/// One step of a replayed series. The funding rate applies to the position held
+/// when the step is reached, a positive rate means longs pay shorts.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BacktestSample {
+    #[serde(default)]
+    pub timestamp: Option<DateTime<Utc>>,
+    pub liability_usd: Decimal,
+    pub btc_price_usd: Decimal,
+    #[serde(default)]
+    pub funding_rate: Option<Decimal>,
+}
+
+impl BacktestSample {
+    fn validate(self) -> Result<Self, HedgingError> {
+        if self.liability_usd.is_sign_negative() {
+            return Err(HedgingError::InvalidBacktestSeries(format!(
+                "liability_usd must not be negative, got {}",
+                self.liability_usd
+            )));
+        }
+        if self.btc_price_usd <= Decimal::ZERO {
+            return Err(HedgingError::InvalidBacktestSeries(format!(
+                "btc_price_usd must be positive, got {}",
+                self.btc_price_usd
+            )));
+        }
+        Ok(self)
+    }
+}
+
+/// Reads a JSON array of samples
+pub fn parse_json_series(contents: &str) -> Result<Vec<BacktestSample>, HedgingError> {
+    let samples: Vec<BacktestSample> = serde_json::from_str(contents)?;
+    samples.into_iter().map(BacktestSample::validate).collect()
+}
+
+/// Reads comma separated samples with a header row naming the columns.
+/// `liability_usd` and `btc_price_usd` are required, `timestamp` and `funding_rate` optional.
+pub fn parse_csv_series(contents: &str) -> Result<Vec<BacktestSample>, HedgingError> {
+    let mut lines = contents
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty());
+    let header: HashMap<&str, usize> = match lines.next() {
+        Some((_, header)) => header
+            .split(',')
+            .enumerate()
+            .map(|(idx, column)| (column.trim(), idx))
+            .collect(),
+        None => return Ok(Vec::new()),
+    };
+    let column = |name: &str| {
+        header
+            .get(name)
+            .copied()
+            .ok_or_else(|| HedgingError::InvalidBacktestSeries(format!("missing column '{name}'")))
+    };
+    let liability_idx = column("liability_usd")?;
+    let price_idx = column("btc_price_usd")?;
+    let timestamp_idx = header.get("timestamp").copied();
+    let funding_rate_idx = header.get("funding_rate").copied();
+
+    lines
+        .map(|(line_no, line)| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let field = |idx: Option<usize>| {
+                idx.and_then(|idx| fields.get(idx).copied())
+                    .filter(|value| !value.is_empty())
+            };
+            let invalid = |name: &str| {
+                HedgingError::InvalidBacktestSeries(format!("line {line_no}: invalid {name}"))
+            };
+            BacktestSample {
+                timestamp: field(timestamp_idx)
+                    .map(|value| value.parse().map_err(|_| invalid("timestamp")))
+                    .transpose()?,
+                liability_usd: field(Some(liability_idx))
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(|| invalid("liability_usd"))?,
+                btc_price_usd: field(Some(price_idx))
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(|| invalid("btc_price_usd"))?,
+                funding_rate: field(funding_rate_idx)
+                    .map(|value| value.parse().map_err(|_| invalid("funding_rate")))
+                    .transpose()?,
+            }
+            .validate()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn csv_columns_are_matched_by_name() {
+        let samples = parse_csv_series(
+            "btc_price_usd,timestamp,liability_usd,funding_rate
+             40000,2024-05-01T00:00:00Z,1000,0.0001
+
+             41000,,1200,",
+        )
+        .unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(
+            samples[0],
+            BacktestSample {
+                timestamp: Some("2024-05-01T00:00:00Z".parse().unwrap()),
+                liability_usd: dec!(1000),
+                btc_price_usd: dec!(40000),
+                funding_rate: Some(dec!(0.0001)),
+            }
+        );
+        assert_eq!(samples[1].timestamp, None);
+        assert_eq!(samples[1].funding_rate, None);
+    }
+
+    #[test]
+    fn csv_errors_name_the_line() {
+        let err = parse_csv_series("liability_usd,btc_price_usd\n1000,40000\n1000,abc")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("line 3: invalid btc_price_usd"));
+        assert!(parse_csv_series("liability_usd\n1000").is_err());
+        assert!(parse_csv_series("liability_usd,btc_price_usd\n-1,40000").is_err());
+    }
+
+    #[test]
+    fn json_series() {
+        let samples = parse_json_series(
+            r#"[{ "liability_usd": 1000, "btc_price_usd": "40000.5" },
+                { "liability_usd": 0, "btc_price_usd": 39000, "funding_rate": -0.0002 }]"#,
+        )
+        .unwrap();
+        assert_eq!(samples[0].btc_price_usd, dec!(40000.5));
+        assert_eq!(samples[1].funding_rate, Some(dec!(-0.0002)));
+        assert!(parse_json_series(r#"[{ "liability_usd": 1000, "btc_price_usd": 0 }]"#).is_err());
+    }
+}
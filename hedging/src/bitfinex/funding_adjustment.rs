@@ -0,0 +1,341 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use shared::{payload::BITFINEX_EXCHANGE_ID, pubsub::CorrelationId};
+
+pub use shared::payload::{SyntheticCentExposure, SyntheticCentLiability};
+
+use crate::{bitfinex::*, control::HedgingProcess, decisions::HedgingDecision};
+
+const SATS_PER_BTC: Decimal = dec!(100_000_000);
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum BitfinexFundingAdjustment {
+    DoNothing,
+    TransferTradingToFunding(Decimal),
+    TransferFundingToTrading(Decimal),
+    OnchainDeposit(Decimal),
+    OnchainWithdraw(Decimal),
+}
+impl std::fmt::Display for BitfinexFundingAdjustment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BitfinexFundingAdjustment::DoNothing => write!(f, "DoNothing"),
+            BitfinexFundingAdjustment::TransferTradingToFunding(amount_in_btc) => {
+                write!(f, "TransferTradingToFunding({amount_in_btc})")
+            }
+            BitfinexFundingAdjustment::TransferFundingToTrading(amount_in_btc) => {
+                write!(f, "TransferFundingToTrading({amount_in_btc})")
+            }
+            BitfinexFundingAdjustment::OnchainDeposit(amount_in_btc) => {
+                write!(f, "OnchainDeposit({amount_in_btc})")
+            }
+            BitfinexFundingAdjustment::OnchainWithdraw(amount_in_btc) => {
+                write!(f, "OnchainWithdraw({amount_in_btc})")
+            }
+        }
+    }
+}
+impl BitfinexFundingAdjustment {
+    pub fn action_required(&self) -> bool {
+        !matches!(*self, Self::DoNothing)
+    }
+
+    pub fn action_type(&self) -> &'static str {
+        match *self {
+            Self::DoNothing => "do-nothing",
+            Self::TransferTradingToFunding(_) => "transfer-trading-to-funding",
+            Self::TransferFundingToTrading(_) => "transfer-funding-to-trading",
+            Self::OnchainDeposit(_) => "deposit",
+            Self::OnchainWithdraw(_) => "withdraw",
+        }
+    }
+
+    pub fn size(&self) -> Option<Decimal> {
+        match *self {
+            Self::TransferTradingToFunding(size)
+            | Self::TransferFundingToTrading(size)
+            | Self::OnchainDeposit(size)
+            | Self::OnchainWithdraw(size) => Some(size),
+            _ => None,
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        "btc"
+    }
+}
+
+fn round_btc(amount_in_btc: Decimal) -> Decimal {
+    let amount_in_sats = amount_in_btc * SATS_PER_BTC;
+    amount_in_sats.round() / SATS_PER_BTC
+}
+
+fn floor_btc(amount_in_btc: Decimal) -> Decimal {
+    let amount_in_sats = amount_in_btc * SATS_PER_BTC;
+    amount_in_sats.floor() / SATS_PER_BTC
+}
+
+#[derive(Debug, Clone)]
+pub struct FundingAdjustment {
+    config: BitfinexFundingConfig,
+    hedging_config: BitfinexHedgingConfig,
+}
+
+impl FundingAdjustment {
+    pub fn new(config: BitfinexFundingConfig, hedging_config: BitfinexHedgingConfig) -> Self {
+        Self {
+            config,
+            hedging_config,
+        }
+    }
+
+    /// Determines the action and keeps what it was based on for the decision log
+    #[allow(clippy::too_many_arguments)]
+    pub fn evaluate(
+        &self,
+        evaluated_in: &'static str,
+        correlation_id: CorrelationId,
+        abs_liability_in_cents: SyntheticCentLiability,
+        signed_exposure_in_cents: SyntheticCentExposure,
+        total_collateral_in_btc: Decimal,
+        btc_price_in_cents: Decimal,
+        funding_btc_total_balance: Decimal,
+    ) -> (BitfinexFundingAdjustment, HedgingDecision) {
+        let inputs = serde_json::json!({
+            "abs_liability_in_cents": Decimal::from(abs_liability_in_cents),
+            "signed_exposure_in_cents": Decimal::from(signed_exposure_in_cents),
+            "total_collateral_in_btc": total_collateral_in_btc,
+            "btc_price_in_cents": btc_price_in_cents,
+            "funding_btc_total_balance": funding_btc_total_balance,
+        });
+        let action = self.determine_action(
+            abs_liability_in_cents,
+            signed_exposure_in_cents,
+            total_collateral_in_btc,
+            btc_price_in_cents,
+            funding_btc_total_balance,
+        );
+        let decision = HedgingDecision {
+            correlation_id,
+            exchange: BITFINEX_EXCHANGE_ID,
+            process: HedgingProcess::Funding,
+            evaluated_in,
+            inputs,
+            config: serde_json::json!({
+                "funding": self.config,
+                "hedging": self.hedging_config,
+            }),
+            action: action.to_string(),
+            action_required: action.action_required(),
+        };
+        (action, decision)
+    }
+
+    pub fn determine_action(
+        &self,
+        abs_liability_in_cents: SyntheticCentLiability,
+        signed_exposure_in_cents: SyntheticCentExposure,
+        total_collateral_in_btc: Decimal,
+        btc_price_in_cents: Decimal,
+        funding_btc_total_balance: Decimal,
+    ) -> BitfinexFundingAdjustment {
+        if btc_price_in_cents.is_zero() {
+            return BitfinexFundingAdjustment::DoNothing;
+        }
+        let abs_liability_in_btc = Decimal::from(abs_liability_in_cents) / btc_price_in_cents;
+        let abs_exposure_in_btc =
+            Decimal::from(signed_exposure_in_cents).abs() / btc_price_in_cents;
+        let minimum_transfer_in_btc =
+            self.config.minimum_transfer_amount_cents / btc_price_in_cents;
+
+        let action = if abs_liability_in_cents
+            < self.hedging_config.minimum_liability_threshold_cents
+        {
+            if !abs_exposure_in_btc.is_zero() {
+                // Wait for the position to be closed before moving collateral out
+                BitfinexFundingAdjustment::DoNothing
+            } else if !total_collateral_in_btc.is_zero() {
+                BitfinexFundingAdjustment::TransferTradingToFunding(floor_btc(
+                    total_collateral_in_btc,
+                ))
+            } else {
+                BitfinexFundingAdjustment::OnchainWithdraw(floor_btc(funding_btc_total_balance))
+            }
+        } else {
+            let abs_target_in_btc = std::cmp::max(abs_liability_in_btc, abs_exposure_in_btc);
+            if abs_target_in_btc > total_collateral_in_btc * self.config.high_bound_ratio_leverage {
+                let new_collateral_in_btc =
+                    abs_target_in_btc / self.config.high_safebound_ratio_leverage;
+                let missing_collateral_in_btc =
+                    round_btc(new_collateral_in_btc - total_collateral_in_btc);
+                if funding_btc_total_balance > Decimal::ZERO {
+                    BitfinexFundingAdjustment::TransferFundingToTrading(std::cmp::min(
+                        funding_btc_total_balance,
+                        missing_collateral_in_btc,
+                    ))
+                } else {
+                    BitfinexFundingAdjustment::OnchainDeposit(
+                        missing_collateral_in_btc + self.config.minimum_funding_balance_btc,
+                    )
+                }
+            } else if abs_exposure_in_btc
+                < total_collateral_in_btc * self.config.low_bound_ratio_leverage
+            {
+                let new_collateral_in_btc =
+                    abs_target_in_btc / self.config.low_safebound_ratio_leverage;
+                BitfinexFundingAdjustment::TransferTradingToFunding(floor_btc(
+                    total_collateral_in_btc - new_collateral_in_btc,
+                ))
+            } else if funding_btc_total_balance
+                > self.config.minimum_funding_balance_btc / self.config.high_bound_buffer_percentage
+            {
+                BitfinexFundingAdjustment::OnchainWithdraw(floor_btc(
+                    funding_btc_total_balance - self.config.minimum_funding_balance_btc,
+                ))
+            } else {
+                BitfinexFundingAdjustment::DoNothing
+            }
+        };
+
+        match action.size() {
+            Some(size) if size < minimum_transfer_in_btc => BitfinexFundingAdjustment::DoNothing,
+            _ => action,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn funding_adjustment() -> FundingAdjustment {
+        FundingAdjustment::new(
+            BitfinexFundingConfig::default(),
+            BitfinexHedgingConfig::default(),
+        )
+    }
+
+    const PRICE_IN_CENTS: Decimal = dec!(1_000_000);
+
+    #[test]
+    fn do_nothing_when_balanced() {
+        let liability = SyntheticCentLiability::try_from(dec!(3_000_000)).unwrap();
+        let exposure = SyntheticCentExposure::from(dec!(-3_000_000));
+        let action = funding_adjustment().determine_action(
+            liability,
+            exposure,
+            dec!(1),
+            PRICE_IN_CENTS,
+            dec!(0.1),
+        );
+        assert_eq!(action, BitfinexFundingAdjustment::DoNothing);
+    }
+
+    #[test]
+    fn transfer_in_when_under_collateralized() {
+        let liability = SyntheticCentLiability::try_from(dec!(6_000_000)).unwrap();
+        let exposure = SyntheticCentExposure::from(dec!(-3_000_000));
+        let action = funding_adjustment().determine_action(
+            liability,
+            exposure,
+            dec!(1),
+            PRICE_IN_CENTS,
+            dec!(5),
+        );
+        assert_eq!(
+            action,
+            BitfinexFundingAdjustment::TransferFundingToTrading(dec!(1))
+        );
+    }
+
+    #[test]
+    fn deposit_when_funding_is_empty() {
+        let liability = SyntheticCentLiability::try_from(dec!(6_000_000)).unwrap();
+        let exposure = SyntheticCentExposure::from(dec!(0));
+        let action = funding_adjustment().determine_action(
+            liability,
+            exposure,
+            dec!(0),
+            PRICE_IN_CENTS,
+            dec!(0),
+        );
+        assert_eq!(action, BitfinexFundingAdjustment::OnchainDeposit(dec!(2.1)));
+    }
+
+    #[test]
+    fn transfer_out_when_over_collateralized() {
+        let liability = SyntheticCentLiability::try_from(dec!(3_000_000)).unwrap();
+        let exposure = SyntheticCentExposure::from(dec!(-3_000_000));
+        let action = funding_adjustment().determine_action(
+            liability,
+            exposure,
+            dec!(3),
+            PRICE_IN_CENTS,
+            dec!(0.1),
+        );
+        assert_eq!(
+            action,
+            BitfinexFundingAdjustment::TransferTradingToFunding(dec!(2))
+        );
+    }
+
+    #[test]
+    fn withdraw_excess_funding() {
+        let liability = SyntheticCentLiability::try_from(dec!(3_000_000)).unwrap();
+        let exposure = SyntheticCentExposure::from(dec!(-3_000_000));
+        let action = funding_adjustment().determine_action(
+            liability,
+            exposure,
+            dec!(1),
+            PRICE_IN_CENTS,
+            dec!(1.1),
+        );
+        assert_eq!(action, BitfinexFundingAdjustment::OnchainWithdraw(dec!(1)));
+    }
+
+    #[test]
+    fn empty_trading_account_when_liability_is_gone() {
+        let liability = SyntheticCentLiability::try_from(dec!(0)).unwrap();
+        let exposure = SyntheticCentExposure::from(dec!(0));
+        let action = funding_adjustment().determine_action(
+            liability,
+            exposure,
+            dec!(0.5),
+            PRICE_IN_CENTS,
+            dec!(0.1),
+        );
+        assert_eq!(
+            action,
+            BitfinexFundingAdjustment::TransferTradingToFunding(dec!(0.5))
+        );
+    }
+
+    #[test]
+    fn wait_for_position_to_close() {
+        let liability = SyntheticCentLiability::try_from(dec!(0)).unwrap();
+        let exposure = SyntheticCentExposure::from(dec!(-100_000));
+        let action = funding_adjustment().determine_action(
+            liability,
+            exposure,
+            dec!(0.5),
+            PRICE_IN_CENTS,
+            dec!(0.1),
+        );
+        assert_eq!(action, BitfinexFundingAdjustment::DoNothing);
+    }
+
+    #[test]
+    fn ignore_small_transfers() {
+        let liability = SyntheticCentLiability::try_from(dec!(0)).unwrap();
+        let exposure = SyntheticCentExposure::from(dec!(0));
+        let action = funding_adjustment().determine_action(
+            liability,
+            exposure,
+            dec!(0.001),
+            PRICE_IN_CENTS,
+            dec!(0),
+        );
+        assert_eq!(action, BitfinexFundingAdjustment::DoNothing);
+    }
+}
@@ -0,0 +1,219 @@
+use rust_decimal::Decimal;
+
+use shared::{payload::BITFINEX_EXCHANGE_ID, pubsub::CorrelationId};
+
+pub use shared::payload::{SyntheticCentExposure, SyntheticCentLiability};
+
+use crate::{
+    bitfinex::BitfinexHedgingConfig, control::HedgingProcess, decisions::HedgingDecision,
+    risk::ProposedOrder,
+};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum BitfinexHedgeAdjustment {
+    DoNothing,
+    ClosePosition,
+    Sell(Decimal),
+    Buy(Decimal),
+}
+impl std::fmt::Display for BitfinexHedgeAdjustment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BitfinexHedgeAdjustment::DoNothing => write!(f, "DoNothing"),
+            BitfinexHedgeAdjustment::ClosePosition => write!(f, "ClosePosition"),
+            BitfinexHedgeAdjustment::Sell(amount_in_cents) => write!(f, "Sell({amount_in_cents})"),
+            BitfinexHedgeAdjustment::Buy(amount_in_cents) => write!(f, "Buy({amount_in_cents})"),
+        }
+    }
+}
+impl BitfinexHedgeAdjustment {
+    pub fn action_required(&self) -> bool {
+        !matches!(*self, Self::DoNothing)
+    }
+
+    pub fn action_type(&self) -> &'static str {
+        match *self {
+            Self::DoNothing => "do-nothing",
+            Self::ClosePosition => "close-position",
+            Self::Sell(_) => "sell",
+            Self::Buy(_) => "buy",
+        }
+    }
+
+    pub fn size(&self) -> Option<Decimal> {
+        match *self {
+            Self::Sell(size) | Self::Buy(size) => Some(size),
+            _ => None,
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        "usd-cents"
+    }
+
+    pub fn size_in_usd(&self) -> Option<Decimal> {
+        self.size().map(|size| size / Decimal::ONE_HUNDRED)
+    }
+
+    pub fn proposed_order(&self, position_before_cents: Decimal) -> Option<ProposedOrder> {
+        match *self {
+            Self::Sell(cents) => Some(ProposedOrder::sell(cents, position_before_cents)),
+            Self::Buy(cents) => Some(ProposedOrder::buy(cents, position_before_cents)),
+            Self::ClosePosition => Some(ProposedOrder::close(position_before_cents)),
+            Self::DoNothing => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HedgingAdjustment {
+    config: BitfinexHedgingConfig,
+}
+
+impl HedgingAdjustment {
+    pub fn new(config: BitfinexHedgingConfig) -> Self {
+        Self { config }
+    }
+
+    /// Determines the action and keeps what it was based on for the decision log
+    pub fn evaluate(
+        &self,
+        evaluated_in: &'static str,
+        correlation_id: CorrelationId,
+        abs_liability: SyntheticCentLiability,
+        signed_exposure: SyntheticCentExposure,
+    ) -> (BitfinexHedgeAdjustment, HedgingDecision) {
+        let action = self.determine_action(abs_liability, signed_exposure);
+        let decision = HedgingDecision {
+            correlation_id,
+            exchange: BITFINEX_EXCHANGE_ID,
+            process: HedgingProcess::Hedging,
+            evaluated_in,
+            inputs: serde_json::json!({
+                "abs_liability_in_cents": Decimal::from(abs_liability),
+                "signed_exposure_in_cents": Decimal::from(signed_exposure),
+            }),
+            config: serde_json::to_value(&self.config).expect("couldn't serialize config"),
+            action: action.to_string(),
+            action_required: action.action_required(),
+        };
+        (action, decision)
+    }
+
+    pub fn determine_action(
+        &self,
+        abs_liability: SyntheticCentLiability,
+        signed_exposure: SyntheticCentExposure,
+    ) -> BitfinexHedgeAdjustment {
+        if abs_liability >= Decimal::ZERO
+            && abs_liability < self.config.minimum_liability_threshold_cents
+        {
+            if signed_exposure == Decimal::ZERO {
+                BitfinexHedgeAdjustment::DoNothing
+            } else {
+                BitfinexHedgeAdjustment::ClosePosition
+            }
+        } else {
+            let signed_liability = abs_liability * Decimal::NEGATIVE_ONE;
+            let abs_exposure = Decimal::from(signed_exposure).abs();
+            let exposure_ratio = signed_exposure / signed_liability;
+            if exposure_ratio.is_sign_negative() {
+                let target_exposure = abs_liability * self.config.low_safebound_ratio_shorting;
+                self.sell(target_exposure + abs_exposure)
+            } else if exposure_ratio < self.config.low_bound_ratio_shorting {
+                let target_exposure = abs_liability * self.config.low_safebound_ratio_shorting;
+                self.sell(target_exposure - abs_exposure)
+            } else if exposure_ratio > self.config.high_bound_ratio_shorting {
+                let target_exposure = abs_liability * self.config.high_safebound_ratio_shorting;
+                self.buy(abs_exposure - target_exposure)
+            } else {
+                BitfinexHedgeAdjustment::DoNothing
+            }
+        }
+    }
+
+    fn sell(&self, amount_in_cents: Decimal) -> BitfinexHedgeAdjustment {
+        let amount_in_cents = amount_in_cents.round().abs();
+        if amount_in_cents < self.config.minimum_order_size_cents {
+            BitfinexHedgeAdjustment::DoNothing
+        } else {
+            BitfinexHedgeAdjustment::Sell(amount_in_cents)
+        }
+    }
+
+    fn buy(&self, amount_in_cents: Decimal) -> BitfinexHedgeAdjustment {
+        let amount_in_cents = amount_in_cents.round().abs();
+        if amount_in_cents < self.config.minimum_order_size_cents {
+            BitfinexHedgeAdjustment::DoNothing
+        } else {
+            BitfinexHedgeAdjustment::Buy(amount_in_cents)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn hedging_adjustment() -> HedgingAdjustment {
+        HedgingAdjustment::new(BitfinexHedgingConfig::default())
+    }
+
+    #[test]
+    fn no_adjustment() {
+        let liability = SyntheticCentLiability::try_from(dec!(100000)).unwrap();
+        let exposure = SyntheticCentExposure::from(dec!(-99000));
+        let adjustment = hedging_adjustment().determine_action(liability, exposure);
+        assert_eq!(adjustment, BitfinexHedgeAdjustment::DoNothing);
+    }
+
+    #[test]
+    fn close_position_below_threshold() {
+        let liability = SyntheticCentLiability::try_from(dec!(100)).unwrap();
+        let exposure = SyntheticCentExposure::from(dec!(-10000));
+        let adjustment = hedging_adjustment().determine_action(liability, exposure);
+        assert_eq!(adjustment, BitfinexHedgeAdjustment::ClosePosition);
+    }
+
+    #[test]
+    fn do_nothing_below_threshold_without_exposure() {
+        let liability = SyntheticCentLiability::try_from(dec!(100)).unwrap();
+        let exposure = SyntheticCentExposure::from(dec!(0));
+        let adjustment = hedging_adjustment().determine_action(liability, exposure);
+        assert_eq!(adjustment, BitfinexHedgeAdjustment::DoNothing);
+    }
+
+    #[test]
+    fn sell_when_under_hedged() {
+        let liability = SyntheticCentLiability::try_from(dec!(100000)).unwrap();
+        let exposure = SyntheticCentExposure::from(dec!(-50000));
+        let adjustment = hedging_adjustment().determine_action(liability, exposure);
+        assert_eq!(adjustment, BitfinexHedgeAdjustment::Sell(dec!(48000)));
+    }
+
+    #[test]
+    fn sell_when_exposure_has_wrong_sign() {
+        let liability = SyntheticCentLiability::try_from(dec!(100000)).unwrap();
+        let exposure = SyntheticCentExposure::from(dec!(10000));
+        let adjustment = hedging_adjustment().determine_action(liability, exposure);
+        assert_eq!(adjustment, BitfinexHedgeAdjustment::Sell(dec!(108000)));
+    }
+
+    #[test]
+    fn buy_when_over_hedged() {
+        let liability = SyntheticCentLiability::try_from(dec!(100000)).unwrap();
+        let exposure = SyntheticCentExposure::from(dec!(-150000));
+        let adjustment = hedging_adjustment().determine_action(liability, exposure);
+        assert_eq!(adjustment, BitfinexHedgeAdjustment::Buy(dec!(50000)));
+    }
+
+    #[test]
+    fn ignore_orders_below_minimum_size() {
+        let liability = SyntheticCentLiability::try_from(dec!(5000)).unwrap();
+        let exposure = SyntheticCentExposure::from(dec!(-4500));
+        let adjustment = hedging_adjustment().determine_action(liability, exposure);
+        assert_eq!(adjustment, BitfinexHedgeAdjustment::DoNothing);
+    }
+}
@@ -0,0 +1,232 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use tracing::instrument;
+
+use bitfinex_client::*;
+use bria_client::*;
+use shared::pubsub::CorrelationId;
+
+use crate::{
+    bitfinex::*, control::*, decisions::HedgingDecisions, error::*,
+    liability_watermark::LiabilityWatermarkCheck, risk::RiskGuard,
+};
+
+const SATS_PER_BTC: Decimal = dec!(100_000_000);
+
+#[allow(clippy::too_many_arguments)]
+#[instrument(name = "hedging.bitfinex.job.adjust_funding", skip_all, fields(correlation_id = %correlation_id,
+        target_liability, current_position, last_price_in_usd_cents, funding_available_balance,
+        trading_available_balance, action, client_transfer_id,
+        amount_with_jitter,
+        transferred_funding, liability_fresh, halted, paused), err)]
+pub(super) async fn execute(
+    correlation_id: CorrelationId,
+    liability_watermark: &LiabilityWatermarkCheck,
+    risk_guard: &RiskGuard,
+    process_control: &ProcessControl,
+    hedging_decisions: &HedgingDecisions,
+    ledger: ledger::Ledger,
+    bitfinex: BitfinexClient,
+    bitfinex_transfers: BitfinexTransfers,
+    bria: &mut BriaClient,
+    funding_adjustment: FundingAdjustment,
+) -> Result<(), HedgingError> {
+    let span = tracing::Span::current();
+    if !liability_watermark.is_fresh().await? {
+        span.record("liability_fresh", tracing::field::display(false));
+        return Ok(());
+    }
+    if let Some(trip) = risk_guard.halted().await? {
+        span.record("halted", tracing::field::display(&trip.reason));
+        return Ok(());
+    }
+    if let Some(pause) = process_control.paused(HedgingProcess::Funding).await? {
+        span.record("paused", tracing::field::display(&pause.reason));
+        return Ok(());
+    }
+
+    let target_liability_in_cents = ledger
+        .balances()
+        .usd_liability_balances()
+        .await?
+        .bitfinex_allocation;
+    span.record(
+        "target_liability",
+        tracing::field::display(target_liability_in_cents),
+    );
+
+    let current_position = bitfinex.get_position_in_signed_usd_cents().await?;
+    span.record(
+        "current_position",
+        tracing::field::display(current_position.usd_cents),
+    );
+
+    let mut last_price_in_usd_cents = current_position.last_price_in_usd_cents;
+    if last_price_in_usd_cents.is_zero() {
+        last_price_in_usd_cents = bitfinex.get_last_price_in_usd_cents().await?.usd_cents;
+    }
+    span.record(
+        "last_price_in_usd_cents",
+        tracing::field::display(last_price_in_usd_cents),
+    );
+
+    let funding_available_balance = bitfinex.funding_account_balance().await?;
+    span.record(
+        "funding_available_balance",
+        tracing::field::display(&funding_available_balance),
+    );
+
+    let trading_available_balance = bitfinex.trading_account_balance().await?;
+    span.record(
+        "trading_available_balance",
+        tracing::field::display(&trading_available_balance),
+    );
+    let (action, decision) = funding_adjustment.evaluate(
+        "adjust_funding",
+        correlation_id,
+        target_liability_in_cents,
+        current_position.usd_cents.into(),
+        trading_available_balance.total_amt_in_btc,
+        last_price_in_usd_cents,
+        funding_available_balance.total_amt_in_btc,
+    );
+    hedging_decisions.record(decision).await?;
+    span.record("action", tracing::field::display(&action));
+
+    let shared = TransferReservationSharedData {
+        correlation_id,
+        action_type: action.action_type().to_string(),
+        action_unit: action.unit().to_string(),
+        target_usd_exposure: target_liability_in_cents.into(),
+        current_usd_exposure: current_position.usd_cents.abs(),
+        trading_btc_used_balance: trading_available_balance.used_amt_in_btc,
+        trading_btc_total_balance: trading_available_balance.total_amt_in_btc,
+        current_usd_btc_price: last_price_in_usd_cents,
+        funding_btc_total_balance: funding_available_balance.total_amt_in_btc,
+    };
+
+    match action {
+        BitfinexFundingAdjustment::DoNothing => {}
+        _ => {
+            match action {
+                BitfinexFundingAdjustment::TransferTradingToFunding(amount)
+                | BitfinexFundingAdjustment::TransferFundingToTrading(amount) => {
+                    let (from, to) = match action {
+                        BitfinexFundingAdjustment::TransferTradingToFunding(_) => {
+                            ("trading", "funding")
+                        }
+                        _ => ("funding", "trading"),
+                    };
+                    let reservation = TransferReservation {
+                        shared: &shared,
+                        action_size: Some(amount),
+                        fee: Decimal::ZERO,
+                        transfer_from: from.to_string(),
+                        transfer_to: to.to_string(),
+                    };
+                    if let Some(client_id) = bitfinex_transfers
+                        .reserve_transfer_slot(reservation)
+                        .await?
+                    {
+                        span.record(
+                            "client_transfer_id",
+                            tracing::field::display(String::from(client_id.clone())),
+                        );
+
+                        let res = match action {
+                            BitfinexFundingAdjustment::TransferTradingToFunding(_) => {
+                                bitfinex.transfer_trading_to_funding(amount).await
+                            }
+                            _ => bitfinex.transfer_funding_to_trading(amount).await,
+                        };
+                        match res {
+                            Ok(transfer_id) => {
+                                bitfinex_transfers
+                                    .update_transfer(client_id, "success", Some(transfer_id.value))
+                                    .await?;
+                            }
+                            Err(e) => {
+                                bitfinex_transfers
+                                    .update_transfer(client_id, "failed", None)
+                                    .await?;
+                                return Err(e.into());
+                            }
+                        }
+                    }
+                }
+                BitfinexFundingAdjustment::OnchainDeposit(amount) => {
+                    if bitfinex.is_simulated() {
+                        return Ok(());
+                    }
+
+                    let amount_with_jitter = {
+                        use rand::Rng;
+                        let mut rng = rand::thread_rng();
+                        let jitter: i32 = rng.gen_range(1..=1000);
+                        amount + (Decimal::from(jitter) / SATS_PER_BTC)
+                    };
+                    let deposit_address = bitfinex.get_funding_deposit_address().await?.value;
+                    let reservation = TransferReservation {
+                        shared: &shared,
+                        action_size: Some(amount_with_jitter),
+                        fee: Decimal::ZERO,
+                        transfer_from: "galoy".to_string(),
+                        transfer_to: deposit_address.clone(),
+                    };
+                    if let Some(client_id) = bitfinex_transfers
+                        .reserve_transfer_slot(reservation)
+                        .await?
+                    {
+                        let client_transfer_id = String::from(client_id.clone());
+                        span.record("client_transfer_id", &client_transfer_id);
+                        span.record(
+                            "amount_with_jitter",
+                            tracing::field::display(amount_with_jitter),
+                        );
+
+                        let amount_in_sats = amount_with_jitter * SATS_PER_BTC;
+                        bria.send_onchain_payment(
+                            deposit_address,
+                            amount_in_sats,
+                            client_transfer_id,
+                        )
+                        .await?;
+                    }
+                }
+                BitfinexFundingAdjustment::OnchainWithdraw(amount) => {
+                    if bitfinex.is_simulated() {
+                        return Ok(());
+                    }
+
+                    let deposit_address = bria.onchain_address().await?.address;
+                    let reservation = TransferReservation {
+                        shared: &shared,
+                        action_size: Some(amount),
+                        fee: BITFINEX_WITHDRAWAL_FEE,
+                        transfer_from: "bitfinex".to_string(),
+                        transfer_to: deposit_address.clone(),
+                    };
+                    if let Some(client_id) = bitfinex_transfers
+                        .reserve_transfer_slot(reservation)
+                        .await?
+                    {
+                        span.record(
+                            "client_transfer_id",
+                            tracing::field::display(String::from(client_id.clone())),
+                        );
+
+                        let withdraw_id = bitfinex
+                            .withdraw_btc_onchain(amount, deposit_address)
+                            .await?;
+                        bitfinex_transfers
+                            .record_withdrawal_id(client_id, withdraw_id.value)
+                            .await?;
+                    }
+                }
+                _ => unreachable!(),
+            }
+            span.record("transferred_funding", tracing::field::display(true));
+        }
+    };
+    Ok(())
+}
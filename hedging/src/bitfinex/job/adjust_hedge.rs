@@ -0,0 +1,138 @@
+use rust_decimal::Decimal;
+use tracing::instrument;
+
+use bitfinex_client::*;
+use shared::pubsub::CorrelationId;
+
+use crate::{
+    bitfinex::*, control::*, decisions::HedgingDecisions, error::*,
+    liability_watermark::LiabilityWatermarkCheck, risk::RiskGuard,
+};
+
+const SATS_PER_BTC: Decimal = rust_decimal_macros::dec!(100_000_000);
+
+#[allow(clippy::too_many_arguments)]
+#[instrument(name = "hedging.bitfinex.job.adjust_hedge", skip_all, fields(correlation_id = %correlation_id,
+        target_liability, current_position, action, placed_order, client_order_id, liability_fresh, halted, paused), err)]
+pub(super) async fn execute(
+    correlation_id: CorrelationId,
+    liability_watermark: &LiabilityWatermarkCheck,
+    risk_guard: &RiskGuard,
+    process_control: &ProcessControl,
+    hedging_decisions: &HedgingDecisions,
+    ledger: ledger::Ledger,
+    bitfinex: BitfinexClient,
+    bitfinex_orders: BitfinexOrders,
+    hedging_adjustment: HedgingAdjustment,
+) -> Result<(), HedgingError> {
+    let span = tracing::Span::current();
+    if !liability_watermark.is_fresh().await? {
+        span.record("liability_fresh", tracing::field::display(false));
+        return Ok(());
+    }
+    if let Some(trip) = risk_guard.halted().await? {
+        span.record("halted", tracing::field::display(&trip.reason));
+        return Ok(());
+    }
+    if let Some(pause) = process_control.paused(HedgingProcess::Hedging).await? {
+        span.record("paused", tracing::field::display(&pause.reason));
+        return Ok(());
+    }
+    let target_liability = ledger
+        .balances()
+        .usd_liability_balances()
+        .await?
+        .bitfinex_allocation;
+    span.record(
+        "target_liability",
+        tracing::field::display(target_liability),
+    );
+    let current_position = bitfinex.get_position_in_signed_usd_cents().await?;
+    span.record(
+        "current_position",
+        tracing::field::display(current_position.usd_cents),
+    );
+
+    let (action, decision) = hedging_adjustment.evaluate(
+        "adjust_hedge",
+        correlation_id,
+        target_liability,
+        current_position.usd_cents.into(),
+    );
+    hedging_decisions.record(decision).await?;
+    span.record("action", tracing::field::display(&action));
+    match action {
+        BitfinexHedgeAdjustment::DoNothing => {}
+        _ => {
+            let reservation = OrderReservation {
+                correlation_id,
+                instrument: bitfinex.instrument_id().to_string(),
+                action: &action,
+                target_usd_value: target_liability * Decimal::NEGATIVE_ONE,
+                usd_value_before_order: current_position.usd_cents,
+            };
+            let within_limits = match action.proposed_order(current_position.usd_cents) {
+                Some(order) => risk_guard.check_order(order).await?,
+                None => true,
+            };
+            if !within_limits {
+                span.record(
+                    "halted",
+                    tracing::field::display("order exceeds risk limits"),
+                );
+                span.record("placed_order", tracing::field::display(false));
+            } else if let Some(order_id) = bitfinex_orders.reserve_order_slot(reservation).await? {
+                span.record(
+                    "client_order_id",
+                    tracing::field::display(String::from(order_id.clone())),
+                );
+                let placed = match action {
+                    BitfinexHedgeAdjustment::ClosePosition => {
+                        bitfinex.close_positions(order_id.clone()).await?
+                    }
+                    BitfinexHedgeAdjustment::Sell(amount_in_cents) => Some(
+                        bitfinex
+                            .place_order(
+                                order_id.clone(),
+                                BitfinexOrderSide::Sell,
+                                btc_amount(&bitfinex, amount_in_cents).await?,
+                            )
+                            .await?,
+                    ),
+                    BitfinexHedgeAdjustment::Buy(amount_in_cents) => Some(
+                        bitfinex
+                            .place_order(
+                                order_id.clone(),
+                                BitfinexOrderSide::Buy,
+                                btc_amount(&bitfinex, amount_in_cents).await?,
+                            )
+                            .await?,
+                    ),
+                    _ => unreachable!(),
+                };
+                if let Some(placed) = placed {
+                    bitfinex_orders
+                        .record_order_id(order_id, placed.value)
+                        .await?;
+                }
+                span.record("placed_order", tracing::field::display(true));
+            } else {
+                span.record("placed_order", tracing::field::display(false));
+            }
+        }
+    };
+    Ok(())
+}
+
+async fn btc_amount(
+    bitfinex: &BitfinexClient,
+    amount_in_cents: Decimal,
+) -> Result<Decimal, HedgingError> {
+    let last_price_in_cents = bitfinex.get_last_price_in_usd_cents().await?.usd_cents;
+    let amount_in_btc =
+        (amount_in_cents / last_price_in_cents * SATS_PER_BTC).round() / SATS_PER_BTC;
+    Ok(std::cmp::max(
+        amount_in_btc,
+        BITFINEX_MINIMUM_ORDER_SIZE_BTC,
+    ))
+}
@@ -0,0 +1,235 @@
+mod adjust_funding;
+mod adjust_hedge;
+mod poll_bitfinex;
+
+use bria_client::BriaClient;
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, Postgres};
+use sqlxmq::{job, CurrentJob, JobBuilder};
+use tracing::instrument;
+use uuid::{uuid, Uuid};
+
+use std::collections::HashMap;
+
+use bitfinex_client::BitfinexClient;
+use shared::{payload::BITFINEX_EXCHANGE_ID, pubsub::CorrelationId, sqlxmq::JobExecutor};
+
+use crate::{
+    allocation::ExchangeHealth, bitfinex::*, control::ProcessControl, decisions::HedgingDecisions,
+    error::*, liability_watermark::LiabilityWatermarkCheck, risk::RiskGuard,
+};
+
+pub const POLL_BITFINEX_ID: Uuid = uuid!("10000000-0000-0000-0000-000000000003");
+
+/// The okex jobs are keyed by the bare correlation id, so the bitfinex ones
+/// derive their own to not be deduplicated against them.
+pub fn job_id(correlation_id: Uuid, job_name: &str) -> Uuid {
+    Uuid::new_v5(&correlation_id, job_name.as_bytes())
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct BitfinexPollDelay(pub(super) std::time::Duration);
+
+#[instrument(name = "hedging.bitfinex.job.spawn_poll_bitfinex", skip_all, fields(error, error.level, error.message), err)]
+pub async fn spawn_poll_bitfinex(
+    pool: &sqlx::PgPool,
+    duration: std::time::Duration,
+) -> Result<(), HedgingError> {
+    match JobBuilder::new_with_id(POLL_BITFINEX_ID, "poll_bitfinex")
+        .set_channel_name("hedging.bitfinex")
+        .set_channel_args("poll_bitfinex")
+        .set_delay(duration)
+        .spawn(pool)
+        .await
+    {
+        Err(sqlx::Error::Database(err)) if err.message().contains("duplicate key") => Ok(()),
+        Err(e) => {
+            shared::tracing::insert_error_fields(tracing::Level::ERROR, &e);
+            Err(e.into())
+        }
+        Ok(_) => Ok(()),
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct AdjustHedgeData {
+    correlation_id: CorrelationId,
+    #[serde(flatten)]
+    tracing_data: HashMap<String, String>,
+}
+
+#[instrument(name = "hedging.bitfinex.job.spawn_adjust_hedge", skip_all, fields(error, error.message), err)]
+pub async fn spawn_adjust_hedge<'a>(
+    tx: impl Executor<'a, Database = Postgres>,
+    trigger_id: impl Into<Uuid>,
+) -> Result<(), HedgingError> {
+    let correlation_id = trigger_id.into();
+    match JobBuilder::new_with_id(
+        job_id(correlation_id, "bitfinex_adjust_hedge"),
+        "bitfinex_adjust_hedge",
+    )
+    .set_ordered(true)
+    .set_channel_name("hedging.bitfinex")
+    .set_channel_args("bitfinex_adjust_hedge")
+    .set_json(&AdjustHedgeData {
+        tracing_data: shared::tracing::extract_tracing_data(),
+        correlation_id: CorrelationId::from(correlation_id),
+    })
+    .expect("Couldn't set json")
+    .spawn(tx)
+    .await
+    {
+        Err(sqlx::Error::Database(err)) if err.message().contains("duplicate key") => Ok(()),
+        Err(e) => {
+            shared::tracing::insert_error_fields(tracing::Level::ERROR, &e);
+            Err(e.into())
+        }
+        Ok(_) => Ok(()),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+#[job(name = "poll_bitfinex")]
+pub(super) async fn poll_bitfinex(
+    mut current_job: CurrentJob,
+    BitfinexPollDelay(delay): BitfinexPollDelay,
+    exchange_health: ExchangeHealth,
+    bitfinex: BitfinexClient,
+    bitfinex_orders: BitfinexOrders,
+    bitfinex_transfers: BitfinexTransfers,
+    funding_config: BitfinexFundingConfig,
+    ledger: ledger::Ledger,
+) -> Result<(), HedgingError> {
+    let pool = current_job.pool().clone();
+    JobExecutor::builder(&mut current_job)
+        .build()
+        .expect("couldn't build JobExecutor")
+        .execute(|_| async move {
+            poll_bitfinex::execute(
+                &pool,
+                bitfinex_orders,
+                bitfinex_transfers,
+                bitfinex,
+                funding_config,
+                &ledger,
+            )
+            .await
+        })
+        .await?;
+    exchange_health
+        .record_successful_poll(BITFINEX_EXCHANGE_ID)
+        .await?;
+    spawn_poll_bitfinex(current_job.pool(), delay).await?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+#[job(name = "bitfinex_adjust_hedge")]
+pub(super) async fn adjust_hedge(
+    mut current_job: CurrentJob,
+    liability_watermark: LiabilityWatermarkCheck,
+    risk_guard: RiskGuard,
+    process_control: ProcessControl,
+    hedging_decisions: HedgingDecisions,
+    ledger: ledger::Ledger,
+    bitfinex: BitfinexClient,
+    bitfinex_orders: BitfinexOrders,
+    hedging_adjustment: HedgingAdjustment,
+) -> Result<(), HedgingError> {
+    JobExecutor::builder(&mut current_job)
+        .build()
+        .expect("couldn't build JobExecutor")
+        .execute(|data| async move {
+            let data: AdjustHedgeData = data.ok_or(HedgingError::NoJobDataPresent)?;
+            adjust_hedge::execute(
+                data.correlation_id,
+                &liability_watermark,
+                &risk_guard,
+                &process_control,
+                &hedging_decisions,
+                ledger,
+                bitfinex,
+                bitfinex_orders,
+                hedging_adjustment,
+            )
+            .await?;
+            Ok::<_, HedgingError>(data)
+        })
+        .await?;
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize)]
+struct AdjustFundingData {
+    correlation_id: CorrelationId,
+    #[serde(flatten)]
+    tracing_data: HashMap<String, String>,
+}
+
+#[instrument(name = "hedging.bitfinex.job.spawn_adjust_funding", skip_all, fields(error, error.message) err)]
+pub async fn spawn_adjust_funding<'a>(
+    tx: impl Executor<'a, Database = Postgres>,
+    trigger_id: impl Into<Uuid>,
+) -> Result<(), HedgingError> {
+    let correlation_id = trigger_id.into();
+    match JobBuilder::new_with_id(
+        job_id(correlation_id, "bitfinex_adjust_funding"),
+        "bitfinex_adjust_funding",
+    )
+    .set_ordered(true)
+    .set_channel_name("hedging.bitfinex")
+    .set_channel_args("bitfinex_adjust_funding")
+    .set_json(&AdjustFundingData {
+        tracing_data: shared::tracing::extract_tracing_data(),
+        correlation_id: CorrelationId::from(correlation_id),
+    })
+    .expect("Couldn't set json")
+    .spawn(tx)
+    .await
+    {
+        Err(sqlx::Error::Database(err)) if err.message().contains("duplicate key") => Ok(()),
+        Err(e) => {
+            shared::tracing::insert_error_fields(tracing::Level::ERROR, &e);
+            Err(e.into())
+        }
+        Ok(_) => Ok(()),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+#[job(name = "bitfinex_adjust_funding")]
+pub(super) async fn adjust_funding(
+    mut current_job: CurrentJob,
+    liability_watermark: LiabilityWatermarkCheck,
+    risk_guard: RiskGuard,
+    process_control: ProcessControl,
+    hedging_decisions: HedgingDecisions,
+    ledger: ledger::Ledger,
+    bitfinex: BitfinexClient,
+    bitfinex_transfers: BitfinexTransfers,
+    mut bria: BriaClient,
+    funding_adjustment: FundingAdjustment,
+) -> Result<(), HedgingError> {
+    JobExecutor::builder(&mut current_job)
+        .build()
+        .expect("couldn't build JobExecutor")
+        .execute(|data| async move {
+            let data: AdjustFundingData = data.ok_or(HedgingError::NoJobDataPresent)?;
+            adjust_funding::execute(
+                data.correlation_id,
+                &liability_watermark,
+                &risk_guard,
+                &process_control,
+                &hedging_decisions,
+                ledger,
+                bitfinex,
+                bitfinex_transfers,
+                &mut bria,
+                funding_adjustment,
+            )
+            .await?;
+            Ok::<_, HedgingError>(data)
+        })
+        .await?;
+    Ok(())
+}
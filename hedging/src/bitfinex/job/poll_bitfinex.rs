@@ -0,0 +1,101 @@
+use tracing::instrument;
+
+use bitfinex_client::{BitfinexClient, BitfinexClientError, PositionSize};
+use shared::payload::BITFINEX_EXCHANGE_ID;
+
+use crate::{bitfinex::*, error::HedgingError};
+
+#[instrument(name = "hedging.bitfinex.job.poll_bitfinex", skip_all)]
+pub async fn execute(
+    pool: &sqlx::PgPool,
+    bitfinex_orders: BitfinexOrders,
+    bitfinex_transfers: BitfinexTransfers,
+    bitfinex: BitfinexClient,
+    funding_config: BitfinexFundingConfig,
+    ledger: &ledger::Ledger,
+) -> Result<(), HedgingError> {
+    let PositionSize {
+        usd_cents,
+        instrument_id,
+        ..
+    } = bitfinex.get_position_in_signed_usd_cents().await?;
+    let tx = pool.begin().await?;
+
+    ledger
+        .adjust_bitfinex_position(
+            tx,
+            usd_cents,
+            BITFINEX_EXCHANGE_ID.to_string(),
+            instrument_id.to_string(),
+        )
+        .await?;
+
+    let mut execute_sweep = false;
+    for (id, order_id) in bitfinex_orders.open_orders().await? {
+        let order_id = match order_id {
+            Some(order_id) => order_id,
+            None => {
+                bitfinex_orders.mark_as_lost(id).await?;
+                execute_sweep = true;
+                continue;
+            }
+        };
+        match bitfinex.order_details(id.clone(), order_id).await {
+            Ok(details) => {
+                bitfinex_orders.update_order(details).await?;
+            }
+            Err(BitfinexClientError::OrderDoesNotExist) => {
+                bitfinex_orders.mark_as_lost(id).await?;
+                execute_sweep = true;
+            }
+            Err(res) => return Err(res.into()),
+        }
+    }
+
+    if execute_sweep {
+        bitfinex_orders.sweep_lost_records().await?;
+    }
+
+    let mut execute_transfer_sweep = false;
+    for (id, address, amount, created_at) in bitfinex_transfers.get_pending_deposits().await? {
+        match bitfinex.fetch_deposit(address, amount).await {
+            Ok(details) => {
+                bitfinex_transfers.update_movement(id, details).await?;
+            }
+            Err(BitfinexClientError::MovementDoesNotExist) => {
+                if chrono::Utc::now() - created_at > funding_config.deposit_lost_timeout_seconds {
+                    bitfinex_transfers.mark_as_lost(id).await?;
+                    execute_transfer_sweep = true;
+                }
+            }
+            Err(res) => return Err(res.into()),
+        }
+    }
+
+    for (id, withdraw_id) in bitfinex_transfers.get_pending_withdrawals().await? {
+        let withdraw_id = match withdraw_id {
+            Some(withdraw_id) => withdraw_id,
+            None => {
+                bitfinex_transfers.mark_as_lost(id).await?;
+                execute_transfer_sweep = true;
+                continue;
+            }
+        };
+        match bitfinex.fetch_withdrawal(withdraw_id).await {
+            Ok(details) => {
+                bitfinex_transfers.update_movement(id, details).await?;
+            }
+            Err(BitfinexClientError::MovementDoesNotExist) => {
+                bitfinex_transfers.mark_as_lost(id).await?;
+                execute_transfer_sweep = true;
+            }
+            Err(res) => return Err(res.into()),
+        }
+    }
+
+    if execute_transfer_sweep {
+        bitfinex_transfers.sweep_lost_records().await?;
+    }
+
+    Ok(())
+}
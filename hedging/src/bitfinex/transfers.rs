@@ -0,0 +1,225 @@
+use rust_decimal::Decimal;
+use sqlx::{Executor, PgPool};
+use uuid::Uuid;
+
+use bitfinex_client::{ClientTransferId, MovementStatus};
+use shared::{health::HealthCheckResponse, pubsub::CorrelationId};
+
+use crate::error::HedgingError;
+
+pub struct TransferReservationSharedData {
+    pub correlation_id: CorrelationId,
+    pub action_type: String,
+    pub action_unit: String,
+    pub target_usd_exposure: Decimal,
+    pub current_usd_exposure: Decimal,
+    pub trading_btc_used_balance: Decimal,
+    pub trading_btc_total_balance: Decimal,
+    pub current_usd_btc_price: Decimal,
+    pub funding_btc_total_balance: Decimal,
+}
+
+pub struct TransferReservation<'a> {
+    pub action_size: Option<Decimal>,
+    pub fee: Decimal,
+    pub transfer_from: String,
+    pub transfer_to: String,
+    pub shared: &'a TransferReservationSharedData,
+}
+
+#[derive(Clone)]
+pub struct BitfinexTransfers {
+    pool: PgPool,
+}
+
+impl BitfinexTransfers {
+    pub async fn new(pool: PgPool) -> Result<Self, HedgingError> {
+        Ok(Self { pool })
+    }
+
+    pub async fn reserve_transfer_slot<'a>(
+        &self,
+        reservation: TransferReservation<'a>,
+    ) -> Result<Option<ClientTransferId>, HedgingError> {
+        let mut tx = self.pool.begin().await?;
+        tx.execute("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE")
+            .await?;
+        let res = sqlx::query!(
+            r#"SELECT client_transfer_id FROM bitfinex_transfers WHERE state = 'pending' AND lost = false"#,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        if !res.is_empty() {
+            return Ok(None);
+        }
+        let id = ClientTransferId::new();
+        sqlx::query!(
+            r#"INSERT INTO bitfinex_transfers (
+                client_transfer_id,
+                correlation_id,
+                action,
+                currency,
+                amount,
+                fee,
+                transfer_from,
+                transfer_to,
+                target_usd_exposure,
+                current_usd_exposure,
+                trading_btc_used_balance,
+                trading_btc_total_balance,
+                current_usd_btc_price,
+                funding_btc_total_balance,
+                state
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)"#,
+            String::from(id.clone()),
+            Uuid::from(reservation.shared.correlation_id),
+            reservation.shared.action_type,
+            reservation.shared.action_unit,
+            reservation.action_size,
+            reservation.fee,
+            reservation.transfer_from,
+            reservation.transfer_to,
+            reservation.shared.target_usd_exposure,
+            reservation.shared.current_usd_exposure,
+            reservation.shared.trading_btc_used_balance,
+            reservation.shared.trading_btc_total_balance,
+            reservation.shared.current_usd_btc_price,
+            reservation.shared.funding_btc_total_balance,
+            "pending"
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(Some(id))
+    }
+
+    /// Wallet transfers settle synchronously on bitfinex so the
+    /// outcome is recorded as soon as the api call returns
+    pub async fn update_transfer(
+        &self,
+        client_id: ClientTransferId,
+        state: &str,
+        transfer_id: Option<String>,
+    ) -> Result<(), HedgingError> {
+        sqlx::query!(
+            r#"UPDATE bitfinex_transfers SET lost = false, transfer_id = $1, state = $2 WHERE client_transfer_id = $3"#,
+            transfer_id,
+            state,
+            String::from(client_id),
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_pending_deposits(
+        &self,
+    ) -> Result<
+        Vec<(
+            ClientTransferId,
+            String,
+            Decimal,
+            chrono::DateTime<chrono::Utc>,
+        )>,
+        HedgingError,
+    > {
+        let res =
+            sqlx::query!(r#"SELECT client_transfer_id, transfer_to, amount, created_at FROM bitfinex_transfers WHERE action = 'deposit' AND state = 'pending'"#)
+                .fetch_all(&self.pool)
+                .await?;
+        Ok(res
+            .into_iter()
+            .map(|r| {
+                (
+                    ClientTransferId::from(r.client_transfer_id),
+                    r.transfer_to.unwrap_or_default(),
+                    r.amount,
+                    r.created_at,
+                )
+            })
+            .collect())
+    }
+
+    pub async fn get_pending_withdrawals(
+        &self,
+    ) -> Result<Vec<(ClientTransferId, Option<String>)>, HedgingError> {
+        let res =
+            sqlx::query!(r#"SELECT client_transfer_id, transfer_id FROM bitfinex_transfers WHERE action = 'withdraw' AND state = 'pending'"#)
+                .fetch_all(&self.pool)
+                .await?;
+        Ok(res
+            .into_iter()
+            .map(|r| (ClientTransferId::from(r.client_transfer_id), r.transfer_id))
+            .collect())
+    }
+
+    pub async fn record_withdrawal_id(
+        &self,
+        client_id: ClientTransferId,
+        withdraw_id: String,
+    ) -> Result<(), HedgingError> {
+        sqlx::query!(
+            r#"UPDATE bitfinex_transfers SET transfer_id = $1 WHERE client_transfer_id = $2"#,
+            withdraw_id,
+            String::from(client_id),
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn update_movement(
+        &self,
+        client_id: ClientTransferId,
+        details: MovementStatus,
+    ) -> Result<(), HedgingError> {
+        sqlx::query!(
+            r#"UPDATE bitfinex_transfers SET lost = false, transfer_id = $1, state = $2 WHERE client_transfer_id = $3"#,
+            details.id,
+            details.state,
+            String::from(client_id),
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn mark_as_lost(&self, id: ClientTransferId) -> Result<(), HedgingError> {
+        sqlx::query!(
+            r#"UPDATE bitfinex_transfers SET lost = true WHERE client_transfer_id = $1"#,
+            String::from(id),
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn healthy(&self) -> HealthCheckResponse {
+        match sqlx::query!(
+            r#"SELECT client_transfer_id, action FROM bitfinex_transfers WHERE lost = true AND state = 'pending'"#
+        )
+        .fetch_all(&self.pool)
+        .await
+        {
+            Ok(lost) if lost.is_empty() => Ok(()),
+            Ok(lost) => Err(format!(
+                "Bitfinex transfers lost: {}",
+                lost.iter()
+                    .map(|r| format!("{} {}", r.client_transfer_id, r.action))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+            Err(e) => Err(format!("Couldn't load bitfinex transfers: {e}")),
+        }
+    }
+
+    pub async fn sweep_lost_records(&self) -> Result<(), HedgingError> {
+        sqlx::query!(
+            r#"UPDATE bitfinex_transfers SET state = 'deleted' WHERE lost = true AND state = 'pending' AND created_at < now() - interval '1 day'"#
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
@@ -2,11 +2,12 @@ use rust_decimal::Decimal;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::time::Duration;
 
-use crate::okex::OkexConfig;
+use crate::{admin::HedgingAdminServerConfig, bitfinex::BitfinexConfig, okex::OkexConfig};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ExchangesConfig {
     pub okex: Option<ExchangeConfig<OkexConfig>>,
+    pub bitfinex: Option<ExchangeConfig<BitfinexConfig>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -22,6 +23,96 @@ pub struct ExchangeConfig<T: DeserializeOwned + Serialize + Default> {
 pub struct HedgingAppConfig {
     #[serde(default)]
     pub health: HedgingAppHealthConfig,
+    #[serde(default)]
+    pub allocation: AllocationConfig,
+    #[serde(default)]
+    pub risk: RiskConfig,
+    #[serde(default)]
+    pub admin: HedgingAdminServerConfig,
+}
+
+#[serde_with::serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllocationConfig {
+    #[serde(default = "default_rebalance_threshold_cents")]
+    pub rebalance_threshold_cents: Decimal,
+    #[serde_as(as = "serde_with::DurationSeconds<u64>")]
+    #[serde(default = "default_rebalance_interval")]
+    pub rebalance_interval: Duration,
+    #[serde_as(as = "serde_with::DurationSeconds<i64>")]
+    #[serde(default = "default_unhealthy_exchange_after")]
+    pub unhealthy_exchange_after: chrono::Duration,
+}
+
+impl Default for AllocationConfig {
+    fn default() -> Self {
+        Self {
+            rebalance_threshold_cents: default_rebalance_threshold_cents(),
+            rebalance_interval: default_rebalance_interval(),
+            unhealthy_exchange_after: default_unhealthy_exchange_after(),
+        }
+    }
+}
+
+fn default_rebalance_threshold_cents() -> Decimal {
+    Decimal::from(10_000)
+}
+
+fn default_rebalance_interval() -> Duration {
+    Duration::from_secs(60)
+}
+
+fn default_unhealthy_exchange_after() -> chrono::Duration {
+    chrono::Duration::from_std(Duration::from_secs(300))
+        .expect("bad default unhealthy_exchange_after")
+}
+
+#[serde_with::serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskConfig {
+    #[serde(default = "default_max_liability_delta_cents")]
+    pub max_liability_delta_cents: Decimal,
+    #[serde_as(as = "serde_with::DurationSeconds<i64>")]
+    #[serde(default = "default_liability_delta_window")]
+    pub liability_delta_window: chrono::Duration,
+    #[serde(default = "default_max_order_notional_cents")]
+    pub max_order_notional_cents: Decimal,
+    #[serde(default = "default_max_daily_traded_volume_cents")]
+    pub max_daily_traded_volume_cents: Decimal,
+    #[serde(default = "default_max_position_cents")]
+    pub max_position_cents: Decimal,
+}
+
+impl Default for RiskConfig {
+    fn default() -> Self {
+        Self {
+            max_liability_delta_cents: default_max_liability_delta_cents(),
+            liability_delta_window: default_liability_delta_window(),
+            max_order_notional_cents: default_max_order_notional_cents(),
+            max_daily_traded_volume_cents: default_max_daily_traded_volume_cents(),
+            max_position_cents: default_max_position_cents(),
+        }
+    }
+}
+
+fn default_max_liability_delta_cents() -> Decimal {
+    Decimal::from(5_000_000)
+}
+
+fn default_liability_delta_window() -> chrono::Duration {
+    chrono::Duration::from_std(Duration::from_secs(60)).expect("bad default liability_delta_window")
+}
+
+fn default_max_order_notional_cents() -> Decimal {
+    Decimal::from(2_500_000)
+}
+
+fn default_max_daily_traded_volume_cents() -> Decimal {
+    Decimal::from(50_000_000)
+}
+
+fn default_max_position_cents() -> Decimal {
+    Decimal::from(100_000_000)
 }
 
 #[serde_with::serde_as]
@@ -36,6 +127,13 @@ pub struct HedgingAppHealthConfig {
     #[serde_as(as = "serde_with::DurationSeconds<i64>")]
     #[serde(default = "default_unhealthy_msg_interval")]
     pub unhealthy_msg_interval_price: chrono::Duration,
+    #[serde_as(as = "serde_with::DurationSeconds<i64>")]
+    #[serde(default = "default_max_liability_watermark_age")]
+    pub max_liability_watermark_age: chrono::Duration,
+    /// Hedging waits for user-trades to record a liability watermark. Set this on
+    /// deployments that don't run user-trades to hedge without one.
+    #[serde(default)]
+    pub allow_missing_liability_watermark: bool,
 }
 
 impl Default for HedgingAppHealthConfig {
@@ -44,6 +142,8 @@ impl Default for HedgingAppHealthConfig {
             unhealthy_msg_interval_liability: default_unhealthy_msg_interval(),
             unhealthy_msg_interval_position: default_unhealthy_msg_interval(),
             unhealthy_msg_interval_price: default_unhealthy_msg_interval(),
+            max_liability_watermark_age: default_max_liability_watermark_age(),
+            allow_missing_liability_watermark: false,
         }
     }
 }
@@ -52,3 +152,8 @@ fn default_unhealthy_msg_interval() -> chrono::Duration {
     chrono::Duration::from_std(Duration::from_secs(20))
         .expect("bad default unhealthy_after_msg_delay")
 }
+
+fn default_max_liability_watermark_age() -> chrono::Duration {
+    chrono::Duration::from_std(Duration::from_secs(60))
+        .expect("bad default max_liability_watermark_age")
+}
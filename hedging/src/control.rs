@@ -0,0 +1,214 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::instrument;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use shared::pubsub::CorrelationId;
+
+use crate::error::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HedgingProcess {
+    Hedging,
+    Funding,
+}
+
+impl HedgingProcess {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            HedgingProcess::Hedging => "hedging",
+            HedgingProcess::Funding => "funding",
+        }
+    }
+}
+
+impl std::fmt::Display for HedgingProcess {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for HedgingProcess {
+    type Err = HedgingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hedging" => Ok(HedgingProcess::Hedging),
+            "funding" => Ok(HedgingProcess::Funding),
+            _ => Err(HedgingError::UnknownProcess(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcessPause {
+    pub reason: String,
+    pub paused_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcessAction {
+    pub correlation_id: CorrelationId,
+    pub action: String,
+    pub recorded_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Default)]
+struct LastActions {
+    inner: Arc<RwLock<HashMap<HedgingProcess, ProcessAction>>>,
+}
+
+impl LastActions {
+    fn record(&self, process: HedgingProcess, action: ProcessAction) {
+        self.inner
+            .write()
+            .expect("last actions lock poisoned")
+            .insert(process, action);
+    }
+
+    fn get(&self, process: HedgingProcess) -> Option<ProcessAction> {
+        self.inner
+            .read()
+            .expect("last actions lock poisoned")
+            .get(&process)
+            .cloned()
+    }
+}
+
+/// Lets operators pause the hedging and funding processes independently.
+/// Pauses are persisted, the last action each process decided on is kept in memory.
+#[derive(Clone)]
+pub struct ProcessControl {
+    pool: sqlx::PgPool,
+    last_actions: LastActions,
+}
+
+impl ProcessControl {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self {
+            pool,
+            last_actions: LastActions::default(),
+        }
+    }
+
+    pub async fn paused(
+        &self,
+        process: HedgingProcess,
+    ) -> Result<Option<ProcessPause>, HedgingError> {
+        let res = sqlx::query!(
+            r#"SELECT event_type, reason, created_at FROM hedging_process_control_events
+               WHERE process = $1 ORDER BY id DESC LIMIT 1"#,
+            process.as_str()
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(res.and_then(|row| {
+            (row.event_type == "paused").then_some(ProcessPause {
+                reason: row.reason,
+                paused_at: row.created_at,
+            })
+        }))
+    }
+
+    /// Returns false when the process was already paused.
+    #[instrument(name = "hedging.control.pause", skip(self), err)]
+    pub async fn pause(
+        &self,
+        process: HedgingProcess,
+        reason: String,
+    ) -> Result<bool, HedgingError> {
+        let res = sqlx::query!(
+            r#"INSERT INTO hedging_process_control_events (process, event_type, reason)
+               SELECT $1::VARCHAR, 'paused', $2
+               WHERE NOT EXISTS (
+                 SELECT 1 FROM (
+                   SELECT event_type FROM hedging_process_control_events
+                   WHERE process = $1 ORDER BY id DESC LIMIT 1
+                 ) latest WHERE latest.event_type = 'paused'
+               )"#,
+            process.as_str(),
+            reason
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    /// Resumes a paused process. Returns the pause that was lifted, if any.
+    #[instrument(name = "hedging.control.resume", skip(self), err)]
+    pub async fn resume(
+        &self,
+        process: HedgingProcess,
+        reason: String,
+    ) -> Result<Option<ProcessPause>, HedgingError> {
+        let pause = self.paused(process).await?;
+        if pause.is_some() {
+            sqlx::query!(
+                r#"INSERT INTO hedging_process_control_events (process, event_type, reason)
+                   VALUES ($1, 'resumed', $2)"#,
+                process.as_str(),
+                reason
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+        Ok(pause)
+    }
+
+    pub fn record_action(
+        &self,
+        process: HedgingProcess,
+        correlation_id: CorrelationId,
+        action: impl std::fmt::Display,
+    ) {
+        self.last_actions.record(
+            process,
+            ProcessAction {
+                correlation_id,
+                action: action.to_string(),
+                recorded_at: Utc::now(),
+            },
+        );
+    }
+
+    pub fn last_action(&self, process: HedgingProcess) -> Option<ProcessAction> {
+        self.last_actions.get(process)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(action: &str) -> ProcessAction {
+        ProcessAction {
+            correlation_id: CorrelationId::new(),
+            action: action.to_string(),
+            recorded_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn last_actions_are_kept_per_process() {
+        let last_actions = LastActions::default();
+        assert!(last_actions.get(HedgingProcess::Hedging).is_none());
+
+        last_actions.record(HedgingProcess::Hedging, action("sell 10 contracts"));
+        last_actions.record(HedgingProcess::Hedging, action("do nothing"));
+        last_actions.record(HedgingProcess::Funding, action("deposit 0.1 BTC"));
+
+        assert_eq!(
+            last_actions.get(HedgingProcess::Hedging).unwrap().action,
+            "do nothing"
+        );
+        assert_eq!(
+            last_actions.get(HedgingProcess::Funding).unwrap().action,
+            "deposit 0.1 BTC"
+        );
+    }
+}
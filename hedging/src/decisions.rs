@@ -0,0 +1,175 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::instrument;
+use uuid::Uuid;
+
+use shared::pubsub::CorrelationId;
+
+use crate::{control::HedgingProcess, error::*};
+
+/// One evaluation of a hedging or funding adjustment, with everything it was based on
+#[derive(Debug, Clone)]
+pub struct HedgingDecision {
+    pub correlation_id: CorrelationId,
+    pub exchange: &'static str,
+    pub process: HedgingProcess,
+    pub evaluated_in: &'static str,
+    pub inputs: serde_json::Value,
+    pub config: serde_json::Value,
+    pub action: String,
+    pub action_required: bool,
+}
+
+impl HedgingDecision {
+    /// Identical configs share a version so decisions can be grouped by the config they ran with
+    pub fn config_version(&self) -> String {
+        let config = serde_json::to_string(&self.config).expect("couldn't serialize config");
+        format!("{:08x}", crc32fast::hash(config.as_bytes()))
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct HedgingDecisionFilter {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub process: Option<HedgingProcess>,
+    pub exchange: Option<String>,
+    pub correlation_id: Option<Uuid>,
+    pub action_required_only: bool,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HedgingDecisionRecord {
+    pub id: i64,
+    pub correlation_id: Uuid,
+    pub exchange: String,
+    pub process: HedgingProcess,
+    pub evaluated_in: String,
+    pub inputs: serde_json::Value,
+    pub config_version: String,
+    pub config: serde_json::Value,
+    pub action: String,
+    pub action_required: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Durable log of every adjustment evaluation, including the ones that decided to do nothing.
+#[derive(Clone)]
+pub struct HedgingDecisions {
+    pool: sqlx::PgPool,
+}
+
+impl HedgingDecisions {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+
+    #[instrument(
+        name = "hedging.decisions.record",
+        skip_all,
+        fields(config_version),
+        err
+    )]
+    pub async fn record(&self, decision: HedgingDecision) -> Result<(), HedgingError> {
+        let config_version = decision.config_version();
+        tracing::Span::current().record("config_version", tracing::field::display(&config_version));
+        sqlx::query!(
+            r#"WITH config AS (
+                 INSERT INTO hedging_decision_configs (version, config) VALUES ($1, $2)
+                 ON CONFLICT (version) DO NOTHING
+               )
+               INSERT INTO hedging_decisions
+                 (correlation_id, exchange, process, evaluated_in, inputs, config_version, action, action_required)
+               VALUES ($3, $4, $5, $6, $7, $1, $8, $9)"#,
+            config_version,
+            decision.config,
+            Uuid::from(decision.correlation_id),
+            decision.exchange,
+            decision.process.as_str(),
+            decision.evaluated_in,
+            decision.inputs,
+            decision.action,
+            decision.action_required,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn list(
+        &self,
+        filter: &HedgingDecisionFilter,
+    ) -> Result<Vec<HedgingDecisionRecord>, HedgingError> {
+        let rows = sqlx::query!(
+            r#"SELECT d.id, d.correlation_id, d.exchange, d.process, d.evaluated_in, d.inputs,
+                      d.config_version, c.config, d.action, d.action_required, d.created_at
+               FROM hedging_decisions d
+               JOIN hedging_decision_configs c ON c.version = d.config_version
+               WHERE ($1::TIMESTAMPTZ IS NULL OR d.created_at >= $1)
+                 AND ($2::TIMESTAMPTZ IS NULL OR d.created_at < $2)
+                 AND ($3::VARCHAR IS NULL OR d.process = $3)
+                 AND ($4::VARCHAR IS NULL OR d.exchange = $4)
+                 AND ($5::UUID IS NULL OR d.correlation_id = $5)
+                 AND ($6 = false OR d.action_required)
+               ORDER BY d.id
+               LIMIT $7"#,
+            filter.since,
+            filter.until,
+            filter.process.map(|process| process.as_str()),
+            filter.exchange,
+            filter.correlation_id,
+            filter.action_required_only,
+            filter.limit,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter()
+            .map(|row| {
+                Ok(HedgingDecisionRecord {
+                    id: row.id,
+                    correlation_id: row.correlation_id,
+                    exchange: row.exchange,
+                    process: row.process.parse()?,
+                    evaluated_in: row.evaluated_in,
+                    inputs: row.inputs,
+                    config_version: row.config_version,
+                    config: row.config,
+                    action: row.action,
+                    action_required: row.action_required,
+                    created_at: row.created_at,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decision(config: serde_json::Value) -> HedgingDecision {
+        HedgingDecision {
+            correlation_id: CorrelationId::new(),
+            exchange: "okex",
+            process: HedgingProcess::Hedging,
+            evaluated_in: "adjust_hedge",
+            inputs: serde_json::json!({}),
+            config,
+            action: "DoNothing".to_string(),
+            action_required: false,
+        }
+    }
+
+    #[test]
+    fn config_version_follows_the_config() {
+        let config = serde_json::json!({ "low_bound_ratio_shorting": "0.95", "high_bound_ratio_shorting": "1.03" });
+        let reordered = serde_json::json!({ "high_bound_ratio_shorting": "1.03", "low_bound_ratio_shorting": "0.95" });
+        let changed = serde_json::json!({ "low_bound_ratio_shorting": "0.96", "high_bound_ratio_shorting": "1.03" });
+
+        let version = decision(config).config_version();
+        assert_eq!(version.len(), 8);
+        assert_eq!(version, decision(reordered).config_version());
+        assert_ne!(version, decision(changed).config_version());
+    }
+}
@@ -13,14 +13,28 @@ pub enum HedgingError {
     Migrate(#[from] sqlx::migrate::MigrateError),
     #[error("HedgingError - OkexClient: {0}")]
     OkexClient(#[from] okex_client::OkexClientError),
+    #[error("HedgingError - BitfinexClient: {0}")]
+    BitfinexClient(#[from] bitfinex_client::BitfinexClientError),
     #[error("HedgingError - GaloyClient: {0}")]
     GaloyClient(#[from] galoy_client::GaloyClientError),
+    #[error("HedgingError - VenueRejected: {0}")]
+    VenueRejected(String),
+    #[error("HedgingError - LightningPaymentFailed: {0}")]
+    LightningPaymentFailed(String),
+    #[error("HedgingError - UnknownProcess: {0}")]
+    UnknownProcess(String),
+    #[error("HedgingError - InvalidBacktestSeries: {0}")]
+    InvalidBacktestSeries(String),
     #[error("HedgingError - NoJobDataPresent")]
     NoJobDataPresent,
     #[error("UserTradesError - Leger: {0}")]
     Ledger(#[from] ledger::LedgerError),
     #[error("BriaClientError - BriaClient: {0}")]
     BriaClient(#[from] bria_client::BriaClientError),
+    #[error("HedgingError - TonicError: {0}")]
+    TonicError(#[from] tonic::transport::Error),
+    #[error("HedgingError - UserTrades: {0}")]
+    UserTrades(#[from] user_trades::UserTradesError),
 }
 
 impl JobExecutionError for HedgingError {}
@@ -0,0 +1,73 @@
+use tracing::instrument;
+
+use shared::health::HealthCheckResponse;
+use user_trades::LiabilityWatermarks;
+
+use crate::error::*;
+
+/// Gates hedging on how recently user-trades reported the ledger
+/// as caught up with galoy.
+#[derive(Clone)]
+pub struct LiabilityWatermarkCheck {
+    watermarks: LiabilityWatermarks,
+    max_age: chrono::Duration,
+    allow_missing: bool,
+}
+
+impl LiabilityWatermarkCheck {
+    pub fn new(pool: sqlx::PgPool, max_age: chrono::Duration) -> Self {
+        Self {
+            watermarks: LiabilityWatermarks::new(pool),
+            max_age,
+            allow_missing: false,
+        }
+    }
+
+    /// Treat a database without any watermark as fresh, for deployments where
+    /// user-trades never records one
+    pub fn allow_missing(mut self, allow_missing: bool) -> Self {
+        self.allow_missing = allow_missing;
+        self
+    }
+
+    #[instrument(
+        name = "hedging.liability_watermark.is_fresh",
+        skip_all,
+        fields(galoy_cursor, watermark_age, watermark_missing, liability_fresh),
+        err
+    )]
+    pub async fn is_fresh(&self) -> Result<bool, HedgingError> {
+        let span = tracing::Span::current();
+        let fresh = match self.watermarks.current().await? {
+            Some(watermark) => {
+                if let Some(cursor) = watermark.galoy_cursor.as_ref() {
+                    span.record("galoy_cursor", tracing::field::display(cursor));
+                }
+                span.record(
+                    "watermark_age",
+                    tracing::field::display(watermark.age().num_seconds()),
+                );
+                watermark.is_within(self.max_age)
+            }
+            None => {
+                span.record("watermark_missing", true);
+                self.allow_missing
+            }
+        };
+        span.record("liability_fresh", fresh);
+        Ok(fresh)
+    }
+
+    pub async fn healthy(&self) -> HealthCheckResponse {
+        match self.watermarks.current().await {
+            Ok(Some(watermark)) if watermark.is_within(self.max_age) => Ok(()),
+            Ok(Some(watermark)) => Err(format!(
+                "Liability watermark not advanced in the last {} seconds",
+                watermark.age().num_seconds()
+            )),
+            Ok(None) if self.allow_missing => Ok(()),
+            Ok(None) => Err("No liability watermark recorded".to_string()),
+            Err(e) => Err(format!("Couldn't load liability watermark: {e}")),
+        }
+    }
+}
@@ -1,27 +1,45 @@
 #![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
 #![cfg_attr(feature = "fail-on-warnings", deny(clippy::all))]
 
+mod admin;
+mod allocation;
 mod app;
+mod backtest;
+pub mod bitfinex;
 mod config;
+mod control;
+mod decisions;
 mod error;
-pub(crate) mod hack_user_trades_lag;
-mod okex;
+mod liability_watermark;
+mod lightning;
+pub mod okex;
+mod risk;
+mod venue;
 
 use bria_client::BriaClientConfig;
 use galoy_client::GaloyClientConfig;
 use shared::{health::HealthCheckTrigger, payload::*, pubsub::*};
 
+pub use admin::{proto as admin_proto, HedgingAdminServerConfig};
 pub use app::*;
+pub use backtest::*;
+pub use bitfinex::BitfinexConfig;
 pub use config::*;
+pub use control::{HedgingProcess, ProcessControl};
+pub use decisions::{HedgingDecisionFilter, HedgingDecisionRecord, HedgingDecisions};
 pub use error::*;
-pub use okex::OkexConfig;
+pub use liability_watermark::LiabilityWatermarkCheck;
+pub use lightning::*;
+pub use okex::{okex_account_changes, setup_okex_account, OkexConfig};
+pub use risk::{current_trip, reset_circuit_breaker, CircuitBreakerTrip, RiskGuard};
+pub use venue::*;
 
 #[allow(clippy::too_many_arguments)]
 pub async fn run(
     pool: sqlx::PgPool,
     health_check_trigger: HealthCheckTrigger,
     config: HedgingAppConfig,
-    okex_config: OkexConfig,
+    exchanges: ExchangesConfig,
     galoy_config: GaloyClientConfig,
     bria_config: BriaClientConfig,
     tick_receiver: memory::Subscriber<PriceStreamPayload>,
@@ -31,7 +49,7 @@ pub async fn run(
         pool,
         health_check_trigger,
         config,
-        okex_config,
+        exchanges,
         galoy_config,
         bria_config,
         tick_receiver,
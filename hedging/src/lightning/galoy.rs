@@ -0,0 +1,58 @@
+use async_trait::async_trait;
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use rust_decimal_macros::dec;
+
+use galoy_client::{GaloyClient, PaymentSendResult};
+
+use super::*;
+
+const SATS_PER_BTC: Decimal = dec!(100_000_000);
+
+/// Pays and receives through the galoy BTC wallet configured as `btc_wallet_id`
+#[derive(Clone)]
+pub struct GaloyLightningPayer {
+    client: GaloyClient,
+}
+
+impl GaloyLightningPayer {
+    pub fn new(client: GaloyClient) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl LightningPayer for GaloyLightningPayer {
+    async fn pay_invoice(
+        &self,
+        invoice: String,
+        memo: String,
+    ) -> Result<LightningPayment, HedgingError> {
+        match self.client.pay_ln_invoice(invoice, memo).await? {
+            PaymentSendResult::SUCCESS | PaymentSendResult::ALREADY_PAID => {
+                Ok(LightningPayment::Settled)
+            }
+            PaymentSendResult::PENDING => Ok(LightningPayment::Pending),
+            PaymentSendResult::FAILURE => Err(HedgingError::LightningPaymentFailed(
+                "galoy reported a failed payment".to_string(),
+            )),
+            PaymentSendResult::Other(status) => Err(HedgingError::LightningPaymentFailed(format!(
+                "unknown galoy payment status {status}"
+            ))),
+        }
+    }
+
+    async fn create_invoice(
+        &self,
+        amount_btc: Decimal,
+        memo: String,
+    ) -> Result<String, HedgingError> {
+        let sats = (amount_btc * SATS_PER_BTC)
+            .round()
+            .to_u64()
+            .ok_or_else(|| {
+                HedgingError::LightningPaymentFailed(format!("can't invoice {amount_btc} btc"))
+            })?;
+        let invoice = self.client.create_ln_invoice(sats, memo).await?;
+        Ok(invoice.payment_request)
+    }
+}
@@ -0,0 +1,96 @@
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::*;
+
+type Settle = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalPayment {
+    pub invoice: String,
+    pub memo: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalInvoice {
+    pub invoice: String,
+    pub amount_btc: Decimal,
+    pub memo: String,
+}
+
+#[derive(Default)]
+struct LocalWallet {
+    payments: Vec<LocalPayment>,
+    invoices: Vec<LocalInvoice>,
+}
+
+/// An in memory stand-in for a lightning wallet. Invoices it pays are handed
+/// to `settle`, so a paper venue or the okex mock can credit the deposit,
+/// and the invoices it issues encode their amount the way okex-mock expects.
+#[derive(Clone)]
+pub struct LocalLightningPayer {
+    wallet: Arc<Mutex<LocalWallet>>,
+    settle: Settle,
+}
+
+impl Default for LocalLightningPayer {
+    fn default() -> Self {
+        Self::new(|_| true)
+    }
+}
+
+impl LocalLightningPayer {
+    /// `settle` returns false when the invoice couldn't be paid
+    pub fn new(settle: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            wallet: Arc::new(Mutex::new(LocalWallet::default())),
+            settle: Arc::new(settle),
+        }
+    }
+
+    pub fn payments(&self) -> Vec<LocalPayment> {
+        self.wallet().payments.clone()
+    }
+
+    pub fn invoices(&self) -> Vec<LocalInvoice> {
+        self.wallet().invoices.clone()
+    }
+
+    fn wallet(&self) -> MutexGuard<'_, LocalWallet> {
+        self.wallet.lock().expect("local wallet lock poisoned")
+    }
+}
+
+#[async_trait]
+impl LightningPayer for LocalLightningPayer {
+    async fn pay_invoice(
+        &self,
+        invoice: String,
+        memo: String,
+    ) -> Result<LightningPayment, HedgingError> {
+        if !(self.settle)(&invoice) {
+            return Err(HedgingError::LightningPaymentFailed(format!(
+                "couldn't settle {invoice}"
+            )));
+        }
+        self.wallet().payments.push(LocalPayment { invoice, memo });
+        Ok(LightningPayment::Settled)
+    }
+
+    async fn create_invoice(
+        &self,
+        amount_btc: Decimal,
+        memo: String,
+    ) -> Result<String, HedgingError> {
+        let mut wallet = self.wallet();
+        let invoice = format!("lnlocal:{amount_btc}:{}", wallet.invoices.len());
+        wallet.invoices.push(LocalInvoice {
+            invoice: invoice.clone(),
+            amount_btc,
+            memo,
+        });
+        Ok(invoice)
+    }
+}
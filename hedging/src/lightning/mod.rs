@@ -0,0 +1,39 @@
+mod galoy;
+mod local;
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+
+use std::sync::Arc;
+
+use crate::error::HedgingError;
+
+pub use galoy::*;
+pub use local::*;
+
+pub type SharedLightningPayer = Arc<dyn LightningPayer>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightningPayment {
+    /// The preimage is known, the recipient has the funds
+    Settled,
+    /// The payment is still routing and may yet fail
+    Pending,
+}
+
+/// The wallet on our side of a lightning movement: it pays the invoices a
+/// venue issues for deposits and issues the invoices a venue pays out to.
+#[async_trait]
+pub trait LightningPayer: Send + Sync {
+    async fn pay_invoice(
+        &self,
+        invoice: String,
+        memo: String,
+    ) -> Result<LightningPayment, HedgingError>;
+
+    async fn create_invoice(
+        &self,
+        amount_btc: Decimal,
+        memo: String,
+    ) -> Result<String, HedgingError>;
+}
@@ -0,0 +1,44 @@
+use tracing::instrument;
+
+use okex_client::{OkexAccountChange, OkexClient};
+
+use super::{InstrumentSelection, OkexConfig};
+use crate::error::HedgingError;
+
+/// Differences between the okex account and what hedging with `config` requires
+pub async fn okex_account_changes(
+    config: &OkexConfig,
+) -> Result<Vec<OkexAccountChange>, HedgingError> {
+    let client = OkexClient::new_unchecked(config.client.clone())?;
+    let changes = client
+        .account_changes(
+            config.funding.high_bound_ratio_leverage,
+            &InstrumentSelection::new(config.instrument.clone()).candidates(),
+        )
+        .await?;
+    Ok(changes)
+}
+
+/// Applies whatever `okex_account_changes` reports and returns what was changed
+#[instrument(name = "hedging.okex.setup_account", skip_all, fields(changes), err)]
+pub async fn setup_okex_account(
+    config: &OkexConfig,
+) -> Result<Vec<OkexAccountChange>, HedgingError> {
+    let changes = okex_account_changes(config).await?;
+    tracing::Span::current().record(
+        "changes",
+        tracing::field::display(
+            changes
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+    );
+    if !changes.is_empty() {
+        OkexClient::new_unchecked(config.client.clone())?
+            .apply_account_changes(&changes)
+            .await?;
+    }
+    Ok(changes)
+}
@@ -0,0 +1,168 @@
+use rust_decimal::Decimal;
+use sqlx::{Acquire, PgPool};
+use uuid::Uuid;
+
+use ledger::{
+    Ledger, LedgerTxId, OkexFundingPaymentMeta, OkexFundingPaymentParams, OkexRealizedPnlMeta,
+    OkexRealizedPnlParams, OkexTradingFeeMeta, OkexTradingFeeParams, OkexUsdtBillKind,
+    OkexUsdtBillMeta, OkexUsdtBillParams,
+};
+use okex_client::{AccountBill, OkexBillType};
+
+use crate::error::HedgingError;
+
+#[derive(Clone)]
+pub struct OkexBills {
+    pool: PgPool,
+}
+
+impl OkexBills {
+    pub async fn new(pool: PgPool) -> Result<Self, HedgingError> {
+        Ok(Self { pool })
+    }
+
+    pub async fn known_bill_ids(&self, bill_ids: &[String]) -> Result<Vec<String>, HedgingError> {
+        let res = sqlx::query!(
+            r#"SELECT bill_id FROM okex_bills WHERE bill_id = ANY($1)"#,
+            bill_ids
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(res.into_iter().map(|r| r.bill_id).collect())
+    }
+
+    /// Records the bill and posts it to the ledger in one transaction.
+    /// Trade bills carry both the fee and the pnl realized by the fill.
+    /// Bills of the usdt margined swap are booked in usdt.
+    /// Returns false if the bill is not one we track or was already imported.
+    pub async fn import(&self, ledger: &Ledger, bill: AccountBill) -> Result<bool, HedgingError> {
+        let (bill_type, amount, realized_pnl) = match bill.bill_type {
+            OkexBillType::FundingFee => ("funding-payment", bill.balance_change, Decimal::ZERO),
+            OkexBillType::Trade => ("trade", bill.fee, bill.pnl),
+            OkexBillType::Other(_) => return Ok(false),
+        };
+        if !matches!(bill.currency.as_str(), "BTC" | "USDT")
+            || (amount == Decimal::ZERO && realized_pnl == Decimal::ZERO)
+        {
+            return Ok(false);
+        }
+        let ledger_tx_id = (amount != Decimal::ZERO).then(LedgerTxId::new);
+        let pnl_ledger_tx_id = (realized_pnl != Decimal::ZERO).then(LedgerTxId::new);
+
+        let mut tx = self.pool.begin().await?;
+        let res = sqlx::query!(
+            r#"INSERT INTO okex_bills (bill_id, bill_type, instrument, order_id, currency, amount, ledger_tx_id, realized_pnl, pnl_ledger_tx_id, billed_at)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+               ON CONFLICT (bill_id) DO NOTHING"#,
+            bill.bill_id,
+            bill_type,
+            bill.instrument_id,
+            if bill.order_id.is_empty() {
+                None
+            } else {
+                Some(bill.order_id.clone())
+            },
+            bill.currency,
+            amount,
+            ledger_tx_id.map(Uuid::from),
+            realized_pnl,
+            pnl_ledger_tx_id.map(Uuid::from),
+            bill.timestamp,
+        )
+        .execute(&mut *tx)
+        .await?;
+        if res.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        if bill.currency == "USDT" {
+            let usdt_bill = |kind, usdt_balance_change| OkexUsdtBillParams {
+                usdt_balance_change,
+                meta: OkexUsdtBillMeta {
+                    timestamp: bill.timestamp,
+                    kind,
+                    bill_id: bill.bill_id.clone(),
+                    order_id: bill.order_id.clone(),
+                    instrument_id: bill.instrument_id.clone(),
+                },
+            };
+            if let Some(ledger_tx_id) = ledger_tx_id {
+                let kind = match bill.bill_type {
+                    OkexBillType::FundingFee => OkexUsdtBillKind::FundingPayment,
+                    _ => OkexUsdtBillKind::TradingFee,
+                };
+                ledger
+                    .okex_usdt_bill(tx.begin().await?, ledger_tx_id, usdt_bill(kind, amount))
+                    .await?;
+            }
+            if let Some(pnl_ledger_tx_id) = pnl_ledger_tx_id {
+                ledger
+                    .okex_usdt_bill(
+                        tx.begin().await?,
+                        pnl_ledger_tx_id,
+                        usdt_bill(OkexUsdtBillKind::RealizedPnl, realized_pnl),
+                    )
+                    .await?;
+            }
+            tx.commit().await?;
+            return Ok(true);
+        }
+
+        if let Some(ledger_tx_id) = ledger_tx_id {
+            match bill.bill_type {
+                OkexBillType::FundingFee => {
+                    ledger
+                        .okex_funding_payment(
+                            tx.begin().await?,
+                            ledger_tx_id,
+                            OkexFundingPaymentParams {
+                                btc_balance_change: amount,
+                                meta: OkexFundingPaymentMeta {
+                                    timestamp: bill.timestamp,
+                                    bill_id: bill.bill_id.clone(),
+                                    instrument_id: bill.instrument_id.clone(),
+                                },
+                            },
+                        )
+                        .await?
+                }
+                _ => {
+                    ledger
+                        .okex_trading_fee(
+                            tx.begin().await?,
+                            ledger_tx_id,
+                            OkexTradingFeeParams {
+                                btc_balance_change: amount,
+                                meta: OkexTradingFeeMeta {
+                                    timestamp: bill.timestamp,
+                                    bill_id: bill.bill_id.clone(),
+                                    order_id: bill.order_id.clone(),
+                                    instrument_id: bill.instrument_id.clone(),
+                                },
+                            },
+                        )
+                        .await?
+                }
+            }
+        }
+        if let Some(pnl_ledger_tx_id) = pnl_ledger_tx_id {
+            ledger
+                .okex_realized_pnl(
+                    tx.begin().await?,
+                    pnl_ledger_tx_id,
+                    OkexRealizedPnlParams {
+                        btc_amount: realized_pnl,
+                        meta: OkexRealizedPnlMeta {
+                            timestamp: bill.timestamp,
+                            bill_id: bill.bill_id,
+                            order_id: bill.order_id,
+                            instrument_id: bill.instrument_id,
+                        },
+                    },
+                )
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(true)
+    }
+}
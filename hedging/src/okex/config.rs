@@ -1,4 +1,4 @@
-use okex_client::OkexClientConfig;
+use okex_client::{OkexClientConfig, OkexInstrumentId};
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
@@ -12,16 +12,262 @@ pub struct OkexConfig {
     #[serde_as(as = "serde_with::DurationSeconds<u64>")]
     #[serde(default = "default_okex_poll_frequency")]
     pub poll_frequency: Duration,
+    /// How often fees, funding and pnl bills are imported and the unrealized pnl
+    /// is marked to market in the ledger
+    #[serde_as(as = "serde_with::DurationSeconds<u64>")]
+    #[serde(default = "default_okex_bills_import_frequency")]
+    pub bills_import_frequency: Duration,
     #[serde(default)]
     pub funding: OkexFundingConfig,
     #[serde(default)]
     pub hedging: OkexHedgingConfig,
+    #[serde(default)]
+    pub execution: OkexExecutionConfig,
+    #[serde(default)]
+    pub websocket: OkexWebsocketConfig,
+    #[serde(default)]
+    pub instrument: OkexInstrumentConfig,
+    #[serde(default)]
+    pub margin: OkexMarginConfig,
+    #[serde(default)]
+    pub transfers: OkexTransfersConfig,
+    #[serde(default)]
+    pub lightning: OkexLightningConfig,
+    #[serde(default)]
+    pub reconciliation: OkexReconciliationConfig,
+    #[serde(default)]
+    pub shadow_mode: bool,
+    /// Converge the account's level, position mode and leverage on startup
+    /// instead of refusing to run against a misconfigured account
+    #[serde(default)]
+    pub setup_account: bool,
 }
 
 fn default_okex_poll_frequency() -> Duration {
     Duration::from_secs(10)
 }
+fn default_okex_bills_import_frequency() -> Duration {
+    Duration::from_secs(60)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OkexInstrumentConfig {
+    #[serde(default = "default_primary_instrument")]
+    pub primary: OkexInstrumentId,
+    #[serde(default)]
+    pub alternative: Option<OkexInstrumentId>,
+    #[serde(default = "default_min_funding_rate_spread")]
+    pub min_funding_rate_spread: Decimal,
+}
+impl Default for OkexInstrumentConfig {
+    fn default() -> Self {
+        Self {
+            primary: default_primary_instrument(),
+            alternative: None,
+            min_funding_rate_spread: default_min_funding_rate_spread(),
+        }
+    }
+}
+
+fn default_primary_instrument() -> OkexInstrumentId {
+    OkexInstrumentId::BtcUsdSwap
+}
+fn default_min_funding_rate_spread() -> Decimal {
+    dec!(0.0001)
+}
+
+#[serde_with::serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OkexExecutionConfig {
+    #[serde(default = "default_max_order_size_contracts")]
+    pub max_order_size_contracts: u32,
+    #[serde_as(as = "serde_with::DurationSeconds<u64>")]
+    #[serde(default = "default_slice_interval")]
+    pub slice_interval: Duration,
+    /// A sliced adjustment without a new slice for this long is abandoned and started over
+    #[serde_as(as = "serde_with::DurationSeconds<u64>")]
+    #[serde(default = "default_stale_parent_after")]
+    pub stale_parent_after: Duration,
+}
+impl Default for OkexExecutionConfig {
+    fn default() -> Self {
+        Self {
+            max_order_size_contracts: default_max_order_size_contracts(),
+            slice_interval: default_slice_interval(),
+            stale_parent_after: default_stale_parent_after(),
+        }
+    }
+}
+
+fn default_max_order_size_contracts() -> u32 {
+    100
+}
+fn default_slice_interval() -> Duration {
+    Duration::from_secs(15)
+}
+fn default_stale_parent_after() -> Duration {
+    Duration::from_secs(300)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OkexMarginConfig {
+    /// Distance between mark and liquidation price, as a fraction of the mark
+    /// price, below which funding is moved to trading regardless of leverage
+    #[serde(default = "default_min_liquidation_distance")]
+    pub min_liquidation_distance: Decimal,
+}
+impl Default for OkexMarginConfig {
+    fn default() -> Self {
+        Self {
+            min_liquidation_distance: default_min_liquidation_distance(),
+        }
+    }
+}
+
+fn default_min_liquidation_distance() -> Decimal {
+    dec!(0.25)
+}
+
+#[serde_with::serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OkexTransfersConfig {
+    #[serde(default = "default_max_submission_attempts")]
+    pub max_submission_attempts: u32,
+    /// Delay before the first resubmission, doubled on every further attempt
+    #[serde_as(as = "serde_with::DurationSeconds<u64>")]
+    #[serde(default = "default_retry_backoff")]
+    pub retry_backoff: Duration,
+    #[serde_as(as = "serde_with::DurationSeconds<u64>")]
+    #[serde(default = "default_internal_stuck_after")]
+    pub internal_stuck_after: Duration,
+    #[serde_as(as = "serde_with::DurationSeconds<u64>")]
+    #[serde(default = "default_onchain_stuck_after")]
+    pub onchain_stuck_after: Duration,
+}
+impl Default for OkexTransfersConfig {
+    fn default() -> Self {
+        Self {
+            max_submission_attempts: default_max_submission_attempts(),
+            retry_backoff: default_retry_backoff(),
+            internal_stuck_after: default_internal_stuck_after(),
+            onchain_stuck_after: default_onchain_stuck_after(),
+        }
+    }
+}
+
+fn default_max_submission_attempts() -> u32 {
+    5
+}
+fn default_retry_backoff() -> Duration {
+    Duration::from_secs(10)
+}
+fn default_internal_stuck_after() -> Duration {
+    Duration::from_secs(600)
+}
+fn default_onchain_stuck_after() -> Duration {
+    Duration::from_secs(6 * 60 * 60)
+}
+
+#[serde_with::serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OkexReconciliationConfig {
+    #[serde_as(as = "serde_with::DurationSeconds<u64>")]
+    #[serde(default = "default_reconciliation_run_frequency")]
+    pub frequency: Duration,
+    #[serde(default = "default_position_tolerance_usd")]
+    pub position_tolerance_usd: Decimal,
+    #[serde(default = "default_btc_tolerance")]
+    pub btc_tolerance: Decimal,
+    /// Consecutive runs a discrepancy has to show up in before the health check fails
+    #[serde(default = "default_persistent_after_runs")]
+    pub persistent_after_runs: u32,
+}
+impl Default for OkexReconciliationConfig {
+    fn default() -> Self {
+        Self {
+            frequency: default_reconciliation_run_frequency(),
+            position_tolerance_usd: default_position_tolerance_usd(),
+            btc_tolerance: default_btc_tolerance(),
+            persistent_after_runs: default_persistent_after_runs(),
+        }
+    }
+}
+
+fn default_reconciliation_run_frequency() -> Duration {
+    Duration::from_secs(300)
+}
+fn default_position_tolerance_usd() -> Decimal {
+    dec!(100)
+}
+fn default_btc_tolerance() -> Decimal {
+    dec!(0.001)
+}
+fn default_persistent_after_runs() -> u32 {
+    3
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OkexLightningConfig {
+    /// Requires a galoy `btc_wallet_id` to pay and receive from
+    #[serde(default)]
+    pub enabled: bool,
+    /// Larger movements always go onchain
+    #[serde(default = "default_lightning_max_amount_btc")]
+    pub max_amount_btc: Decimal,
+    /// Smaller movements go over lightning to save the onchain fee
+    #[serde(default = "default_onchain_min_amount_btc")]
+    pub onchain_min_amount_btc: Decimal,
+}
+impl Default for OkexLightningConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_amount_btc: default_lightning_max_amount_btc(),
+            onchain_min_amount_btc: default_onchain_min_amount_btc(),
+        }
+    }
+}
+
+fn default_lightning_max_amount_btc() -> Decimal {
+    dec!(0.1)
+}
+fn default_onchain_min_amount_btc() -> Decimal {
+    dec!(0.01)
+}
 
+#[serde_with::serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OkexWebsocketConfig {
+    #[serde(default = "default_websocket_enabled")]
+    pub enabled: bool,
+    #[serde_as(as = "serde_with::DurationSeconds<u64>")]
+    #[serde(default = "default_websocket_stale_after")]
+    pub stale_after: Duration,
+    #[serde_as(as = "serde_with::DurationSeconds<u64>")]
+    #[serde(default = "default_reconciliation_frequency")]
+    pub reconciliation_frequency: Duration,
+}
+impl Default for OkexWebsocketConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_websocket_enabled(),
+            stale_after: default_websocket_stale_after(),
+            reconciliation_frequency: default_reconciliation_frequency(),
+        }
+    }
+}
+
+fn default_websocket_enabled() -> bool {
+    true
+}
+fn default_websocket_stale_after() -> Duration {
+    Duration::from_secs(45)
+}
+fn default_reconciliation_frequency() -> Duration {
+    Duration::from_secs(60)
+}
+
+#[serde_with::serde_as]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OkexHedgingConfig {
     #[serde(default = "default_low_bound_ratio_shorting")]
@@ -35,6 +281,15 @@ pub struct OkexHedgingConfig {
 
     #[serde(default = "default_minimum_liability_threshold_cents")]
     pub minimum_liability_threshold_cents: Decimal,
+
+    #[serde(default)]
+    pub execution_mode: OkexOrderExecutionMode,
+    #[serde_as(as = "serde_with::DurationSeconds<u64>")]
+    #[serde(default = "default_passive_reprice_interval")]
+    pub passive_reprice_interval: Duration,
+    #[serde_as(as = "serde_with::DurationSeconds<u64>")]
+    #[serde(default = "default_passive_fallback_after")]
+    pub passive_fallback_after: Duration,
 }
 impl Default for OkexHedgingConfig {
     fn default() -> Self {
@@ -44,10 +299,29 @@ impl Default for OkexHedgingConfig {
             high_safebound_ratio_shorting: default_high_safebound_ratio_shorting(),
             high_bound_ratio_shorting: default_high_bound_ratio_shorting(),
             minimum_liability_threshold_cents: default_minimum_liability_threshold_cents(),
+
+            execution_mode: OkexOrderExecutionMode::default(),
+            passive_reprice_interval: default_passive_reprice_interval(),
+            passive_fallback_after: default_passive_fallback_after(),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OkexOrderExecutionMode {
+    #[default]
+    Market,
+    Passive,
+}
+
+fn default_passive_reprice_interval() -> Duration {
+    Duration::from_secs(10)
+}
+fn default_passive_fallback_after() -> Duration {
+    Duration::from_secs(120)
+}
+
 fn default_minimum_liability_threshold_cents() -> Decimal {
     dec!(5000)
 }
@@ -64,7 +338,6 @@ fn default_high_bound_ratio_shorting() -> Decimal {
     dec!(1.03)
 }
 
-#[serde_with::serde_as]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OkexFundingConfig {
     #[serde(default = "default_minimum_transfer_amount_cents")]
@@ -83,10 +356,6 @@ pub struct OkexFundingConfig {
     pub high_bound_ratio_leverage: Decimal,
     #[serde(default = "default_high_bound_buffer_percentage")]
     pub high_bound_buffer_percentage: Decimal,
-
-    #[serde_as(as = "serde_with::DurationSeconds<i64>")]
-    #[serde(default = "default_deposit_lost_timeout_seconds")]
-    pub deposit_lost_timeout_seconds: chrono::Duration,
 }
 impl Default for OkexFundingConfig {
     fn default() -> Self {
@@ -100,8 +369,6 @@ impl Default for OkexFundingConfig {
             high_safebound_ratio_leverage: default_high_safebound_ratio_leverage(),
             high_bound_ratio_leverage: default_high_bound_ratio_leverage(),
             high_bound_buffer_percentage: default_high_bound_buffer_percentage(),
-
-            deposit_lost_timeout_seconds: default_deposit_lost_timeout_seconds(),
         }
     }
 }
@@ -129,6 +396,3 @@ fn default_high_bound_ratio_leverage() -> Decimal {
 fn default_high_bound_buffer_percentage() -> Decimal {
     dec!(0.9)
 }
-fn default_deposit_lost_timeout_seconds() -> chrono::Duration {
-    chrono::Duration::try_seconds(3600).expect("should always be able to create a timeout duration")
-}
@@ -1,25 +1,49 @@
+use futures::StreamExt;
 use sqlxmq::NamedJob;
 use tracing::{info_span, instrument, Instrument};
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
+use bria_client::{BriaClient, PayoutEvent};
 use ledger::Ledger;
-use okex_client::OkexClient;
-use shared::{payload::*, pubsub::memory};
+use okex_client::{OkexClient, OkexPrivateEvent, TradeCurrency};
+use shared::{
+    health::HealthCheckResponse,
+    payload::*,
+    pubsub::{memory, CorrelationId},
+};
 
-use super::{config::*, funding_adjustment::*, hedge_adjustment::*, job, orders::*, transfers::*};
-use crate::error::HedgingError;
+use super::{
+    account_setup::*, bills::*, config::*, execution::*, funding_adjustment::*, funding_rail::*,
+    hedge_adjustment::*, instrument::*, job, live_view::*, margin::*, order_placement::*,
+    orders::*, reconciliation::*, transfers::*, venue::*,
+};
+use crate::{
+    decisions::HedgingDecisions, error::HedgingError, lightning::SharedLightningPayer, venue::*,
+};
+
+const PRIVATE_WS_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+const BRIA_RECONNECT_DELAY: Duration = Duration::from_secs(5);
 
 pub struct OkexEngine {
     config: OkexConfig,
     pool: sqlx::PgPool,
     orders: OkexOrders,
     transfers: OkexTransfers,
+    bills: OkexBills,
+    margin: OkexMargin,
+    reconciliation: OkexReconciliation,
     okex_client: OkexClient,
+    selection: InstrumentSelection,
+    venue: SharedVenue,
     ledger: Ledger,
     funding_adjustment: FundingAdjustment,
+    funding_rails: FundingRails,
     hedging_adjustment: HedgingAdjustment,
+    order_placement: OrderPlacement,
+    live_view: OkexLiveView,
+    decisions: HedgingDecisions,
 }
 
 impl OkexEngine {
@@ -27,26 +51,53 @@ impl OkexEngine {
         pool: sqlx::PgPool,
         config: OkexConfig,
         ledger: Ledger,
+        bria: BriaClient,
+        lightning_payer: Option<SharedLightningPayer>,
         price_receiver: memory::Subscriber<PriceStreamPayload>,
     ) -> Result<Arc<Self>, HedgingError> {
+        if config.setup_account {
+            setup_okex_account(&config).await?;
+        }
         let okex_client = OkexClient::new(config.client.clone()).await?;
         let orders = OkexOrders::new(pool.clone()).await?;
-        let transfers = OkexTransfers::new(pool.clone()).await?;
-        okex_client
-            .check_leverage(config.funding.high_bound_ratio_leverage)
-            .await?;
+        let transfers = OkexTransfers::new(pool.clone(), config.transfers.clone()).await?;
+        let bills = OkexBills::new(pool.clone()).await?;
+        let margin = OkexMargin::new(pool.clone(), config.margin.clone()).await?;
+        let reconciliation =
+            OkexReconciliation::new(pool.clone(), config.reconciliation.clone()).await?;
+        let selection = InstrumentSelection::load(&pool, config.instrument.clone()).await?;
+        for instrument_id in selection.candidates() {
+            okex_client
+                .for_instrument(instrument_id)
+                .check_leverage(config.funding.high_bound_ratio_leverage)
+                .await?;
+        }
         let funding_adjustment =
             FundingAdjustment::new(config.funding.clone(), config.hedging.clone());
+        let funding_rails = FundingRails::new(config.lightning.clone(), lightning_payer);
         let hedging_adjustment = HedgingAdjustment::new(config.hedging.clone());
+        let order_placement = OrderPlacement::new(&config.hedging);
+        let live_view = OkexLiveView::new(config.websocket.stale_after);
+        let venue: SharedVenue = Arc::new(OkexVenue::new(okex_client.clone(), selection.clone()));
+        let decisions = HedgingDecisions::new(pool.clone());
         let ret = Arc::new(Self {
             config,
             pool,
             okex_client,
+            selection,
+            venue,
             orders,
             transfers,
+            bills,
+            margin,
+            reconciliation,
             ledger,
             funding_adjustment,
+            funding_rails,
             hedging_adjustment,
+            order_placement,
+            live_view,
+            decisions,
         });
 
         Arc::clone(&ret)
@@ -57,6 +108,12 @@ impl OkexEngine {
 
         Arc::clone(&ret).spawn_liability_listener().await?;
 
+        if ret.config.websocket.enabled {
+            Arc::clone(&ret).spawn_private_ws_listener().await?;
+        }
+
+        Arc::clone(&ret).spawn_bria_payout_listener(bria).await?;
+
         Arc::clone(&ret).spawn_non_stop_polling().await?;
 
         Ok(ret)
@@ -64,19 +121,67 @@ impl OkexEngine {
 
     pub fn add_context_to_job_registry(&self, runner: &mut sqlxmq::JobRegistry) {
         runner.set_context(self.okex_client.clone());
+        runner.set_context(Arc::clone(&self.venue));
+        runner.set_context(self.selection.clone());
         runner.set_context(self.orders.clone());
         runner.set_context(self.transfers.clone());
+        runner.set_context(self.bills.clone());
+        runner.set_context(self.margin.clone());
+        runner.set_context(self.reconciliation.clone());
         runner.set_context(job::OkexPollDelay(self.config.poll_frequency));
+        runner.set_context(job::OkexBillsImportDelay(
+            self.config.bills_import_frequency,
+        ));
+        runner.set_context(job::OkexShadowMode(self.config.shadow_mode));
         runner.set_context(self.funding_adjustment.clone());
+        runner.set_context(self.funding_rails.clone());
         runner.set_context(self.hedging_adjustment.clone());
+        runner.set_context(self.order_placement.clone());
+        runner.set_context(ExecutionPlanner::new(self.config.execution.clone()));
         runner.set_context(self.config.funding.clone());
         runner.set_context(self.ledger.clone());
     }
 
+    pub async fn healthy(&self) -> HealthCheckResponse {
+        self.margin
+            .healthy()
+            .await
+            .and(self.transfers.healthy().await)
+            .and(self.reconciliation.healthy().await)
+    }
+
+    pub async fn open_orders(&self) -> Result<Vec<OpenOrder>, HedgingError> {
+        self.orders.open_order_details().await
+    }
+
+    pub async fn in_flight_transfers(&self) -> Result<Vec<InFlightTransfer>, HedgingError> {
+        self.transfers.in_flight_transfers().await
+    }
+
+    pub async fn trigger_adjust_hedge(
+        &self,
+        correlation_id: CorrelationId,
+    ) -> Result<(), HedgingError> {
+        job::spawn_adjust_hedge(&self.pool, correlation_id).await
+    }
+
+    pub async fn trigger_adjust_funding(
+        &self,
+        correlation_id: CorrelationId,
+    ) -> Result<(), HedgingError> {
+        job::spawn_adjust_funding(&self.pool, correlation_id).await
+    }
+
     pub fn register_jobs(jobs: &mut Vec<&'static NamedJob>, channels: &mut Vec<&str>) {
         jobs.push(job::adjust_hedge);
         jobs.push(job::poll_okex);
         jobs.push(job::adjust_funding);
+        jobs.push(job::emergency_funding);
+        jobs.push(job::retry_okex_transfers);
+        jobs.push(job::execute_hedge_slice);
+        jobs.push(job::manage_passive_order);
+        jobs.push(job::import_okex_bills);
+        jobs.push(job::reconcile_okex);
         channels.push("hedging.okex");
     }
 
@@ -86,7 +191,10 @@ impl OkexEngine {
     ) -> Result<(), HedgingError> {
         tokio::spawn(async move {
             while let Some(msg) = tick_recv.next().await {
-                if let PriceStreamPayload::OkexBtcUsdSwapOrderBookPayload(_) = msg.payload {
+                if let PriceStreamPayload::OkexBtcUsdSwapOrderBookPayload(ref order_book) =
+                    msg.payload
+                {
+                    self.order_placement.update_order_book(order_book);
                     let correlation_id = msg.meta.correlation_id;
                     let span = info_span!(
                         "hedging.okex.okex_btc_usd_swap_price_received",
@@ -99,9 +207,7 @@ impl OkexEngine {
                     );
                     shared::tracing::inject_tracing_data(&span, &msg.meta.tracing_data);
                     async {
-                        if let Ok(current_position_in_cents) =
-                            self.okex_client.get_position_in_signed_usd_cents().await
-                        {
+                        if let Ok(current_position_in_cents) = self.current_position().await {
                             let _ = self
                                 .conditionally_spawn_adjust_funding(
                                     correlation_id,
@@ -141,8 +247,7 @@ impl OkexEngine {
 
                             span.set_parent(received.otel_context.clone());
                             async {
-                                if let Ok(current_position_in_cents) =
-                                    self.okex_client.get_position_in_signed_usd_cents().await
+                                if let Ok(current_position_in_cents) = self.current_position().await
                                 {
                                     let exposure = current_position_in_cents.usd_cents.into();
                                     let _ = self
@@ -231,15 +336,21 @@ impl OkexEngine {
         correlation_id: impl Into<uuid::Uuid> + std::fmt::Debug,
         signed_usd_exposure: SyntheticCentExposure,
     ) -> Result<(), HedgingError> {
+        let correlation_id: uuid::Uuid = correlation_id.into();
         let amount = self
             .ledger
             .balances()
             .usd_liability_balances()
             .await?
             .okex_allocation;
-        let action = self
-            .hedging_adjustment
-            .determine_action(amount, signed_usd_exposure);
+        let (action, decision) = self.hedging_adjustment.evaluate(
+            "conditionally_spawn_adjust_hedge",
+            CorrelationId::from(correlation_id),
+            amount,
+            signed_usd_exposure,
+            self.venue.contract_size_cents().await?,
+        );
+        self.decisions.record(decision).await?;
         tracing::Span::current().record("hedging_action", &tracing::field::display(&action));
         if action.action_required() {
             job::spawn_adjust_hedge(&self.pool, correlation_id).await?;
@@ -253,27 +364,39 @@ impl OkexEngine {
         correlation_id: impl Into<uuid::Uuid> + std::fmt::Debug,
         signed_usd_exposure: SyntheticCentExposure,
     ) -> Result<(), HedgingError> {
+        let correlation_id: uuid::Uuid = correlation_id.into();
         let target_liability_in_cents = self
             .ledger
             .balances()
             .usd_liability_balances()
             .await?
             .okex_allocation;
-        let last_price_in_usd_cents = self
-            .okex_client
-            .get_last_price_in_usd_cents()
-            .await?
-            .usd_cents;
-        let trading_available_balance = self.okex_client.trading_account_balance().await?;
-        let funding_available_balance = self.okex_client.funding_account_balance().await?;
+        let last_price_in_usd_cents = self.venue.last_price_in_usd_cents().await?;
+        let trading_available_balance = self.trading_balance().await?;
+        let funding_available_balance = self.venue.funding_balance().await?;
+
+        let margin_currency = self
+            .venue
+            .margin_currency(&self.selection.active().to_string())?;
+        let signed_usd_exposure = if margin_currency == TradeCurrency::BTC {
+            signed_usd_exposure
+        } else {
+            let position = self.venue.position().await?;
+            self.venue.btc_margined_exposure(&position).await?.into()
+        };
 
-        let action = self.funding_adjustment.determine_action(
+        let (action, decision) = self.funding_adjustment.evaluate(
+            "conditionally_spawn_adjust_funding",
+            CorrelationId::from(correlation_id),
             target_liability_in_cents,
             signed_usd_exposure,
             trading_available_balance.total_amt_in_btc,
             last_price_in_usd_cents,
             funding_available_balance.total_amt_in_btc,
+            self.venue.contract_size_cents().await?,
+            margin_currency,
         );
+        self.decisions.record(decision).await?;
         tracing::Span::current().record("funding_action", &tracing::field::display(&action));
         if action.action_required() {
             job::spawn_adjust_funding(&self.pool, correlation_id).await?;
@@ -281,11 +404,118 @@ impl OkexEngine {
         Ok(())
     }
 
+    async fn spawn_private_ws_listener(self: Arc<Self>) -> Result<(), HedgingError> {
+        tokio::spawn(async move {
+            loop {
+                let subscribed = self.selection.active();
+                if let Ok(mut stream) = self
+                    .okex_client
+                    .for_instrument(subscribed)
+                    .subscribe_private_channels()
+                    .await
+                {
+                    while let Some(event) = stream.next().await {
+                        // Resubscribe once poll_okex has moved the hedge to another instrument
+                        if self.selection.active() != subscribed {
+                            break;
+                        }
+                        let _ = self.private_event_received(event).await;
+                    }
+                }
+                self.live_view.disconnected();
+                tokio::time::sleep(PRIVATE_WS_RECONNECT_DELAY).await;
+            }
+        });
+        Ok(())
+    }
+
+    #[instrument(name = "hedging.okex.private_event_received", skip(self), err)]
+    async fn private_event_received(&self, event: OkexPrivateEvent) -> Result<(), HedgingError> {
+        match event {
+            OkexPrivateEvent::Position(position) => {
+                // Unrealized pnl moves with every tick, import_okex_bills marks it to market
+                let changed = self
+                    .live_view
+                    .position()
+                    .is_none_or(|previous| previous.usd_cents != position.usd_cents);
+                if changed {
+                    self.ledger
+                        .adjust_okex_position(
+                            self.pool.begin().await?,
+                            position.usd_cents,
+                            OKEX_EXCHANGE_ID.to_string(),
+                            position.instrument_id.to_string(),
+                        )
+                        .await?;
+                }
+                self.live_view.update_position(position);
+            }
+            OkexPrivateEvent::Order(details) => {
+                self.orders.update_order(details).await?;
+                self.live_view.heartbeat();
+            }
+            OkexPrivateEvent::TradingBalance(balance) => {
+                self.live_view.update_trading_balance(balance);
+            }
+            OkexPrivateEvent::Heartbeat => self.live_view.heartbeat(),
+        }
+        Ok(())
+    }
+
+    async fn spawn_bria_payout_listener(
+        self: Arc<Self>,
+        mut bria: BriaClient,
+    ) -> Result<(), HedgingError> {
+        tokio::spawn(async move {
+            loop {
+                if let Ok(cursor) = self.transfers.payout_cursor().await {
+                    if let Ok(stream) = bria.subscribe_payouts(cursor).await {
+                        let mut stream = std::pin::pin!(stream);
+                        while let Some(Ok(event)) = stream.next().await {
+                            let _ = self.payout_event_received(event).await;
+                        }
+                    }
+                }
+                tokio::time::sleep(BRIA_RECONNECT_DELAY).await;
+            }
+        });
+        Ok(())
+    }
+
+    #[instrument(name = "hedging.okex.payout_event_received", skip(self), err)]
+    async fn payout_event_received(&self, event: PayoutEvent) -> Result<(), HedgingError> {
+        self.transfers.record_payout_event(&event).await
+    }
+
+    pub async fn current_position(&self) -> Result<VenuePosition, HedgingError> {
+        match self.live_view.position() {
+            Some(position) => Ok(position.into()),
+            None => self.venue.position().await,
+        }
+    }
+
+    async fn trading_balance(&self) -> Result<VenueBalance, HedgingError> {
+        match self.live_view.trading_balance() {
+            Some(balance) => Ok(balance.into()),
+            None => self.venue.trading_balance().await,
+        }
+    }
+
+    /// While the private websocket keeps the view fresh polling only reconciles
     async fn spawn_non_stop_polling(self: Arc<Self>) -> Result<(), HedgingError> {
         tokio::spawn(async move {
             loop {
                 let _ = job::spawn_poll_okex(&self.pool, std::time::Duration::from_secs(1)).await;
-                tokio::time::sleep(self.config.poll_frequency).await;
+                let _ = job::spawn_import_okex_bills(&self.pool, std::time::Duration::from_secs(1))
+                    .await;
+                let _ = job::spawn_reconcile_okex(&self.pool, self.config.reconciliation.frequency)
+                    .await;
+                let delay = if self.live_view.is_fresh() {
+                    self.config.websocket.reconciliation_frequency
+                } else {
+                    self.config.poll_frequency
+                };
+                tokio::time::sleep(delay).await;
             }
         });
         Ok(())
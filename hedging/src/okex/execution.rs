@@ -0,0 +1,267 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+
+use crate::{
+    okex::{hedge_adjustment::*, OkexExecutionConfig, ParentOrder},
+    venue::{ResidualPosition, SwapContracts},
+};
+
+/// Next order of an instrument switch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SwitchStep {
+    /// Grows the hedge on the active instrument
+    Active(OkexHedgeAdjustment),
+    /// Unwinds part of the residual position
+    Unwind(OkexHedgeAdjustment),
+}
+
+#[derive(Debug, Clone)]
+pub struct ExecutionPlanner {
+    config: OkexExecutionConfig,
+}
+
+impl ExecutionPlanner {
+    pub fn new(config: OkexExecutionConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn slice_interval(&self) -> std::time::Duration {
+        self.config.slice_interval
+    }
+
+    /// The slice job chain of a parent can die once its retries are used up,
+    /// without a new slice for too long the parent has to be started over
+    pub fn is_stale(&self, parent: &ParentOrder, now: DateTime<Utc>) -> bool {
+        chrono::Duration::from_std(self.config.stale_parent_after)
+            .map(|stale_after| now - parent.last_activity_at > stale_after)
+            .unwrap_or(false)
+    }
+
+    pub fn requires_slicing(
+        &self,
+        action: &OkexHedgeAdjustment,
+        signed_exposure: SyntheticCentExposure,
+        contract_size_cents: Decimal,
+    ) -> bool {
+        &self.next_slice(action, signed_exposure, contract_size_cents) != action
+    }
+
+    /// Alternates between the two instruments so the book stays hedged while it
+    /// moves: the active instrument grows as long as the combined position isn't
+    /// short of the target, the residual is unwound once it is.
+    pub fn next_switch_step(
+        &self,
+        active_action: &OkexHedgeAdjustment,
+        active_position_cents: Decimal,
+        target_usd_value: Decimal,
+        residual: &ResidualPosition,
+        contract_size_cents: Decimal,
+    ) -> SwitchStep {
+        let combined_position_cents = active_position_cents + residual.usd_cents;
+        if matches!(active_action, OkexHedgeAdjustment::Sell(_))
+            && combined_position_cents >= target_usd_value
+        {
+            SwitchStep::Active(self.next_slice(
+                active_action,
+                active_position_cents.into(),
+                contract_size_cents,
+            ))
+        } else {
+            SwitchStep::Unwind(self.next_slice(
+                &OkexHedgeAdjustment::ClosePosition,
+                residual.usd_cents.into(),
+                residual.contract_size_cents,
+            ))
+        }
+    }
+
+    pub fn next_slice(
+        &self,
+        action: &OkexHedgeAdjustment,
+        signed_exposure: SyntheticCentExposure,
+        contract_size_cents: Decimal,
+    ) -> OkexHedgeAdjustment {
+        let max = self.config.max_order_size_contracts.max(1);
+        match action {
+            OkexHedgeAdjustment::Sell(contracts) => OkexHedgeAdjustment::Sell(SwapContracts::new(
+                u32::from(contracts).min(max),
+                contracts.contract_size_cents(),
+            )),
+            OkexHedgeAdjustment::Buy(contracts) => OkexHedgeAdjustment::Buy(SwapContracts::new(
+                u32::from(contracts).min(max),
+                contracts.contract_size_cents(),
+            )),
+            OkexHedgeAdjustment::ClosePosition => {
+                let exposure = Decimal::from(signed_exposure);
+                let position_contracts = (exposure.abs() / contract_size_cents)
+                    .round()
+                    .to_u32()
+                    .unwrap_or(u32::MAX);
+                if position_contracts <= max {
+                    OkexHedgeAdjustment::ClosePosition
+                } else if exposure < Decimal::ZERO {
+                    OkexHedgeAdjustment::Buy(SwapContracts::new(max, contract_size_cents))
+                } else {
+                    OkexHedgeAdjustment::Sell(SwapContracts::new(max, contract_size_cents))
+                }
+            }
+            OkexHedgeAdjustment::DoNothing => OkexHedgeAdjustment::DoNothing,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::okex::INVERSE_CONTRACT_SIZE_CENTS;
+
+    fn planner(max_order_size_contracts: u32) -> ExecutionPlanner {
+        ExecutionPlanner::new(OkexExecutionConfig {
+            max_order_size_contracts,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn small_adjustment_is_not_sliced() {
+        let planner = planner(100);
+        let action = OkexHedgeAdjustment::Sell(50.into());
+        assert!(!planner.requires_slicing(
+            &action,
+            dec!(-10_000).into(),
+            INVERSE_CONTRACT_SIZE_CENTS
+        ));
+        assert_eq!(
+            planner.next_slice(&action, dec!(-10_000).into(), INVERSE_CONTRACT_SIZE_CENTS),
+            action
+        );
+    }
+
+    #[test]
+    fn large_adjustment_is_bounded_by_max_order_size() {
+        let planner = planner(100);
+        let sell = OkexHedgeAdjustment::Sell(350.into());
+        assert!(planner.requires_slicing(&sell, dec!(0).into(), INVERSE_CONTRACT_SIZE_CENTS));
+        assert_eq!(
+            planner.next_slice(&sell, dec!(0).into(), INVERSE_CONTRACT_SIZE_CENTS),
+            OkexHedgeAdjustment::Sell(100.into())
+        );
+        let buy = OkexHedgeAdjustment::Buy(101.into());
+        assert_eq!(
+            planner.next_slice(&buy, dec!(-1_010_000).into(), INVERSE_CONTRACT_SIZE_CENTS),
+            OkexHedgeAdjustment::Buy(100.into())
+        );
+    }
+
+    #[test]
+    fn closing_large_position_is_sliced() {
+        let planner = planner(100);
+        let close = OkexHedgeAdjustment::ClosePosition;
+        assert_eq!(
+            planner.next_slice(&close, dec!(-3_000_000).into(), INVERSE_CONTRACT_SIZE_CENTS),
+            OkexHedgeAdjustment::Buy(100.into())
+        );
+        assert_eq!(
+            planner.next_slice(&close, dec!(3_000_000).into(), INVERSE_CONTRACT_SIZE_CENTS),
+            OkexHedgeAdjustment::Sell(100.into())
+        );
+        assert_eq!(
+            planner.next_slice(&close, dec!(-1_000_000).into(), INVERSE_CONTRACT_SIZE_CENTS),
+            OkexHedgeAdjustment::ClosePosition
+        );
+    }
+
+    #[test]
+    fn linear_slices_keep_their_contract_size() {
+        let planner = planner(100);
+        let linear_size = dec!(40_000);
+        assert_eq!(
+            planner.next_slice(
+                &OkexHedgeAdjustment::ClosePosition,
+                dec!(-3_000_000).into(),
+                linear_size
+            ),
+            OkexHedgeAdjustment::ClosePosition
+        );
+        let sell = OkexHedgeAdjustment::Sell(SwapContracts::new(150, linear_size));
+        assert_eq!(
+            planner
+                .next_slice(&sell, dec!(0).into(), linear_size)
+                .size_in_usd(),
+            Some(dec!(40_000))
+        );
+    }
+
+    #[test]
+    fn parent_without_recent_slices_is_stale() {
+        let planner = ExecutionPlanner::new(OkexExecutionConfig {
+            stale_parent_after: std::time::Duration::from_secs(300),
+            ..Default::default()
+        });
+        let now = Utc::now();
+        let parent = |seconds_ago| ParentOrder {
+            id: okex_client::ClientOrderId::new(),
+            instrument: "BTC-USD-SWAP".to_string(),
+            action_type: "sell".to_string(),
+            target_usd_value: dec!(-10_000),
+            last_activity_at: now - chrono::Duration::seconds(seconds_ago),
+        };
+        assert!(!planner.is_stale(&parent(15), now));
+        assert!(!planner.is_stale(&parent(300), now));
+        assert!(planner.is_stale(&parent(301), now));
+    }
+
+    #[test]
+    fn instrument_switch_alternates_between_the_legs() {
+        let planner = planner(100);
+        let linear_size = dec!(40_000);
+        let residual = |usd_cents| ResidualPosition {
+            instrument_id: "BTC-USD-SWAP".to_string(),
+            usd_cents,
+            contract_size_cents: INVERSE_CONTRACT_SIZE_CENTS,
+        };
+        let sell = OkexHedgeAdjustment::Sell(SwapContracts::new(75, linear_size));
+        assert_eq!(
+            planner.next_switch_step(
+                &sell,
+                dec!(0),
+                dec!(-3_000_000),
+                &residual(dec!(-3_000_000)),
+                linear_size
+            ),
+            SwitchStep::Active(sell.clone())
+        );
+        assert_eq!(
+            planner.next_switch_step(
+                &sell,
+                dec!(-1_000_000),
+                dec!(-3_000_000),
+                &residual(dec!(-3_000_000)),
+                linear_size
+            ),
+            SwitchStep::Unwind(OkexHedgeAdjustment::Buy(100.into()))
+        );
+        assert_eq!(
+            planner.next_switch_step(
+                &OkexHedgeAdjustment::DoNothing,
+                dec!(-3_000_000),
+                dec!(-3_000_000),
+                &residual(dec!(-500_000)),
+                linear_size
+            ),
+            SwitchStep::Unwind(OkexHedgeAdjustment::ClosePosition)
+        );
+    }
+
+    #[test]
+    fn do_nothing_stays_do_nothing() {
+        let planner = planner(100);
+        assert!(!planner.requires_slicing(
+            &OkexHedgeAdjustment::DoNothing,
+            dec!(0).into(),
+            INVERSE_CONTRACT_SIZE_CENTS
+        ));
+    }
+}
@@ -1,9 +1,12 @@
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 
+use okex_client::TradeCurrency;
+use shared::{payload::OKEX_EXCHANGE_ID, pubsub::CorrelationId};
+
 pub use shared::payload::{SyntheticCentExposure, SyntheticCentLiability};
 
-use crate::okex::*;
+use crate::{control::HedgingProcess, decisions::HedgingDecision, okex::*};
 
 const SATS_PER_BTC: Decimal = dec!(100_000_000);
 
@@ -39,16 +42,6 @@ impl OkexFundingAdjustment {
         !matches!(*self, Self::DoNothing)
     }
 
-    pub fn action_type(&self) -> &'static str {
-        match *self {
-            Self::DoNothing => "do-nothing",
-            Self::TransferTradingToFunding(_) => "transfer-trading-to-funding",
-            Self::TransferFundingToTrading(_) => "transfer-funding-to-trading",
-            Self::OnchainDeposit(_) => "deposit",
-            Self::OnchainWithdraw(_) => "withdraw",
-        }
-    }
-
     pub fn size(&self) -> Option<Decimal> {
         match *self {
             Self::TransferTradingToFunding(size)
@@ -64,9 +57,9 @@ impl OkexFundingAdjustment {
     }
 }
 
-fn round_contract_in_cents(amount_in_cents: Decimal) -> Decimal {
-    let number_of_contract = amount_in_cents / CONTRACT_SIZE_CENTS;
-    number_of_contract.round() * CONTRACT_SIZE_CENTS
+fn round_contract_in_cents(amount_in_cents: Decimal, contract_size_cents: Decimal) -> Decimal {
+    let number_of_contract = amount_in_cents / contract_size_cents;
+    number_of_contract.round() * contract_size_cents
 }
 
 fn round_btc(amount_in_btc: Decimal) -> Decimal {
@@ -93,6 +86,57 @@ impl FundingAdjustment {
         }
     }
 
+    /// Determines the action and keeps what it was based on for the decision log
+    #[allow(clippy::too_many_arguments)]
+    pub fn evaluate(
+        &self,
+        evaluated_in: &'static str,
+        correlation_id: CorrelationId,
+        abs_liability_in_cents: SyntheticCentLiability,
+        signed_exposure_in_cents: SyntheticCentExposure,
+        total_collateral_in_btc: Decimal,
+        btc_price_in_cents: Decimal,
+        funding_btc_total_balance: Decimal,
+        contract_size_cents: Decimal,
+        margin_currency: TradeCurrency,
+    ) -> (OkexFundingAdjustment, HedgingDecision) {
+        let inputs = serde_json::json!({
+            "abs_liability_in_cents": Decimal::from(abs_liability_in_cents),
+            "signed_exposure_in_cents": Decimal::from(signed_exposure_in_cents),
+            "total_collateral_in_btc": total_collateral_in_btc,
+            "btc_price_in_cents": btc_price_in_cents,
+            "funding_btc_total_balance": funding_btc_total_balance,
+            "contract_size_cents": contract_size_cents,
+            "margin_currency": margin_currency.to_string(),
+        });
+        let action = self.determine_action(
+            abs_liability_in_cents,
+            signed_exposure_in_cents,
+            total_collateral_in_btc,
+            btc_price_in_cents,
+            funding_btc_total_balance,
+            contract_size_cents,
+            margin_currency,
+        );
+        let decision = HedgingDecision {
+            correlation_id,
+            exchange: OKEX_EXCHANGE_ID,
+            process: HedgingProcess::Funding,
+            evaluated_in,
+            inputs,
+            config: serde_json::json!({
+                "funding": self.config,
+                "hedging": self.hedging_config,
+            }),
+            action: action.to_string(),
+            action_required: action.action_required(),
+        };
+        (action, decision)
+    }
+
+    /// `signed_exposure_in_cents` is the part of the hedge margined in BTC, on
+    /// a USDT margined instrument that is only what a switch has left behind
+    #[allow(clippy::too_many_arguments)]
     pub fn determine_action(
         &self,
         abs_liability_in_cents: SyntheticCentLiability,
@@ -100,8 +144,19 @@ impl FundingAdjustment {
         total_collateral_in_btc: Decimal,
         btc_price_in_cents: Decimal,
         funding_btc_total_balance: Decimal,
+        contract_size_cents: Decimal,
+        margin_currency: TradeCurrency,
     ) -> OkexFundingAdjustment {
-        let round_liability_in_cents = round_contract_in_cents(abs_liability_in_cents.into());
+        if margin_currency != TradeCurrency::BTC {
+            return self.determine_residual_action(
+                signed_exposure_in_cents,
+                total_collateral_in_btc,
+                btc_price_in_cents,
+                funding_btc_total_balance,
+            );
+        }
+        let round_liability_in_cents =
+            round_contract_in_cents(abs_liability_in_cents.into(), contract_size_cents);
         let abs_liability_in_btc = round_liability_in_cents / btc_price_in_cents;
         let abs_exposure_in_btc =
             Decimal::from(signed_exposure_in_cents).abs() / btc_price_in_cents;
@@ -215,6 +270,78 @@ impl FundingAdjustment {
             OkexFundingAdjustment::DoNothing
         }
     }
+
+    /// The liability is margined in USDT, which is never moved or converted here.
+    /// BTC collateral only backs what is left on the inverse swap and whatever it
+    /// frees goes back to the funding account, where it stays for a switch back.
+    fn determine_residual_action(
+        &self,
+        signed_exposure_in_cents: SyntheticCentExposure,
+        total_collateral_in_btc: Decimal,
+        btc_price_in_cents: Decimal,
+        funding_btc_total_balance: Decimal,
+    ) -> OkexFundingAdjustment {
+        let abs_exposure_in_btc =
+            Decimal::from(signed_exposure_in_cents).abs() / btc_price_in_cents;
+
+        if abs_exposure_in_btc.is_zero() {
+            calculate_transfer_out(floor_btc(std::cmp::max(
+                Decimal::ZERO,
+                total_collateral_in_btc,
+            )))
+        } else if abs_exposure_in_btc
+            < total_collateral_in_btc * self.config.low_bound_ratio_leverage
+        {
+            let new_collateral_in_btc =
+                abs_exposure_in_btc / self.config.low_safebound_ratio_leverage;
+            let transfer_size_in_btc = floor_btc(total_collateral_in_btc - new_collateral_in_btc);
+
+            calculate_transfer_out(transfer_size_in_btc)
+        } else if abs_exposure_in_btc
+            > total_collateral_in_btc
+                * self.config.high_bound_buffer_percentage
+                * self.config.high_bound_ratio_leverage
+        {
+            let new_collateral_in_btc =
+                abs_exposure_in_btc / self.config.high_safebound_ratio_leverage;
+            let transfer_size_in_btc = round_btc(new_collateral_in_btc - total_collateral_in_btc);
+
+            calculate_transfer_in_deposit(
+                funding_btc_total_balance,
+                transfer_size_in_btc,
+                self.config.minimum_funding_balance_btc,
+            )
+        } else {
+            OkexFundingAdjustment::DoNothing
+        }
+    }
+
+    /// USDT the trading account needs to carry the liability on the linear swap
+    /// at the leverage funding targets for BTC collateral
+    pub fn usdt_margin_required_cents(&self, abs_liability_in_cents: Decimal) -> Decimal {
+        abs_liability_in_cents / self.config.high_safebound_ratio_leverage
+    }
+
+    /// A deposit is urgent while the position is above the leverage at which
+    /// collateral gets topped up, ie. it can't wait for onchain confirmations
+    pub fn urgency(
+        &self,
+        signed_exposure_in_cents: SyntheticCentExposure,
+        total_collateral_in_btc: Decimal,
+        btc_price_in_cents: Decimal,
+    ) -> FundingUrgency {
+        let abs_exposure_in_btc =
+            Decimal::from(signed_exposure_in_cents).abs() / btc_price_in_cents;
+        if abs_exposure_in_btc
+            > total_collateral_in_btc
+                * self.config.high_bound_buffer_percentage
+                * self.config.high_bound_ratio_leverage
+        {
+            FundingUrgency::Urgent
+        } else {
+            FundingUrgency::Routine
+        }
+    }
 }
 
 fn calculate_transfer_in_deposit(
@@ -303,7 +430,10 @@ fn calculate_withdraw(
 
 #[cfg(test)]
 mod tests {
+    use okex_client::OkexInstrumentId;
+
     use super::*;
+    use crate::okex::contract_size_cents;
 
     fn split_deposit(
         funding_btc_total_balance: Decimal,
@@ -353,6 +483,8 @@ mod tests {
             total_collateral,
             btc_price,
             funding_adjustment.config.minimum_funding_balance_btc,
+            INVERSE_CONTRACT_SIZE_CENTS,
+            TradeCurrency::BTC,
         );
         assert_eq!(adjustment, OkexFundingAdjustment::DoNothing);
     }
@@ -381,6 +513,8 @@ mod tests {
             total_collateral,
             btc_price,
             funding_btc_total_balance,
+            INVERSE_CONTRACT_SIZE_CENTS,
+            TradeCurrency::BTC,
         );
         assert_eq!(
             adjustment,
@@ -412,6 +546,8 @@ mod tests {
             total_collateral,
             btc_price,
             funding_btc_total_balance,
+            INVERSE_CONTRACT_SIZE_CENTS,
+            TradeCurrency::BTC,
         );
         assert_eq!(
             adjustment,
@@ -446,6 +582,8 @@ mod tests {
             total_collateral,
             btc_price,
             funding_btc_total_balance,
+            INVERSE_CONTRACT_SIZE_CENTS,
+            TradeCurrency::BTC,
         );
         assert_eq!(
             adjustment,
@@ -476,6 +614,8 @@ mod tests {
             total_collateral,
             btc_price,
             funding_btc_total_balance,
+            INVERSE_CONTRACT_SIZE_CENTS,
+            TradeCurrency::BTC,
         );
         assert_eq!(
             adjustment,
@@ -509,6 +649,8 @@ mod tests {
             total_collateral,
             btc_price,
             funding_btc_total_balance,
+            INVERSE_CONTRACT_SIZE_CENTS,
+            TradeCurrency::BTC,
         );
         assert_eq!(
             adjustment,
@@ -542,6 +684,8 @@ mod tests {
             total_collateral,
             btc_price,
             funding_btc_total_balance,
+            INVERSE_CONTRACT_SIZE_CENTS,
+            TradeCurrency::BTC,
         );
         assert_eq!(
             adjustment,
@@ -573,6 +717,8 @@ mod tests {
             total_collateral,
             btc_price,
             funding_btc_total_balance,
+            INVERSE_CONTRACT_SIZE_CENTS,
+            TradeCurrency::BTC,
         );
         assert_eq!(
             adjustment,
@@ -607,6 +753,8 @@ mod tests {
             total_collateral,
             btc_price,
             funding_btc_total_balance,
+            INVERSE_CONTRACT_SIZE_CENTS,
+            TradeCurrency::BTC,
         );
         assert_eq!(
             adjustment,
@@ -614,6 +762,102 @@ mod tests {
         );
     }
 
+    #[test]
+    fn linear_instrument_returns_collateral_without_residual() {
+        let funding_adjustment = FundingAdjustment {
+            config: OkexFundingConfig::default(),
+            hedging_config: OkexHedgingConfig::default(),
+        };
+        let liability = SyntheticCentLiability::try_from(dec!(1_000_000)).unwrap();
+        let exposure = SyntheticCentExposure::from(dec!(0));
+        let total_collateral: Decimal = dec!(0.5);
+        let btc_price: Decimal = dec!(4_000_000);
+        let adjustment = funding_adjustment.determine_action(
+            liability,
+            exposure,
+            total_collateral,
+            btc_price,
+            dec!(0),
+            contract_size_cents(OkexInstrumentId::BtcUsdtSwap, btc_price),
+            TradeCurrency::USDT,
+        );
+        assert_eq!(
+            adjustment,
+            OkexFundingAdjustment::TransferTradingToFunding(total_collateral)
+        );
+    }
+
+    #[test]
+    fn linear_instrument_never_deposits_for_the_liability() {
+        let funding_adjustment = FundingAdjustment {
+            config: OkexFundingConfig::default(),
+            hedging_config: OkexHedgingConfig::default(),
+        };
+        let liability = SyntheticCentLiability::try_from(dec!(1_000_000)).unwrap();
+        let exposure = SyntheticCentExposure::from(dec!(0));
+        let btc_price: Decimal = dec!(4_000_000);
+        let adjustment = funding_adjustment.determine_action(
+            liability,
+            exposure,
+            dec!(0),
+            btc_price,
+            dec!(0),
+            contract_size_cents(OkexInstrumentId::BtcUsdtSwap, btc_price),
+            TradeCurrency::USDT,
+        );
+        assert_eq!(adjustment, OkexFundingAdjustment::DoNothing);
+
+        let adjustment = funding_adjustment.determine_action(
+            liability,
+            exposure,
+            dec!(0),
+            btc_price,
+            dec!(0),
+            INVERSE_CONTRACT_SIZE_CENTS,
+            TradeCurrency::BTC,
+        );
+        assert!(matches!(
+            adjustment,
+            OkexFundingAdjustment::OnchainDeposit(_)
+        ));
+    }
+
+    #[test]
+    fn linear_instrument_funds_the_inverse_residual() {
+        let funding_adjustment = FundingAdjustment {
+            config: OkexFundingConfig::default(),
+            hedging_config: OkexHedgingConfig::default(),
+        };
+        let liability = SyntheticCentLiability::try_from(dec!(1_000_000)).unwrap();
+        let residual_exposure = dec!(1_000_000);
+        let signed_exposure = SyntheticCentExposure::from(-residual_exposure);
+        let total_collateral: Decimal = dec!(0.01);
+        let funding_btc_total_balance: Decimal = dec!(1);
+        let btc_price: Decimal = dec!(4_000_000);
+        let expected_total: Decimal = round_btc(
+            residual_exposure / btc_price / funding_adjustment.config.high_safebound_ratio_leverage
+                - total_collateral,
+        );
+        let (expected_internal, _) = split_deposit(
+            funding_btc_total_balance,
+            expected_total,
+            funding_adjustment.config.minimum_funding_balance_btc,
+        );
+        let adjustment = funding_adjustment.determine_action(
+            liability,
+            signed_exposure,
+            total_collateral,
+            btc_price,
+            funding_btc_total_balance,
+            contract_size_cents(OkexInstrumentId::BtcUsdtSwap, btc_price),
+            TradeCurrency::USDT,
+        );
+        assert_eq!(
+            adjustment,
+            OkexFundingAdjustment::TransferFundingToTrading(expected_internal)
+        );
+    }
+
     #[test]
     fn split_deposit_no_funding() {
         let funding_adjustment = FundingAdjustment {
@@ -904,17 +1148,35 @@ mod tests {
 
     #[test]
     fn contract_round_down() {
-        let amount = dec!(1.4) * CONTRACT_SIZE_CENTS;
-        let expected_amount = dec!(1.0) * CONTRACT_SIZE_CENTS;
-        let rounded_amount = round_contract_in_cents(amount);
+        let amount = dec!(1.4) * INVERSE_CONTRACT_SIZE_CENTS;
+        let expected_amount = dec!(1.0) * INVERSE_CONTRACT_SIZE_CENTS;
+        let rounded_amount = round_contract_in_cents(amount, INVERSE_CONTRACT_SIZE_CENTS);
         assert_eq!(rounded_amount, expected_amount);
     }
 
     #[test]
     fn contract_round_up() {
-        let amount = dec!(1.6) * CONTRACT_SIZE_CENTS;
-        let expected_amount = dec!(2.0) * CONTRACT_SIZE_CENTS;
-        let rounded_amount = round_contract_in_cents(amount);
+        let amount = dec!(1.6) * INVERSE_CONTRACT_SIZE_CENTS;
+        let expected_amount = dec!(2.0) * INVERSE_CONTRACT_SIZE_CENTS;
+        let rounded_amount = round_contract_in_cents(amount, INVERSE_CONTRACT_SIZE_CENTS);
         assert_eq!(rounded_amount, expected_amount);
     }
+
+    #[test]
+    fn deposits_are_urgent_above_the_high_bound() {
+        let funding_adjustment = FundingAdjustment {
+            config: OkexFundingConfig::default(),
+            hedging_config: OkexHedgingConfig::default(),
+        };
+        let exposure = SyntheticCentExposure::from(dec!(-1_000_000));
+        let btc_price = dec!(4_000_000);
+        assert_eq!(
+            funding_adjustment.urgency(exposure, dec!(0.05), btc_price),
+            FundingUrgency::Urgent
+        );
+        assert_eq!(
+            funding_adjustment.urgency(exposure, dec!(0.1), btc_price),
+            FundingUrgency::Routine
+        );
+    }
 }
@@ -0,0 +1,134 @@
+use rust_decimal::Decimal;
+
+use std::fmt;
+
+use super::config::OkexLightningConfig;
+use crate::lightning::SharedLightningPayer;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FundingRail {
+    Onchain,
+    Lightning,
+}
+
+impl fmt::Display for FundingRail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Onchain => write!(f, "onchain"),
+            Self::Lightning => write!(f, "lightning"),
+        }
+    }
+}
+
+/// Urgent movements can't wait for onchain confirmations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FundingUrgency {
+    Routine,
+    Urgent,
+}
+
+/// Picks the rail an external funding movement takes. Lightning is only
+/// ever chosen when a payer is configured.
+#[derive(Clone)]
+pub struct FundingRails {
+    config: OkexLightningConfig,
+    payer: Option<SharedLightningPayer>,
+}
+
+impl FundingRails {
+    pub fn new(config: OkexLightningConfig, payer: Option<SharedLightningPayer>) -> Self {
+        Self { config, payer }
+    }
+
+    /// The payer to use together with the rail, None when onchain
+    pub fn choose(
+        &self,
+        amount: Decimal,
+        urgency: FundingUrgency,
+    ) -> (FundingRail, Option<SharedLightningPayer>) {
+        match self.payer {
+            Some(ref payer)
+                if choose_rail(&self.config, amount, urgency) == FundingRail::Lightning =>
+            {
+                (FundingRail::Lightning, Some(payer.clone()))
+            }
+            _ => (FundingRail::Onchain, None),
+        }
+    }
+}
+
+fn choose_rail(
+    config: &OkexLightningConfig,
+    amount: Decimal,
+    urgency: FundingUrgency,
+) -> FundingRail {
+    if !config.enabled || amount > config.max_amount_btc {
+        FundingRail::Onchain
+    } else if urgency == FundingUrgency::Urgent || amount < config.onchain_min_amount_btc {
+        FundingRail::Lightning
+    } else {
+        FundingRail::Onchain
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::lightning::LocalLightningPayer;
+
+    fn enabled() -> OkexLightningConfig {
+        OkexLightningConfig {
+            enabled: true,
+            max_amount_btc: dec!(0.1),
+            onchain_min_amount_btc: dec!(0.01),
+        }
+    }
+
+    #[test]
+    fn disabled_lightning_stays_onchain() {
+        let config = OkexLightningConfig::default();
+        assert_eq!(
+            choose_rail(&config, dec!(0.001), FundingUrgency::Urgent),
+            FundingRail::Onchain
+        );
+    }
+
+    #[test]
+    fn rail_by_amount_and_urgency() {
+        let config = enabled();
+        assert_eq!(
+            choose_rail(&config, dec!(0.005), FundingUrgency::Routine),
+            FundingRail::Lightning
+        );
+        assert_eq!(
+            choose_rail(&config, dec!(0.05), FundingUrgency::Routine),
+            FundingRail::Onchain
+        );
+        assert_eq!(
+            choose_rail(&config, dec!(0.05), FundingUrgency::Urgent),
+            FundingRail::Lightning
+        );
+        assert_eq!(
+            choose_rail(&config, dec!(0.5), FundingUrgency::Urgent),
+            FundingRail::Onchain
+        );
+    }
+
+    #[test]
+    fn lightning_requires_a_payer() {
+        let without_payer = FundingRails::new(enabled(), None);
+        assert_eq!(
+            without_payer.choose(dec!(0.005), FundingUrgency::Urgent).0,
+            FundingRail::Onchain
+        );
+        let with_payer =
+            FundingRails::new(enabled(), Some(Arc::new(LocalLightningPayer::default())));
+        let (rail, payer) = with_payer.choose(dec!(0.005), FundingUrgency::Urgent);
+        assert_eq!(rail, FundingRail::Lightning);
+        assert!(payer.is_some());
+    }
+}
@@ -1,19 +1,20 @@
 use rust_decimal::Decimal;
-use rust_decimal_macros::dec;
 
-pub use okex_client::BtcUsdSwapContracts;
-pub use shared::payload::{SyntheticCentExposure, SyntheticCentLiability};
+use shared::{payload::OKEX_EXCHANGE_ID, pubsub::CorrelationId};
 
-use crate::okex::OkexHedgingConfig;
+pub use shared::payload::{SyntheticCentExposure, SyntheticCentLiability};
 
-pub const CONTRACT_SIZE_CENTS: Decimal = dec!(10000);
+use crate::{
+    control::HedgingProcess, decisions::HedgingDecision, okex::OkexHedgingConfig,
+    risk::ProposedOrder, venue::SwapContracts,
+};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum OkexHedgeAdjustment {
     DoNothing,
     ClosePosition,
-    Sell(BtcUsdSwapContracts),
-    Buy(BtcUsdSwapContracts),
+    Sell(SwapContracts),
+    Buy(SwapContracts),
 }
 impl std::fmt::Display for OkexHedgeAdjustment {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -51,8 +52,22 @@ impl OkexHedgeAdjustment {
     }
 
     pub fn size_in_usd(&self) -> Option<Decimal> {
-        self.size()
-            .map(|size| Decimal::ONE_HUNDRED * Decimal::from(size))
+        match *self {
+            Self::Sell(ref contracts) | Self::Buy(ref contracts) => {
+                Some(contracts.notional_cents() / Decimal::ONE_HUNDRED)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn proposed_order(&self, position_before_cents: Decimal) -> Option<ProposedOrder> {
+        let notional_cents = self.size_in_usd().map(|usd| usd * Decimal::ONE_HUNDRED);
+        match (self, notional_cents) {
+            (Self::Sell(_), Some(cents)) => Some(ProposedOrder::sell(cents, position_before_cents)),
+            (Self::Buy(_), Some(cents)) => Some(ProposedOrder::buy(cents, position_before_cents)),
+            (Self::ClosePosition, _) => Some(ProposedOrder::close(position_before_cents)),
+            _ => None,
+        }
     }
 }
 
@@ -66,10 +81,38 @@ impl HedgingAdjustment {
         Self { config }
     }
 
+    /// Determines the action and keeps what it was based on for the decision log
+    pub fn evaluate(
+        &self,
+        evaluated_in: &'static str,
+        correlation_id: CorrelationId,
+        abs_liability: SyntheticCentLiability,
+        signed_exposure: SyntheticCentExposure,
+        contract_size_cents: Decimal,
+    ) -> (OkexHedgeAdjustment, HedgingDecision) {
+        let action = self.determine_action(abs_liability, signed_exposure, contract_size_cents);
+        let decision = HedgingDecision {
+            correlation_id,
+            exchange: OKEX_EXCHANGE_ID,
+            process: HedgingProcess::Hedging,
+            evaluated_in,
+            inputs: serde_json::json!({
+                "abs_liability_in_cents": Decimal::from(abs_liability),
+                "signed_exposure_in_cents": Decimal::from(signed_exposure),
+                "contract_size_cents": contract_size_cents,
+            }),
+            config: serde_json::to_value(&self.config).expect("couldn't serialize config"),
+            action: action.to_string(),
+            action_required: action.action_required(),
+        };
+        (action, decision)
+    }
+
     pub fn determine_action(
         &self,
         abs_liability: SyntheticCentLiability,
         signed_exposure: SyntheticCentExposure,
+        contract_size_cents: Decimal,
     ) -> OkexHedgeAdjustment {
         if abs_liability >= Decimal::ZERO
             && abs_liability < self.config.minimum_liability_threshold_cents
@@ -85,38 +128,41 @@ impl HedgingAdjustment {
             let exposure_ratio = signed_exposure / signed_liability;
             if exposure_ratio.is_sign_negative() {
                 let target_exposure = abs_liability * self.config.low_safebound_ratio_shorting;
-                let contracts = ((target_exposure + abs_exposure) / CONTRACT_SIZE_CENTS)
+                let contracts = ((target_exposure + abs_exposure) / contract_size_cents)
                     .round()
                     .abs();
                 if contracts.is_zero() {
                     OkexHedgeAdjustment::DoNothing
                 } else {
-                    OkexHedgeAdjustment::Sell(BtcUsdSwapContracts::from(
+                    OkexHedgeAdjustment::Sell(SwapContracts::new(
                         u32::try_from(contracts).expect("decimal to u32"),
+                        contract_size_cents,
                     ))
                 }
             } else if exposure_ratio < self.config.low_bound_ratio_shorting {
                 let target_exposure = abs_liability * self.config.low_safebound_ratio_shorting;
-                let contracts = ((target_exposure - abs_exposure) / CONTRACT_SIZE_CENTS)
+                let contracts = ((target_exposure - abs_exposure) / contract_size_cents)
                     .round()
                     .abs();
                 if contracts.is_zero() {
                     OkexHedgeAdjustment::DoNothing
                 } else {
-                    OkexHedgeAdjustment::Sell(BtcUsdSwapContracts::from(
+                    OkexHedgeAdjustment::Sell(SwapContracts::new(
                         u32::try_from(contracts).expect("decimal to u32"),
+                        contract_size_cents,
                     ))
                 }
             } else if exposure_ratio > self.config.high_bound_ratio_shorting {
                 let target_exposure = abs_liability * self.config.high_safebound_ratio_shorting;
-                let contracts = ((abs_exposure - target_exposure) / CONTRACT_SIZE_CENTS)
+                let contracts = ((abs_exposure - target_exposure) / contract_size_cents)
                     .round()
                     .abs();
                 if contracts.is_zero() {
                     OkexHedgeAdjustment::DoNothing
                 } else {
-                    OkexHedgeAdjustment::Buy(BtcUsdSwapContracts::from(
+                    OkexHedgeAdjustment::Buy(SwapContracts::new(
                         u32::try_from(contracts).expect("decimal to u32"),
+                        contract_size_cents,
                     ))
                 }
             } else {
@@ -128,7 +174,10 @@ impl HedgingAdjustment {
 
 #[cfg(test)]
 mod tests {
+    use rust_decimal_macros::dec;
+
     use super::*;
+    use crate::okex::INVERSE_CONTRACT_SIZE_CENTS;
 
     #[test]
     fn no_adjustment() {
@@ -137,7 +186,8 @@ mod tests {
         };
         let liability = SyntheticCentLiability::try_from(dec!(10000)).unwrap();
         let exposure = SyntheticCentExposure::from(dec!(-10000));
-        let adjustment = hedging_adjustment.determine_action(liability, exposure);
+        let adjustment =
+            hedging_adjustment.determine_action(liability, exposure, INVERSE_CONTRACT_SIZE_CENTS);
         assert_eq!(adjustment, OkexHedgeAdjustment::DoNothing);
     }
 
@@ -148,7 +198,8 @@ mod tests {
         };
         let liability = SyntheticCentLiability::try_from(dec!(0)).unwrap();
         let exposure = SyntheticCentExposure::from(dec!(-10000));
-        let adjustment = hedging_adjustment.determine_action(liability, exposure);
+        let adjustment =
+            hedging_adjustment.determine_action(liability, exposure, INVERSE_CONTRACT_SIZE_CENTS);
         assert_eq!(adjustment, OkexHedgeAdjustment::ClosePosition);
     }
 
@@ -159,10 +210,11 @@ mod tests {
         };
         let liability = SyntheticCentLiability::try_from(dec!(20000)).unwrap();
         let exposure = SyntheticCentExposure::from(dec!(-10000));
-        let adjustment = hedging_adjustment.determine_action(liability, exposure);
+        let adjustment =
+            hedging_adjustment.determine_action(liability, exposure, INVERSE_CONTRACT_SIZE_CENTS);
         assert_eq!(
             adjustment,
-            OkexHedgeAdjustment::Sell(BtcUsdSwapContracts::from(1))
+            OkexHedgeAdjustment::Sell(SwapContracts::from(1))
         );
     }
 
@@ -173,10 +225,11 @@ mod tests {
         };
         let liability = SyntheticCentLiability::try_from(dec!(100000)).unwrap();
         let exposure = SyntheticCentExposure::from(dec!(-599800));
-        let adjustment = hedging_adjustment.determine_action(liability, exposure);
+        let adjustment =
+            hedging_adjustment.determine_action(liability, exposure, INVERSE_CONTRACT_SIZE_CENTS);
         assert_eq!(
             adjustment,
-            OkexHedgeAdjustment::Buy(BtcUsdSwapContracts::from(50))
+            OkexHedgeAdjustment::Buy(SwapContracts::from(50))
         );
     }
 
@@ -187,7 +240,8 @@ mod tests {
         };
         let liability = SyntheticCentLiability::try_from(dec!(10000)).unwrap();
         let exposure = SyntheticCentExposure::from(dec!(-9980));
-        let adjustment = hedging_adjustment.determine_action(liability, exposure);
+        let adjustment =
+            hedging_adjustment.determine_action(liability, exposure, INVERSE_CONTRACT_SIZE_CENTS);
         assert_eq!(adjustment, OkexHedgeAdjustment::DoNothing);
     }
 
@@ -198,10 +252,11 @@ mod tests {
         };
         let liability = SyntheticCentLiability::try_from(dec!(10000)).unwrap();
         let exposure = SyntheticCentExposure::from(dec!(10000));
-        let adjustment = hedging_adjustment.determine_action(liability, exposure);
+        let adjustment =
+            hedging_adjustment.determine_action(liability, exposure, INVERSE_CONTRACT_SIZE_CENTS);
         assert_eq!(
             adjustment,
-            OkexHedgeAdjustment::Sell(BtcUsdSwapContracts::from(2))
+            OkexHedgeAdjustment::Sell(SwapContracts::from(2))
         );
     }
 
@@ -218,7 +273,8 @@ mod tests {
                 * Decimal::NEGATIVE_ONE,
         );
 
-        let adjustment = hedging_adjustment.determine_action(liability, exposure);
+        let adjustment =
+            hedging_adjustment.determine_action(liability, exposure, INVERSE_CONTRACT_SIZE_CENTS);
         assert_eq!(adjustment, OkexHedgeAdjustment::DoNothing);
     }
 
@@ -236,14 +292,16 @@ mod tests {
         );
 
         let expected = liability * hedging_adjustment.config.low_safebound_ratio_shorting;
-        let expected_ct = ((expected - Decimal::from(exposure).abs()) / CONTRACT_SIZE_CENTS)
+        let expected_ct = ((expected - Decimal::from(exposure).abs())
+            / INVERSE_CONTRACT_SIZE_CENTS)
             .round()
             .abs();
 
-        let adjustment = hedging_adjustment.determine_action(liability, exposure);
+        let adjustment =
+            hedging_adjustment.determine_action(liability, exposure, INVERSE_CONTRACT_SIZE_CENTS);
         assert_eq!(
             adjustment,
-            OkexHedgeAdjustment::Sell(BtcUsdSwapContracts::from(
+            OkexHedgeAdjustment::Sell(SwapContracts::from(
                 u32::try_from(expected_ct).expect("decimal to u32")
             ))
         );
@@ -262,7 +320,8 @@ mod tests {
                 * Decimal::NEGATIVE_ONE,
         );
 
-        let adjustment = hedging_adjustment.determine_action(liability, exposure);
+        let adjustment =
+            hedging_adjustment.determine_action(liability, exposure, INVERSE_CONTRACT_SIZE_CENTS);
         assert_eq!(adjustment, OkexHedgeAdjustment::DoNothing);
     }
 
@@ -277,15 +336,18 @@ mod tests {
             * hedging_adjustment.config.high_bound_ratio_shorting;
 
         let expected = liability * hedging_adjustment.config.high_safebound_ratio_shorting;
-        let expected_ct = ((exposure.abs() - expected) / CONTRACT_SIZE_CENTS)
+        let expected_ct = ((exposure.abs() - expected) / INVERSE_CONTRACT_SIZE_CENTS)
             .round()
             .abs();
 
-        let adjustment =
-            hedging_adjustment.determine_action(liability.try_into().unwrap(), exposure.into());
+        let adjustment = hedging_adjustment.determine_action(
+            liability.try_into().unwrap(),
+            exposure.into(),
+            INVERSE_CONTRACT_SIZE_CENTS,
+        );
         assert_eq!(
             adjustment,
-            OkexHedgeAdjustment::Buy(BtcUsdSwapContracts::from(
+            OkexHedgeAdjustment::Buy(SwapContracts::from(
                 u32::try_from(expected_ct).expect("decimal to u32")
             ))
         );
@@ -299,7 +361,8 @@ mod tests {
         let liability = SyntheticCentLiability::try_from(dec!(4900)).unwrap();
         let exposure = SyntheticCentExposure::from(dec!(-19998));
 
-        let adjustment = hedging_adjustment.determine_action(liability, exposure);
+        let adjustment =
+            hedging_adjustment.determine_action(liability, exposure, INVERSE_CONTRACT_SIZE_CENTS);
         assert_eq!(adjustment, OkexHedgeAdjustment::ClosePosition);
     }
 
@@ -311,7 +374,8 @@ mod tests {
         let liability = SyntheticCentLiability::try_from(dec!(4900)).unwrap();
         let exposure = SyntheticCentExposure::from(dec!(0));
 
-        let adjustment = hedging_adjustment.determine_action(liability, exposure);
+        let adjustment =
+            hedging_adjustment.determine_action(liability, exposure, INVERSE_CONTRACT_SIZE_CENTS);
         assert_eq!(adjustment, OkexHedgeAdjustment::DoNothing);
     }
 
@@ -324,10 +388,11 @@ mod tests {
         let exposure = SyntheticCentExposure::from(dec!(-19998));
         let expected_ct = 1;
 
-        let adjustment = hedging_adjustment.determine_action(liability, exposure);
+        let adjustment =
+            hedging_adjustment.determine_action(liability, exposure, INVERSE_CONTRACT_SIZE_CENTS);
         assert_eq!(
             adjustment,
-            OkexHedgeAdjustment::Buy(BtcUsdSwapContracts::from(
+            OkexHedgeAdjustment::Buy(SwapContracts::from(
                 u32::try_from(expected_ct).expect("decimal to u32")
             ))
         );
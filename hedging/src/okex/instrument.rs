@@ -0,0 +1,258 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use sqlx::PgPool;
+
+use std::sync::{Arc, RwLock};
+
+use okex_client::{FundingRate, OkexInstrumentId, TradeCurrency};
+
+use super::OkexInstrumentConfig;
+use crate::error::HedgingError;
+
+pub const INVERSE_CONTRACT_SIZE_CENTS: Decimal = dec!(10000);
+pub const LINEAR_CONTRACT_SIZE_BTC: Decimal = dec!(0.01);
+
+/// Notional of a single contract. Linear contracts are sized in BTC so their
+/// value in cents moves with the price.
+pub fn contract_size_cents(
+    instrument_id: OkexInstrumentId,
+    last_price_in_usd_cents: Decimal,
+) -> Decimal {
+    match instrument_id {
+        OkexInstrumentId::BtcUsdSwap => INVERSE_CONTRACT_SIZE_CENTS,
+        OkexInstrumentId::BtcUsdtSwap => LINEAR_CONTRACT_SIZE_BTC * last_price_in_usd_cents,
+    }
+}
+
+/// Tracks which swap currently holds the hedge
+#[derive(Debug, Clone)]
+pub struct InstrumentSelection {
+    config: OkexInstrumentConfig,
+    active: Arc<RwLock<OkexInstrumentId>>,
+}
+
+impl InstrumentSelection {
+    pub fn new(config: OkexInstrumentConfig) -> Self {
+        Self {
+            active: Arc::new(RwLock::new(config.primary)),
+            config,
+        }
+    }
+
+    /// Picks up the instrument the last switch persisted, so a restart keeps
+    /// hedging on the swap that holds the position
+    pub async fn load(pool: &PgPool, config: OkexInstrumentConfig) -> Result<Self, HedgingError> {
+        let selection = Self::new(config);
+        let persisted = sqlx::query_scalar!(
+            "SELECT active_instrument FROM okex_instrument_selection WHERE id = TRUE"
+        )
+        .fetch_optional(pool)
+        .await?;
+        if let Some(instrument_id) = persisted {
+            let instrument_id: OkexInstrumentId = instrument_id.parse()?;
+            if selection.candidates().contains(&instrument_id) {
+                *selection
+                    .active
+                    .write()
+                    .expect("instrument selection lock poisoned") = instrument_id;
+            }
+        }
+        Ok(selection)
+    }
+
+    pub async fn persist(&self, pool: &PgPool) -> Result<(), HedgingError> {
+        sqlx::query!(
+            r#"INSERT INTO okex_instrument_selection (active_instrument)
+               VALUES ($1)
+               ON CONFLICT (id) DO UPDATE
+               SET active_instrument = EXCLUDED.active_instrument, updated_at = NOW()"#,
+            self.active().to_string()
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub fn active(&self) -> OkexInstrumentId {
+        *self
+            .active
+            .read()
+            .expect("instrument selection lock poisoned")
+    }
+
+    pub fn candidates(&self) -> Vec<OkexInstrumentId> {
+        std::iter::once(self.config.primary)
+            .chain(self.config.alternative)
+            .collect()
+    }
+
+    pub fn inactive(&self) -> Vec<OkexInstrumentId> {
+        let active = self.active();
+        self.candidates()
+            .into_iter()
+            .filter(|candidate| *candidate != active)
+            .collect()
+    }
+
+    /// The hedge is short so it earns the funding rate. Moves to the candidate
+    /// paying the most once it beats the active one by `min_funding_rate_spread`.
+    /// Nothing converts BTC into USDT, so a USDT margined candidate is only
+    /// considered once the trading account already holds enough USDT to carry it.
+    pub fn select(&self, rates: &[FundingRate], usdt_margin: UsdtMargin) -> OkexInstrumentId {
+        let mut active = self
+            .active
+            .write()
+            .expect("instrument selection lock poisoned");
+        let rate_of = |instrument_id: OkexInstrumentId| {
+            rates
+                .iter()
+                .find(|rate| rate.instrument_id == instrument_id)
+                .map(|rate| rate.rate)
+        };
+        if let Some(active_rate) = rate_of(*active) {
+            let best = self
+                .candidates()
+                .into_iter()
+                .filter(|candidate| {
+                    *candidate == *active
+                        || candidate.margin_currency() != TradeCurrency::USDT
+                        || usdt_margin.is_sufficient()
+                })
+                .filter_map(|candidate| rate_of(candidate).map(|rate| (candidate, rate)))
+                .max_by_key(|(_, rate)| *rate);
+            if let Some((candidate, rate)) = best {
+                if rate - active_rate > self.config.min_funding_rate_spread {
+                    *active = candidate;
+                }
+            }
+        }
+        *active
+    }
+}
+
+/// USDT held in the trading account against what the hedge would need on a
+/// USDT margined instrument, both in cents
+#[derive(Debug, Clone, Copy)]
+pub struct UsdtMargin {
+    pub available_cents: Decimal,
+    pub required_cents: Decimal,
+}
+
+impl UsdtMargin {
+    fn is_sufficient(&self) -> bool {
+        self.available_cents >= self.required_cents
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn rate(instrument_id: OkexInstrumentId, rate: Decimal) -> FundingRate {
+        FundingRate {
+            instrument_id,
+            rate,
+            funding_time: Utc::now(),
+        }
+    }
+
+    const FUNDED: UsdtMargin = UsdtMargin {
+        available_cents: dec!(1_000_000),
+        required_cents: dec!(1_000_000),
+    };
+
+    fn selection() -> InstrumentSelection {
+        InstrumentSelection::new(OkexInstrumentConfig {
+            primary: OkexInstrumentId::BtcUsdSwap,
+            alternative: Some(OkexInstrumentId::BtcUsdtSwap),
+            min_funding_rate_spread: dec!(0.0001),
+        })
+    }
+
+    #[test]
+    fn contract_sizes() {
+        assert_eq!(
+            contract_size_cents(OkexInstrumentId::BtcUsdSwap, dec!(4_000_000)),
+            dec!(10_000)
+        );
+        assert_eq!(
+            contract_size_cents(OkexInstrumentId::BtcUsdtSwap, dec!(4_000_000)),
+            dec!(40_000)
+        );
+    }
+
+    #[test]
+    fn switches_once_the_spread_is_exceeded() {
+        let selection = selection();
+        assert_eq!(selection.inactive(), vec![OkexInstrumentId::BtcUsdtSwap]);
+
+        let rates = [
+            rate(OkexInstrumentId::BtcUsdSwap, dec!(0.0001)),
+            rate(OkexInstrumentId::BtcUsdtSwap, dec!(0.00015)),
+        ];
+        assert_eq!(
+            selection.select(&rates, FUNDED),
+            OkexInstrumentId::BtcUsdSwap
+        );
+
+        let rates = [
+            rate(OkexInstrumentId::BtcUsdSwap, dec!(0.0001)),
+            rate(OkexInstrumentId::BtcUsdtSwap, dec!(0.0003)),
+        ];
+        assert_eq!(
+            selection.select(&rates, FUNDED),
+            OkexInstrumentId::BtcUsdtSwap
+        );
+        assert_eq!(selection.active(), OkexInstrumentId::BtcUsdtSwap);
+        assert_eq!(selection.inactive(), vec![OkexInstrumentId::BtcUsdSwap]);
+
+        let rates = [
+            rate(OkexInstrumentId::BtcUsdSwap, dec!(0.00035)),
+            rate(OkexInstrumentId::BtcUsdtSwap, dec!(0.0003)),
+        ];
+        assert_eq!(
+            selection.select(&rates, FUNDED),
+            OkexInstrumentId::BtcUsdtSwap
+        );
+    }
+
+    #[test]
+    fn refuses_a_usdt_margined_instrument_without_usdt_margin() {
+        let selection = selection();
+        let rates = [
+            rate(OkexInstrumentId::BtcUsdSwap, dec!(0.0001)),
+            rate(OkexInstrumentId::BtcUsdtSwap, dec!(0.0003)),
+        ];
+        let unfunded = UsdtMargin {
+            available_cents: dec!(999_999),
+            required_cents: dec!(1_000_000),
+        };
+        assert_eq!(
+            selection.select(&rates, unfunded),
+            OkexInstrumentId::BtcUsdSwap
+        );
+        assert_eq!(
+            selection.select(&rates, FUNDED),
+            OkexInstrumentId::BtcUsdtSwap
+        );
+
+        // Already on the linear swap it is kept even once the margin runs short
+        assert_eq!(
+            selection.select(&rates, unfunded),
+            OkexInstrumentId::BtcUsdtSwap
+        );
+    }
+
+    #[test]
+    fn stays_put_without_an_alternative_or_rates() {
+        let selection = InstrumentSelection::new(OkexInstrumentConfig::default());
+        assert!(selection.inactive().is_empty());
+        let rates = [rate(OkexInstrumentId::BtcUsdtSwap, dec!(0.01))];
+        assert_eq!(
+            selection.select(&rates, FUNDED),
+            OkexInstrumentId::BtcUsdSwap
+        );
+    }
+}
@@ -3,31 +3,46 @@ use rust_decimal_macros::dec;
 use tracing::instrument;
 
 use bria_client::*;
-use okex_client::*;
 use shared::pubsub::CorrelationId;
 
-use crate::{error::*, okex::*};
+use crate::{
+    control::*, decisions::HedgingDecisions, error::*,
+    liability_watermark::LiabilityWatermarkCheck, lightning::*, okex::*, risk::RiskGuard, venue::*,
+};
 
 const SATS_PER_BTC: Decimal = dec!(100_000_000);
 
 #[allow(clippy::too_many_arguments)]
 #[instrument(name = "hedging.okex.job.adjust_funding", skip_all, fields(correlation_id = %correlation_id,
-        target_liability, current_position, last_price_in_usd_cents, funding_available_balance,
-        trading_available_balance, onchain_fees, action, client_transfer_id,
-        amount_with_jitter,
-        transferred_funding, lag_ok), err)]
-pub(super) async fn execute(
+        target_liability, current_position, btc_margined_exposure, last_price_in_usd_cents,
+        funding_available_balance, trading_available_balance, onchain_fees, action, funding_rail, client_transfer_id,
+        transferred_funding, shadow_mode, liability_fresh, halted, paused), err)]
+pub async fn execute(
     correlation_id: CorrelationId,
-    pool: &sqlx::PgPool,
+    liability_watermark: &LiabilityWatermarkCheck,
+    risk_guard: &RiskGuard,
+    process_control: &ProcessControl,
+    hedging_decisions: &HedgingDecisions,
     ledger: ledger::Ledger,
-    okex: OkexClient,
+    venue: SharedVenue,
     okex_transfers: OkexTransfers,
     bria: &mut BriaClient,
     funding_adjustment: FundingAdjustment,
+    funding_rails: FundingRails,
+    shadow_mode: bool,
 ) -> Result<(), HedgingError> {
     let span = tracing::Span::current();
-    if !crate::hack_user_trades_lag::lag_ok(pool).await? {
-        span.record("lag_ok", &tracing::field::display(false));
+    span.record("shadow_mode", shadow_mode);
+    if !liability_watermark.is_fresh().await? {
+        span.record("liability_fresh", tracing::field::display(false));
+        return Ok(());
+    }
+    if let Some(trip) = risk_guard.halted().await? {
+        span.record("halted", tracing::field::display(&trip.reason));
+        return Ok(());
+    }
+    if let Some(pause) = process_control.paused(HedgingProcess::Funding).await? {
+        span.record("paused", tracing::field::display(&pause.reason));
         return Ok(());
     }
 
@@ -41,7 +56,7 @@ pub(super) async fn execute(
         &tracing::field::display(target_liability_in_cents),
     );
 
-    let current_position = okex.get_position_in_signed_usd_cents().await?;
+    let current_position = venue.position().await?;
     span.record(
         "current_position",
         &tracing::field::display(current_position.usd_cents),
@@ -49,7 +64,7 @@ pub(super) async fn execute(
 
     let mut last_price_in_usd_cents = current_position.last_price_in_usd_cents;
     if last_price_in_usd_cents.is_zero() {
-        last_price_in_usd_cents = okex.get_last_price_in_usd_cents().await?.usd_cents;
+        last_price_in_usd_cents = venue.last_price_in_usd_cents().await?;
     }
 
     span.record(
@@ -57,32 +72,43 @@ pub(super) async fn execute(
         &tracing::field::display(last_price_in_usd_cents),
     );
 
-    let funding_available_balance = okex.funding_account_balance().await?;
+    let funding_available_balance = venue.funding_balance().await?;
     span.record(
         "funding_available_balance",
         &tracing::field::display(&funding_available_balance),
     );
 
-    let trading_available_balance = okex.trading_account_balance().await?;
+    let trading_available_balance = venue.trading_balance().await?;
     span.record(
         "trading_available_balance",
         &tracing::field::display(&trading_available_balance),
     );
-    let action = funding_adjustment.determine_action(
+    let margin_currency = venue.margin_currency(&current_position.instrument_id)?;
+    let btc_margined_exposure = venue.btc_margined_exposure(&current_position).await?;
+    span.record(
+        "btc_margined_exposure",
+        &tracing::field::display(btc_margined_exposure),
+    );
+    let (action, decision) = funding_adjustment.evaluate(
+        "adjust_funding",
+        correlation_id,
         target_liability_in_cents,
-        current_position.usd_cents.into(),
+        btc_margined_exposure.into(),
         trading_available_balance.total_amt_in_btc,
         last_price_in_usd_cents,
         funding_available_balance.total_amt_in_btc,
+        venue.contract_size_cents().await?,
+        margin_currency,
     );
+    hedging_decisions.record(decision).await?;
     span.record("action", &tracing::field::display(&action));
+    process_control.record_action(HedgingProcess::Funding, correlation_id, &action);
 
-    let fees = okex.get_onchain_fees().await?;
-    span.record("onchain_fees", &tracing::field::display(&fees));
+    let withdrawal_fee = venue.withdrawal_fee().await?;
+    span.record("onchain_fees", &tracing::field::display(withdrawal_fee));
 
     let shared = TransferReservationSharedData {
         correlation_id,
-        action_type: action.action_type().to_string(),
         action_unit: action.unit().to_string(),
         target_usd_exposure: target_liability_in_cents.into(),
         current_usd_exposure: current_position.usd_cents.abs(),
@@ -98,13 +124,23 @@ pub(super) async fn execute(
             match action {
                 OkexFundingAdjustment::TransferTradingToFunding(amount) => {
                     let reservation = TransferReservation {
+                        kind: OkexTransferKind::TradingToFunding,
                         shared: &shared,
                         action_size: action.size(),
                         fee: Decimal::ZERO,
                         transfer_from: "trading".to_string(),
                         transfer_to: "funding".to_string(),
                     };
-                    if let Some(client_id) =
+                    if shadow_mode {
+                        if let Some(client_id) =
+                            okex_transfers.record_shadow_transfer(reservation).await?
+                        {
+                            span.record(
+                                "client_transfer_id",
+                                tracing::field::display(String::from(client_id)),
+                            );
+                        }
+                    } else if let Some(client_id) =
                         okex_transfers.reserve_transfer_slot(reservation).await?
                     {
                         span.record(
@@ -112,18 +148,33 @@ pub(super) async fn execute(
                             &tracing::field::display(String::from(client_id.clone())),
                         );
 
-                        let _ = okex.transfer_trading_to_funding(client_id, amount).await?;
+                        okex_transfers
+                            .submit(
+                                client_id.clone(),
+                                venue.transfer_trading_to_funding(client_id, amount),
+                            )
+                            .await?;
                     }
                 }
                 OkexFundingAdjustment::TransferFundingToTrading(amount) => {
                     let reservation = TransferReservation {
+                        kind: OkexTransferKind::FundingToTrading,
                         shared: &shared,
                         action_size: Some(amount),
                         fee: Decimal::ZERO,
                         transfer_from: "funding".to_string(),
                         transfer_to: "trading".to_string(),
                     };
-                    if let Some(client_id) =
+                    if shadow_mode {
+                        if let Some(client_id) =
+                            okex_transfers.record_shadow_transfer(reservation).await?
+                        {
+                            span.record(
+                                "client_transfer_id",
+                                tracing::field::display(String::from(client_id)),
+                            );
+                        }
+                    } else if let Some(client_id) =
                         okex_transfers.reserve_transfer_slot(reservation).await?
                     {
                         span.record(
@@ -131,76 +182,230 @@ pub(super) async fn execute(
                             &tracing::field::display(String::from(client_id.clone())),
                         );
 
-                        let _ = okex.transfer_funding_to_trading(client_id, amount).await?;
+                        okex_transfers
+                            .submit(
+                                client_id.clone(),
+                                venue.transfer_funding_to_trading(client_id, amount),
+                            )
+                            .await?;
                     }
                 }
                 OkexFundingAdjustment::OnchainDeposit(amount) => {
-                    if okex.is_simulated() {
+                    if !shadow_mode && venue.is_simulated() {
                         return Ok(());
                     }
 
-                    let amount_with_jitter = {
-                        use rand::Rng;
-                        let mut rng = rand::thread_rng();
-                        let jitter: i32 = rng.gen_range(1..=1000);
-                        amount + (Decimal::from(jitter) / SATS_PER_BTC)
-                    };
-                    let deposit_address = okex.get_funding_deposit_address().await?.value;
-                    let reservation = TransferReservation {
-                        shared: &shared,
-                        action_size: Some(amount_with_jitter),
-                        fee: Decimal::ZERO,
-                        transfer_from: "galoy".to_string(),
-                        transfer_to: deposit_address.clone(),
-                    };
-                    if let Some(client_id) =
-                        okex_transfers.reserve_transfer_slot(reservation).await?
-                    {
-                        let client_transfer_id = String::from(client_id.clone());
-                        span.record("client_transfer_id", &client_transfer_id);
-                        span.record(
-                            "amount_with_jitter",
-                            &tracing::field::display(amount_with_jitter),
-                        );
-
-                        let amount_in_sats = amount_with_jitter * SATS_PER_BTC;
-                        bria.send_onchain_payment(
-                            deposit_address,
-                            amount_in_sats,
-                            client_transfer_id,
+                    let urgency = funding_adjustment.urgency(
+                        btc_margined_exposure.into(),
+                        trading_available_balance.total_amt_in_btc,
+                        last_price_in_usd_cents,
+                    );
+                    let (rail, payer) = funding_rails.choose(amount, urgency);
+                    span.record("funding_rail", tracing::field::display(rail));
+                    if let Some(payer) = payer {
+                        lightning_deposit(
+                            &venue,
+                            &okex_transfers,
+                            payer,
+                            &shared,
+                            amount,
+                            shadow_mode,
                         )
                         .await?;
+                    } else {
+                        let deposit_address = venue.deposit_address().await?;
+                        let reservation = TransferReservation {
+                            kind: OkexTransferKind::Deposit,
+                            shared: &shared,
+                            action_size: Some(amount),
+                            fee: Decimal::ZERO,
+                            transfer_from: "galoy".to_string(),
+                            transfer_to: deposit_address.clone(),
+                        };
+                        if shadow_mode {
+                            if let Some(client_id) =
+                                okex_transfers.record_shadow_transfer(reservation).await?
+                            {
+                                span.record(
+                                    "client_transfer_id",
+                                    tracing::field::display(String::from(client_id)),
+                                );
+                            }
+                        } else if let Some(client_id) =
+                            okex_transfers.reserve_transfer_slot(reservation).await?
+                        {
+                            let client_transfer_id = String::from(client_id.clone());
+                            span.record("client_transfer_id", &client_transfer_id);
+
+                            let amount_in_sats = amount * SATS_PER_BTC;
+                            okex_transfers
+                                .submit(client_id, async {
+                                    bria.send_onchain_payment(
+                                        deposit_address,
+                                        amount_in_sats,
+                                        client_transfer_id,
+                                    )
+                                    .await?;
+                                    Ok(())
+                                })
+                                .await?;
+                        }
                     }
                 }
                 OkexFundingAdjustment::OnchainWithdraw(amount) => {
-                    if okex.is_simulated() {
+                    if !shadow_mode && venue.is_simulated() {
                         return Ok(());
                     }
 
-                    let deposit_address = bria.onchain_address().await?.address;
-                    let reservation = TransferReservation {
-                        shared: &shared,
-                        action_size: Some(amount),
-                        fee: fees.min_fee,
-                        transfer_from: "okx".to_string(),
-                        transfer_to: deposit_address.clone(),
-                    };
-                    if let Some(client_id) =
-                        okex_transfers.reserve_transfer_slot(reservation).await?
-                    {
-                        span.record(
-                            "client_transfer_id",
-                            &tracing::field::display(String::from(client_id.clone())),
-                        );
+                    let (rail, payer) = funding_rails.choose(amount, FundingUrgency::Routine);
+                    span.record("funding_rail", tracing::field::display(rail));
+                    if let Some(payer) = payer {
+                        lightning_withdraw(
+                            &venue,
+                            &okex_transfers,
+                            payer,
+                            &shared,
+                            amount,
+                            shadow_mode,
+                        )
+                        .await?;
+                    } else {
+                        let deposit_address = if shadow_mode {
+                            "bria".to_string()
+                        } else {
+                            bria.onchain_address().await?.address
+                        };
+                        let reservation = TransferReservation {
+                            kind: OkexTransferKind::Withdraw,
+                            shared: &shared,
+                            action_size: Some(amount),
+                            fee: withdrawal_fee,
+                            transfer_from: "okx".to_string(),
+                            transfer_to: deposit_address.clone(),
+                        };
+                        if shadow_mode {
+                            if let Some(client_id) =
+                                okex_transfers.record_shadow_transfer(reservation).await?
+                            {
+                                span.record(
+                                    "client_transfer_id",
+                                    tracing::field::display(String::from(client_id)),
+                                );
+                            }
+                        } else if let Some(client_id) =
+                            okex_transfers.reserve_transfer_slot(reservation).await?
+                        {
+                            span.record(
+                                "client_transfer_id",
+                                &tracing::field::display(String::from(client_id.clone())),
+                            );
 
-                        okex.withdraw_btc_onchain(client_id, amount, fees.min_fee, deposit_address)
-                            .await?;
+                            okex_transfers
+                                .submit(
+                                    client_id.clone(),
+                                    venue.withdraw_btc_onchain(
+                                        client_id,
+                                        amount,
+                                        withdrawal_fee,
+                                        deposit_address,
+                                    ),
+                                )
+                                .await?;
+                        }
                     }
                 }
                 _ => unreachable!(),
             }
-            span.record("transferred_funding", &tracing::field::display(true));
+            span.record("transferred_funding", tracing::field::display(!shadow_mode));
+        }
+    };
+    Ok(())
+}
+
+/// Pays the invoice okex issues from our own wallet, a settled payment
+/// leaves nothing to wait for
+async fn lightning_deposit(
+    venue: &SharedVenue,
+    okex_transfers: &OkexTransfers,
+    payer: SharedLightningPayer,
+    shared: &TransferReservationSharedData,
+    amount: Decimal,
+    shadow_mode: bool,
+) -> Result<(), HedgingError> {
+    let span = tracing::Span::current();
+    let reservation = TransferReservation {
+        kind: OkexTransferKind::LightningDeposit,
+        shared,
+        action_size: Some(amount),
+        fee: Decimal::ZERO,
+        transfer_from: "galoy".to_string(),
+        transfer_to: "okx-lightning".to_string(),
+    };
+    if shadow_mode {
+        if let Some(client_id) = okex_transfers.record_shadow_transfer(reservation).await? {
+            span.record(
+                "client_transfer_id",
+                tracing::field::display(String::from(client_id)),
+            );
+        }
+    } else if let Some(client_id) = okex_transfers.reserve_transfer_slot(reservation).await? {
+        let client_transfer_id = String::from(client_id.clone());
+        span.record("client_transfer_id", &client_transfer_id);
+
+        let payment = okex_transfers
+            .submit(client_id.clone(), async {
+                let invoice = venue.lightning_deposit_invoice(amount).await?;
+                payer.pay_invoice(invoice, client_transfer_id).await
+            })
+            .await?;
+        if payment == LightningPayment::Settled {
+            okex_transfers.mark_as_settled(client_id).await?;
         }
+    }
+    Ok(())
+}
+
+/// Has okex pay an invoice from our own wallet, the withdrawal is then
+/// followed by the id okex returns
+async fn lightning_withdraw(
+    venue: &SharedVenue,
+    okex_transfers: &OkexTransfers,
+    payer: SharedLightningPayer,
+    shared: &TransferReservationSharedData,
+    amount: Decimal,
+    shadow_mode: bool,
+) -> Result<(), HedgingError> {
+    let span = tracing::Span::current();
+    let reservation = TransferReservation {
+        kind: OkexTransferKind::LightningWithdraw,
+        shared,
+        action_size: Some(amount),
+        fee: Decimal::ZERO,
+        transfer_from: "okx".to_string(),
+        transfer_to: "galoy-lightning".to_string(),
     };
+    if shadow_mode {
+        if let Some(client_id) = okex_transfers.record_shadow_transfer(reservation).await? {
+            span.record(
+                "client_transfer_id",
+                tracing::field::display(String::from(client_id)),
+            );
+        }
+    } else if let Some(client_id) = okex_transfers.reserve_transfer_slot(reservation).await? {
+        let client_transfer_id = String::from(client_id.clone());
+        span.record("client_transfer_id", &client_transfer_id);
+
+        let withdraw_id = okex_transfers
+            .submit(client_id.clone(), async {
+                let invoice = payer.create_invoice(amount, client_transfer_id).await?;
+                venue
+                    .withdraw_lightning(client_id.clone(), amount, invoice)
+                    .await
+            })
+            .await?;
+        okex_transfers
+            .attach_withdraw_id(client_id, withdraw_id)
+            .await?;
+    }
     Ok(())
 }
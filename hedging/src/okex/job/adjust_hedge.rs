@@ -1,24 +1,44 @@
 use rust_decimal::Decimal;
 use tracing::instrument;
 
-use okex_client::*;
+use okex_client::{ClientOrderId, OkexOrderType};
 use shared::pubsub::CorrelationId;
 
-use crate::{error::*, okex::*};
+use crate::{
+    control::*, decisions::HedgingDecisions, error::*,
+    liability_watermark::LiabilityWatermarkCheck, okex::*, risk::RiskGuard, venue::*,
+};
 
+#[allow(clippy::too_many_arguments)]
 #[instrument(name = "hedging.okex.job.adjust_hedge", skip_all, fields(correlation_id = %correlation_id,
-        target_liability, current_position, action, placed_order, client_order_id, lag_ok), err)]
-pub(super) async fn execute(
+        target_liability, current_position, action, placed_order, client_order_id, parent_order_id, switching_from, shadow_mode, liability_fresh, halted, paused), err)]
+pub async fn execute(
     correlation_id: CorrelationId,
     pool: &sqlx::PgPool,
+    liability_watermark: &LiabilityWatermarkCheck,
+    risk_guard: &RiskGuard,
+    process_control: &ProcessControl,
+    hedging_decisions: &HedgingDecisions,
     ledger: ledger::Ledger,
-    okex: OkexClient,
+    venue: SharedVenue,
     okex_orders: OkexOrders,
     hedging_adjustment: HedgingAdjustment,
+    execution_planner: ExecutionPlanner,
+    order_placement: OrderPlacement,
+    shadow_mode: bool,
 ) -> Result<(), HedgingError> {
     let span = tracing::Span::current();
-    if !crate::hack_user_trades_lag::lag_ok(pool).await? {
-        span.record("lag_ok", &tracing::field::display(false));
+    span.record("shadow_mode", shadow_mode);
+    if !liability_watermark.is_fresh().await? {
+        span.record("liability_fresh", tracing::field::display(false));
+        return Ok(());
+    }
+    if let Some(trip) = risk_guard.halted().await? {
+        span.record("halted", tracing::field::display(&trip.reason));
+        return Ok(());
+    }
+    if let Some(pause) = process_control.paused(HedgingProcess::Hedging).await? {
+        span.record("paused", tracing::field::display(&pause.reason));
         return Ok(());
     }
     let target_liability = ledger
@@ -30,47 +50,186 @@ pub(super) async fn execute(
         "target_liability",
         &tracing::field::display(target_liability),
     );
-    let current_position = okex.get_position_in_signed_usd_cents().await?.usd_cents;
+    let VenuePosition {
+        instrument_id,
+        usd_cents: current_position,
+        ..
+    } = venue.position().await?;
     span.record(
         "current_position",
         &tracing::field::display(current_position),
     );
 
-    let action = hedging_adjustment.determine_action(target_liability, current_position.into());
+    let contract_size_cents = venue.contract_size_cents().await?;
+    let (action, decision) = hedging_adjustment.evaluate(
+        "adjust_hedge",
+        correlation_id,
+        target_liability,
+        current_position.into(),
+        contract_size_cents,
+    );
+    hedging_decisions.record(decision).await?;
     span.record("action", &tracing::field::display(&action));
+    process_control.record_action(HedgingProcess::Hedging, correlation_id, &action);
+    let target_usd_value = target_liability * Decimal::NEGATIVE_ONE;
+    if !shadow_mode {
+        if let Some(parent) = okex_orders.open_parent_order().await? {
+            let stale = execution_planner.is_stale(&parent, chrono::Utc::now());
+            if parent.target_usd_value == target_usd_value && !stale {
+                span.record(
+                    "parent_order_id",
+                    tracing::field::display(String::from(parent.id)),
+                );
+                span.record("placed_order", tracing::field::display(false));
+                return Ok(());
+            }
+            okex_orders
+                .complete_parent_order(&parent.id, if stale { "stale" } else { "superseded" })
+                .await?;
+        }
+    }
+    // Moving to another instrument is tracked as one sliced adjustment that
+    // unwinds the residual position while it rebuilds the hedge
+    if let Some(residual) = venue.residual_position().await? {
+        span.record(
+            "switching_from",
+            tracing::field::display(&residual.instrument_id),
+        );
+        let reservation = OrderReservation {
+            correlation_id,
+            instrument: residual.instrument_id.clone(),
+            action: &OkexHedgeAdjustment::ClosePosition,
+            target_usd_value,
+            usd_value_before_order: residual.usd_cents,
+        };
+        if shadow_mode {
+            if let Some(order_id) = okex_orders.record_shadow_order(reservation).await? {
+                span.record(
+                    "client_order_id",
+                    tracing::field::display(String::from(order_id)),
+                );
+            }
+        } else if let Some(parent_id) = okex_orders.reserve_parent_order(reservation).await? {
+            span.record(
+                "parent_order_id",
+                tracing::field::display(String::from(parent_id.clone())),
+            );
+            super::spawn_execute_hedge_slice(
+                pool,
+                correlation_id,
+                parent_id,
+                std::time::Duration::ZERO,
+            )
+            .await?;
+        }
+        span.record("placed_order", tracing::field::display(false));
+        return Ok(());
+    }
     match action {
         OkexHedgeAdjustment::DoNothing => {}
         _ => {
             let reservation = OrderReservation {
                 correlation_id,
+                instrument: instrument_id,
                 action: &action,
-                target_usd_value: target_liability * Decimal::NEGATIVE_ONE,
+                target_usd_value,
                 usd_value_before_order: current_position,
             };
-            if let Some(order_id) = okex_orders.reserve_order_slot(reservation).await? {
+            if shadow_mode {
+                if let Some(order_id) = okex_orders.record_shadow_order(reservation).await? {
+                    span.record(
+                        "client_order_id",
+                        tracing::field::display(String::from(order_id)),
+                    );
+                }
+                span.record("placed_order", tracing::field::display(false));
+            } else if execution_planner.requires_slicing(
+                &action,
+                current_position.into(),
+                contract_size_cents,
+            ) {
+                if let Some(parent_id) = okex_orders.reserve_parent_order(reservation).await? {
+                    span.record(
+                        "parent_order_id",
+                        tracing::field::display(String::from(parent_id.clone())),
+                    );
+                    super::spawn_execute_hedge_slice(
+                        pool,
+                        correlation_id,
+                        parent_id,
+                        std::time::Duration::ZERO,
+                    )
+                    .await?;
+                }
+                span.record("placed_order", tracing::field::display(false));
+            } else if !within_risk_limits(risk_guard, &action, current_position).await? {
+                span.record(
+                    "halted",
+                    tracing::field::display("order exceeds risk limits"),
+                );
+                span.record("placed_order", tracing::field::display(false));
+            } else if let Some(order_id) = okex_orders.reserve_order_slot(reservation).await? {
                 span.record(
                     "client_order_id",
-                    &tracing::field::display(String::from(order_id.clone())),
+                    tracing::field::display(String::from(order_id.clone())),
                 );
-                match action {
-                    OkexHedgeAdjustment::ClosePosition => {
-                        okex.close_positions(order_id).await?;
-                    }
-                    OkexHedgeAdjustment::Sell(ref contracts) => {
-                        okex.place_order(order_id, OkexOrderSide::Sell, contracts)
-                            .await?;
-                    }
-                    OkexHedgeAdjustment::Buy(ref contracts) => {
-                        okex.place_order(order_id, OkexOrderSide::Buy, contracts)
-                            .await?;
-                    }
-                    _ => unreachable!(),
-                }
-                span.record("placed_order", &tracing::field::display(true));
+                place_order(
+                    pool,
+                    venue.as_ref(),
+                    &okex_orders,
+                    &order_placement,
+                    order_id,
+                    &action,
+                )
+                .await?;
+                span.record("placed_order", tracing::field::display(true));
             } else {
-                span.record("placed_order", &tracing::field::display(false));
+                span.record("placed_order", tracing::field::display(false));
             }
         }
     };
     Ok(())
 }
+
+pub(super) async fn within_risk_limits(
+    risk_guard: &RiskGuard,
+    action: &OkexHedgeAdjustment,
+    position_before_cents: Decimal,
+) -> Result<bool, HedgingError> {
+    match action.proposed_order(position_before_cents) {
+        Some(order) => risk_guard.check_order(order).await,
+        None => Ok(true),
+    }
+}
+
+pub(super) async fn place_order(
+    pool: &sqlx::PgPool,
+    venue: &dyn HedgingVenue,
+    okex_orders: &OkexOrders,
+    order_placement: &OrderPlacement,
+    order_id: ClientOrderId,
+    action: &OkexHedgeAdjustment,
+) -> Result<(), HedgingError> {
+    let (side, contracts) = match action {
+        OkexHedgeAdjustment::ClosePosition => {
+            venue.close_position(order_id).await?;
+            return Ok(());
+        }
+        OkexHedgeAdjustment::Sell(ref contracts) => (OrderSide::Sell, contracts),
+        OkexHedgeAdjustment::Buy(ref contracts) => (OrderSide::Buy, contracts),
+        OkexHedgeAdjustment::DoNothing => unreachable!(),
+    };
+    if let Some(price) = order_placement.passive_price(&side) {
+        okex_orders
+            .record_passive_order(&order_id, &OkexOrderType::PostOnly.to_string(), price)
+            .await?;
+        venue
+            .place_post_only_order(order_id.clone(), side, contracts, price)
+            .await?;
+        super::spawn_manage_passive_order(pool, order_id, order_placement.reprice_interval())
+            .await?;
+    } else {
+        venue.place_market_order(order_id, side, contracts).await?;
+    }
+    Ok(())
+}
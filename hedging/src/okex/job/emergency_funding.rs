@@ -0,0 +1,99 @@
+use rust_decimal::Decimal;
+use tracing::instrument;
+
+use okex_client::TradeCurrency;
+use shared::pubsub::CorrelationId;
+
+use crate::{error::*, okex::*, venue::*};
+
+/// Moves everything available in the funding account to trading.
+/// Unlike adjust_funding this doesn't wait for a fresh liability watermark
+/// or a reset circuit breaker, the position is about to be liquidated.
+/// BTC doesn't margin the linear swap, so nothing is moved while it is active.
+#[instrument(name = "hedging.okex.job.emergency_funding", skip_all, fields(correlation_id = %correlation_id,
+        current_position, funding_available_balance, trading_available_balance,
+        amount, client_transfer_id, shadow_mode, funding_exhausted, margin_currency), err)]
+pub(super) async fn execute(
+    correlation_id: CorrelationId,
+    ledger: ledger::Ledger,
+    venue: SharedVenue,
+    okex_transfers: OkexTransfers,
+    shadow_mode: bool,
+) -> Result<(), HedgingError> {
+    let span = tracing::Span::current();
+    span.record("shadow_mode", shadow_mode);
+
+    let current_position = venue.position().await?;
+    span.record(
+        "current_position",
+        tracing::field::display(current_position.usd_cents),
+    );
+    let margin_currency = venue.margin_currency(&current_position.instrument_id)?;
+    span.record("margin_currency", tracing::field::display(&margin_currency));
+    if margin_currency != TradeCurrency::BTC {
+        return Ok(());
+    }
+
+    let funding_available_balance = venue.funding_balance().await?;
+    span.record(
+        "funding_available_balance",
+        tracing::field::display(&funding_available_balance),
+    );
+    let amount = funding_available_balance.free_amt_in_btc;
+    if amount <= Decimal::ZERO {
+        span.record("funding_exhausted", true);
+        return Ok(());
+    }
+    span.record("amount", tracing::field::display(amount));
+
+    let target_liability_in_cents = ledger
+        .balances()
+        .usd_liability_balances()
+        .await?
+        .okex_allocation;
+    let trading_available_balance = venue.trading_balance().await?;
+    span.record(
+        "trading_available_balance",
+        tracing::field::display(&trading_available_balance),
+    );
+
+    let action = OkexFundingAdjustment::TransferFundingToTrading(amount);
+    let shared = TransferReservationSharedData {
+        correlation_id,
+        action_unit: action.unit().to_string(),
+        target_usd_exposure: target_liability_in_cents.into(),
+        current_usd_exposure: current_position.usd_cents.abs(),
+        trading_btc_used_balance: trading_available_balance.used_amt_in_btc,
+        trading_btc_total_balance: trading_available_balance.total_amt_in_btc,
+        current_usd_btc_price: current_position.last_price_in_usd_cents,
+        funding_btc_total_balance: funding_available_balance.total_amt_in_btc,
+    };
+    let reservation = TransferReservation {
+        kind: OkexTransferKind::FundingToTrading,
+        shared: &shared,
+        action_size: Some(amount),
+        fee: Decimal::ZERO,
+        transfer_from: "funding".to_string(),
+        transfer_to: "trading".to_string(),
+    };
+    if shadow_mode {
+        if let Some(client_id) = okex_transfers.record_shadow_transfer(reservation).await? {
+            span.record(
+                "client_transfer_id",
+                tracing::field::display(String::from(client_id)),
+            );
+        }
+    } else if let Some(client_id) = okex_transfers.reserve_transfer_slot(reservation).await? {
+        span.record(
+            "client_transfer_id",
+            tracing::field::display(String::from(client_id.clone())),
+        );
+        okex_transfers
+            .submit(
+                client_id.clone(),
+                venue.transfer_funding_to_trading(client_id, amount),
+            )
+            .await?;
+    }
+    Ok(())
+}
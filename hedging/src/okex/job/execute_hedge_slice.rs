@@ -0,0 +1,226 @@
+use rust_decimal::Decimal;
+use tracing::instrument;
+
+use okex_client::ClientOrderId;
+use shared::pubsub::CorrelationId;
+
+use crate::{
+    control::*, decisions::HedgingDecisions, error::*, okex::*, risk::RiskGuard, venue::*,
+};
+
+#[allow(clippy::too_many_arguments)]
+#[instrument(name = "hedging.okex.job.execute_hedge_slice", skip_all, fields(correlation_id = %correlation_id,
+        parent_order_id, target_liability, current_position, action, switching_from, slice, placed_order, client_order_id, execution_state), err)]
+pub(super) async fn execute(
+    correlation_id: CorrelationId,
+    parent_order_id: ClientOrderId,
+    pool: &sqlx::PgPool,
+    risk_guard: &RiskGuard,
+    process_control: &ProcessControl,
+    hedging_decisions: &HedgingDecisions,
+    ledger: ledger::Ledger,
+    venue: SharedVenue,
+    okex_orders: OkexOrders,
+    hedging_adjustment: HedgingAdjustment,
+    execution_planner: ExecutionPlanner,
+    order_placement: OrderPlacement,
+) -> Result<(), HedgingError> {
+    let span = tracing::Span::current();
+    let parent_order_id = String::from(parent_order_id);
+    span.record("parent_order_id", tracing::field::display(&parent_order_id));
+    let parent = match okex_orders.open_parent_order().await? {
+        Some(parent) if String::from(parent.id.clone()) == parent_order_id => parent,
+        _ => {
+            span.record("execution_state", "stopped");
+            return Ok(());
+        }
+    };
+    if risk_guard.halted().await?.is_some() {
+        okex_orders
+            .complete_parent_order(&parent.id, "halted")
+            .await?;
+        span.record("execution_state", "halted");
+        return Ok(());
+    }
+    if process_control
+        .paused(HedgingProcess::Hedging)
+        .await?
+        .is_some()
+    {
+        okex_orders
+            .complete_parent_order(&parent.id, "paused")
+            .await?;
+        span.record("execution_state", "paused");
+        return Ok(());
+    }
+
+    let target_liability = ledger
+        .balances()
+        .usd_liability_balances()
+        .await?
+        .okex_allocation;
+    span.record(
+        "target_liability",
+        tracing::field::display(target_liability),
+    );
+    if target_liability * Decimal::NEGATIVE_ONE != parent.target_usd_value {
+        okex_orders
+            .complete_parent_order(&parent.id, "superseded")
+            .await?;
+        span.record("execution_state", "superseded");
+        return Ok(());
+    }
+
+    let VenuePosition {
+        instrument_id,
+        usd_cents: current_position,
+        ..
+    } = venue.position().await?;
+    span.record(
+        "current_position",
+        tracing::field::display(current_position),
+    );
+    let contract_size_cents = venue.contract_size_cents().await?;
+    let (action, decision) = hedging_adjustment.evaluate(
+        "execute_hedge_slice",
+        correlation_id,
+        target_liability,
+        current_position.into(),
+        contract_size_cents,
+    );
+    hedging_decisions.record(decision).await?;
+    span.record("action", tracing::field::display(&action));
+
+    // A parent reserved on another instrument is moving the hedge to the active one
+    let (slice, residual) = if parent.instrument != instrument_id {
+        let residual = match venue.residual_position().await? {
+            Some(residual) => residual,
+            None => {
+                okex_orders
+                    .complete_parent_order(&parent.id, "done")
+                    .await?;
+                span.record("execution_state", "done");
+                return Ok(());
+            }
+        };
+        span.record(
+            "switching_from",
+            tracing::field::display(&residual.instrument_id),
+        );
+        match execution_planner.next_switch_step(
+            &action,
+            current_position,
+            parent.target_usd_value,
+            &residual,
+            contract_size_cents,
+        ) {
+            SwitchStep::Active(slice) => (slice, None),
+            SwitchStep::Unwind(slice) => (slice, Some(residual)),
+        }
+    } else {
+        if !action.action_required() {
+            okex_orders
+                .complete_parent_order(&parent.id, "done")
+                .await?;
+            span.record("execution_state", "done");
+            return Ok(());
+        }
+        if action.action_type() != parent.action_type {
+            okex_orders
+                .complete_parent_order(&parent.id, "superseded")
+                .await?;
+            span.record("execution_state", "superseded");
+            return Ok(());
+        }
+        (
+            execution_planner.next_slice(&action, current_position.into(), contract_size_cents),
+            None,
+        )
+    };
+    span.record("slice", tracing::field::display(&slice));
+    let position_before_slice = residual
+        .as_ref()
+        .map(|residual| residual.usd_cents)
+        .unwrap_or(current_position);
+    if !super::adjust_hedge::within_risk_limits(risk_guard, &slice, position_before_slice).await? {
+        okex_orders
+            .complete_parent_order(&parent.id, "halted")
+            .await?;
+        span.record("execution_state", "halted");
+        return Ok(());
+    }
+    let reservation = OrderReservation {
+        correlation_id,
+        instrument: residual
+            .as_ref()
+            .map(|residual| residual.instrument_id.clone())
+            .unwrap_or(instrument_id),
+        action: &slice,
+        target_usd_value: parent.target_usd_value,
+        usd_value_before_order: position_before_slice,
+    };
+    if let Some(order_id) = okex_orders
+        .reserve_child_order_slot(reservation, &parent.id)
+        .await?
+    {
+        span.record(
+            "client_order_id",
+            tracing::field::display(String::from(order_id.clone())),
+        );
+        match residual {
+            Some(residual) => {
+                unwind_residual(venue.as_ref(), &residual.instrument_id, order_id, &slice).await?
+            }
+            None => {
+                super::adjust_hedge::place_order(
+                    pool,
+                    venue.as_ref(),
+                    &okex_orders,
+                    &order_placement,
+                    order_id,
+                    &slice,
+                )
+                .await?
+            }
+        }
+        span.record("placed_order", true);
+    } else {
+        span.record("placed_order", false);
+    }
+    span.record("execution_state", "executing");
+
+    super::spawn_execute_hedge_slice(
+        pool,
+        correlation_id,
+        parent.id,
+        execution_planner.slice_interval(),
+    )
+    .await
+}
+
+/// Residual slices go out as market orders so the unwind keeps pace with the
+/// hedge growing on the active instrument
+async fn unwind_residual(
+    venue: &dyn HedgingVenue,
+    instrument_id: &str,
+    order_id: ClientOrderId,
+    slice: &OkexHedgeAdjustment,
+) -> Result<(), HedgingError> {
+    match slice {
+        OkexHedgeAdjustment::ClosePosition => {
+            venue.close_position_on(instrument_id, order_id).await?
+        }
+        OkexHedgeAdjustment::Buy(contracts) => {
+            venue
+                .place_order_on(instrument_id, order_id, OrderSide::Buy, contracts)
+                .await?
+        }
+        OkexHedgeAdjustment::Sell(contracts) => {
+            venue
+                .place_order_on(instrument_id, order_id, OrderSide::Sell, contracts)
+                .await?
+        }
+        OkexHedgeAdjustment::DoNothing => unreachable!(),
+    }
+    Ok(())
+}
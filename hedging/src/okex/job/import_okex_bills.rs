@@ -0,0 +1,69 @@
+use tracing::instrument;
+
+use okex_client::{OkexClient, OKEX_BILLS_PAGE_LIMIT};
+
+use crate::{error::HedgingError, okex::*, venue::*};
+
+#[instrument(
+    name = "hedging.okex.job.import_okex_bills",
+    skip_all,
+    fields(n_fetched, n_imported, unrealized_pnl_btc),
+    err
+)]
+pub async fn execute(
+    pool: &sqlx::PgPool,
+    okex: OkexClient,
+    selection: InstrumentSelection,
+    venue: SharedVenue,
+    okex_bills: OkexBills,
+    ledger: &ledger::Ledger,
+) -> Result<(), HedgingError> {
+    // Bills are fetched per instrument so that each comes in its margin currency,
+    // a residual position on an inactive instrument still pays fees and funding
+    let mut new_bills = Vec::new();
+    for instrument_id in selection.candidates() {
+        let okex = okex.for_instrument(instrument_id);
+        let mut after_bill_id = None;
+        loop {
+            let page = okex.account_bills(after_bill_id.take()).await?;
+            let page_len = page.len();
+            let ids: Vec<String> = page.iter().map(|bill| bill.bill_id.clone()).collect();
+            let known = okex_bills.known_bill_ids(&ids).await?;
+            after_bill_id = page.last().map(|bill| bill.bill_id.clone());
+            new_bills.extend(
+                page.into_iter()
+                    .filter(|bill| !known.contains(&bill.bill_id)),
+            );
+            if !known.is_empty() || page_len < OKEX_BILLS_PAGE_LIMIT {
+                break;
+            }
+        }
+    }
+    // Pages come newest first, post the bills in the order they happened
+    new_bills.reverse();
+    new_bills.sort_by_key(|bill| bill.timestamp);
+
+    let span = tracing::Span::current();
+    span.record("n_fetched", new_bills.len());
+    let mut n_imported = 0;
+    for bill in new_bills {
+        if okex_bills.import(ledger, bill).await? {
+            n_imported += 1;
+        }
+    }
+    span.record("n_imported", n_imported);
+
+    let VenuePosition {
+        instrument_id,
+        unrealized_pnl_btc,
+        ..
+    } = venue.position().await?;
+    span.record(
+        "unrealized_pnl_btc",
+        tracing::field::display(unrealized_pnl_btc),
+    );
+    ledger
+        .adjust_okex_unrealized_pnl(pool.begin().await?, unrealized_pnl_btc, instrument_id)
+        .await?;
+    Ok(())
+}
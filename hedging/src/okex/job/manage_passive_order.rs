@@ -0,0 +1,102 @@
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use tracing::instrument;
+
+use okex_client::*;
+
+use crate::{error::*, okex::*, venue::*};
+
+#[instrument(
+    name = "hedging.okex.job.manage_passive_order",
+    skip_all,
+    fields(
+        client_order_id,
+        state,
+        filled_size,
+        remaining_size,
+        limit_price,
+        new_price,
+        fallback_order_id
+    ),
+    err
+)]
+pub(super) async fn execute(
+    id: ClientOrderId,
+    pool: &sqlx::PgPool,
+    okex: OkexClient,
+    venue: SharedVenue,
+    okex_orders: OkexOrders,
+    order_placement: OrderPlacement,
+) -> Result<(), HedgingError> {
+    let span = tracing::Span::current();
+    span.record(
+        "client_order_id",
+        tracing::field::display(String::from(id.clone())),
+    );
+    let order = match okex_orders.passive_order(&id).await? {
+        Some(order) => order,
+        None => return Ok(()),
+    };
+    span.record("limit_price", tracing::field::display(order.limit_price));
+    let okex = okex.for_instrument(order.instrument.parse()?);
+
+    let mut details = match okex.order_details(id.clone()).await {
+        Ok(details) => details,
+        // poll_okex takes care of orders that never reached okex
+        Err(OkexClientError::OrderDoesNotExist)
+        | Err(OkexClientError::ParameterClientIdNotFound) => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    if !details.complete && order_placement.fallback_due(order.created_at) {
+        okex.cancel_order(id.clone()).await?;
+        details = okex.order_details(id.clone()).await?;
+    }
+
+    let remaining = details.sz - details.acc_fill_sz;
+    let complete = details.complete;
+    span.record("state", tracing::field::display(&details.state));
+    span.record("filled_size", tracing::field::display(details.acc_fill_sz));
+    span.record("remaining_size", tracing::field::display(remaining));
+    okex_orders.update_order(details).await?;
+
+    let side = if order.action_type == "sell" {
+        OrderSide::Sell
+    } else {
+        OrderSide::Buy
+    };
+    if complete {
+        if remaining > Decimal::ZERO {
+            let contracts = SwapContracts::new(
+                remaining.to_u32().unwrap_or_default(),
+                venue.contract_size_cents().await?,
+            );
+            let action = match side {
+                OrderSide::Sell => OkexHedgeAdjustment::Sell(contracts.clone()),
+                OrderSide::Buy => OkexHedgeAdjustment::Buy(contracts.clone()),
+            };
+            if let Some(fallback_id) = okex_orders.reserve_fallback_order(&id, &action).await? {
+                span.record(
+                    "fallback_order_id",
+                    tracing::field::display(String::from(fallback_id.clone())),
+                );
+                venue
+                    .place_market_order(fallback_id, side, &contracts)
+                    .await?;
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(price) = order_placement.passive_price(&side) {
+        if price != order.limit_price {
+            span.record("new_price", tracing::field::display(price));
+            match okex.amend_order_price(id.clone(), price).await {
+                Ok(_) => okex_orders.update_limit_price(&id, price).await?,
+                // The order may have filled in the meantime, the next run picks it up
+                Err(OkexClientError::UnexpectedResponse { .. }) => (),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    super::spawn_manage_passive_order(pool, id, order_placement.reprice_interval()).await
+}
@@ -1,6 +1,12 @@
-mod adjust_funding;
-mod adjust_hedge;
+pub mod adjust_funding;
+pub mod adjust_hedge;
+mod emergency_funding;
+mod execute_hedge_slice;
+mod import_okex_bills;
+mod manage_passive_order;
 mod poll_okex;
+mod reconcile_okex;
+mod retry_okex_transfers;
 
 use bria_client::BriaClient;
 use serde::{Deserialize, Serialize};
@@ -11,17 +17,30 @@ use uuid::{uuid, Uuid};
 
 use std::collections::HashMap;
 
-use okex_client::OkexClient;
-use shared::{pubsub::CorrelationId, sqlxmq::JobExecutor};
+use okex_client::{ClientOrderId, OkexClient};
+use shared::{payload::OKEX_EXCHANGE_ID, pubsub::CorrelationId, sqlxmq::JobExecutor};
 
-use crate::{error::*, okex::*};
+use crate::{
+    allocation::ExchangeHealth, control::ProcessControl, decisions::HedgingDecisions, error::*,
+    liability_watermark::LiabilityWatermarkCheck, okex::*, risk::RiskGuard, venue::SharedVenue,
+};
 
 // retired: uuid!("10000000-0000-0000-0000-000000000001");
 pub const POLL_OKEX_ID: Uuid = uuid!("10000000-0000-0000-0000-000000000002");
+pub const EMERGENCY_FUNDING_ID: Uuid = uuid!("10000000-0000-0000-0000-000000000004");
+pub const RETRY_OKEX_TRANSFERS_ID: Uuid = uuid!("10000000-0000-0000-0000-000000000005");
+pub const RECONCILE_OKEX_ID: Uuid = uuid!("10000000-0000-0000-0000-000000000006");
+pub const IMPORT_OKEX_BILLS_ID: Uuid = uuid!("10000000-0000-0000-0000-000000000007");
 
 #[derive(Debug, Clone)]
 pub(super) struct OkexPollDelay(pub(super) std::time::Duration);
 
+#[derive(Debug, Clone)]
+pub(super) struct OkexBillsImportDelay(pub(super) std::time::Duration);
+
+#[derive(Debug, Clone)]
+pub(super) struct OkexShadowMode(pub(super) bool);
+
 #[instrument(name = "hedging.okex.job.spawn_poll_okex", skip_all, fields(error, error.level, error.message), err)]
 pub async fn spawn_poll_okex(
     pool: &sqlx::PgPool,
@@ -43,6 +62,48 @@ pub async fn spawn_poll_okex(
     }
 }
 
+#[instrument(name = "hedging.okex.job.spawn_import_okex_bills", skip_all, fields(error, error.level, error.message), err)]
+pub async fn spawn_import_okex_bills(
+    pool: &sqlx::PgPool,
+    duration: std::time::Duration,
+) -> Result<(), HedgingError> {
+    match JobBuilder::new_with_id(IMPORT_OKEX_BILLS_ID, "import_okex_bills")
+        .set_channel_name("hedging.okex")
+        .set_channel_args("import_okex_bills")
+        .set_delay(duration)
+        .spawn(pool)
+        .await
+    {
+        Err(sqlx::Error::Database(err)) if err.message().contains("duplicate key") => Ok(()),
+        Err(e) => {
+            shared::tracing::insert_error_fields(tracing::Level::ERROR, &e);
+            Err(e.into())
+        }
+        Ok(_) => Ok(()),
+    }
+}
+
+#[instrument(name = "hedging.okex.job.spawn_reconcile_okex", skip_all, fields(error, error.level, error.message), err)]
+pub async fn spawn_reconcile_okex(
+    pool: &sqlx::PgPool,
+    duration: std::time::Duration,
+) -> Result<(), HedgingError> {
+    match JobBuilder::new_with_id(RECONCILE_OKEX_ID, "reconcile_okex")
+        .set_channel_name("hedging.okex")
+        .set_channel_args("reconcile_okex")
+        .set_delay(duration)
+        .spawn(pool)
+        .await
+    {
+        Err(sqlx::Error::Database(err)) if err.message().contains("duplicate key") => Ok(()),
+        Err(e) => {
+            shared::tracing::insert_error_fields(tracing::Level::ERROR, &e);
+            Err(e.into())
+        }
+        Ok(_) => Ok(()),
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 struct AdjustHedgeData {
     correlation_id: CorrelationId,
@@ -77,14 +138,19 @@ pub async fn spawn_adjust_hedge<'a>(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 #[job(name = "poll_okex")]
 pub(super) async fn poll_okex(
     mut current_job: CurrentJob,
     OkexPollDelay(delay): OkexPollDelay,
+    exchange_health: ExchangeHealth,
     okex: OkexClient,
+    venue: SharedVenue,
+    selection: InstrumentSelection,
     okex_orders: OkexOrders,
     okex_transfers: OkexTransfers,
-    funding_config: OkexFundingConfig,
+    okex_margin: OkexMargin,
+    funding_adjustment: FundingAdjustment,
     ledger: ledger::Ledger,
 ) -> Result<(), HedgingError> {
     let pool = current_job.pool().clone();
@@ -97,23 +163,80 @@ pub(super) async fn poll_okex(
                 okex_orders,
                 okex_transfers,
                 okex,
-                funding_config,
+                venue,
+                selection,
+                okex_margin,
+                &funding_adjustment,
                 &ledger,
             )
             .await
         })
         .await?;
+    exchange_health
+        .record_successful_poll(OKEX_EXCHANGE_ID)
+        .await?;
     spawn_poll_okex(current_job.pool(), delay).await?;
     Ok(())
 }
 
+#[job(name = "import_okex_bills")]
+pub(super) async fn import_okex_bills(
+    mut current_job: CurrentJob,
+    OkexBillsImportDelay(delay): OkexBillsImportDelay,
+    okex: OkexClient,
+    selection: InstrumentSelection,
+    venue: SharedVenue,
+    okex_bills: OkexBills,
+    ledger: ledger::Ledger,
+) -> Result<(), HedgingError> {
+    let pool = current_job.pool().clone();
+    JobExecutor::builder(&mut current_job)
+        .build()
+        .expect("couldn't build JobExecutor")
+        .execute(|_| async move {
+            import_okex_bills::execute(&pool, okex, selection, venue, okex_bills, &ledger).await
+        })
+        .await?;
+    spawn_import_okex_bills(current_job.pool(), delay).await?;
+    Ok(())
+}
+
+#[job(name = "reconcile_okex")]
+pub(super) async fn reconcile_okex(
+    mut current_job: CurrentJob,
+    okex: OkexClient,
+    selection: InstrumentSelection,
+    mut bria: BriaClient,
+    ledger: ledger::Ledger,
+    reconciliation: OkexReconciliation,
+) -> Result<(), HedgingError> {
+    let delay = reconciliation.config().frequency;
+    JobExecutor::builder(&mut current_job)
+        .build()
+        .expect("couldn't build JobExecutor")
+        .execute(|_| async move {
+            reconcile_okex::execute(okex, selection, &mut bria, &ledger, reconciliation).await
+        })
+        .await?;
+    spawn_reconcile_okex(current_job.pool(), delay).await?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 #[job(name = "adjust_hedge")]
 pub(super) async fn adjust_hedge(
     mut current_job: CurrentJob,
+    liability_watermark: LiabilityWatermarkCheck,
+    risk_guard: RiskGuard,
+    process_control: ProcessControl,
+    hedging_decisions: HedgingDecisions,
     ledger: ledger::Ledger,
-    okex: OkexClient,
+    venue: SharedVenue,
     okex_orders: OkexOrders,
     hedging_adjustment: HedgingAdjustment,
+    execution_planner: ExecutionPlanner,
+    order_placement: OrderPlacement,
+    OkexShadowMode(shadow_mode): OkexShadowMode,
 ) -> Result<(), HedgingError> {
     let pool = current_job.pool().clone();
     JobExecutor::builder(&mut current_job)
@@ -124,10 +247,156 @@ pub(super) async fn adjust_hedge(
             adjust_hedge::execute(
                 data.correlation_id,
                 &pool,
+                &liability_watermark,
+                &risk_guard,
+                &process_control,
+                &hedging_decisions,
                 ledger,
-                okex,
+                venue,
                 okex_orders,
                 hedging_adjustment,
+                execution_planner,
+                order_placement,
+                shadow_mode,
+            )
+            .await?;
+            Ok::<_, HedgingError>(data)
+        })
+        .await?;
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExecuteHedgeSliceData {
+    correlation_id: CorrelationId,
+    parent_order_id: String,
+    #[serde(flatten)]
+    tracing_data: HashMap<String, String>,
+}
+
+#[instrument(name = "hedging.okex.job.spawn_execute_hedge_slice", skip_all, fields(error, error.message), err)]
+pub(super) async fn spawn_execute_hedge_slice<'a>(
+    tx: impl Executor<'a, Database = Postgres>,
+    correlation_id: CorrelationId,
+    parent_order_id: ClientOrderId,
+    delay: std::time::Duration,
+) -> Result<(), HedgingError> {
+    match JobBuilder::new("execute_hedge_slice")
+        .set_channel_name("hedging.okex")
+        .set_channel_args("execute_hedge_slice")
+        .set_delay(delay)
+        .set_json(&ExecuteHedgeSliceData {
+            tracing_data: shared::tracing::extract_tracing_data(),
+            correlation_id,
+            parent_order_id: String::from(parent_order_id),
+        })
+        .expect("Couldn't set json")
+        .spawn(tx)
+        .await
+    {
+        Err(e) => {
+            shared::tracing::insert_error_fields(tracing::Level::ERROR, &e);
+            Err(e.into())
+        }
+        Ok(_) => Ok(()),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+#[job(name = "execute_hedge_slice")]
+pub(super) async fn execute_hedge_slice(
+    mut current_job: CurrentJob,
+    risk_guard: RiskGuard,
+    process_control: ProcessControl,
+    hedging_decisions: HedgingDecisions,
+    ledger: ledger::Ledger,
+    venue: SharedVenue,
+    okex_orders: OkexOrders,
+    hedging_adjustment: HedgingAdjustment,
+    execution_planner: ExecutionPlanner,
+    order_placement: OrderPlacement,
+) -> Result<(), HedgingError> {
+    let pool = current_job.pool().clone();
+    JobExecutor::builder(&mut current_job)
+        .build()
+        .expect("couldn't build JobExecutor")
+        .execute(|data| async move {
+            let data: ExecuteHedgeSliceData = data.ok_or(HedgingError::NoJobDataPresent)?;
+            execute_hedge_slice::execute(
+                data.correlation_id,
+                ClientOrderId::from(data.parent_order_id.clone()),
+                &pool,
+                &risk_guard,
+                &process_control,
+                &hedging_decisions,
+                ledger,
+                venue,
+                okex_orders,
+                hedging_adjustment,
+                execution_planner,
+                order_placement,
+            )
+            .await?;
+            Ok::<_, HedgingError>(data)
+        })
+        .await?;
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize)]
+struct ManagePassiveOrderData {
+    client_order_id: String,
+    #[serde(flatten)]
+    tracing_data: HashMap<String, String>,
+}
+
+#[instrument(name = "hedging.okex.job.spawn_manage_passive_order", skip_all, fields(error, error.message), err)]
+pub(super) async fn spawn_manage_passive_order<'a>(
+    tx: impl Executor<'a, Database = Postgres>,
+    client_order_id: ClientOrderId,
+    delay: std::time::Duration,
+) -> Result<(), HedgingError> {
+    match JobBuilder::new("manage_passive_order")
+        .set_channel_name("hedging.okex")
+        .set_channel_args("manage_passive_order")
+        .set_delay(delay)
+        .set_json(&ManagePassiveOrderData {
+            tracing_data: shared::tracing::extract_tracing_data(),
+            client_order_id: String::from(client_order_id),
+        })
+        .expect("Couldn't set json")
+        .spawn(tx)
+        .await
+    {
+        Err(e) => {
+            shared::tracing::insert_error_fields(tracing::Level::ERROR, &e);
+            Err(e.into())
+        }
+        Ok(_) => Ok(()),
+    }
+}
+
+#[job(name = "manage_passive_order")]
+pub(super) async fn manage_passive_order(
+    mut current_job: CurrentJob,
+    okex: OkexClient,
+    venue: SharedVenue,
+    okex_orders: OkexOrders,
+    order_placement: OrderPlacement,
+) -> Result<(), HedgingError> {
+    let pool = current_job.pool().clone();
+    JobExecutor::builder(&mut current_job)
+        .build()
+        .expect("couldn't build JobExecutor")
+        .execute(|data| async move {
+            let data: ManagePassiveOrderData = data.ok_or(HedgingError::NoJobDataPresent)?;
+            manage_passive_order::execute(
+                ClientOrderId::from(data.client_order_id.clone()),
+                &pool,
+                okex,
+                venue,
+                okex_orders,
+                order_placement,
             )
             .await?;
             Ok::<_, HedgingError>(data)
@@ -170,16 +439,22 @@ pub async fn spawn_adjust_funding<'a>(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 #[job(name = "adjust_funding")]
 pub(super) async fn adjust_funding(
     mut current_job: CurrentJob,
+    liability_watermark: LiabilityWatermarkCheck,
+    risk_guard: RiskGuard,
+    process_control: ProcessControl,
+    hedging_decisions: HedgingDecisions,
     ledger: ledger::Ledger,
-    okex: OkexClient,
+    venue: SharedVenue,
     okex_transfers: OkexTransfers,
     mut bria: BriaClient,
     funding_adjustment: FundingAdjustment,
+    funding_rails: FundingRails,
+    OkexShadowMode(shadow_mode): OkexShadowMode,
 ) -> Result<(), HedgingError> {
-    let pool = current_job.pool().clone();
     JobExecutor::builder(&mut current_job)
         .build()
         .expect("couldn't build JobExecutor")
@@ -187,12 +462,73 @@ pub(super) async fn adjust_funding(
             let data: AdjustFundingData = data.ok_or(HedgingError::NoJobDataPresent)?;
             adjust_funding::execute(
                 data.correlation_id,
-                &pool,
+                &liability_watermark,
+                &risk_guard,
+                &process_control,
+                &hedging_decisions,
                 ledger,
-                okex,
+                venue,
                 okex_transfers,
                 &mut bria,
                 funding_adjustment,
+                funding_rails,
+                shadow_mode,
+            )
+            .await?;
+            Ok::<_, HedgingError>(data)
+        })
+        .await?;
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize)]
+struct EmergencyFundingData {
+    correlation_id: CorrelationId,
+    #[serde(flatten)]
+    tracing_data: HashMap<String, String>,
+}
+
+#[instrument(name = "hedging.okex.job.spawn_emergency_funding", skip_all, fields(error, error.message), err)]
+pub(super) async fn spawn_emergency_funding(pool: &sqlx::PgPool) -> Result<(), HedgingError> {
+    match JobBuilder::new_with_id(EMERGENCY_FUNDING_ID, "emergency_funding")
+        .set_channel_name("hedging.okex")
+        .set_channel_args("emergency_funding")
+        .set_json(&EmergencyFundingData {
+            tracing_data: shared::tracing::extract_tracing_data(),
+            correlation_id: CorrelationId::new(),
+        })
+        .expect("Couldn't set json")
+        .spawn(pool)
+        .await
+    {
+        Err(sqlx::Error::Database(err)) if err.message().contains("duplicate key") => Ok(()),
+        Err(e) => {
+            shared::tracing::insert_error_fields(tracing::Level::ERROR, &e);
+            Err(e.into())
+        }
+        Ok(_) => Ok(()),
+    }
+}
+
+#[job(name = "emergency_funding")]
+pub(super) async fn emergency_funding(
+    mut current_job: CurrentJob,
+    ledger: ledger::Ledger,
+    venue: SharedVenue,
+    okex_transfers: OkexTransfers,
+    OkexShadowMode(shadow_mode): OkexShadowMode,
+) -> Result<(), HedgingError> {
+    JobExecutor::builder(&mut current_job)
+        .build()
+        .expect("couldn't build JobExecutor")
+        .execute(|data| async move {
+            let data: EmergencyFundingData = data.ok_or(HedgingError::NoJobDataPresent)?;
+            emergency_funding::execute(
+                data.correlation_id,
+                ledger,
+                venue,
+                okex_transfers,
+                shadow_mode,
             )
             .await?;
             Ok::<_, HedgingError>(data)
@@ -200,3 +536,59 @@ pub(super) async fn adjust_funding(
         .await?;
     Ok(())
 }
+
+#[instrument(name = "hedging.okex.job.spawn_retry_okex_transfers", skip_all, fields(error, error.level, error.message), err)]
+pub(super) async fn spawn_retry_okex_transfers(pool: &sqlx::PgPool) -> Result<(), HedgingError> {
+    match JobBuilder::new_with_id(RETRY_OKEX_TRANSFERS_ID, "retry_okex_transfers")
+        .set_channel_name("hedging.okex")
+        .set_channel_args("retry_okex_transfers")
+        .spawn(pool)
+        .await
+    {
+        Err(sqlx::Error::Database(err)) if err.message().contains("duplicate key") => Ok(()),
+        Err(e) => {
+            shared::tracing::insert_error_fields(tracing::Level::ERROR, &e);
+            Err(e.into())
+        }
+        Ok(_) => Ok(()),
+    }
+}
+
+#[job(name = "retry_okex_transfers")]
+pub(super) async fn retry_okex_transfers(
+    mut current_job: CurrentJob,
+    okex: OkexClient,
+    venue: SharedVenue,
+    okex_transfers: OkexTransfers,
+    mut bria: BriaClient,
+) -> Result<(), HedgingError> {
+    JobExecutor::builder(&mut current_job)
+        .build()
+        .expect("couldn't build JobExecutor")
+        .execute(|_| async move {
+            retry_okex_transfers::execute(okex, venue, okex_transfers, &mut bria).await
+        })
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn singleton_job_ids_are_distinct() {
+        let ids = [
+            POLL_OKEX_ID,
+            EMERGENCY_FUNDING_ID,
+            RETRY_OKEX_TRANSFERS_ID,
+            RECONCILE_OKEX_ID,
+            IMPORT_OKEX_BILLS_ID,
+            crate::bitfinex::job::POLL_BITFINEX_ID,
+            user_trades::job::POLL_GALOY_TRANSACTIONS_ID,
+        ];
+        assert_eq!(ids.iter().collect::<HashSet<_>>().len(), ids.len());
+    }
+}
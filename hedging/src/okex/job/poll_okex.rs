@@ -1,38 +1,102 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
 use tracing::instrument;
 
-use okex_client::{OkexClient, OkexClientError, PositionSize};
+use okex_client::{OkexClient, OkexClientError, TradeCurrency};
 use shared::payload::OKEX_EXCHANGE_ID;
 
-use crate::{error::HedgingError, okex::*};
+use crate::{error::HedgingError, okex::*, venue::*};
 
-#[instrument(name = "hedging.okex.job.poll_okex", skip_all)]
+#[allow(clippy::too_many_arguments)]
+#[instrument(
+    name = "hedging.okex.job.poll_okex",
+    skip_all,
+    fields(
+        active_instrument,
+        margin_ratio,
+        liquidation_distance,
+        emergency_funding,
+        transfer_retries,
+        stuck_transfers,
+        ledger_postings
+    )
+)]
 pub async fn execute(
     pool: &sqlx::PgPool,
     okex_orders: OkexOrders,
     okex_transfers: OkexTransfers,
     okex: OkexClient,
-    funding_config: OkexFundingConfig,
+    venue: SharedVenue,
+    selection: InstrumentSelection,
+    okex_margin: OkexMargin,
+    funding_adjustment: &FundingAdjustment,
     ledger: &ledger::Ledger,
 ) -> Result<(), HedgingError> {
-    let PositionSize {
+    if selection.candidates().len() > 1 {
+        let target_liability_in_cents = ledger
+            .balances()
+            .usd_liability_balances()
+            .await?
+            .okex_allocation;
+        let usdt_margin = UsdtMargin {
+            available_cents: okex
+                .trading_account_balance_in(TradeCurrency::USDT)
+                .await?
+                .total_amt
+                * dec!(100),
+            required_cents: funding_adjustment
+                .usdt_margin_required_cents(Decimal::from(target_liability_in_cents)),
+        };
+        select_instrument(
+            pool,
+            &okex,
+            venue.as_ref(),
+            &selection,
+            usdt_margin,
+            &okex_orders,
+        )
+        .await?;
+    }
+    let span = tracing::Span::current();
+    span.record(
+        "active_instrument",
+        tracing::field::display(selection.active()),
+    );
+
+    let risk = okex
+        .for_instrument(selection.active())
+        .get_position_risk()
+        .await?;
+    if let Some(margin_ratio) = risk.margin_ratio {
+        span.record("margin_ratio", tracing::field::display(margin_ratio));
+    }
+    if let Some(distance) = risk.liquidation_distance() {
+        span.record("liquidation_distance", tracing::field::display(distance));
+    }
+    okex_margin.record(&risk).await?;
+    if okex_margin.requires_emergency_funding(&risk) {
+        span.record("emergency_funding", true);
+        super::spawn_emergency_funding(pool).await?;
+    }
+
+    let VenuePosition {
         usd_cents,
         instrument_id,
         ..
-    } = okex.get_position_in_signed_usd_cents().await?;
+    } = venue.position().await?;
     let tx = pool.begin().await?;
 
     ledger
-        .adjust_okex_position(
-            tx,
-            usd_cents,
-            OKEX_EXCHANGE_ID.to_string(),
-            instrument_id.to_string(),
-        )
+        .adjust_okex_position(tx, usd_cents, OKEX_EXCHANGE_ID.to_string(), instrument_id)
         .await?;
 
     let mut execute_sweep = false;
-    for id in okex_orders.open_orders().await? {
-        match okex.order_details(id.clone()).await {
+    for (id, instrument) in okex_orders.open_orders().await? {
+        match okex
+            .for_instrument(instrument.parse()?)
+            .order_details(id.clone())
+            .await
+        {
             Ok(details) => {
                 okex_orders.update_order(details).await?;
             }
@@ -49,7 +113,6 @@ pub async fn execute(
         okex_orders.sweep_lost_records().await?;
     }
 
-    let mut execute_transfer_sweep = false;
     for id in okex_transfers.get_pending_transfers().await? {
         match okex.transfer_state_by_client_id(id.clone()).await {
             Ok(details) => {
@@ -58,26 +121,16 @@ pub async fn execute(
             Err(OkexClientError::ParameterClientIdError)
             | Err(OkexClientError::ParameterClientIdNotFound) => {
                 okex_transfers.mark_as_lost(id).await?;
-                execute_transfer_sweep = true;
             }
             Err(res) => return Err(res.into()),
         }
     }
 
-    for (id, address, amount, created_at) in okex_transfers.get_pending_deposits().await? {
-        match okex.fetch_deposit(address, amount).await {
-            Ok(details) => {
-                okex_transfers
-                    .update_deposit(id, details.state, details.transaction_id)
-                    .await?;
-            }
-            Err(OkexClientError::UnexpectedResponse { .. }) => {
-                if chrono::Utc::now() - created_at > funding_config.deposit_lost_timeout_seconds {
-                    okex_transfers.mark_as_lost(id).await?;
-                    execute_transfer_sweep = true;
-                }
-            }
-            Err(res) => return Err(res.into()),
+    for (id, tx_id) in okex_transfers.get_pending_deposits().await? {
+        if let Some(details) = okex.fetch_deposit_by_tx_id(tx_id).await? {
+            okex_transfers
+                .update_deposit(id, details.state, details.transaction_id)
+                .await?;
         }
     }
 
@@ -90,15 +143,67 @@ pub async fn execute(
             | Err(OkexClientError::ParameterClientIdError)
             | Err(OkexClientError::ParameterClientIdNotFound) => {
                 okex_transfers.mark_as_lost(id).await?;
-                execute_transfer_sweep = true;
             }
             Err(res) => return Err(res.into()),
         }
     }
 
-    if execute_transfer_sweep {
-        okex_transfers.sweep_lost_records().await?;
+    for (id, withdraw_id) in okex_transfers.get_pending_lightning_withdrawals().await? {
+        match okex.fetch_withdrawal_by_id(withdraw_id.clone()).await {
+            Ok(details) => {
+                okex_transfers
+                    .update_lightning_withdrawal(id, withdraw_id, details)
+                    .await?;
+            }
+            Err(OkexClientError::WithdrawalIdDoesNotExist)
+            | Err(OkexClientError::ParameterClientIdNotFound) => {
+                okex_transfers.mark_as_lost(id).await?;
+            }
+            Err(res) => return Err(res.into()),
+        }
+    }
+
+    span.record(
+        "ledger_postings",
+        okex_transfers.post_to_ledger(ledger).await?,
+    );
+
+    let retries = okex_transfers.due_for_retry().await?.len();
+    span.record("transfer_retries", retries);
+    if retries > 0 {
+        super::spawn_retry_okex_transfers(pool).await?;
     }
+    span.record(
+        "stuck_transfers",
+        okex_transfers.stuck_transfers().await?.len(),
+    );
 
     Ok(())
 }
+
+/// Follows the funding rates. Whatever is left on an instrument that is no
+/// longer active is moved over by adjust_hedge, under the same gates and
+/// limits as any other adjustment.
+async fn select_instrument(
+    pool: &sqlx::PgPool,
+    okex: &OkexClient,
+    venue: &dyn HedgingVenue,
+    selection: &InstrumentSelection,
+    usdt_margin: UsdtMargin,
+    okex_orders: &OkexOrders,
+) -> Result<(), HedgingError> {
+    let mut rates = Vec::new();
+    for instrument_id in selection.candidates() {
+        rates.push(okex.for_instrument(instrument_id).funding_rate().await?);
+    }
+    let previous = selection.active();
+    if selection.select(&rates, usdt_margin) != previous {
+        selection.persist(pool).await?;
+    }
+    if okex_orders.open_parent_order().await?.is_none()
+        && venue.residual_position().await?.is_some()
+    {
+        super::spawn_adjust_hedge(pool, uuid::Uuid::new_v4()).await?;
+    }
+    Ok(())
+}
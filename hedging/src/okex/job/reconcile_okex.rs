@@ -0,0 +1,84 @@
+use rust_decimal::Decimal;
+use tracing::instrument;
+
+use bria_client::BriaClient;
+use ledger::constants::{CENTS_PER_USD, SATS_PER_BTC};
+use okex_client::OkexClient;
+
+use crate::{error::HedgingError, okex::*};
+
+/// Compares the ledger with the position and balances okex reports and with the
+/// bria wallet. Lost orders and transfers are swept without touching the ledger,
+/// this is where the difference shows up.
+#[instrument(
+    name = "hedging.okex.job.reconcile_okex",
+    skip_all,
+    fields(discrepancies),
+    err
+)]
+pub async fn execute(
+    okex: OkexClient,
+    selection: InstrumentSelection,
+    bria: &mut BriaClient,
+    ledger: &ledger::Ledger,
+    reconciliation: OkexReconciliation,
+) -> Result<(), HedgingError> {
+    let config = reconciliation.config();
+    let balances = ledger.balances();
+
+    // The ledger carries the short position as a positive usd balance
+    let ledger_position_usd = balances
+        .okex_position_account_balance()
+        .await?
+        .map(|b| b.settled())
+        .unwrap_or(Decimal::ZERO);
+    let okex_position_usd = -okex
+        .for_instrument(selection.active())
+        .get_position_in_signed_usd_cents()
+        .await?
+        .usd_cents
+        / CENTS_PER_USD;
+
+    let ledger_trading_btc = balances.okex_btc_margin().await?;
+    let ledger_funding_btc = balances.okex_btc_funding().await?;
+    let ledger_wallet_btc = balances.stablesats_btc_assets().await?
+        + balances
+            .quotes_btc_assets()
+            .await?
+            .map(|b| b.settled())
+            .unwrap_or(Decimal::ZERO);
+    let wallet = bria.wallet_balance().await?;
+
+    let checks = vec![
+        ReconciliationCheck::new(
+            "okex_position_usd",
+            ledger_position_usd,
+            okex_position_usd,
+            config.position_tolerance_usd,
+        ),
+        ReconciliationCheck::new(
+            "okex_trading_btc",
+            ledger_trading_btc,
+            okex.trading_account_balance().await?.total_amt_in_btc,
+            config.btc_tolerance,
+        ),
+        ReconciliationCheck::new(
+            "okex_funding_btc",
+            ledger_funding_btc,
+            okex.funding_account_balance().await?.total_amt_in_btc,
+            config.btc_tolerance,
+        ),
+        ReconciliationCheck::new(
+            "wallet_btc",
+            ledger_wallet_btc,
+            Decimal::from(wallet.effective_settled_sats) / SATS_PER_BTC,
+            config.btc_tolerance,
+        ),
+    ];
+    let discrepancies = reconciliation.record(checks).await?;
+    tracing::Span::current().record(
+        "discrepancies",
+        tracing::field::display(discrepancies.join(",")),
+    );
+    Ok(())
+}
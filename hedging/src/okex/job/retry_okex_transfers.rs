@@ -0,0 +1,109 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use tracing::instrument;
+
+use bria_client::BriaClient;
+use okex_client::{OkexClient, OkexClientError};
+
+use crate::{error::*, okex::*, venue::*};
+
+const SATS_PER_BTC: Decimal = dec!(100_000_000);
+
+/// Resubmits reserved transfers whose submission failed. Okex or bria may
+/// have accepted a submission that still reported an error, so the client id
+/// is looked up before submitting the same transfer again.
+#[instrument(
+    name = "hedging.okex.job.retry_okex_transfers",
+    skip_all,
+    fields(n_retries, n_already_submitted, n_failed),
+    err
+)]
+pub(super) async fn execute(
+    okex: OkexClient,
+    venue: SharedVenue,
+    okex_transfers: OkexTransfers,
+    bria: &mut BriaClient,
+) -> Result<(), HedgingError> {
+    let retries = okex_transfers.due_for_retry().await?;
+    let span = tracing::Span::current();
+    span.record("n_retries", retries.len());
+
+    let mut n_already_submitted = 0;
+    let mut n_failed = 0;
+    for TransferRetry {
+        client_transfer_id: id,
+        kind,
+        amount,
+        fee,
+        transfer_to,
+    } in retries
+    {
+        let submission = match kind {
+            OkexTransferKind::TradingToFunding | OkexTransferKind::FundingToTrading => {
+                match okex.transfer_state_by_client_id(id.clone()).await {
+                    Ok(details) => {
+                        okex_transfers.update_transfer(details).await?;
+                        n_already_submitted += 1;
+                        continue;
+                    }
+                    Err(OkexClientError::ParameterClientIdError)
+                    | Err(OkexClientError::ParameterClientIdNotFound) => {}
+                    Err(e) => return Err(e.into()),
+                }
+                if kind == OkexTransferKind::TradingToFunding {
+                    okex_transfers
+                        .submit(id.clone(), venue.transfer_trading_to_funding(id, amount))
+                        .await
+                } else {
+                    okex_transfers
+                        .submit(id.clone(), venue.transfer_funding_to_trading(id, amount))
+                        .await
+                }
+            }
+            OkexTransferKind::Withdraw => {
+                match okex.fetch_withdrawal_by_client_id(id.clone()).await {
+                    Ok(details) => {
+                        okex_transfers.update_withdrawal(details).await?;
+                        n_already_submitted += 1;
+                        continue;
+                    }
+                    Err(OkexClientError::WithdrawalIdDoesNotExist)
+                    | Err(OkexClientError::ParameterClientIdError)
+                    | Err(OkexClientError::ParameterClientIdNotFound) => {}
+                    Err(e) => return Err(e.into()),
+                }
+                okex_transfers
+                    .submit(
+                        id.clone(),
+                        venue.withdraw_btc_onchain(id, amount, fee, transfer_to),
+                    )
+                    .await
+            }
+            OkexTransferKind::Deposit => {
+                let external_id = String::from(id.clone());
+                if bria.find_payout(external_id.clone()).await?.is_some() {
+                    okex_transfers.mark_as_submitted(id).await?;
+                    n_already_submitted += 1;
+                    continue;
+                }
+                okex_transfers
+                    .submit(id, async {
+                        bria.send_onchain_payment(transfer_to, amount * SATS_PER_BTC, external_id)
+                            .await?;
+                        Ok(())
+                    })
+                    .await
+            }
+            OkexTransferKind::LightningDeposit | OkexTransferKind::LightningWithdraw => {
+                // never scheduled for a retry, see OkexTransferKind::is_retryable
+                continue;
+            }
+        };
+        if submission.is_err() {
+            n_failed += 1;
+        }
+    }
+    span.record("n_already_submitted", n_already_submitted);
+    span.record("n_failed", n_failed);
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use okex_client::{AvailableBalance, PositionSize};
+
+use std::{
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Default)]
+struct LiveState {
+    position: Option<PositionSize>,
+    trading_balance: Option<AvailableBalance>,
+    last_message_at: Option<Instant>,
+}
+
+/// Position and trading balance as last pushed over the okex private websocket.
+/// Nothing is returned once the connection has been silent for `stale_after`.
+#[derive(Debug, Clone)]
+pub struct OkexLiveView {
+    stale_after: Duration,
+    state: Arc<RwLock<LiveState>>,
+}
+
+impl OkexLiveView {
+    pub fn new(stale_after: Duration) -> Self {
+        Self {
+            stale_after,
+            state: Arc::new(RwLock::new(LiveState::default())),
+        }
+    }
+
+    pub fn update_position(&self, position: PositionSize) {
+        let mut state = self.state.write().expect("live view lock poisoned");
+        state.position = Some(position);
+        state.last_message_at = Some(Instant::now());
+    }
+
+    pub fn update_trading_balance(&self, balance: AvailableBalance) {
+        let mut state = self.state.write().expect("live view lock poisoned");
+        state.trading_balance = Some(balance);
+        state.last_message_at = Some(Instant::now());
+    }
+
+    pub fn heartbeat(&self) {
+        self.state
+            .write()
+            .expect("live view lock poisoned")
+            .last_message_at = Some(Instant::now());
+    }
+
+    pub fn disconnected(&self) {
+        *self.state.write().expect("live view lock poisoned") = LiveState::default();
+    }
+
+    pub fn is_fresh(&self) -> bool {
+        let state = self.state.read().expect("live view lock poisoned");
+        self.fresh(&state)
+    }
+
+    pub fn position(&self) -> Option<PositionSize> {
+        let state = self.state.read().expect("live view lock poisoned");
+        state.position.clone().filter(|_| self.fresh(&state))
+    }
+
+    pub fn trading_balance(&self) -> Option<AvailableBalance> {
+        let state = self.state.read().expect("live view lock poisoned");
+        state.trading_balance.clone().filter(|_| self.fresh(&state))
+    }
+
+    fn fresh(&self, state: &LiveState) -> bool {
+        state
+            .last_message_at
+            .is_some_and(|at| at.elapsed() < self.stale_after)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use okex_client::OkexInstrumentId;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn position(usd_cents: Decimal) -> PositionSize {
+        PositionSize {
+            instrument_id: OkexInstrumentId::BtcUsdSwap,
+            usd_cents,
+            last_price_in_usd_cents: dec!(4_000_000),
+            unrealized_pnl_btc: Decimal::ZERO,
+        }
+    }
+
+    #[test]
+    fn serves_latest_push_while_fresh() {
+        let view = OkexLiveView::new(Duration::from_secs(30));
+        assert!(!view.is_fresh());
+        assert!(view.position().is_none());
+
+        view.update_position(position(dec!(-50_000)));
+        view.update_position(position(dec!(-100_000)));
+        assert!(view.is_fresh());
+        assert_eq!(view.position().unwrap().usd_cents, dec!(-100_000));
+        assert!(view.trading_balance().is_none());
+    }
+
+    #[test]
+    fn stale_view_is_not_served() {
+        let view = OkexLiveView::new(Duration::ZERO);
+        view.update_position(position(dec!(-50_000)));
+        assert!(!view.is_fresh());
+        assert!(view.position().is_none());
+    }
+
+    #[test]
+    fn disconnect_clears_view() {
+        let view = OkexLiveView::new(Duration::from_secs(30));
+        view.update_position(position(dec!(-50_000)));
+        view.disconnected();
+        view.heartbeat();
+        assert!(view.is_fresh());
+        assert!(view.position().is_none());
+    }
+}
@@ -0,0 +1,109 @@
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+
+use okex_client::PositionRisk;
+use shared::health::HealthCheckResponse;
+
+use super::config::OkexMarginConfig;
+use crate::error::HedgingError;
+
+pub struct OkexMarginSnapshot {
+    pub instrument: String,
+    pub margin_ratio: Option<Decimal>,
+    pub liquidation_distance: Option<Decimal>,
+}
+
+/// Time series of the margin ratio and liquidation price okex reports
+/// for the active instrument.
+#[derive(Clone)]
+pub struct OkexMargin {
+    pool: PgPool,
+    config: OkexMarginConfig,
+}
+
+impl OkexMargin {
+    pub async fn new(pool: PgPool, config: OkexMarginConfig) -> Result<Self, HedgingError> {
+        Ok(Self { pool, config })
+    }
+
+    pub async fn record(&self, risk: &PositionRisk) -> Result<(), HedgingError> {
+        sqlx::query!(
+            r#"INSERT INTO okex_margin_snapshots (instrument, margin_ratio, liquidation_price_usd_cents, mark_price_usd_cents, liquidation_distance)
+               VALUES ($1, $2, $3, $4, $5)"#,
+            risk.instrument_id.to_string(),
+            risk.margin_ratio,
+            risk.liquidation_price_in_usd_cents,
+            risk.mark_price_in_usd_cents,
+            risk.liquidation_distance(),
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn latest(&self) -> Result<Option<OkexMarginSnapshot>, HedgingError> {
+        let res = sqlx::query_as!(
+            OkexMarginSnapshot,
+            r#"SELECT instrument, margin_ratio, liquidation_distance
+               FROM okex_margin_snapshots ORDER BY id DESC LIMIT 1"#
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(res)
+    }
+
+    pub fn requires_emergency_funding(&self, risk: &PositionRisk) -> bool {
+        is_below(
+            risk.liquidation_distance(),
+            self.config.min_liquidation_distance,
+        )
+    }
+
+    pub async fn healthy(&self) -> HealthCheckResponse {
+        match self.latest().await {
+            Ok(Some(snapshot))
+                if is_below(
+                    snapshot.liquidation_distance,
+                    self.config.min_liquidation_distance,
+                ) =>
+            {
+                Err(format!(
+                    "Okex {} liquidation distance {} below {} (margin ratio {})",
+                    snapshot.instrument,
+                    snapshot.liquidation_distance.unwrap_or_default(),
+                    self.config.min_liquidation_distance,
+                    snapshot
+                        .margin_ratio
+                        .map(|ratio| ratio.to_string())
+                        .unwrap_or_else(|| "unknown".to_string()),
+                ))
+            }
+            Ok(_) => Ok(()),
+            Err(e) => Err(format!("Couldn't load okex margin snapshot: {e}")),
+        }
+    }
+}
+
+/// A flat position has no liquidation price and is never at risk
+fn is_below(liquidation_distance: Option<Decimal>, min_liquidation_distance: Decimal) -> bool {
+    liquidation_distance.is_some_and(|distance| distance < min_liquidation_distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn flat_position_is_not_at_risk() {
+        assert!(!is_below(None, dec!(0.25)));
+    }
+
+    #[test]
+    fn distance_below_threshold_is_at_risk() {
+        assert!(is_below(Some(dec!(0.1)), dec!(0.25)));
+        assert!(!is_below(Some(dec!(0.25)), dec!(0.25)));
+        assert!(!is_below(Some(dec!(0.5)), dec!(0.25)));
+    }
+}
@@ -1,14 +1,33 @@
+mod account_setup;
+mod bills;
 mod config;
 mod engine;
+mod execution;
 mod funding_adjustment;
+mod funding_rail;
 mod hedge_adjustment;
+mod instrument;
 pub mod job;
+mod live_view;
+mod margin;
+mod order_placement;
 mod orders;
+mod reconciliation;
 mod transfers;
+mod venue;
 
+pub use account_setup::*;
+pub use bills::*;
 pub use config::*;
 pub use engine::*;
+pub use execution::*;
 pub use funding_adjustment::*;
+pub use funding_rail::*;
 pub use hedge_adjustment::*;
+pub use instrument::*;
+pub use margin::*;
+pub use order_placement::*;
 pub use orders::*;
+pub use reconciliation::*;
 pub use transfers::*;
+pub use venue::OkexVenue;
@@ -0,0 +1,160 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::{Decimal, RoundingStrategy};
+use rust_decimal_macros::dec;
+
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use shared::{payload::OrderBookPayload, time::TimeStamp};
+
+use super::{OkexHedgingConfig, OkexOrderExecutionMode};
+use crate::venue::OrderSide;
+
+// Order book prices are in cents per sat, okex expects usd per btc
+const CENTS_PER_SAT_TO_USD_PER_BTC: Decimal = dec!(1_000_000);
+const PRICE_TICK_DECIMALS: u32 = 1;
+const MAX_ORDER_BOOK_AGE_SECS: i64 = 30;
+
+#[derive(Debug, Clone, Copy)]
+struct TopOfBook {
+    best_bid: Decimal,
+    best_ask: Decimal,
+    timestamp: TimeStamp,
+}
+
+#[derive(Debug, Clone)]
+pub struct OrderPlacement {
+    mode: OkexOrderExecutionMode,
+    reprice_interval: Duration,
+    fallback_after: Duration,
+    top_of_book: Arc<RwLock<Option<TopOfBook>>>,
+}
+
+impl OrderPlacement {
+    pub fn new(config: &OkexHedgingConfig) -> Self {
+        Self {
+            mode: config.execution_mode,
+            reprice_interval: config.passive_reprice_interval,
+            fallback_after: config.passive_fallback_after,
+            top_of_book: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    pub fn reprice_interval(&self) -> Duration {
+        self.reprice_interval
+    }
+
+    pub fn fallback_due(&self, placed_at: DateTime<Utc>) -> bool {
+        let fallback_after =
+            chrono::Duration::from_std(self.fallback_after).unwrap_or(chrono::Duration::zero());
+        Utc::now() - placed_at >= fallback_after
+    }
+
+    pub fn update_order_book(&self, payload: &OrderBookPayload) {
+        let (Some((best_ask, _)), Some((best_bid, _))) =
+            (payload.asks.iter().next(), payload.bids.iter().next_back())
+        else {
+            return;
+        };
+        *self.top_of_book.write().expect("top of book lock poisoned") = Some(TopOfBook {
+            best_bid: Decimal::from(best_bid.clone()) * CENTS_PER_SAT_TO_USD_PER_BTC,
+            best_ask: Decimal::from(best_ask.clone()) * CENTS_PER_SAT_TO_USD_PER_BTC,
+            timestamp: payload.timestamp,
+        });
+    }
+
+    /// Price to rest a post-only order at, joining our own side of the book.
+    /// Returns None when orders should go to market instead.
+    pub fn passive_price(&self, side: &OrderSide) -> Option<Decimal> {
+        if self.mode != OkexOrderExecutionMode::Passive {
+            return None;
+        }
+        let top = (*self.top_of_book.read().expect("top of book lock poisoned"))?;
+        if top.timestamp.duration_since() > chrono::Duration::seconds(MAX_ORDER_BOOK_AGE_SECS) {
+            return None;
+        }
+        let price = match side {
+            OrderSide::Sell => top
+                .best_ask
+                .round_dp_with_strategy(PRICE_TICK_DECIMALS, RoundingStrategy::ToPositiveInfinity),
+            OrderSide::Buy => top
+                .best_bid
+                .round_dp_with_strategy(PRICE_TICK_DECIMALS, RoundingStrategy::ToNegativeInfinity),
+        };
+        Some(price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use shared::payload::{ExchangeIdRaw, PriceRaw, VolumeInCentsRaw};
+
+    use super::*;
+
+    fn passive_placement() -> OrderPlacement {
+        OrderPlacement::new(&OkexHedgingConfig {
+            execution_mode: OkexOrderExecutionMode::Passive,
+            ..Default::default()
+        })
+    }
+
+    fn order_book(timestamp: TimeStamp) -> OrderBookPayload {
+        let mut asks = BTreeMap::new();
+        asks.insert(
+            PriceRaw::from(dec!(0.04300012)),
+            VolumeInCentsRaw::from(dec!(10000)),
+        );
+        asks.insert(
+            PriceRaw::from(dec!(0.0431)),
+            VolumeInCentsRaw::from(dec!(10000)),
+        );
+        let mut bids = BTreeMap::new();
+        bids.insert(
+            PriceRaw::from(dec!(0.0429)),
+            VolumeInCentsRaw::from(dec!(10000)),
+        );
+        bids.insert(
+            PriceRaw::from(dec!(0.04299988)),
+            VolumeInCentsRaw::from(dec!(10000)),
+        );
+        OrderBookPayload {
+            asks,
+            bids,
+            timestamp,
+            exchange: ExchangeIdRaw::from("okex".to_string()),
+        }
+    }
+
+    #[test]
+    fn passive_price_joins_own_side_of_book() {
+        let placement = passive_placement();
+        placement.update_order_book(&order_book(TimeStamp::now()));
+        assert_eq!(
+            placement.passive_price(&OrderSide::Sell),
+            Some(dec!(43000.2))
+        );
+        assert_eq!(
+            placement.passive_price(&OrderSide::Buy),
+            Some(dec!(42999.8))
+        );
+    }
+
+    #[test]
+    fn no_passive_price_in_market_mode() {
+        let placement = OrderPlacement::new(&OkexHedgingConfig::default());
+        placement.update_order_book(&order_book(TimeStamp::now()));
+        assert_eq!(placement.passive_price(&OrderSide::Sell), None);
+    }
+
+    #[test]
+    fn no_passive_price_without_fresh_book() {
+        let placement = passive_placement();
+        assert_eq!(placement.passive_price(&OrderSide::Buy), None);
+        placement.update_order_book(&order_book(TimeStamp::from(1_600_000_000)));
+        assert_eq!(placement.passive_price(&OrderSide::Buy), None);
+    }
+}
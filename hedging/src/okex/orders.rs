@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use sqlx::{Executor, PgPool};
 use uuid::Uuid;
@@ -10,11 +11,42 @@ use crate::error::HedgingError;
 
 pub struct OrderReservation<'a> {
     pub correlation_id: CorrelationId,
+    pub instrument: String,
     pub action: &'a OkexHedgeAdjustment,
     pub target_usd_value: Decimal,
     pub usd_value_before_order: Decimal,
 }
 
+pub struct PassiveOrder {
+    pub id: ClientOrderId,
+    pub instrument: String,
+    pub action_type: String,
+    pub size: Decimal,
+    pub limit_price: Decimal,
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct ParentOrder {
+    pub id: ClientOrderId,
+    pub instrument: String,
+    pub action_type: String,
+    pub target_usd_value: Decimal,
+    /// When the parent or its latest slice was reserved
+    pub last_activity_at: DateTime<Utc>,
+}
+
+pub struct OpenOrder {
+    pub id: ClientOrderId,
+    pub correlation_id: CorrelationId,
+    pub instrument: String,
+    pub action_type: String,
+    pub order_type: String,
+    pub size: Option<Decimal>,
+    pub size_usd_value: Option<Decimal>,
+    pub is_parent: bool,
+    pub created_at: DateTime<Utc>,
+}
+
 #[derive(Clone)]
 pub struct OkexOrders {
     pool: PgPool,
@@ -28,12 +60,37 @@ impl OkexOrders {
     pub async fn reserve_order_slot<'a>(
         &self,
         reservation: OrderReservation<'a>,
+    ) -> Result<Option<ClientOrderId>, HedgingError> {
+        self.reserve(reservation, false, None).await
+    }
+
+    pub async fn reserve_parent_order<'a>(
+        &self,
+        reservation: OrderReservation<'a>,
+    ) -> Result<Option<ClientOrderId>, HedgingError> {
+        self.reserve(reservation, true, None).await
+    }
+
+    pub async fn reserve_child_order_slot<'a>(
+        &self,
+        reservation: OrderReservation<'a>,
+        parent_id: &ClientOrderId,
+    ) -> Result<Option<ClientOrderId>, HedgingError> {
+        self.reserve(reservation, false, Some(parent_id)).await
+    }
+
+    async fn reserve<'a>(
+        &self,
+        reservation: OrderReservation<'a>,
+        is_parent: bool,
+        parent_id: Option<&ClientOrderId>,
     ) -> Result<Option<ClientOrderId>, HedgingError> {
         let mut tx = self.pool.begin().await?;
         tx.execute("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE")
             .await?;
         let res = sqlx::query!(
-            r#"SELECT client_order_id FROM okex_orders WHERE complete = false AND lost = false"#
+            r#"SELECT client_order_id FROM okex_orders WHERE complete = false AND lost = false AND NOT (is_parent AND $1)"#,
+            parent_id.is_some()
         )
         .fetch_all(&mut *tx)
         .await?;
@@ -46,17 +103,20 @@ impl OkexOrders {
             r#"INSERT INTO okex_orders (
               client_order_id, correlation_id, instrument,
               action, size, unit, size_usd_value, target_usd_value,
-              position_usd_value_before_order
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"#,
+              position_usd_value_before_order, is_parent, parent_client_order_id, state
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)"#,
             String::from(id.clone()),
             Uuid::from(reservation.correlation_id),
-            "BTC-USD-SWAP",
+            reservation.instrument,
             reservation.action.action_type(),
             reservation.action.size().map(Decimal::from),
             reservation.action.unit(),
             reservation.action.size_in_usd(),
             reservation.target_usd_value,
             reservation.usd_value_before_order,
+            is_parent,
+            parent_id.map(|id| String::from(id.clone())),
+            if is_parent { Some("executing") } else { None },
         )
         .execute(&mut *tx)
         .await?;
@@ -64,24 +124,198 @@ impl OkexOrders {
         Ok(Some(id))
     }
 
-    pub async fn open_orders(&self) -> Result<Vec<ClientOrderId>, HedgingError> {
-        let res = sqlx::query!(r#"SELECT client_order_id FROM okex_orders WHERE complete = false"#)
+    pub async fn open_parent_order(&self) -> Result<Option<ParentOrder>, HedgingError> {
+        let res = sqlx::query!(
+            r#"SELECT p.client_order_id, p.instrument, p.action, p.target_usd_value,
+                 GREATEST(p.created_at, MAX(c.created_at)) AS "last_activity_at!"
+               FROM okex_orders p
+               LEFT JOIN okex_orders c ON c.parent_client_order_id = p.client_order_id
+               WHERE p.is_parent = true AND p.complete = false
+               GROUP BY p.client_order_id"#
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(res.map(|r| ParentOrder {
+            id: ClientOrderId::from(r.client_order_id),
+            instrument: r.instrument,
+            action_type: r.action,
+            target_usd_value: r.target_usd_value,
+            last_activity_at: r.last_activity_at,
+        }))
+    }
+
+    pub async fn complete_parent_order(
+        &self,
+        id: &ClientOrderId,
+        state: &str,
+    ) -> Result<(), HedgingError> {
+        sqlx::query!(
+            r#"UPDATE okex_orders SET complete = true, state = $1 WHERE client_order_id = $2 AND is_parent = true"#,
+            state,
+            String::from(id.clone()),
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Records the order a cycle would have placed, once per correlation id
+    pub async fn record_shadow_order<'a>(
+        &self,
+        reservation: OrderReservation<'a>,
+    ) -> Result<Option<ClientOrderId>, HedgingError> {
+        let id = ClientOrderId::new();
+        let res = sqlx::query!(
+            r#"INSERT INTO okex_orders (
+              client_order_id, correlation_id, instrument,
+              action, size, unit, size_usd_value, target_usd_value,
+              position_usd_value_before_order, complete, state, shadow
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, true, 'shadow', true)
+            ON CONFLICT (correlation_id) WHERE shadow DO NOTHING"#,
+            String::from(id.clone()),
+            Uuid::from(reservation.correlation_id),
+            reservation.instrument,
+            reservation.action.action_type(),
+            reservation.action.size().map(Decimal::from),
+            reservation.action.unit(),
+            reservation.action.size_in_usd(),
+            reservation.target_usd_value,
+            reservation.usd_value_before_order,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok((res.rows_affected() > 0).then_some(id))
+    }
+
+    pub async fn record_passive_order(
+        &self,
+        id: &ClientOrderId,
+        order_type: &str,
+        limit_price: Decimal,
+    ) -> Result<(), HedgingError> {
+        sqlx::query!(
+            r#"UPDATE okex_orders SET order_type = $1, limit_price = $2 WHERE client_order_id = $3"#,
+            order_type,
+            limit_price,
+            String::from(id.clone()),
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn update_limit_price(
+        &self,
+        id: &ClientOrderId,
+        limit_price: Decimal,
+    ) -> Result<(), HedgingError> {
+        sqlx::query!(
+            r#"UPDATE okex_orders SET limit_price = $1 WHERE client_order_id = $2"#,
+            limit_price,
+            String::from(id.clone()),
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn passive_order(
+        &self,
+        id: &ClientOrderId,
+    ) -> Result<Option<PassiveOrder>, HedgingError> {
+        let res = sqlx::query!(
+            r#"SELECT client_order_id, instrument, action, size as "size!", limit_price as "limit_price!", created_at
+               FROM okex_orders
+               WHERE client_order_id = $1 AND order_type != 'market' AND size IS NOT NULL AND limit_price IS NOT NULL"#,
+            String::from(id.clone()),
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(res.map(|r| PassiveOrder {
+            id: ClientOrderId::from(r.client_order_id),
+            instrument: r.instrument,
+            action_type: r.action,
+            size: r.size,
+            limit_price: r.limit_price,
+            created_at: r.created_at,
+        }))
+    }
+
+    /// Reserves a market order for the unfilled remainder of a passive order.
+    /// Returns None if a fallback has already been reserved for it.
+    pub async fn reserve_fallback_order(
+        &self,
+        replaces: &ClientOrderId,
+        action: &OkexHedgeAdjustment,
+    ) -> Result<Option<ClientOrderId>, HedgingError> {
+        let id = ClientOrderId::new();
+        let res = sqlx::query!(
+            r#"INSERT INTO okex_orders (
+              client_order_id, correlation_id, instrument,
+              action, size, unit, size_usd_value, target_usd_value,
+              position_usd_value_before_order, parent_client_order_id, replaces_client_order_id
+            ) SELECT $1, correlation_id, instrument, $2, $3, $4, $5, target_usd_value,
+                position_usd_value_before_order, parent_client_order_id, client_order_id
+              FROM okex_orders WHERE client_order_id = $6 AND complete = true"#,
+            String::from(id.clone()),
+            action.action_type(),
+            action.size().map(Decimal::from),
+            action.unit(),
+            action.size_in_usd(),
+            String::from(replaces.clone()),
+        )
+        .execute(&self.pool)
+        .await;
+        match res {
+            Ok(res) if res.rows_affected() == 1 => Ok(Some(id)),
+            Ok(_) => Ok(None),
+            Err(sqlx::Error::Database(err)) if err.message().contains("duplicate key") => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn open_orders(&self) -> Result<Vec<(ClientOrderId, String)>, HedgingError> {
+        let res = sqlx::query!(r#"SELECT client_order_id, instrument FROM okex_orders WHERE complete = false AND is_parent = false"#)
             .fetch_all(&self.pool)
             .await?;
         Ok(res
             .into_iter()
-            .map(|r| ClientOrderId::from(r.client_order_id))
+            .map(|r| (ClientOrderId::from(r.client_order_id), r.instrument))
+            .collect())
+    }
+
+    pub async fn open_order_details(&self) -> Result<Vec<OpenOrder>, HedgingError> {
+        let res = sqlx::query!(
+            r#"SELECT client_order_id, correlation_id, instrument, action, order_type, size, size_usd_value, is_parent, created_at
+               FROM okex_orders WHERE complete = false AND shadow = false ORDER BY created_at"#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(res
+            .into_iter()
+            .map(|r| OpenOrder {
+                id: ClientOrderId::from(r.client_order_id),
+                correlation_id: CorrelationId::from(r.correlation_id),
+                instrument: r.instrument,
+                action_type: r.action,
+                order_type: r.order_type,
+                size: r.size,
+                size_usd_value: r.size_usd_value,
+                is_parent: r.is_parent,
+                created_at: r.created_at,
+            })
             .collect())
     }
 
     pub async fn update_order(&self, details: OrderDetails) -> Result<(), HedgingError> {
         sqlx::query!(
-            r#"UPDATE okex_orders SET lost = false, order_id = $1, avg_price = $2, fee = $3, state = $4, complete = $5 WHERE client_order_id = $6"#,
+            r#"UPDATE okex_orders SET lost = false, order_id = $1, avg_price = $2, fee = $3, state = $4, complete = $5, filled_size = $6 WHERE client_order_id = $7"#,
             details.ord_id,
             details.avg_px,
             details.fee,
             details.state,
             details.complete,
+            details.acc_fill_sz,
             String::from(details.cl_ord_id),
         )
         .execute(&self.pool)
@@ -101,7 +335,7 @@ impl OkexOrders {
 
     pub async fn sweep_lost_records(&self) -> Result<(), HedgingError> {
         sqlx::query!(
-            r#"DELETE FROM okex_orders WHERE lost = true AND complete = false AND created_at < now() - interval '5 hour'"#
+            r#"DELETE FROM okex_orders WHERE lost = true AND complete = false AND is_parent = false AND created_at < now() - interval '5 hour'"#
         )
         .execute(&self.pool)
         .await?;
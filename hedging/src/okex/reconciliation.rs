@@ -0,0 +1,165 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use shared::{health::HealthCheckResponse, payload::OKEX_EXCHANGE_ID};
+
+use super::config::OkexReconciliationConfig;
+use crate::error::HedgingError;
+
+/// One balance as the ledger sees it next to what the exchange or wallet reports
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReconciliationCheck {
+    pub name: String,
+    pub ledger: Decimal,
+    pub external: Decimal,
+    pub tolerance: Decimal,
+}
+
+impl ReconciliationCheck {
+    pub fn new(name: &str, ledger: Decimal, external: Decimal, tolerance: Decimal) -> Self {
+        Self {
+            name: name.to_string(),
+            ledger,
+            external,
+            tolerance,
+        }
+    }
+
+    pub fn difference(&self) -> Decimal {
+        self.external - self.ledger
+    }
+
+    pub fn is_discrepancy(&self) -> bool {
+        self.difference().abs() > self.tolerance
+    }
+}
+
+/// Periodic comparison of the ledger against okex and the bria wallet.
+#[derive(Clone)]
+pub struct OkexReconciliation {
+    pool: PgPool,
+    config: OkexReconciliationConfig,
+}
+
+impl OkexReconciliation {
+    pub async fn new(pool: PgPool, config: OkexReconciliationConfig) -> Result<Self, HedgingError> {
+        Ok(Self { pool, config })
+    }
+
+    pub fn config(&self) -> &OkexReconciliationConfig {
+        &self.config
+    }
+
+    /// Returns the names of the checks that were out of tolerance
+    pub async fn record(
+        &self,
+        checks: Vec<ReconciliationCheck>,
+    ) -> Result<Vec<String>, HedgingError> {
+        let discrepancies: Vec<String> = checks
+            .iter()
+            .filter(|check| check.is_discrepancy())
+            .map(|check| check.name.clone())
+            .collect();
+        sqlx::query!(
+            r#"INSERT INTO reconciliation_runs (exchange, checks, discrepancies)
+               VALUES ($1, $2, $3)"#,
+            OKEX_EXCHANGE_ID,
+            serde_json::to_value(checks)?,
+            &discrepancies,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(discrepancies)
+    }
+
+    /// Checks that were out of tolerance in every one of the latest runs, with their latest state
+    pub async fn persistent_discrepancies(&self) -> Result<Vec<ReconciliationCheck>, HedgingError> {
+        let runs = sqlx::query!(
+            r#"SELECT checks, discrepancies FROM reconciliation_runs
+               WHERE exchange = $1 ORDER BY id DESC LIMIT $2"#,
+            OKEX_EXCHANGE_ID,
+            i64::from(self.config.persistent_after_runs),
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        if runs.len() < self.config.persistent_after_runs as usize {
+            return Ok(Vec::new());
+        }
+        let persistent = persistent_in(runs.iter().map(|run| run.discrepancies.as_slice()));
+        let latest: Vec<ReconciliationCheck> = match runs.into_iter().next() {
+            Some(run) => serde_json::from_value(run.checks)?,
+            None => return Ok(Vec::new()),
+        };
+        Ok(latest
+            .into_iter()
+            .filter(|check| persistent.contains(&check.name))
+            .collect())
+    }
+
+    pub async fn healthy(&self) -> HealthCheckResponse {
+        match self.persistent_discrepancies().await {
+            Ok(drift) if drift.is_empty() => Ok(()),
+            Ok(drift) => Err(format!(
+                "Ledger drifted from okex over the last {} reconciliations: {}",
+                self.config.persistent_after_runs,
+                drift
+                    .iter()
+                    .map(|check| format!(
+                        "{} ledger {} external {}",
+                        check.name, check.ledger, check.external
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+            Err(e) => Err(format!("Couldn't load reconciliation runs: {e}")),
+        }
+    }
+}
+
+/// Names present in every run
+fn persistent_in<'a>(mut runs: impl Iterator<Item = &'a [String]>) -> Vec<String> {
+    let mut persistent = match runs.next() {
+        Some(first) => first.to_vec(),
+        None => return Vec::new(),
+    };
+    for run in runs {
+        persistent.retain(|name| run.contains(name));
+    }
+    persistent
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn discrepancy_is_beyond_tolerance_either_way() {
+        let check = |ledger, external| {
+            ReconciliationCheck::new("okex_funding_btc", ledger, external, dec!(0.001))
+        };
+        assert!(!check(dec!(1), dec!(1.001)).is_discrepancy());
+        assert!(!check(dec!(1), dec!(0.999)).is_discrepancy());
+        assert!(check(dec!(1), dec!(1.0011)).is_discrepancy());
+        assert!(check(dec!(1), dec!(0.9989)).is_discrepancy());
+        assert_eq!(check(dec!(1), dec!(0.9)).difference(), dec!(-0.1));
+    }
+
+    #[test]
+    fn only_discrepancies_in_every_run_persist() {
+        let runs = [
+            vec!["okex_position_usd".to_string(), "wallet_btc".to_string()],
+            vec!["wallet_btc".to_string()],
+            vec!["okex_funding_btc".to_string(), "wallet_btc".to_string()],
+        ];
+        assert_eq!(
+            persistent_in(runs.iter().map(Vec::as_slice)),
+            vec!["wallet_btc".to_string()]
+        );
+        assert!(persistent_in(runs[..2].iter().rev().map(Vec::as_slice))
+            .contains(&"wallet_btc".to_string()));
+        assert!(persistent_in(std::iter::empty()).is_empty());
+    }
+}
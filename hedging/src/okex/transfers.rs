@@ -1,15 +1,127 @@
 use rust_decimal::Decimal;
-use sqlx::{Executor, PgPool};
+use sqlx::{Acquire, Executor, PgPool};
 use uuid::Uuid;
 
+use std::{fmt, future::Future, time::Duration};
+
+use bria_client::{PayoutEvent, PayoutEventKind};
+use ledger::{
+    Ledger, LedgerTxId, OkexDepositInitiatedParams, OkexDepositMeta, OkexDepositSettledParams,
+    OkexInternalTransferMeta, OkexInternalTransferParams, OkexWithdrawalMeta, OkexWithdrawalParams,
+};
 use okex_client::{ClientTransferId, TransferState, WithdrawalStatus};
-use shared::pubsub::CorrelationId;
+use shared::{health::HealthCheckResponse, pubsub::CorrelationId};
 
+use super::config::OkexTransfersConfig;
 use crate::error::HedgingError;
 
+const LEDGER_POSTING_BATCH_SIZE: i64 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "OkexTransferKind", rename_all = "snake_case")]
+pub enum OkexTransferKind {
+    TradingToFunding,
+    FundingToTrading,
+    Deposit,
+    Withdraw,
+    LightningDeposit,
+    LightningWithdraw,
+}
+
+impl sqlx::postgres::PgHasArrayType for OkexTransferKind {
+    fn array_type_info() -> sqlx::postgres::PgTypeInfo {
+        sqlx::postgres::PgTypeInfo::with_name("_OkexTransferKind")
+    }
+}
+
+impl OkexTransferKind {
+    /// Kinds that may not be in flight at the same time as this one.
+    /// Internal transfers settle within seconds and never wait on the chain,
+    /// movements in and out of okex share a lane whatever their rail.
+    pub fn conflicting(&self) -> &'static [OkexTransferKind] {
+        match self {
+            Self::TradingToFunding | Self::FundingToTrading => {
+                &[Self::TradingToFunding, Self::FundingToTrading]
+            }
+            Self::Deposit | Self::Withdraw | Self::LightningDeposit | Self::LightningWithdraw => &[
+                Self::Deposit,
+                Self::Withdraw,
+                Self::LightningDeposit,
+                Self::LightningWithdraw,
+            ],
+        }
+    }
+
+    pub fn is_onchain(&self) -> bool {
+        matches!(self, Self::Deposit | Self::Withdraw)
+    }
+
+    /// Deposits are in flight on the ledger from the moment the btc leaves the wallet
+    fn is_deposit(&self) -> bool {
+        matches!(self, Self::Deposit | Self::LightningDeposit)
+    }
+
+    /// Okex can't look lightning movements up by client id, so resubmitting
+    /// one after an error could pay twice
+    pub fn is_retryable(&self) -> bool {
+        !matches!(self, Self::LightningDeposit | Self::LightningWithdraw)
+    }
+}
+
+impl fmt::Display for OkexTransferKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TradingToFunding => write!(f, "trading_to_funding"),
+            Self::FundingToTrading => write!(f, "funding_to_trading"),
+            Self::Deposit => write!(f, "deposit"),
+            Self::Withdraw => write!(f, "withdraw"),
+            Self::LightningDeposit => write!(f, "lightning_deposit"),
+            Self::LightningWithdraw => write!(f, "lightning_withdraw"),
+        }
+    }
+}
+
+/// reserved -> submitted -> confirming -> settled, with failed and lost as
+/// the other ends. A failed submission stays reserved until it is retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "OkexTransferStatus", rename_all = "snake_case")]
+pub enum OkexTransferStatus {
+    Reserved,
+    Submitted,
+    Confirming,
+    Settled,
+    Failed,
+    Lost,
+}
+
+impl OkexTransferStatus {
+    /// Maps the `pending`, `success` and `failed` states okex-client reports.
+    /// A pending on-chain movement is confirming once okex has seen it.
+    pub fn observed(okex_state: &str, on_chain: bool) -> Self {
+        match okex_state {
+            "success" => Self::Settled,
+            "failed" => Self::Failed,
+            _ if on_chain => Self::Confirming,
+            _ => Self::Submitted,
+        }
+    }
+}
+
+impl fmt::Display for OkexTransferStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Reserved => write!(f, "reserved"),
+            Self::Submitted => write!(f, "submitted"),
+            Self::Confirming => write!(f, "confirming"),
+            Self::Settled => write!(f, "settled"),
+            Self::Failed => write!(f, "failed"),
+            Self::Lost => write!(f, "lost"),
+        }
+    }
+}
+
 pub struct TransferReservationSharedData {
     pub correlation_id: CorrelationId,
-    pub action_type: String,
     pub action_unit: String,
     pub target_usd_exposure: Decimal,
     pub current_usd_exposure: Decimal,
@@ -20,6 +132,7 @@ pub struct TransferReservationSharedData {
 }
 
 pub struct TransferReservation<'a> {
+    pub kind: OkexTransferKind,
     pub action_size: Option<Decimal>,
     pub fee: Decimal,
     pub transfer_from: String,
@@ -27,14 +140,43 @@ pub struct TransferReservation<'a> {
     pub shared: &'a TransferReservationSharedData,
 }
 
+/// A reserved transfer whose submission failed and is due again
+pub struct TransferRetry {
+    pub client_transfer_id: ClientTransferId,
+    pub kind: OkexTransferKind,
+    pub amount: Decimal,
+    pub fee: Decimal,
+    pub transfer_to: String,
+}
+
+pub struct StuckTransfer {
+    pub client_transfer_id: ClientTransferId,
+    pub kind: OkexTransferKind,
+    pub status: OkexTransferStatus,
+    pub since: chrono::DateTime<chrono::Utc>,
+}
+
+pub struct InFlightTransfer {
+    pub client_transfer_id: ClientTransferId,
+    pub correlation_id: CorrelationId,
+    pub kind: OkexTransferKind,
+    pub status: OkexTransferStatus,
+    pub amount: Decimal,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
 #[derive(Clone)]
 pub struct OkexTransfers {
     pool: PgPool,
+    config: OkexTransfersConfig,
 }
 
 impl OkexTransfers {
-    pub async fn new(pool: PgPool) -> Result<Self, HedgingError> {
-        Ok(Self { pool })
+    pub async fn new(pool: PgPool, config: OkexTransfersConfig) -> Result<Self, HedgingError> {
+        Ok(Self { pool, config })
     }
 
     pub async fn reserve_transfer_slot<'a>(
@@ -45,7 +187,9 @@ impl OkexTransfers {
         tx.execute("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE")
             .await?;
         let res = sqlx::query!(
-            r#"SELECT client_transfer_id FROM okex_transfers WHERE state = 'pending' AND lost = false"#,
+            r#"SELECT client_transfer_id FROM okex_transfers
+               WHERE kind = ANY($1) AND status IN ('reserved', 'submitted', 'confirming') AND shadow = false"#,
+            reservation.kind.conflicting() as &[OkexTransferKind],
         )
         .fetch_all(&mut *tx)
         .await?;
@@ -56,9 +200,9 @@ impl OkexTransfers {
         let id = ClientTransferId::new();
         sqlx::query!(
             r#"INSERT INTO okex_transfers (
-                client_transfer_id, 
-                correlation_id, 
-                action, 
+                client_transfer_id,
+                correlation_id,
+                kind,
                 currency,
                 amount,
                 fee,
@@ -70,11 +214,11 @@ impl OkexTransfers {
                 trading_btc_total_balance,
                 current_usd_btc_price,
                 funding_btc_total_balance,
-                state
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)"#,
+                status
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, 'reserved')"#,
             String::from(id.clone()),
             Uuid::from(reservation.shared.correlation_id),
-            reservation.shared.action_type,
+            reservation.kind as OkexTransferKind,
             reservation.shared.action_unit,
             reservation.action_size,
             reservation.fee,
@@ -86,7 +230,6 @@ impl OkexTransfers {
             reservation.shared.trading_btc_total_balance,
             reservation.shared.current_usd_btc_price,
             reservation.shared.funding_btc_total_balance,
-            "pending"
         )
         .execute(&mut *tx)
         .await?;
@@ -94,56 +237,223 @@ impl OkexTransfers {
         Ok(Some(id))
     }
 
-    pub async fn get_pending_deposits(
+    /// Records the transfer a cycle would have made, once per correlation id and kind
+    pub async fn record_shadow_transfer<'a>(
+        &self,
+        reservation: TransferReservation<'a>,
+    ) -> Result<Option<ClientTransferId>, HedgingError> {
+        let id = ClientTransferId::new();
+        let res = sqlx::query!(
+            r#"INSERT INTO okex_transfers (
+                client_transfer_id,
+                correlation_id,
+                kind,
+                currency,
+                amount,
+                fee,
+                transfer_from,
+                transfer_to,
+                target_usd_exposure,
+                current_usd_exposure,
+                trading_btc_used_balance,
+                trading_btc_total_balance,
+                current_usd_btc_price,
+                funding_btc_total_balance,
+                status,
+                shadow
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, 'settled', true)
+            ON CONFLICT (correlation_id, kind) WHERE shadow DO NOTHING"#,
+            String::from(id.clone()),
+            Uuid::from(reservation.shared.correlation_id),
+            reservation.kind as OkexTransferKind,
+            reservation.shared.action_unit,
+            reservation.action_size,
+            reservation.fee,
+            reservation.transfer_from,
+            reservation.transfer_to,
+            reservation.shared.target_usd_exposure,
+            reservation.shared.current_usd_exposure,
+            reservation.shared.trading_btc_used_balance,
+            reservation.shared.trading_btc_total_balance,
+            reservation.shared.current_usd_btc_price,
+            reservation.shared.funding_btc_total_balance,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok((res.rows_affected() > 0).then_some(id))
+    }
+
+    /// Moves a reserved transfer to submitted once the submission went through.
+    /// A failed submission is scheduled for a retry and the error returned.
+    pub async fn submit<T>(
         &self,
-    ) -> Result<
-        Vec<(
-            ClientTransferId,
-            String,
-            Decimal,
-            chrono::DateTime<chrono::Utc>,
-        )>,
-        HedgingError,
-    > {
-        let res =
-            sqlx::query!(r#"SELECT client_transfer_id, transfer_to, amount, created_at FROM okex_transfers WHERE action = 'deposit' AND state = 'pending'"#)
-                .fetch_all(&self.pool)
+        id: ClientTransferId,
+        submission: impl Future<Output = Result<T, HedgingError>>,
+    ) -> Result<T, HedgingError> {
+        match submission.await {
+            Ok(res) => {
+                sqlx::query!(
+                    r#"UPDATE okex_transfers
+                       SET status = 'submitted', attempts = attempts + 1, next_attempt_at = NULL, last_error = NULL, updated_at = NOW()
+                       WHERE client_transfer_id = $1 AND status = 'reserved'"#,
+                    String::from(id),
+                )
+                .execute(&self.pool)
                 .await?;
+                Ok(res)
+            }
+            Err(e) => {
+                self.record_submission_failure(id, &e).await?;
+                Err(e)
+            }
+        }
+    }
+
+    async fn record_submission_failure(
+        &self,
+        id: ClientTransferId,
+        error: &HedgingError,
+    ) -> Result<(), HedgingError> {
+        let mut tx = self.pool.begin().await?;
+        let row = sqlx::query!(
+            r#"SELECT attempts, kind as "kind: OkexTransferKind" FROM okex_transfers WHERE client_transfer_id = $1 FOR UPDATE"#,
+            String::from(id.clone()),
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        let attempts = row.attempts as u32 + 1;
+        let delay = retry_delay(&self.config, attempts).filter(|_| row.kind.is_retryable());
+        let (status, next_attempt_at) = match delay {
+            Some(delay) => (
+                OkexTransferStatus::Reserved,
+                Some(
+                    chrono::Utc::now()
+                        + chrono::Duration::from_std(delay).expect("retry delay out of range"),
+                ),
+            ),
+            None => (OkexTransferStatus::Failed, None),
+        };
+        sqlx::query!(
+            r#"UPDATE okex_transfers
+               SET status = $1, attempts = $2, next_attempt_at = $3, last_error = $4, updated_at = NOW()
+               WHERE client_transfer_id = $5"#,
+            status as OkexTransferStatus,
+            attempts as i32,
+            next_attempt_at,
+            error.to_string(),
+            String::from(id),
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn due_for_retry(&self) -> Result<Vec<TransferRetry>, HedgingError> {
+        let res = sqlx::query!(
+            r#"SELECT client_transfer_id, kind as "kind: OkexTransferKind", amount, fee, transfer_to
+               FROM okex_transfers
+               WHERE status = 'reserved' AND next_attempt_at <= NOW() AND shadow = false
+               ORDER BY created_at"#
+        )
+        .fetch_all(&self.pool)
+        .await?;
         Ok(res
             .into_iter()
-            .map(|r| {
-                (
-                    ClientTransferId::from(r.client_transfer_id),
-                    r.transfer_to.unwrap_or_default(),
-                    r.amount,
-                    r.created_at,
-                )
+            .map(|r| TransferRetry {
+                client_transfer_id: ClientTransferId::from(r.client_transfer_id),
+                kind: r.kind,
+                amount: r.amount,
+                fee: r.fee,
+                transfer_to: r.transfer_to.unwrap_or_default(),
             })
             .collect())
     }
 
+    /// Deposits that bria has put on chain and okex has yet to credit
+    pub async fn get_pending_deposits(
+        &self,
+    ) -> Result<Vec<(ClientTransferId, String)>, HedgingError> {
+        let res = sqlx::query!(
+            r#"SELECT client_transfer_id, tx_id as "tx_id!" FROM okex_transfers
+               WHERE kind = 'deposit' AND status IN ('submitted', 'confirming') AND tx_id IS NOT NULL"#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(res
+            .into_iter()
+            .map(|r| (ClientTransferId::from(r.client_transfer_id), r.tx_id))
+            .collect())
+    }
+
+    pub async fn payout_cursor(&self) -> Result<Option<u64>, HedgingError> {
+        let res = sqlx::query!(r#"SELECT sequence FROM bria_payout_cursor"#)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(res.map(|r| r.sequence as u64))
+    }
+
+    /// Attaches the txid bria broadcast a deposit with, the external id of
+    /// the payout being our client transfer id
+    pub async fn record_payout_event(&self, event: &PayoutEvent) -> Result<(), HedgingError> {
+        let mut tx = self.pool.begin().await?;
+        if let Some(external_id) = event.external_id.as_ref() {
+            match (event.kind, event.tx_id.as_ref()) {
+                (PayoutEventKind::Cancelled, _) => {
+                    sqlx::query!(
+                        r#"UPDATE okex_transfers SET status = 'failed', last_error = 'bria payout cancelled', updated_at = NOW()
+                           WHERE client_transfer_id = $1 AND kind = 'deposit' AND status IN ('reserved', 'submitted')"#,
+                        external_id,
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                (_, Some(tx_id)) => {
+                    sqlx::query!(
+                        r#"UPDATE okex_transfers SET tx_id = $1
+                           WHERE client_transfer_id = $2 AND kind = 'deposit' AND tx_id IS NULL"#,
+                        tx_id,
+                        external_id,
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                _ => (),
+            }
+        }
+        sqlx::query!(
+            r#"INSERT INTO bria_payout_cursor (sequence) VALUES ($1)
+               ON CONFLICT (id) DO UPDATE SET sequence = $1, updated_at = NOW()"#,
+            event.sequence as i64,
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
     pub async fn update_deposit(
         &self,
         client_id: ClientTransferId,
         state: String,
         transfer_id: String,
     ) -> Result<(), HedgingError> {
-        sqlx::query!(
-            r#"UPDATE okex_transfers SET lost = false, transfer_id = $1, state = $2 WHERE client_transfer_id = $3"#,
+        self.observe(
+            client_id,
+            OkexTransferStatus::observed(&state, true),
             transfer_id,
-            state,
-            String::from(client_id),
         )
-        .execute(&self.pool)
-        .await?;
-        Ok(())
+        .await
     }
 
     pub async fn get_pending_transfers(&self) -> Result<Vec<ClientTransferId>, HedgingError> {
-        let res =
-            sqlx::query!(r#"SELECT client_transfer_id FROM okex_transfers WHERE action IN ('transfer-trading-to-funding', 'transfer-funding-to-trading') AND state = 'pending'"#)
-                .fetch_all(&self.pool)
-                .await?;
+        let res = sqlx::query!(
+            r#"SELECT client_transfer_id FROM okex_transfers
+               WHERE kind IN ('trading_to_funding', 'funding_to_trading')
+               AND (status = 'submitted' OR (status = 'lost' AND created_at > NOW() - interval '1 day'))"#
+        )
+        .fetch_all(&self.pool)
+        .await?;
         Ok(res
             .into_iter()
             .map(|r| ClientTransferId::from(r.client_transfer_id))
@@ -151,43 +461,111 @@ impl OkexTransfers {
     }
 
     pub async fn update_transfer(&self, details: TransferState) -> Result<(), HedgingError> {
-        sqlx::query!(
-            r#"UPDATE okex_transfers SET lost = false, transfer_id = $1, state = $2 WHERE client_transfer_id = $3"#,
+        self.observe(
+            ClientTransferId::from(details.client_id),
+            OkexTransferStatus::observed(&details.state, false),
             details.transfer_id,
-            details.state,
-            String::from(details.client_id),
         )
-        .execute(&self.pool)
-        .await?;
-        Ok(())
+        .await
     }
 
     pub async fn get_pending_withdrawals(&self) -> Result<Vec<ClientTransferId>, HedgingError> {
-        let res =
-            sqlx::query!(r#"SELECT client_transfer_id FROM okex_transfers WHERE action = 'withdraw' AND state = 'pending'"#)
-                .fetch_all(&self.pool)
-                .await?;
+        let res = sqlx::query!(
+            r#"SELECT client_transfer_id FROM okex_transfers
+               WHERE kind = 'withdraw'
+               AND (status IN ('submitted', 'confirming') OR (status = 'lost' AND created_at > NOW() - interval '1 day'))"#
+        )
+        .fetch_all(&self.pool)
+        .await?;
         Ok(res
             .into_iter()
             .map(|r| ClientTransferId::from(r.client_transfer_id))
             .collect())
     }
 
-    pub async fn update_withdrawal(&self, details: WithdrawalStatus) -> Result<(), HedgingError> {
+    /// Lightning withdrawals that okex has accepted, with the withdrawal id to follow them by
+    pub async fn get_pending_lightning_withdrawals(
+        &self,
+    ) -> Result<Vec<(ClientTransferId, String)>, HedgingError> {
+        let res = sqlx::query!(
+            r#"SELECT client_transfer_id, transfer_id as "transfer_id!" FROM okex_transfers
+               WHERE kind = 'lightning_withdraw' AND transfer_id IS NOT NULL
+               AND (status IN ('submitted', 'confirming') OR (status = 'lost' AND created_at > NOW() - interval '1 day'))"#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(res
+            .into_iter()
+            .map(|r| (ClientTransferId::from(r.client_transfer_id), r.transfer_id))
+            .collect())
+    }
+
+    pub async fn attach_withdraw_id(
+        &self,
+        id: ClientTransferId,
+        withdraw_id: String,
+    ) -> Result<(), HedgingError> {
         sqlx::query!(
-            r#"UPDATE okex_transfers SET lost = false, transfer_id = $1, state = $2 WHERE client_transfer_id = $3"#,
+            r#"UPDATE okex_transfers SET transfer_id = $1
+               WHERE client_transfer_id = $2 AND kind = 'lightning_withdraw'"#,
+            withdraw_id,
+            String::from(id),
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn update_lightning_withdrawal(
+        &self,
+        client_id: ClientTransferId,
+        withdraw_id: String,
+        details: WithdrawalStatus,
+    ) -> Result<(), HedgingError> {
+        self.observe(
+            client_id,
+            OkexTransferStatus::observed(&details.state, false),
+            withdraw_id,
+        )
+        .await
+    }
+
+    pub async fn update_withdrawal(&self, details: WithdrawalStatus) -> Result<(), HedgingError> {
+        let on_chain = !details.transaction_id.is_empty();
+        self.observe(
+            ClientTransferId::from(details.client_id),
+            OkexTransferStatus::observed(&details.state, on_chain),
             details.transaction_id,
-            details.state,
-            String::from(details.client_id),
+        )
+        .await
+    }
+
+    /// Settled and failed transfers are final, anything else follows what okex reports
+    async fn observe(
+        &self,
+        client_id: ClientTransferId,
+        status: OkexTransferStatus,
+        transfer_id: String,
+    ) -> Result<(), HedgingError> {
+        sqlx::query!(
+            r#"UPDATE okex_transfers
+               SET transfer_id = $1, status = $2, updated_at = CASE WHEN status = $2 THEN updated_at ELSE NOW() END
+               WHERE client_transfer_id = $3 AND status IN ('reserved', 'submitted', 'confirming', 'lost')"#,
+            transfer_id,
+            status as OkexTransferStatus,
+            String::from(client_id),
         )
         .execute(&self.pool)
         .await?;
         Ok(())
     }
 
-    pub async fn mark_as_lost(&self, id: ClientTransferId) -> Result<(), HedgingError> {
+    /// The submission went through even though it reported an error
+    pub async fn mark_as_submitted(&self, id: ClientTransferId) -> Result<(), HedgingError> {
         sqlx::query!(
-            r#"UPDATE okex_transfers SET lost = true WHERE client_transfer_id = $1"#,
+            r#"UPDATE okex_transfers
+               SET status = 'submitted', attempts = attempts + 1, next_attempt_at = NULL, last_error = NULL, updated_at = NOW()
+               WHERE client_transfer_id = $1 AND status = 'reserved'"#,
             String::from(id),
         )
         .execute(&self.pool)
@@ -195,12 +573,291 @@ impl OkexTransfers {
         Ok(())
     }
 
-    pub async fn sweep_lost_records(&self) -> Result<(), HedgingError> {
+    /// A settled lightning payment means okex has been paid
+    pub async fn mark_as_settled(&self, id: ClientTransferId) -> Result<(), HedgingError> {
         sqlx::query!(
-            r#"UPDATE okex_transfers SET state = 'deleted' WHERE lost = true AND state = 'pending' AND created_at < now() - interval '1 day'"#
+            r#"UPDATE okex_transfers SET status = 'settled', updated_at = NOW()
+               WHERE client_transfer_id = $1 AND status IN ('reserved', 'submitted')"#,
+            String::from(id),
         )
         .execute(&self.pool)
         .await?;
         Ok(())
     }
+
+    pub async fn mark_as_lost(&self, id: ClientTransferId) -> Result<(), HedgingError> {
+        sqlx::query!(
+            r#"UPDATE okex_transfers SET status = 'lost', updated_at = NOW()
+               WHERE client_transfer_id = $1 AND status IN ('submitted', 'confirming')"#,
+            String::from(id),
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn stuck_transfers(&self) -> Result<Vec<StuckTransfer>, HedgingError> {
+        let res = sqlx::query!(
+            r#"SELECT client_transfer_id, kind as "kind: OkexTransferKind", status as "status: OkexTransferStatus", updated_at
+               FROM okex_transfers
+               WHERE status IN ('reserved', 'submitted', 'confirming') AND shadow = false"#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let now = chrono::Utc::now();
+        Ok(res
+            .into_iter()
+            .filter(|r| {
+                (now - r.updated_at)
+                    .to_std()
+                    .is_ok_and(|age| age > stuck_after(&self.config, r.kind))
+            })
+            .map(|r| StuckTransfer {
+                client_transfer_id: ClientTransferId::from(r.client_transfer_id),
+                kind: r.kind,
+                status: r.status,
+                since: r.updated_at,
+            })
+            .collect())
+    }
+
+    pub async fn in_flight_transfers(&self) -> Result<Vec<InFlightTransfer>, HedgingError> {
+        let res = sqlx::query!(
+            r#"SELECT client_transfer_id, correlation_id, kind as "kind: OkexTransferKind", status as "status: OkexTransferStatus",
+                      amount, attempts, last_error, created_at, updated_at
+               FROM okex_transfers
+               WHERE status IN ('reserved', 'submitted', 'confirming') AND shadow = false
+               ORDER BY created_at"#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(res
+            .into_iter()
+            .map(|r| InFlightTransfer {
+                client_transfer_id: ClientTransferId::from(r.client_transfer_id),
+                correlation_id: CorrelationId::from(r.correlation_id),
+                kind: r.kind,
+                status: r.status,
+                amount: r.amount,
+                attempts: r.attempts,
+                last_error: r.last_error,
+                created_at: r.created_at,
+                updated_at: r.updated_at,
+            })
+            .collect())
+    }
+
+    /// Posts settled transfers to the ledger, and on-chain deposits as in flight once
+    /// bria has broadcast them. Returns how many transfers were posted.
+    pub async fn post_to_ledger(&self, ledger: &Ledger) -> Result<usize, HedgingError> {
+        let rows = sqlx::query!(
+            r#"SELECT client_transfer_id, kind as "kind: OkexTransferKind", status as "status: OkexTransferStatus",
+                      amount, fee, transfer_id, tx_id, ledger_pending_tx_id, updated_at
+               FROM okex_transfers
+               WHERE ledger_tx_id IS NULL AND shadow = false
+               AND (status = 'settled'
+                    OR (kind = 'deposit' AND status IN ('submitted', 'confirming') AND tx_id IS NOT NULL AND ledger_pending_tx_id IS NULL))
+               ORDER BY updated_at
+               LIMIT $1"#,
+            LEDGER_POSTING_BATCH_SIZE,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut posted = 0;
+        for row in rows {
+            let pending_tx_id =
+                (row.kind.is_deposit() && row.ledger_pending_tx_id.is_none()).then(LedgerTxId::new);
+            let ledger_tx_id = (row.status == OkexTransferStatus::Settled).then(LedgerTxId::new);
+
+            let mut tx = self.pool.begin().await?;
+            let res = sqlx::query!(
+                r#"UPDATE okex_transfers SET ledger_pending_tx_id = COALESCE(ledger_pending_tx_id, $1), ledger_tx_id = $2
+                   WHERE client_transfer_id = $3 AND ledger_tx_id IS NULL AND ledger_pending_tx_id IS NOT DISTINCT FROM $4"#,
+                pending_tx_id.map(Uuid::from),
+                ledger_tx_id.map(Uuid::from),
+                row.client_transfer_id,
+                row.ledger_pending_tx_id,
+            )
+            .execute(&mut *tx)
+            .await?;
+            if res.rows_affected() == 0 {
+                continue;
+            }
+
+            let deposit_meta = OkexDepositMeta {
+                timestamp: row.updated_at,
+                client_transfer_id: row.client_transfer_id.clone(),
+                tx_id: row.tx_id,
+            };
+            if let Some(pending_tx_id) = pending_tx_id {
+                ledger
+                    .okex_deposit_initiated(
+                        tx.begin().await?,
+                        pending_tx_id,
+                        OkexDepositInitiatedParams {
+                            btc_amount: row.amount,
+                            meta: deposit_meta.clone(),
+                        },
+                    )
+                    .await?;
+            }
+            if let Some(ledger_tx_id) = ledger_tx_id {
+                match row.kind {
+                    OkexTransferKind::Deposit | OkexTransferKind::LightningDeposit => {
+                        ledger
+                            .okex_deposit_settled(
+                                tx.begin().await?,
+                                ledger_tx_id,
+                                OkexDepositSettledParams {
+                                    btc_amount: row.amount,
+                                    meta: deposit_meta,
+                                },
+                            )
+                            .await?
+                    }
+                    OkexTransferKind::Withdraw | OkexTransferKind::LightningWithdraw => {
+                        ledger
+                            .okex_withdrawal(
+                                tx.begin().await?,
+                                ledger_tx_id,
+                                OkexWithdrawalParams {
+                                    btc_amount: row.amount,
+                                    btc_fee: row.fee,
+                                    meta: OkexWithdrawalMeta {
+                                        timestamp: row.updated_at,
+                                        client_transfer_id: row.client_transfer_id,
+                                        transfer_id: row.transfer_id,
+                                    },
+                                },
+                            )
+                            .await?
+                    }
+                    OkexTransferKind::TradingToFunding | OkexTransferKind::FundingToTrading => {
+                        ledger
+                            .okex_internal_transfer(
+                                tx.begin().await?,
+                                ledger_tx_id,
+                                OkexInternalTransferParams {
+                                    funding_btc_change: if row.kind
+                                        == OkexTransferKind::TradingToFunding
+                                    {
+                                        row.amount
+                                    } else {
+                                        -row.amount
+                                    },
+                                    meta: OkexInternalTransferMeta {
+                                        timestamp: row.updated_at,
+                                        client_transfer_id: row.client_transfer_id,
+                                        transfer_id: row.transfer_id,
+                                    },
+                                },
+                            )
+                            .await?
+                    }
+                }
+            }
+            tx.commit().await?;
+            posted += 1;
+        }
+        Ok(posted)
+    }
+
+    pub async fn healthy(&self) -> HealthCheckResponse {
+        match self.stuck_transfers().await {
+            Ok(stuck) if stuck.is_empty() => Ok(()),
+            Ok(stuck) => Err(format!(
+                "Okex transfers stuck: {}",
+                stuck
+                    .iter()
+                    .map(|t| format!(
+                        "{} {} {} since {}",
+                        String::from(t.client_transfer_id.clone()),
+                        t.kind,
+                        t.status,
+                        t.since
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+            Err(e) => Err(format!("Couldn't load okex transfers: {e}")),
+        }
+    }
+}
+
+/// None once the attempts are exhausted
+fn retry_delay(config: &OkexTransfersConfig, attempts: u32) -> Option<Duration> {
+    if attempts >= config.max_submission_attempts {
+        return None;
+    }
+    Some(
+        config
+            .retry_backoff
+            .saturating_mul(1 << attempts.saturating_sub(1).min(16)),
+    )
+}
+
+fn stuck_after(config: &OkexTransfersConfig, kind: OkexTransferKind) -> Duration {
+    if kind.is_onchain() {
+        config.onchain_stuck_after
+    } else {
+        config.internal_stuck_after
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn internal_and_onchain_transfers_do_not_conflict() {
+        assert!(!OkexTransferKind::Deposit
+            .conflicting()
+            .contains(&OkexTransferKind::FundingToTrading));
+        assert!(OkexTransferKind::TradingToFunding
+            .conflicting()
+            .contains(&OkexTransferKind::FundingToTrading));
+        assert!(OkexTransferKind::Withdraw
+            .conflicting()
+            .contains(&OkexTransferKind::Deposit));
+        assert!(OkexTransferKind::LightningDeposit
+            .conflicting()
+            .contains(&OkexTransferKind::Deposit));
+        assert!(!OkexTransferKind::LightningWithdraw
+            .conflicting()
+            .contains(&OkexTransferKind::TradingToFunding));
+    }
+
+    #[test]
+    fn observed_status() {
+        assert_eq!(
+            OkexTransferStatus::observed("pending", false),
+            OkexTransferStatus::Submitted
+        );
+        assert_eq!(
+            OkexTransferStatus::observed("pending", true),
+            OkexTransferStatus::Confirming
+        );
+        assert_eq!(
+            OkexTransferStatus::observed("success", true),
+            OkexTransferStatus::Settled
+        );
+        assert_eq!(
+            OkexTransferStatus::observed("failed", false),
+            OkexTransferStatus::Failed
+        );
+    }
+
+    #[test]
+    fn retry_delay_backs_off_until_attempts_are_exhausted() {
+        let config = OkexTransfersConfig {
+            max_submission_attempts: 4,
+            retry_backoff: Duration::from_secs(10),
+            ..Default::default()
+        };
+        assert_eq!(retry_delay(&config, 1), Some(Duration::from_secs(10)));
+        assert_eq!(retry_delay(&config, 2), Some(Duration::from_secs(20)));
+        assert_eq!(retry_delay(&config, 3), Some(Duration::from_secs(40)));
+        assert_eq!(retry_delay(&config, 4), None);
+    }
 }
@@ -0,0 +1,252 @@
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+
+use okex_client::*;
+
+use super::{contract_size_cents, InstrumentSelection};
+use crate::{error::HedgingError, venue::*};
+
+impl From<PositionSize> for VenuePosition {
+    fn from(position: PositionSize) -> Self {
+        Self {
+            instrument_id: position.instrument_id.to_string(),
+            usd_cents: position.usd_cents,
+            last_price_in_usd_cents: position.last_price_in_usd_cents,
+            unrealized_pnl_btc: position.unrealized_pnl_btc,
+        }
+    }
+}
+
+impl From<AvailableBalance> for VenueBalance {
+    fn from(balance: AvailableBalance) -> Self {
+        Self {
+            free_amt_in_btc: balance.free_amt_in_btc,
+            used_amt_in_btc: balance.used_amt_in_btc,
+            total_amt_in_btc: balance.total_amt_in_btc,
+        }
+    }
+}
+
+impl From<OrderSide> for OkexOrderSide {
+    fn from(side: OrderSide) -> Self {
+        match side {
+            OrderSide::Buy => OkexOrderSide::Buy,
+            OrderSide::Sell => OkexOrderSide::Sell,
+        }
+    }
+}
+
+impl From<&SwapContracts> for BtcUsdSwapContracts {
+    fn from(contracts: &SwapContracts) -> Self {
+        BtcUsdSwapContracts::from(u32::from(contracts))
+    }
+}
+
+/// Hedges on whichever swap the instrument selection currently points at.
+/// Account level calls (balances, transfers, withdrawals) are instrument agnostic.
+#[derive(Clone)]
+pub struct OkexVenue {
+    client: OkexClient,
+    selection: InstrumentSelection,
+}
+
+impl OkexVenue {
+    pub fn new(client: OkexClient, selection: InstrumentSelection) -> Self {
+        Self { client, selection }
+    }
+
+    fn active(&self) -> OkexClient {
+        self.client.for_instrument(self.selection.active())
+    }
+
+    fn on(&self, instrument_id: &str) -> Result<OkexClient, HedgingError> {
+        Ok(self.client.for_instrument(instrument_id.parse()?))
+    }
+}
+
+#[async_trait]
+impl HedgingVenue for OkexVenue {
+    fn name(&self) -> &'static str {
+        "okex"
+    }
+
+    fn is_simulated(&self) -> bool {
+        self.client.is_simulated()
+    }
+
+    async fn position(&self) -> Result<VenuePosition, HedgingError> {
+        Ok(self
+            .active()
+            .get_position_in_signed_usd_cents()
+            .await?
+            .into())
+    }
+
+    fn inactive_instruments(&self) -> Vec<String> {
+        self.selection
+            .inactive()
+            .into_iter()
+            .map(|instrument_id| instrument_id.to_string())
+            .collect()
+    }
+
+    fn margin_currency(&self, instrument_id: &str) -> Result<TradeCurrency, HedgingError> {
+        Ok(instrument_id.parse::<OkexInstrumentId>()?.margin_currency())
+    }
+
+    async fn position_on(&self, instrument_id: &str) -> Result<VenuePosition, HedgingError> {
+        Ok(self
+            .on(instrument_id)?
+            .get_position_in_signed_usd_cents()
+            .await?
+            .into())
+    }
+
+    async fn contract_size_cents_on(
+        &self,
+        instrument_id: &str,
+        last_price_in_usd_cents: Decimal,
+    ) -> Result<Decimal, HedgingError> {
+        Ok(contract_size_cents(
+            instrument_id.parse()?,
+            last_price_in_usd_cents,
+        ))
+    }
+
+    async fn place_order_on(
+        &self,
+        instrument_id: &str,
+        id: ClientOrderId,
+        side: OrderSide,
+        contracts: &SwapContracts,
+    ) -> Result<(), HedgingError> {
+        self.on(instrument_id)?
+            .place_order(id, side.into(), &contracts.into())
+            .await?;
+        Ok(())
+    }
+
+    async fn close_position_on(
+        &self,
+        instrument_id: &str,
+        id: ClientOrderId,
+    ) -> Result<(), HedgingError> {
+        self.on(instrument_id)?.close_positions(id).await?;
+        Ok(())
+    }
+
+    async fn last_price_in_usd_cents(&self) -> Result<Decimal, HedgingError> {
+        Ok(self.active().get_last_price_in_usd_cents().await?.usd_cents)
+    }
+
+    async fn contract_size_cents(&self) -> Result<Decimal, HedgingError> {
+        let instrument_id = self.selection.active();
+        let last_price_in_usd_cents = if instrument_id.is_inverse() {
+            Decimal::ZERO
+        } else {
+            self.last_price_in_usd_cents().await?
+        };
+        Ok(contract_size_cents(instrument_id, last_price_in_usd_cents))
+    }
+
+    async fn place_market_order(
+        &self,
+        id: ClientOrderId,
+        side: OrderSide,
+        contracts: &SwapContracts,
+    ) -> Result<(), HedgingError> {
+        self.active()
+            .place_order(id, side.into(), &contracts.into())
+            .await?;
+        Ok(())
+    }
+
+    async fn place_post_only_order(
+        &self,
+        id: ClientOrderId,
+        side: OrderSide,
+        contracts: &SwapContracts,
+        price: Decimal,
+    ) -> Result<(), HedgingError> {
+        self.active()
+            .place_limit_order(
+                id,
+                side.into(),
+                &contracts.into(),
+                OkexOrderType::PostOnly,
+                price,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn close_position(&self, id: ClientOrderId) -> Result<(), HedgingError> {
+        self.active().close_positions(id).await?;
+        Ok(())
+    }
+
+    async fn trading_balance(&self) -> Result<VenueBalance, HedgingError> {
+        Ok(self.client.trading_account_balance().await?.into())
+    }
+
+    async fn funding_balance(&self) -> Result<VenueBalance, HedgingError> {
+        Ok(self.client.funding_account_balance().await?.into())
+    }
+
+    async fn transfer_funding_to_trading(
+        &self,
+        id: ClientTransferId,
+        amount: Decimal,
+    ) -> Result<(), HedgingError> {
+        self.client.transfer_funding_to_trading(id, amount).await?;
+        Ok(())
+    }
+
+    async fn transfer_trading_to_funding(
+        &self,
+        id: ClientTransferId,
+        amount: Decimal,
+    ) -> Result<(), HedgingError> {
+        self.client.transfer_trading_to_funding(id, amount).await?;
+        Ok(())
+    }
+
+    async fn deposit_address(&self) -> Result<String, HedgingError> {
+        Ok(self.client.get_funding_deposit_address().await?.value)
+    }
+
+    async fn withdrawal_fee(&self) -> Result<Decimal, HedgingError> {
+        Ok(self.client.get_onchain_fees().await?.min_fee)
+    }
+
+    async fn withdraw_btc_onchain(
+        &self,
+        id: ClientTransferId,
+        amount: Decimal,
+        fee: Decimal,
+        address: String,
+    ) -> Result<(), HedgingError> {
+        self.client
+            .withdraw_btc_onchain(id, amount, fee, address)
+            .await?;
+        Ok(())
+    }
+
+    async fn lightning_deposit_invoice(&self, amount: Decimal) -> Result<String, HedgingError> {
+        Ok(self.client.lightning_deposit_invoice(amount).await?.value)
+    }
+
+    /// The amount is encoded in the invoice, the client id goes along as memo
+    async fn withdraw_lightning(
+        &self,
+        id: ClientTransferId,
+        _amount: Decimal,
+        invoice: String,
+    ) -> Result<String, HedgingError> {
+        Ok(self
+            .client
+            .withdraw_lightning(invoice, String::from(id))
+            .await?
+            .value)
+    }
+}
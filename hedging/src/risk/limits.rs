@@ -0,0 +1,152 @@
+use rust_decimal::Decimal;
+
+use crate::config::RiskConfig;
+
+/// An order about to be sent to an exchange, in signed usd cents
+/// where a short position is negative.
+#[derive(Debug, Clone, Copy)]
+pub struct ProposedOrder {
+    pub notional_cents: Decimal,
+    pub position_before_cents: Decimal,
+    pub position_after_cents: Decimal,
+}
+
+impl ProposedOrder {
+    pub fn sell(notional_cents: Decimal, position_before_cents: Decimal) -> Self {
+        Self {
+            notional_cents,
+            position_before_cents,
+            position_after_cents: position_before_cents - notional_cents,
+        }
+    }
+
+    pub fn buy(notional_cents: Decimal, position_before_cents: Decimal) -> Self {
+        Self {
+            notional_cents,
+            position_before_cents,
+            position_after_cents: position_before_cents + notional_cents,
+        }
+    }
+
+    pub fn close(position_before_cents: Decimal) -> Self {
+        Self {
+            notional_cents: position_before_cents.abs(),
+            position_before_cents,
+            position_after_cents: Decimal::ZERO,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RiskLimits {
+    config: RiskConfig,
+}
+
+impl RiskLimits {
+    pub fn new(config: RiskConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn liability_delta_window(&self) -> chrono::Duration {
+        self.config.liability_delta_window
+    }
+
+    pub fn liability_violation(&self, reference: Decimal, current: Decimal) -> Option<String> {
+        let delta = (current - reference).abs();
+        if delta > self.config.max_liability_delta_cents {
+            return Some(format!(
+                "liability moved {delta} cents within {}s (max {})",
+                self.config.liability_delta_window.num_seconds(),
+                self.config.max_liability_delta_cents
+            ));
+        }
+        None
+    }
+
+    pub fn order_violation(
+        &self,
+        order: &ProposedOrder,
+        traded_last_day_cents: Decimal,
+    ) -> Option<String> {
+        if order.notional_cents > self.config.max_order_notional_cents {
+            return Some(format!(
+                "order notional {} cents exceeds max {}",
+                order.notional_cents, self.config.max_order_notional_cents
+            ));
+        }
+        let daily_volume = traded_last_day_cents + order.notional_cents;
+        if daily_volume > self.config.max_daily_traded_volume_cents {
+            return Some(format!(
+                "daily traded volume {daily_volume} cents exceeds max {}",
+                self.config.max_daily_traded_volume_cents
+            ));
+        }
+        let increases_position =
+            order.position_after_cents.abs() > order.position_before_cents.abs();
+        if increases_position && order.position_after_cents.abs() > self.config.max_position_cents {
+            return Some(format!(
+                "position {} cents exceeds max {}",
+                order.position_after_cents, self.config.max_position_cents
+            ));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn limits() -> RiskLimits {
+        RiskLimits::new(RiskConfig {
+            max_liability_delta_cents: dec!(1_000),
+            max_order_notional_cents: dec!(500),
+            max_daily_traded_volume_cents: dec!(2_000),
+            max_position_cents: dec!(10_000),
+            ..Default::default()
+        })
+    }
+
+    fn order(notional: Decimal, before: Decimal, after: Decimal) -> ProposedOrder {
+        ProposedOrder {
+            notional_cents: notional,
+            position_before_cents: before,
+            position_after_cents: after,
+        }
+    }
+
+    #[test]
+    fn liability_delta_in_either_direction() {
+        let limits = limits();
+        assert!(limits
+            .liability_violation(dec!(-5_000), dec!(-5_900))
+            .is_none());
+        assert!(limits
+            .liability_violation(dec!(-5_000), dec!(-6_001))
+            .is_some());
+        assert!(limits
+            .liability_violation(dec!(-5_000), dec!(-3_999))
+            .is_some());
+    }
+
+    #[test]
+    fn order_notional_and_daily_volume() {
+        let limits = limits();
+        let sell = order(dec!(500), dec!(-1_000), dec!(-1_500));
+        assert!(limits.order_violation(&sell, dec!(0)).is_none());
+        assert!(limits.order_violation(&sell, dec!(1_600)).is_some());
+        let large = order(dec!(501), dec!(-1_000), dec!(-1_501));
+        assert!(limits.order_violation(&large, dec!(0)).is_some());
+    }
+
+    #[test]
+    fn max_position_only_blocks_increasing_exposure() {
+        let limits = limits();
+        let increase = order(dec!(500), dec!(-9_800), dec!(-10_300));
+        assert!(limits.order_violation(&increase, dec!(0)).is_some());
+        let reduce = order(dec!(500), dec!(-12_000), dec!(-11_500));
+        assert!(limits.order_violation(&reduce, dec!(0)).is_none());
+    }
+}
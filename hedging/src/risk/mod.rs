@@ -0,0 +1,165 @@
+mod limits;
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use tracing::instrument;
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, RwLock},
+};
+
+use crate::{config::RiskConfig, error::*};
+
+pub use limits::*;
+
+type LiabilitySamples = Arc<RwLock<VecDeque<(DateTime<Utc>, Decimal)>>>;
+
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerTrip {
+    pub reason: String,
+    pub tripped_at: DateTime<Utc>,
+}
+
+/// Halts hedging when liability or trading activity looks anomalous.
+/// The halted state is persisted and only cleared by an operator reset.
+#[derive(Clone)]
+pub struct RiskGuard {
+    pool: sqlx::PgPool,
+    limits: RiskLimits,
+    liability_samples: LiabilitySamples,
+}
+
+impl RiskGuard {
+    pub fn new(pool: sqlx::PgPool, config: RiskConfig) -> Self {
+        Self {
+            pool,
+            limits: RiskLimits::new(config),
+            liability_samples: Arc::new(RwLock::new(VecDeque::new())),
+        }
+    }
+
+    pub async fn halted(&self) -> Result<Option<CircuitBreakerTrip>, HedgingError> {
+        current_trip(&self.pool).await
+    }
+
+    #[instrument(name = "hedging.risk.trip", skip(self), err)]
+    pub async fn trip(&self, reason: &str) -> Result<(), HedgingError> {
+        sqlx::query!(
+            r#"INSERT INTO hedging_circuit_breaker_events (event_type, reason)
+               SELECT 'tripped', $1
+               WHERE NOT EXISTS (
+                 SELECT 1 FROM (
+                   SELECT event_type FROM hedging_circuit_breaker_events ORDER BY id DESC LIMIT 1
+                 ) latest WHERE latest.event_type = 'tripped'
+               )"#,
+            reason
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Records the total liability and trips if it moved more than allowed
+    /// within the configured window. Returns false when tripped.
+    #[instrument(
+        name = "hedging.risk.record_liability",
+        skip(self),
+        fields(violation),
+        err
+    )]
+    pub async fn record_liability(&self, total_liability: Decimal) -> Result<bool, HedgingError> {
+        let now = Utc::now();
+        let violation = {
+            let mut samples = self
+                .liability_samples
+                .write()
+                .expect("liability samples lock poisoned");
+            let window_start = now - self.limits.liability_delta_window();
+            while samples.front().map(|(t, _)| *t < window_start) == Some(true) {
+                samples.pop_front();
+            }
+            let violation = samples
+                .iter()
+                .find_map(|(_, sample)| self.limits.liability_violation(*sample, total_liability));
+            samples.push_back((now, total_liability));
+            violation
+        };
+        if let Some(reason) = violation {
+            tracing::Span::current().record("violation", tracing::field::display(&reason));
+            self.trip(&reason).await?;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Checks an order against the notional, daily volume and position limits
+    /// and trips on violation. Returns false when the order must not be placed.
+    #[instrument(
+        name = "hedging.risk.check_order",
+        skip(self),
+        fields(traded_last_day_cents, violation),
+        err
+    )]
+    pub async fn check_order(&self, order: ProposedOrder) -> Result<bool, HedgingError> {
+        let traded_last_day_cents = self.traded_last_day_cents().await?;
+        tracing::Span::current().record(
+            "traded_last_day_cents",
+            tracing::field::display(traded_last_day_cents),
+        );
+        if let Some(reason) = self.limits.order_violation(&order, traded_last_day_cents) {
+            tracing::Span::current().record("violation", tracing::field::display(&reason));
+            self.trip(&reason).await?;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    async fn traded_last_day_cents(&self) -> Result<Decimal, HedgingError> {
+        let res = sqlx::query!(
+            r#"SELECT COALESCE(SUM(ABS(size_usd_value)), 0) * 100 AS "volume!" FROM (
+                 SELECT size_usd_value FROM okex_orders
+                 WHERE shadow = false AND is_parent = false AND lost = false
+                   AND created_at > NOW() - INTERVAL '1 day'
+                 UNION ALL
+                 SELECT size_usd_value FROM bitfinex_orders
+                 WHERE lost = false AND created_at > NOW() - INTERVAL '1 day'
+               ) orders"#
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(res.volume)
+    }
+}
+
+pub async fn current_trip(pool: &sqlx::PgPool) -> Result<Option<CircuitBreakerTrip>, HedgingError> {
+    let res = sqlx::query!(
+        r#"SELECT event_type, reason, created_at FROM hedging_circuit_breaker_events
+           ORDER BY id DESC LIMIT 1"#
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(res.and_then(|row| {
+        (row.event_type == "tripped").then_some(CircuitBreakerTrip {
+            reason: row.reason,
+            tripped_at: row.created_at,
+        })
+    }))
+}
+
+/// Clears a tripped circuit breaker. Returns the trip that was cleared, if any.
+pub async fn reset_circuit_breaker(
+    pool: &sqlx::PgPool,
+    reason: String,
+) -> Result<Option<CircuitBreakerTrip>, HedgingError> {
+    let trip = current_trip(pool).await?;
+    if trip.is_some() {
+        sqlx::query!(
+            r#"INSERT INTO hedging_circuit_breaker_events (event_type, reason) VALUES ('reset', $1)"#,
+            reason
+        )
+        .execute(pool)
+        .await?;
+    }
+    Ok(trip)
+}
@@ -0,0 +1,245 @@
+mod paper;
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+
+use std::{fmt::Display, sync::Arc};
+
+use okex_client::{ClientOrderId, ClientTransferId, TradeCurrency};
+
+use crate::{error::HedgingError, okex::INVERSE_CONTRACT_SIZE_CENTS};
+
+pub use paper::*;
+
+pub type SharedVenue = Arc<dyn HedgingVenue>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderSide {
+    Buy,
+    Sell,
+}
+
+impl Display for OrderSide {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            OrderSide::Buy => write!(f, "buy"),
+            OrderSide::Sell => write!(f, "sell"),
+        }
+    }
+}
+
+/// A number of swap contracts together with the notional each one carries,
+/// which depends on the instrument being traded.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SwapContracts {
+    count: u32,
+    contract_size_cents: Decimal,
+}
+impl SwapContracts {
+    pub fn new(count: u32, contract_size_cents: Decimal) -> Self {
+        Self {
+            count,
+            contract_size_cents,
+        }
+    }
+
+    pub fn contract_size_cents(&self) -> Decimal {
+        self.contract_size_cents
+    }
+
+    pub fn notional_cents(&self) -> Decimal {
+        Decimal::from(self.count) * self.contract_size_cents
+    }
+}
+/// Inverse BTC-USD contracts
+impl From<u32> for SwapContracts {
+    fn from(count: u32) -> Self {
+        Self::new(count, INVERSE_CONTRACT_SIZE_CENTS)
+    }
+}
+impl From<&SwapContracts> for u32 {
+    fn from(contracts: &SwapContracts) -> Self {
+        contracts.count
+    }
+}
+impl Display for SwapContracts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.count)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VenuePosition {
+    pub instrument_id: String,
+    pub usd_cents: Decimal,
+    pub last_price_in_usd_cents: Decimal,
+    pub unrealized_pnl_btc: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VenueBalance {
+    pub free_amt_in_btc: Decimal,
+    pub used_amt_in_btc: Decimal,
+    pub total_amt_in_btc: Decimal,
+}
+
+impl Display for VenueBalance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "free_amt_in_btc={}, used_amt_in_btc={}, total_amt_in_btc={},",
+            self.free_amt_in_btc, self.used_amt_in_btc, self.total_amt_in_btc
+        )
+    }
+}
+
+/// What is left of the hedge on an instrument the venue has moved away from
+#[derive(Debug, Clone)]
+pub struct ResidualPosition {
+    pub instrument_id: String,
+    pub usd_cents: Decimal,
+    pub contract_size_cents: Decimal,
+}
+
+/// What the hedging and funding jobs need from an exchange: a signed USD swap
+/// position, a trading and a funding account, and an onchain and a lightning
+/// route in and out.
+#[async_trait]
+pub trait HedgingVenue: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Simulated venues never move real funds onchain
+    fn is_simulated(&self) -> bool;
+
+    async fn position(&self) -> Result<VenuePosition, HedgingError>;
+
+    /// Instruments the venue can hedge on other than the active one
+    fn inactive_instruments(&self) -> Vec<String>;
+
+    async fn position_on(&self, instrument_id: &str) -> Result<VenuePosition, HedgingError>;
+
+    /// Notional of one contract of `instrument_id` at its last price
+    async fn contract_size_cents_on(
+        &self,
+        instrument_id: &str,
+        last_price_in_usd_cents: Decimal,
+    ) -> Result<Decimal, HedgingError>;
+
+    /// Market order on `instrument_id`, whichever instrument is active
+    async fn place_order_on(
+        &self,
+        instrument_id: &str,
+        id: ClientOrderId,
+        side: OrderSide,
+        contracts: &SwapContracts,
+    ) -> Result<(), HedgingError>;
+
+    async fn close_position_on(
+        &self,
+        instrument_id: &str,
+        id: ClientOrderId,
+    ) -> Result<(), HedgingError>;
+
+    /// Currency positions on `instrument_id` are margined in
+    fn margin_currency(&self, instrument_id: &str) -> Result<TradeCurrency, HedgingError>;
+
+    /// The part of the hedge backed by BTC collateral: `position` when the
+    /// active instrument is margined in BTC, otherwise what a switch has left
+    /// on a BTC margined instrument
+    async fn btc_margined_exposure(
+        &self,
+        position: &VenuePosition,
+    ) -> Result<Decimal, HedgingError> {
+        if self.margin_currency(&position.instrument_id)? == TradeCurrency::BTC {
+            return Ok(position.usd_cents);
+        }
+        match self.residual_position().await? {
+            Some(residual)
+                if self.margin_currency(&residual.instrument_id)? == TradeCurrency::BTC =>
+            {
+                Ok(residual.usd_cents)
+            }
+            _ => Ok(Decimal::ZERO),
+        }
+    }
+
+    /// Open position on an inactive instrument, unwound by adjust_hedge
+    /// while it rebuilds the hedge on the active one
+    async fn residual_position(&self) -> Result<Option<ResidualPosition>, HedgingError> {
+        for instrument_id in self.inactive_instruments() {
+            let position = self.position_on(&instrument_id).await?;
+            if !position.usd_cents.is_zero() {
+                let contract_size_cents = self
+                    .contract_size_cents_on(&instrument_id, position.last_price_in_usd_cents)
+                    .await?;
+                return Ok(Some(ResidualPosition {
+                    instrument_id,
+                    usd_cents: position.usd_cents,
+                    contract_size_cents,
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn last_price_in_usd_cents(&self) -> Result<Decimal, HedgingError>;
+
+    /// Notional of one contract of the instrument currently used for hedging
+    async fn contract_size_cents(&self) -> Result<Decimal, HedgingError>;
+
+    async fn place_market_order(
+        &self,
+        id: ClientOrderId,
+        side: OrderSide,
+        contracts: &SwapContracts,
+    ) -> Result<(), HedgingError>;
+
+    async fn place_post_only_order(
+        &self,
+        id: ClientOrderId,
+        side: OrderSide,
+        contracts: &SwapContracts,
+        price: Decimal,
+    ) -> Result<(), HedgingError>;
+
+    async fn close_position(&self, id: ClientOrderId) -> Result<(), HedgingError>;
+
+    async fn trading_balance(&self) -> Result<VenueBalance, HedgingError>;
+
+    async fn funding_balance(&self) -> Result<VenueBalance, HedgingError>;
+
+    async fn transfer_funding_to_trading(
+        &self,
+        id: ClientTransferId,
+        amount: Decimal,
+    ) -> Result<(), HedgingError>;
+
+    async fn transfer_trading_to_funding(
+        &self,
+        id: ClientTransferId,
+        amount: Decimal,
+    ) -> Result<(), HedgingError>;
+
+    async fn deposit_address(&self) -> Result<String, HedgingError>;
+
+    async fn withdrawal_fee(&self) -> Result<Decimal, HedgingError>;
+
+    async fn withdraw_btc_onchain(
+        &self,
+        id: ClientTransferId,
+        amount: Decimal,
+        fee: Decimal,
+        address: String,
+    ) -> Result<(), HedgingError>;
+
+    /// Invoice that credits the funding account once paid
+    async fn lightning_deposit_invoice(&self, amount: Decimal) -> Result<String, HedgingError>;
+
+    /// Pays `invoice` out of the funding account, returns the venue's withdrawal id
+    async fn withdraw_lightning(
+        &self,
+        id: ClientTransferId,
+        amount: Decimal,
+        invoice: String,
+    ) -> Result<String, HedgingError>;
+}
@@ -0,0 +1,598 @@
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use okex_client::{ClientOrderId, ClientTransferId, TradeCurrency};
+
+use super::*;
+use crate::{error::HedgingError, okex::INVERSE_CONTRACT_SIZE_CENTS};
+
+const PAPER_INSTRUMENT_ID: &str = "PAPER-BTC-USD-SWAP";
+const PAPER_DEPOSIT_ADDRESS: &str = "paper-deposit-address";
+const PAPER_WITHDRAWAL_FEE: Decimal = dec!(0.0001);
+const TAKER_FEE_RATE: Decimal = dec!(0.0005);
+const MAKER_FEE_RATE: Decimal = dec!(0.0002);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaperWithdrawal {
+    pub address: String,
+    pub amount: Decimal,
+    pub fee: Decimal,
+}
+
+#[derive(Debug)]
+struct PaperAccount {
+    leverage: Decimal,
+    last_price_in_usd_cents: Decimal,
+    position_usd_cents: Decimal,
+    entry_value_btc: Decimal,
+    trading_btc: Decimal,
+    funding_btc: Decimal,
+    fees_paid_btc: Decimal,
+    seen_ids: HashSet<String>,
+    withdrawals: Vec<PaperWithdrawal>,
+    lightning_invoices: HashMap<String, Decimal>,
+}
+
+impl PaperAccount {
+    fn unrealized_pnl_btc(&self) -> Decimal {
+        if self.position_usd_cents.is_zero() {
+            return Decimal::ZERO;
+        }
+        self.entry_value_btc - self.position_usd_cents / self.last_price_in_usd_cents
+    }
+
+    fn margin_btc(&self, position_usd_cents: Decimal) -> Decimal {
+        position_usd_cents.abs() / self.last_price_in_usd_cents / self.leverage
+    }
+
+    fn trading_balance(&self) -> VenueBalance {
+        let total = self.trading_btc + self.unrealized_pnl_btc();
+        let used = self.margin_btc(self.position_usd_cents);
+        VenueBalance {
+            free_amt_in_btc: total - used,
+            used_amt_in_btc: used,
+            total_amt_in_btc: total,
+        }
+    }
+
+    /// Returns false when the id has been submitted before
+    fn first_submission(&mut self, id: String) -> bool {
+        self.seen_ids.insert(id)
+    }
+
+    fn fill(
+        &mut self,
+        side: OrderSide,
+        usd_cents: Decimal,
+        price_in_usd_cents: Decimal,
+        fee_rate: Decimal,
+    ) -> Result<(), HedgingError> {
+        let delta = match side {
+            OrderSide::Buy => usd_cents,
+            OrderSide::Sell => -usd_cents,
+        };
+        let position_after = self.position_usd_cents + delta;
+        if position_after.abs() > self.position_usd_cents.abs()
+            && self.margin_btc(position_after) > self.trading_balance().total_amt_in_btc
+        {
+            return Err(HedgingError::VenueRejected(
+                "insufficient margin".to_string(),
+            ));
+        }
+
+        let reduces = !self.position_usd_cents.is_zero()
+            && self.position_usd_cents.is_sign_negative() != delta.is_sign_negative();
+        if reduces {
+            let closed = delta.abs().min(self.position_usd_cents.abs());
+            let fraction = closed / self.position_usd_cents.abs();
+            let closed_entry_value = self.entry_value_btc * fraction;
+            let closed_position = self.position_usd_cents * fraction;
+            self.trading_btc += closed_entry_value - closed_position / price_in_usd_cents;
+            self.entry_value_btc -= closed_entry_value;
+            if delta.abs() > closed {
+                // the order flipped the position, what remains was opened at this price
+                self.entry_value_btc = position_after / price_in_usd_cents;
+            }
+        } else {
+            self.entry_value_btc += delta / price_in_usd_cents;
+        }
+        let fee = usd_cents / price_in_usd_cents * fee_rate;
+        self.trading_btc -= fee;
+        self.fees_paid_btc += fee;
+        self.position_usd_cents = position_after;
+        Ok(())
+    }
+}
+
+/// An in memory venue that fills every order immediately and settles transfers
+/// on the spot. Market orders fill at the last price, post only orders at their limit.
+#[derive(Debug, Clone)]
+pub struct PaperVenue {
+    account: Arc<Mutex<PaperAccount>>,
+}
+
+impl PaperVenue {
+    pub fn new(last_price_in_usd_cents: Decimal, leverage: Decimal) -> Self {
+        Self {
+            account: Arc::new(Mutex::new(PaperAccount {
+                leverage,
+                last_price_in_usd_cents,
+                position_usd_cents: Decimal::ZERO,
+                entry_value_btc: Decimal::ZERO,
+                trading_btc: Decimal::ZERO,
+                funding_btc: Decimal::ZERO,
+                fees_paid_btc: Decimal::ZERO,
+                seen_ids: HashSet::new(),
+                withdrawals: Vec::new(),
+                lightning_invoices: HashMap::new(),
+            })),
+        }
+    }
+
+    pub fn set_last_price(&self, last_price_in_usd_cents: Decimal) {
+        self.account().last_price_in_usd_cents = last_price_in_usd_cents;
+    }
+
+    /// Settles a swap funding payment against the trading account at the last price.
+    /// Longs pay shorts when the rate is positive, returns what this account paid.
+    pub fn apply_funding_rate(&self, rate: Decimal) -> Decimal {
+        let mut account = self.account();
+        let payment = account.position_usd_cents / account.last_price_in_usd_cents * rate;
+        account.trading_btc -= payment;
+        payment
+    }
+
+    /// Trading fees paid on every fill so far
+    pub fn trading_fees_paid(&self) -> Decimal {
+        self.account().fees_paid_btc
+    }
+
+    /// Credits an onchain deposit to the funding account
+    pub fn credit_deposit(&self, amount: Decimal) {
+        self.account().funding_btc += amount;
+    }
+
+    /// Credits the funding account with an invoice issued by `lightning_deposit_invoice`,
+    /// false when the invoice is unknown or already paid
+    pub fn pay_lightning_invoice(&self, invoice: &str) -> bool {
+        let mut account = self.account();
+        match account.lightning_invoices.remove(invoice) {
+            Some(amount) => {
+                account.funding_btc += amount;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn withdrawals(&self) -> Vec<PaperWithdrawal> {
+        self.account().withdrawals.clone()
+    }
+
+    fn account(&self) -> MutexGuard<'_, PaperAccount> {
+        self.account.lock().expect("paper account lock poisoned")
+    }
+
+    fn check_instrument(instrument_id: &str) -> Result<(), HedgingError> {
+        if instrument_id != PAPER_INSTRUMENT_ID {
+            return Err(HedgingError::VenueRejected(format!(
+                "unknown instrument {instrument_id}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl HedgingVenue for PaperVenue {
+    fn name(&self) -> &'static str {
+        "paper"
+    }
+
+    fn is_simulated(&self) -> bool {
+        true
+    }
+
+    async fn position(&self) -> Result<VenuePosition, HedgingError> {
+        let account = self.account();
+        Ok(VenuePosition {
+            instrument_id: PAPER_INSTRUMENT_ID.to_string(),
+            usd_cents: account.position_usd_cents,
+            last_price_in_usd_cents: account.last_price_in_usd_cents,
+            unrealized_pnl_btc: account.unrealized_pnl_btc(),
+        })
+    }
+
+    /// Paper trading only ever hedges on one instrument
+    fn inactive_instruments(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn margin_currency(&self, instrument_id: &str) -> Result<TradeCurrency, HedgingError> {
+        Self::check_instrument(instrument_id)?;
+        Ok(TradeCurrency::BTC)
+    }
+
+    async fn position_on(&self, instrument_id: &str) -> Result<VenuePosition, HedgingError> {
+        Self::check_instrument(instrument_id)?;
+        self.position().await
+    }
+
+    async fn contract_size_cents_on(
+        &self,
+        instrument_id: &str,
+        _last_price_in_usd_cents: Decimal,
+    ) -> Result<Decimal, HedgingError> {
+        Self::check_instrument(instrument_id)?;
+        Ok(INVERSE_CONTRACT_SIZE_CENTS)
+    }
+
+    async fn place_order_on(
+        &self,
+        instrument_id: &str,
+        id: ClientOrderId,
+        side: OrderSide,
+        contracts: &SwapContracts,
+    ) -> Result<(), HedgingError> {
+        Self::check_instrument(instrument_id)?;
+        self.place_market_order(id, side, contracts).await
+    }
+
+    async fn close_position_on(
+        &self,
+        instrument_id: &str,
+        id: ClientOrderId,
+    ) -> Result<(), HedgingError> {
+        Self::check_instrument(instrument_id)?;
+        self.close_position(id).await
+    }
+
+    async fn last_price_in_usd_cents(&self) -> Result<Decimal, HedgingError> {
+        Ok(self.account().last_price_in_usd_cents)
+    }
+
+    async fn contract_size_cents(&self) -> Result<Decimal, HedgingError> {
+        Ok(INVERSE_CONTRACT_SIZE_CENTS)
+    }
+
+    async fn place_market_order(
+        &self,
+        id: ClientOrderId,
+        side: OrderSide,
+        contracts: &SwapContracts,
+    ) -> Result<(), HedgingError> {
+        let mut account = self.account();
+        if !account.first_submission(String::from(id)) {
+            return Ok(());
+        }
+        let price = account.last_price_in_usd_cents;
+        let usd_cents = contracts.notional_cents();
+        account.fill(side, usd_cents, price, TAKER_FEE_RATE)
+    }
+
+    async fn place_post_only_order(
+        &self,
+        id: ClientOrderId,
+        side: OrderSide,
+        contracts: &SwapContracts,
+        price: Decimal,
+    ) -> Result<(), HedgingError> {
+        let mut account = self.account();
+        if !account.first_submission(String::from(id)) {
+            return Ok(());
+        }
+        let usd_cents = contracts.notional_cents();
+        account.fill(
+            side,
+            usd_cents,
+            price * Decimal::ONE_HUNDRED,
+            MAKER_FEE_RATE,
+        )
+    }
+
+    async fn close_position(&self, id: ClientOrderId) -> Result<(), HedgingError> {
+        let mut account = self.account();
+        if !account.first_submission(String::from(id)) || account.position_usd_cents.is_zero() {
+            return Ok(());
+        }
+        let side = if account.position_usd_cents.is_sign_negative() {
+            OrderSide::Buy
+        } else {
+            OrderSide::Sell
+        };
+        let usd_cents = account.position_usd_cents.abs();
+        let price = account.last_price_in_usd_cents;
+        account.fill(side, usd_cents, price, TAKER_FEE_RATE)
+    }
+
+    async fn trading_balance(&self) -> Result<VenueBalance, HedgingError> {
+        Ok(self.account().trading_balance())
+    }
+
+    async fn funding_balance(&self) -> Result<VenueBalance, HedgingError> {
+        let funding_btc = self.account().funding_btc;
+        Ok(VenueBalance {
+            free_amt_in_btc: funding_btc,
+            used_amt_in_btc: Decimal::ZERO,
+            total_amt_in_btc: funding_btc,
+        })
+    }
+
+    async fn transfer_funding_to_trading(
+        &self,
+        id: ClientTransferId,
+        amount: Decimal,
+    ) -> Result<(), HedgingError> {
+        let mut account = self.account();
+        if !account.first_submission(String::from(id)) {
+            return Ok(());
+        }
+        if amount > account.funding_btc {
+            return Err(HedgingError::VenueRejected(
+                "insufficient funding balance".to_string(),
+            ));
+        }
+        account.funding_btc -= amount;
+        account.trading_btc += amount;
+        Ok(())
+    }
+
+    async fn transfer_trading_to_funding(
+        &self,
+        id: ClientTransferId,
+        amount: Decimal,
+    ) -> Result<(), HedgingError> {
+        let mut account = self.account();
+        if !account.first_submission(String::from(id)) {
+            return Ok(());
+        }
+        if amount > account.trading_balance().free_amt_in_btc {
+            return Err(HedgingError::VenueRejected(
+                "insufficient free trading balance".to_string(),
+            ));
+        }
+        account.trading_btc -= amount;
+        account.funding_btc += amount;
+        Ok(())
+    }
+
+    async fn deposit_address(&self) -> Result<String, HedgingError> {
+        Ok(PAPER_DEPOSIT_ADDRESS.to_string())
+    }
+
+    async fn withdrawal_fee(&self) -> Result<Decimal, HedgingError> {
+        Ok(PAPER_WITHDRAWAL_FEE)
+    }
+
+    async fn withdraw_btc_onchain(
+        &self,
+        id: ClientTransferId,
+        amount: Decimal,
+        fee: Decimal,
+        address: String,
+    ) -> Result<(), HedgingError> {
+        let mut account = self.account();
+        if !account.first_submission(String::from(id)) {
+            return Ok(());
+        }
+        if amount + fee > account.funding_btc {
+            return Err(HedgingError::VenueRejected(
+                "insufficient funding balance".to_string(),
+            ));
+        }
+        account.funding_btc -= amount + fee;
+        account.withdrawals.push(PaperWithdrawal {
+            address,
+            amount,
+            fee,
+        });
+        Ok(())
+    }
+
+    async fn lightning_deposit_invoice(&self, amount: Decimal) -> Result<String, HedgingError> {
+        let mut account = self.account();
+        let invoice = format!("lnpaper:{amount}:{}", account.lightning_invoices.len());
+        account.lightning_invoices.insert(invoice.clone(), amount);
+        Ok(invoice)
+    }
+
+    async fn withdraw_lightning(
+        &self,
+        id: ClientTransferId,
+        amount: Decimal,
+        invoice: String,
+    ) -> Result<String, HedgingError> {
+        let withdraw_id = String::from(id);
+        let mut account = self.account();
+        if !account.first_submission(withdraw_id.clone()) {
+            return Ok(withdraw_id);
+        }
+        if amount > account.funding_btc {
+            return Err(HedgingError::VenueRejected(
+                "insufficient funding balance".to_string(),
+            ));
+        }
+        account.funding_btc -= amount;
+        account.withdrawals.push(PaperWithdrawal {
+            address: invoice,
+            amount,
+            fee: Decimal::ZERO,
+        });
+        Ok(withdraw_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::payload::{SyntheticCentExposure, SyntheticCentLiability};
+
+    use super::*;
+    use crate::{
+        lightning::*,
+        okex::{HedgingAdjustment, OkexHedgeAdjustment, OkexHedgingConfig},
+    };
+
+    async fn funded_venue() -> PaperVenue {
+        let venue = PaperVenue::new(dec!(4_000_000), dec!(3));
+        venue.credit_deposit(dec!(1));
+        venue
+            .transfer_funding_to_trading(ClientTransferId::new(), dec!(0.5))
+            .await
+            .unwrap();
+        venue
+    }
+
+    async fn hedge(venue: &dyn HedgingVenue, liability: SyntheticCentLiability) {
+        let adjustment = HedgingAdjustment::new(OkexHedgingConfig::default());
+        for _ in 0..3 {
+            let exposure = SyntheticCentExposure::from(venue.position().await.unwrap().usd_cents);
+            let action = adjustment.determine_action(
+                liability,
+                exposure,
+                venue.contract_size_cents().await.unwrap(),
+            );
+            match action {
+                OkexHedgeAdjustment::DoNothing => return,
+                OkexHedgeAdjustment::ClosePosition => venue.close_position(ClientOrderId::new()),
+                OkexHedgeAdjustment::Sell(ref contracts) => {
+                    venue.place_market_order(ClientOrderId::new(), OrderSide::Sell, contracts)
+                }
+                OkexHedgeAdjustment::Buy(ref contracts) => {
+                    venue.place_market_order(ClientOrderId::new(), OrderSide::Buy, contracts)
+                }
+            }
+            .await
+            .unwrap();
+        }
+        panic!("hedge did not converge");
+    }
+
+    #[tokio::test]
+    async fn hedging_adjustment_converges() {
+        let venue = funded_venue().await;
+        hedge(
+            &venue,
+            SyntheticCentLiability::try_from(dec!(500_000)).unwrap(),
+        )
+        .await;
+        assert_eq!(venue.position().await.unwrap().usd_cents, dec!(-490_000));
+
+        hedge(
+            &venue,
+            SyntheticCentLiability::try_from(dec!(200_000)).unwrap(),
+        )
+        .await;
+        assert_eq!(venue.position().await.unwrap().usd_cents, dec!(-200_000));
+
+        hedge(&venue, SyntheticCentLiability::try_from(dec!(0)).unwrap()).await;
+        assert_eq!(venue.position().await.unwrap().usd_cents, Decimal::ZERO);
+        assert!(venue.trading_balance().await.unwrap().total_amt_in_btc < dec!(0.5));
+    }
+
+    #[tokio::test]
+    async fn short_gains_when_price_falls() {
+        let venue = funded_venue().await;
+        venue
+            .place_market_order(
+                ClientOrderId::new(),
+                OrderSide::Sell,
+                &SwapContracts::from(40),
+            )
+            .await
+            .unwrap();
+        venue.set_last_price(dec!(3_200_000));
+        let position = venue.position().await.unwrap();
+        assert_eq!(position.unrealized_pnl_btc, dec!(0.025));
+        assert_eq!(venue.trading_fees_paid(), dec!(0.00005));
+        assert_eq!(venue.apply_funding_rate(dec!(0.0001)), dec!(-0.0000125));
+
+        venue.close_position(ClientOrderId::new()).await.unwrap();
+        let balance = venue.trading_balance().await.unwrap();
+        assert_eq!(balance.used_amt_in_btc, Decimal::ZERO);
+        assert!(balance.total_amt_in_btc > dec!(0.52));
+    }
+
+    #[tokio::test]
+    async fn rejects_what_the_account_cannot_cover() {
+        let venue = funded_venue().await;
+        assert!(venue
+            .place_market_order(
+                ClientOrderId::new(),
+                OrderSide::Sell,
+                &SwapContracts::from(10_000)
+            )
+            .await
+            .is_err());
+        assert!(venue
+            .withdraw_btc_onchain(
+                ClientTransferId::new(),
+                dec!(0.5),
+                PAPER_WITHDRAWAL_FEE,
+                "bc1q".to_string()
+            )
+            .await
+            .is_err());
+        venue
+            .withdraw_btc_onchain(
+                ClientTransferId::new(),
+                dec!(0.4),
+                PAPER_WITHDRAWAL_FEE,
+                "bc1q".to_string(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(venue.withdrawals().len(), 1);
+        assert_eq!(
+            venue.funding_balance().await.unwrap().total_amt_in_btc,
+            dec!(0.0999)
+        );
+    }
+
+    #[tokio::test]
+    async fn lightning_round_trip_through_a_local_payer() {
+        let venue = PaperVenue::new(dec!(4_000_000), dec!(3));
+        let settling = venue.clone();
+        let payer =
+            LocalLightningPayer::new(move |invoice| settling.pay_lightning_invoice(invoice));
+
+        let invoice = venue.lightning_deposit_invoice(dec!(0.02)).await.unwrap();
+        assert_eq!(
+            payer
+                .pay_invoice(invoice.clone(), "deposit".to_string())
+                .await
+                .unwrap(),
+            LightningPayment::Settled
+        );
+        assert!(payer
+            .pay_invoice(invoice, "deposit".to_string())
+            .await
+            .is_err());
+        assert_eq!(
+            venue.funding_balance().await.unwrap().total_amt_in_btc,
+            dec!(0.02)
+        );
+
+        let invoice = payer
+            .create_invoice(dec!(0.015), "withdraw".to_string())
+            .await
+            .unwrap();
+        let id = ClientTransferId::new();
+        for _ in 0..2 {
+            venue
+                .withdraw_lightning(id.clone(), dec!(0.015), invoice.clone())
+                .await
+                .unwrap();
+        }
+        assert_eq!(venue.withdrawals().len(), 1);
+        assert_eq!(
+            venue.funding_balance().await.unwrap().total_amt_in_btc,
+            dec!(0.005)
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use serial_test::{file_serial, serial};
+
+use hedging::{bitfinex, okex};
+
+async fn init_pool() -> anyhow::Result<sqlx::PgPool> {
+    let pg_host = std::env::var("PG_HOST").unwrap_or_else(|_| "localhost".into());
+    let pg_con = format!("postgres://user:password@{pg_host}:5432/pg");
+    Ok(sqlx::PgPool::connect(&pg_con).await?)
+}
+
+async fn queued_jobs(pool: &sqlx::PgPool, ids: &[uuid::Uuid]) -> anyhow::Result<Vec<String>> {
+    Ok(sqlx::query_scalar(
+        "SELECT channel_args FROM mq_msgs WHERE id = ANY($1) ORDER BY channel_args",
+    )
+    .bind(ids)
+    .fetch_all(pool)
+    .await?)
+}
+
+#[tokio::test]
+#[serial]
+#[file_serial]
+async fn both_exchanges_queue_jobs_for_the_same_correlation_id() -> anyhow::Result<()> {
+    let pool = init_pool().await?;
+    let correlation_id = uuid::Uuid::new_v4();
+
+    okex::job::spawn_adjust_hedge(&pool, correlation_id).await?;
+    bitfinex::job::spawn_adjust_hedge(&pool, correlation_id).await?;
+    bitfinex::job::spawn_adjust_funding(&pool, correlation_id).await?;
+
+    let ids = [
+        correlation_id,
+        bitfinex::job::job_id(correlation_id, "bitfinex_adjust_hedge"),
+        bitfinex::job::job_id(correlation_id, "bitfinex_adjust_funding"),
+    ];
+    let queued = queued_jobs(&pool, &ids).await?;
+
+    sqlx::query("DELETE FROM mq_msgs WHERE id = ANY($1)")
+        .bind(&ids[..])
+        .execute(&pool)
+        .await?;
+    sqlx::query("DELETE FROM mq_payloads WHERE id = ANY($1)")
+        .bind(&ids[..])
+        .execute(&pool)
+        .await?;
+
+    assert_eq!(
+        queued,
+        vec![
+            "adjust_hedge".to_string(),
+            "bitfinex_adjust_funding".to_string(),
+            "bitfinex_adjust_hedge".to_string(),
+        ]
+    );
+    Ok(())
+}
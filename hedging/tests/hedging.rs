@@ -9,20 +9,20 @@ use std::env;
 use bria_client::*;
 use ledger::*;
 use okex_client::*;
+use okex_mock::OkexMock;
 use shared::pubsub::*;
 
 use hedging::*;
 
-fn okex_config() -> OkexConfig {
-    let api_key = env::var("OKEX_API_KEY").expect("OKEX_API_KEY not set");
-    let passphrase = env::var("OKEX_PASSPHRASE").expect("OKEX_PASS_PHRASE not set");
-    let secret_key = env::var("OKEX_SECRET_KEY").expect("OKEX_SECRET_KEY not set");
+fn okex_config(okex_mock: &OkexMock) -> OkexConfig {
     OkexConfig {
         client: OkexClientConfig {
-            api_key,
-            passphrase,
-            secret_key,
+            api_key: "mock".to_string(),
+            passphrase: "mock".to_string(),
+            secret_key: "mock".to_string(),
             simulated: true,
+            api_url: okex_mock.url(),
+            private_ws_url: okex_mock.private_ws_url(),
         },
         ..Default::default()
     }
@@ -37,6 +37,7 @@ fn galoy_client_config() -> GaloyClientConfig {
         api,
         phone_number,
         auth_code: code,
+        ..Default::default()
     }
 }
 
@@ -64,6 +65,12 @@ async fn hedging() -> anyhow::Result<()> {
     let pg_con = format!("postgres://user:password@{}:5432/pg", pg_host);
     let pool = sqlx::PgPool::connect(&pg_con).await?;
     let ledger = ledger::Ledger::init(&pool).await?;
+    // Hedging only adjusts once user-trades has reported the liability as caught up
+    user_trades::LiabilityWatermarks::new(pool.clone())
+        .record(None)
+        .await?;
+    let okex_mock = OkexMock::start().await;
+    okex_mock.account().trading_btc = dec!(1);
 
     let (send, mut receive) = tokio::sync::mpsc::channel(1);
     let (_, tick_recv) = memory::channel(chrono::Duration::from_std(
@@ -71,6 +78,7 @@ async fn hedging() -> anyhow::Result<()> {
     )?);
 
     let ledger_clone = ledger.clone();
+    let exchange_config = okex_config(&okex_mock);
     tokio::spawn(async move {
         let (_, recv) = futures::channel::mpsc::unbounded();
         let _ = send.try_send(
@@ -80,7 +88,13 @@ async fn hedging() -> anyhow::Result<()> {
                 HedgingAppConfig {
                     ..Default::default()
                 },
-                okex_config(),
+                ExchangesConfig {
+                    okex: Some(ExchangeConfig {
+                        weight: dec!(1),
+                        config: exchange_config,
+                    }),
+                    ..Default::default()
+                },
                 galoy_client_config(),
                 bria_client_config(),
                 tick_recv.resubscribe(),
@@ -127,7 +141,7 @@ async fn hedging() -> anyhow::Result<()> {
         panic!("Could not open a position on the exchange!");
     }
 
-    let okex = OkexClient::new(okex_config().client).await?;
+    let okex = OkexClient::new(okex_config(&okex_mock).client).await?;
     okex.place_order(
         ClientOrderId::new(),
         OkexOrderSide::Buy,
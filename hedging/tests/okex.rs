@@ -0,0 +1,410 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serial_test::{file_serial, serial};
+
+use std::sync::Arc;
+
+use bria_client::{BriaClient, BriaClientConfig};
+use okex_client::*;
+use okex_mock::OkexMock;
+use shared::pubsub::CorrelationId;
+
+use hedging::{okex::*, *};
+
+async fn init_pool() -> anyhow::Result<sqlx::PgPool> {
+    let pg_host = std::env::var("PG_HOST").unwrap_or_else(|_| "localhost".into());
+    let pg_con = format!("postgres://user:password@{pg_host}:5432/pg");
+    Ok(sqlx::PgPool::connect(&pg_con).await?)
+}
+
+/// Clears the watermark, circuit breaker and pauses that would stop a cycle before it acts
+async fn open_gates(pool: &sqlx::PgPool) -> anyhow::Result<()> {
+    user_trades::LiabilityWatermarks::new(pool.clone())
+        .record(None)
+        .await?;
+    reset_circuit_breaker(pool, "test".to_string()).await?;
+    let control = ProcessControl::new(pool.clone());
+    control
+        .resume(HedgingProcess::Hedging, "test".to_string())
+        .await?;
+    control
+        .resume(HedgingProcess::Funding, "test".to_string())
+        .await?;
+    Ok(())
+}
+
+fn okex_client(okex_mock: &OkexMock) -> anyhow::Result<OkexClient> {
+    Ok(OkexClient::new_unchecked(OkexClientConfig {
+        api_key: "mock".to_string(),
+        passphrase: "mock".to_string(),
+        secret_key: "mock".to_string(),
+        simulated: true,
+        api_url: okex_mock.url(),
+        private_ws_url: okex_mock.private_ws_url(),
+    })?)
+}
+
+fn okex_venue(
+    okex_mock: &OkexMock,
+    instruments: OkexInstrumentConfig,
+) -> anyhow::Result<SharedVenue> {
+    let selection = InstrumentSelection::new(instruments);
+    Ok(Arc::new(OkexVenue::new(okex_client(okex_mock)?, selection)))
+}
+
+async fn shadow_orders(pool: &sqlx::PgPool, correlation_id: CorrelationId) -> anyhow::Result<i64> {
+    Ok(
+        sqlx::query_scalar("SELECT COUNT(*) FROM okex_orders WHERE correlation_id = $1 AND shadow")
+            .bind(uuid::Uuid::from(correlation_id))
+            .fetch_one(pool)
+            .await?,
+    )
+}
+
+#[tokio::test]
+#[serial]
+#[file_serial]
+async fn shadow_mode_records_the_order_without_placing_it() -> anyhow::Result<()> {
+    let pool = init_pool().await?;
+    open_gates(&pool).await?;
+    let okex_mock = OkexMock::start().await;
+    {
+        let mut account = okex_mock.account();
+        account.position_contracts = -100_000;
+        account.avg_px = account.last_price;
+    }
+    let revision = okex_mock.account().revision();
+    let correlation_id = CorrelationId::new();
+    let hedging_config = OkexHedgingConfig::default();
+
+    for _ in 0..2 {
+        job::adjust_hedge::execute(
+            correlation_id,
+            &pool,
+            &LiabilityWatermarkCheck::new(pool.clone(), chrono::Duration::seconds(60)),
+            &RiskGuard::new(pool.clone(), Default::default()),
+            &ProcessControl::new(pool.clone()),
+            &HedgingDecisions::new(pool.clone()),
+            ledger::Ledger::init(&pool).await?,
+            okex_venue(&okex_mock, OkexInstrumentConfig::default())?,
+            OkexOrders::new(pool.clone()).await?,
+            HedgingAdjustment::new(hedging_config.clone()),
+            ExecutionPlanner::new(OkexExecutionConfig::default()),
+            OrderPlacement::new(&hedging_config),
+            true,
+        )
+        .await?;
+    }
+
+    // Evaluating the same correlation id again does not log another would-be order
+    assert_eq!(shadow_orders(&pool, correlation_id).await?, 1);
+    assert!(okex_mock.account().orders_since(revision).is_empty());
+    assert_eq!(okex_mock.position_contracts(), -100_000);
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+#[file_serial]
+async fn paper_venue_drives_adjust_hedge() -> anyhow::Result<()> {
+    let pool = init_pool().await?;
+    open_gates(&pool).await?;
+    let venue = PaperVenue::new(dec!(4_000_000), dec!(3));
+    venue.credit_deposit(dec!(100));
+    venue
+        .transfer_funding_to_trading(ClientTransferId::new(), dec!(100))
+        .await?;
+    venue
+        .place_market_order(
+            ClientOrderId::new(),
+            OrderSide::Sell,
+            &SwapContracts::from(100_000),
+        )
+        .await?;
+    let correlation_id = CorrelationId::new();
+    let hedging_config = OkexHedgingConfig::default();
+
+    job::adjust_hedge::execute(
+        correlation_id,
+        &pool,
+        &LiabilityWatermarkCheck::new(pool.clone(), chrono::Duration::seconds(60)),
+        &RiskGuard::new(pool.clone(), Default::default()),
+        &ProcessControl::new(pool.clone()),
+        &HedgingDecisions::new(pool.clone()),
+        ledger::Ledger::init(&pool).await?,
+        Arc::new(venue.clone()),
+        OkexOrders::new(pool.clone()).await?,
+        HedgingAdjustment::new(hedging_config.clone()),
+        ExecutionPlanner::new(OkexExecutionConfig::default()),
+        OrderPlacement::new(&hedging_config),
+        true,
+    )
+    .await?;
+
+    let instrument: String = sqlx::query_scalar(
+        "SELECT instrument FROM okex_orders WHERE correlation_id = $1 AND shadow",
+    )
+    .bind(uuid::Uuid::from(correlation_id))
+    .fetch_one(&pool)
+    .await?;
+    assert_eq!(instrument, "PAPER-BTC-USD-SWAP");
+    assert_eq!(venue.position().await?.usd_cents, dec!(-1_000_000_000));
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+#[file_serial]
+async fn stale_parent_order_is_started_over() -> anyhow::Result<()> {
+    let pool = init_pool().await?;
+    open_gates(&pool).await?;
+    let ledger = ledger::Ledger::init(&pool).await?;
+    let target_usd_value = ledger
+        .balances()
+        .usd_liability_balances()
+        .await?
+        .okex_allocation
+        * Decimal::NEGATIVE_ONE;
+    sqlx::query(
+        "UPDATE okex_orders SET complete = true, state = 'superseded' WHERE is_parent AND NOT complete",
+    )
+    .execute(&pool)
+    .await?;
+    let stale_id = ClientOrderId::new();
+    sqlx::query(
+        r#"INSERT INTO okex_orders (
+             client_order_id, correlation_id, instrument, action, unit,
+             target_usd_value, position_usd_value_before_order, is_parent, state, created_at
+           ) VALUES ($1, $2, 'BTC-USD-SWAP', 'sell', 'contract', $3, 0, true, 'executing',
+             NOW() - INTERVAL '1 hour')"#,
+    )
+    .bind(String::from(stale_id.clone()))
+    .bind(uuid::Uuid::new_v4())
+    .bind(target_usd_value)
+    .execute(&pool)
+    .await?;
+    let okex_mock = OkexMock::start().await;
+    okex_mock.account().trading_btc = dec!(1);
+    let hedging_config = OkexHedgingConfig::default();
+
+    job::adjust_hedge::execute(
+        CorrelationId::new(),
+        &pool,
+        &LiabilityWatermarkCheck::new(pool.clone(), chrono::Duration::seconds(60)),
+        &RiskGuard::new(pool.clone(), Default::default()),
+        &ProcessControl::new(pool.clone()),
+        &HedgingDecisions::new(pool.clone()),
+        ledger,
+        okex_venue(&okex_mock, OkexInstrumentConfig::default())?,
+        OkexOrders::new(pool.clone()).await?,
+        HedgingAdjustment::new(hedging_config.clone()),
+        ExecutionPlanner::new(OkexExecutionConfig::default()),
+        OrderPlacement::new(&hedging_config),
+        false,
+    )
+    .await?;
+
+    let (complete, state): (bool, Option<String>) =
+        sqlx::query_as("SELECT complete, state FROM okex_orders WHERE client_order_id = $1")
+            .bind(String::from(stale_id))
+            .fetch_one(&pool)
+            .await?;
+    assert!(complete);
+    assert_eq!(state.as_deref(), Some("stale"));
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+#[file_serial]
+async fn instrument_switch_is_tracked_as_a_parent_order() -> anyhow::Result<()> {
+    let pool = init_pool().await?;
+    open_gates(&pool).await?;
+    sqlx::query(
+        "UPDATE okex_orders SET complete = true, state = 'superseded' WHERE is_parent AND NOT complete",
+    )
+    .execute(&pool)
+    .await?;
+    let okex_mock = OkexMock::start().await;
+    {
+        let mut account = okex_mock.account();
+        account.position_contracts = -100_000;
+        account.avg_px = account.last_price;
+    }
+    let revision = okex_mock.account().revision();
+    let correlation_id = CorrelationId::new();
+    let hedging_config = OkexHedgingConfig::default();
+    // The mock reports its position for any instrument, so it also shows up
+    // as left behind on the inactive alternative
+    let instruments = OkexInstrumentConfig {
+        alternative: Some(OkexInstrumentId::BtcUsdtSwap),
+        ..Default::default()
+    };
+
+    job::adjust_hedge::execute(
+        correlation_id,
+        &pool,
+        &LiabilityWatermarkCheck::new(pool.clone(), chrono::Duration::seconds(60)),
+        &RiskGuard::new(pool.clone(), Default::default()),
+        &ProcessControl::new(pool.clone()),
+        &HedgingDecisions::new(pool.clone()),
+        ledger::Ledger::init(&pool).await?,
+        okex_venue(&okex_mock, instruments)?,
+        OkexOrders::new(pool.clone()).await?,
+        HedgingAdjustment::new(hedging_config.clone()),
+        ExecutionPlanner::new(OkexExecutionConfig::default()),
+        OrderPlacement::new(&hedging_config),
+        false,
+    )
+    .await?;
+
+    let (instrument, action, complete): (String, String, bool) = sqlx::query_as(
+        "SELECT instrument, action, complete FROM okex_orders WHERE correlation_id = $1 AND is_parent",
+    )
+    .bind(uuid::Uuid::from(correlation_id))
+    .fetch_one(&pool)
+    .await?;
+    assert_eq!(instrument, "BTC-USDT-SWAP");
+    assert_eq!(action, "close-position");
+    assert!(!complete);
+    assert!(okex_mock.account().orders_since(revision).is_empty());
+
+    sqlx::query(
+        "UPDATE okex_orders SET complete = true, state = 'superseded' WHERE correlation_id = $1",
+    )
+    .bind(uuid::Uuid::from(correlation_id))
+    .execute(&pool)
+    .await?;
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+#[file_serial]
+async fn shadow_mode_records_the_transfer_without_moving_funds() -> anyhow::Result<()> {
+    let pool = init_pool().await?;
+    open_gates(&pool).await?;
+    let okex_mock = OkexMock::start().await;
+    {
+        let mut account = okex_mock.account();
+        account.position_contracts = -100;
+        account.avg_px = account.last_price;
+        account.funding_btc = dec!(10);
+    }
+    let correlation_id = CorrelationId::new();
+    // Nothing listens here, any bria call would fail the cycle
+    let mut bria = BriaClient::connect_lazy(BriaClientConfig {
+        url: "http://127.0.0.1:1".to_string(),
+        ..Default::default()
+    })?;
+
+    for _ in 0..2 {
+        job::adjust_funding::execute(
+            correlation_id,
+            &LiabilityWatermarkCheck::new(pool.clone(), chrono::Duration::seconds(60)),
+            &RiskGuard::new(pool.clone(), Default::default()),
+            &ProcessControl::new(pool.clone()),
+            &HedgingDecisions::new(pool.clone()),
+            ledger::Ledger::init(&pool).await?,
+            okex_venue(&okex_mock, OkexInstrumentConfig::default())?,
+            OkexTransfers::new(pool.clone(), OkexTransfersConfig::default()).await?,
+            &mut bria,
+            FundingAdjustment::new(OkexFundingConfig::default(), OkexHedgingConfig::default()),
+            FundingRails::new(OkexLightningConfig::default(), None),
+            true,
+        )
+        .await?;
+    }
+
+    // Evaluating the same correlation id again does not log another would-be transfer
+    let shadow_transfers: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM okex_transfers WHERE correlation_id = $1 AND shadow",
+    )
+    .bind(uuid::Uuid::from(correlation_id))
+    .fetch_one(&pool)
+    .await?;
+    assert_eq!(shadow_transfers, 1);
+    let account = okex_mock.account();
+    assert_eq!(account.funding_btc, dec!(10));
+    assert_eq!(account.trading_btc, Decimal::ZERO);
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+#[file_serial]
+async fn instrument_switch_survives_a_restart() -> anyhow::Result<()> {
+    let pool = init_pool().await?;
+    let config = OkexInstrumentConfig {
+        primary: OkexInstrumentId::BtcUsdSwap,
+        alternative: Some(OkexInstrumentId::BtcUsdtSwap),
+        min_funding_rate_spread: dec!(0.0001),
+    };
+    let selection = InstrumentSelection::new(config.clone());
+    let rates = [
+        FundingRate {
+            instrument_id: OkexInstrumentId::BtcUsdSwap,
+            rate: dec!(0.0001),
+            funding_time: chrono::Utc::now(),
+        },
+        FundingRate {
+            instrument_id: OkexInstrumentId::BtcUsdtSwap,
+            rate: dec!(0.0003),
+            funding_time: chrono::Utc::now(),
+        },
+    ];
+    selection.select(
+        &rates,
+        UsdtMargin {
+            available_cents: dec!(1_000_000),
+            required_cents: dec!(1_000_000),
+        },
+    );
+    selection.persist(&pool).await?;
+
+    let restarted = InstrumentSelection::load(&pool, config).await?;
+    assert_eq!(restarted.active(), OkexInstrumentId::BtcUsdtSwap);
+
+    // An instrument that is no longer configured falls back to the primary
+    let restarted = InstrumentSelection::load(&pool, OkexInstrumentConfig::default()).await?;
+    assert_eq!(restarted.active(), OkexInstrumentId::BtcUsdSwap);
+
+    InstrumentSelection::new(OkexInstrumentConfig::default())
+        .persist(&pool)
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+#[file_serial]
+async fn usdt_bills_are_posted_to_the_usdt_margin() -> anyhow::Result<()> {
+    let pool = init_pool().await?;
+    let ledger = ledger::Ledger::init(&pool).await?;
+    let bills = OkexBills::new(pool.clone()).await?;
+    let initial_margin = ledger.balances().okex_usdt_margin().await?;
+    let initial_btc_margin = ledger.balances().okex_btc_margin().await?;
+    let bill = AccountBill {
+        bill_id: uuid::Uuid::new_v4().simple().to_string()[..20].to_string(),
+        bill_type: OkexBillType::Trade,
+        instrument_id: "BTC-USDT-SWAP".to_string(),
+        currency: "USDT".to_string(),
+        balance_change: dec!(0),
+        fee: dec!(-0.8),
+        pnl: dec!(3),
+        order_id: "1".to_string(),
+        timestamp: chrono::Utc::now(),
+    };
+
+    assert!(bills.import(&ledger, bill.clone()).await?);
+    assert!(!bills.import(&ledger, bill).await?);
+    assert_eq!(
+        ledger.balances().okex_usdt_margin().await? - initial_margin,
+        dec!(2.2)
+    );
+    assert_eq!(
+        ledger.balances().okex_btc_margin().await?,
+        initial_btc_margin
+    );
+    Ok(())
+}
@@ -9,6 +9,7 @@ pub struct Balances<'a> {
     pub(super) inner: &'a SqlxLedger,
     pub(super) usd: Currency,
     pub(super) btc: Currency,
+    pub(super) usdt: Currency,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -104,7 +105,7 @@ impl<'a> Balances<'a> {
             .map(|b| b.map(|b| b.settled()).unwrap_or(Decimal::ZERO))
     }
 
-    async fn exchange_position_account_balance(
+    pub(super) async fn exchange_position_account_balance(
         &self,
         exchange_position_id: impl Into<LedgerAccountId> + std::fmt::Debug,
     ) -> Result<Option<AccountBalance>, LedgerError> {
@@ -123,6 +124,74 @@ impl<'a> Balances<'a> {
             .await
     }
 
+    pub async fn bitfinex_position_account_balance(
+        &self,
+    ) -> Result<Option<AccountBalance>, LedgerError> {
+        self.exchange_position_account_balance(BITFINEX_POSITION_ID)
+            .await
+    }
+
+    pub async fn okex_funding_payments_btc(&self) -> Result<Decimal, LedgerError> {
+        self.get_ledger_account_balance(STABLESATS_JOURNAL_ID, OKEX_FUNDING_PAYMENTS_ID, self.btc)
+            .await
+            .map(|b| b.map(|b| b.settled()).unwrap_or(Decimal::ZERO))
+    }
+
+    pub async fn okex_trading_fees_btc(&self) -> Result<Decimal, LedgerError> {
+        self.get_ledger_account_balance(STABLESATS_JOURNAL_ID, OKEX_TRADING_FEES_ID, self.btc)
+            .await
+            .map(|b| b.map(|b| b.settled()).unwrap_or(Decimal::ZERO))
+    }
+
+    /// Btc held in the okex trading account including unrealized pnl
+    pub async fn okex_btc_margin(&self) -> Result<Decimal, LedgerError> {
+        self.get_ledger_account_balance(STABLESATS_JOURNAL_ID, OKEX_BTC_MARGIN_ID, self.btc)
+            .await
+            .map(|b| b.map(|b| b.settled()).unwrap_or(Decimal::ZERO))
+    }
+
+    /// Usdt held in the okex trading account for the usdt margined swap
+    pub async fn okex_usdt_margin(&self) -> Result<Decimal, LedgerError> {
+        self.get_ledger_account_balance(STABLESATS_JOURNAL_ID, OKEX_USDT_MARGIN_ID, self.usdt)
+            .await
+            .map(|b| b.map(|b| b.settled()).unwrap_or(Decimal::ZERO))
+    }
+
+    pub async fn okex_realized_pnl_btc(&self) -> Result<Decimal, LedgerError> {
+        self.get_ledger_account_balance(
+            STABLESATS_JOURNAL_ID,
+            OKEX_REALIZED_PNL_ACCOUNT_ID,
+            self.btc,
+        )
+        .await
+        .map(|b| b.map(|b| b.settled()).unwrap_or(Decimal::ZERO))
+    }
+
+    pub async fn okex_unrealized_pnl_btc(&self) -> Result<Decimal, LedgerError> {
+        self.get_ledger_account_balance(STABLESATS_JOURNAL_ID, OKEX_UNREALIZED_PNL_ID, self.btc)
+            .await
+            .map(|b| b.map(|b| b.settled()).unwrap_or(Decimal::ZERO))
+    }
+
+    pub async fn okex_btc_funding(&self) -> Result<Decimal, LedgerError> {
+        self.get_ledger_account_balance(STABLESATS_JOURNAL_ID, OKEX_BTC_FUNDING_ID, self.btc)
+            .await
+            .map(|b| b.map(|b| b.settled()).unwrap_or(Decimal::ZERO))
+    }
+
+    /// Deposits that have left the wallet and are yet to be credited by okex
+    pub async fn okex_btc_deposits_in_flight(&self) -> Result<Decimal, LedgerError> {
+        self.get_ledger_account_balance(STABLESATS_JOURNAL_ID, OKEX_BTC_FUNDING_ID, self.btc)
+            .await
+            .map(|b| b.map(|b| b.pending()).unwrap_or(Decimal::ZERO))
+    }
+
+    pub async fn okex_withdrawal_fees_btc(&self) -> Result<Decimal, LedgerError> {
+        self.get_ledger_account_balance(STABLESATS_JOURNAL_ID, OKEX_WITHDRAWAL_FEES_ID, self.btc)
+            .await
+            .map(|b| b.map(|b| b.settled()).unwrap_or(Decimal::ZERO))
+    }
+
     #[instrument(name = "ledger.get_ledger_account_balance", skip(self))]
     pub async fn get_ledger_account_balance(
         &self,
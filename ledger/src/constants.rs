@@ -24,6 +24,30 @@ pub(super) const SELL_USD_QUOTE_ACCEPTED_ID: Uuid = uuid!("00000000-0000-0000-00
 pub(super) const ADJUST_EXCHANGE_ALLOCATION_CODE: &str = "ADJUST_EXCHANGE_ALLOCATION";
 pub(super) const ADJUST_EXCHANGE_ALLOCATION_ID: Uuid =
     uuid!("00000000-0000-0000-0000-000000000007");
+pub(super) const TRANSFER_QUOTE_LIABILITY_CODE: &str = "TRANSFER_QUOTE_LIABILITY";
+pub(super) const TRANSFER_QUOTE_LIABILITY_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000008");
+// Init ignores definitions that already exist, so the okex bill templates and
+// accounts get a new id and code whenever their journal, directions or normal
+// balance change. Balances posted before stay on the superseded ids.
+pub(super) const OKEX_FUNDING_PAYMENT_CODE: &str = "OKEX_FUNDING_PAYMENT_V2";
+pub(super) const OKEX_FUNDING_PAYMENT_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000018");
+pub(super) const OKEX_TRADING_FEE_CODE: &str = "OKEX_TRADING_FEE_V2";
+pub(super) const OKEX_TRADING_FEE_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000019");
+pub(super) const OKEX_REALIZED_PNL_CODE: &str = "OKEX_REALIZED_PNL_V2";
+pub(super) const OKEX_REALIZED_PNL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000020");
+pub(super) const ADJUST_OKEX_UNREALIZED_PNL_CODE: &str = "ADJUST_OKEX_UNREALIZED_PNL_V2";
+pub(super) const ADJUST_OKEX_UNREALIZED_PNL_ID: Uuid =
+    uuid!("00000000-0000-0000-0000-000000000021");
+pub(super) const OKEX_INTERNAL_TRANSFER_CODE: &str = "OKEX_INTERNAL_TRANSFER_V2";
+pub(super) const OKEX_INTERNAL_TRANSFER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000022");
+pub(super) const OKEX_DEPOSIT_INITIATED_CODE: &str = "OKEX_DEPOSIT_INITIATED";
+pub(super) const OKEX_DEPOSIT_INITIATED_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000014");
+pub(super) const OKEX_DEPOSIT_SETTLED_CODE: &str = "OKEX_DEPOSIT_SETTLED";
+pub(super) const OKEX_DEPOSIT_SETTLED_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000015");
+pub(super) const OKEX_WITHDRAWAL_CODE: &str = "OKEX_WITHDRAWAL";
+pub(super) const OKEX_WITHDRAWAL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000016");
+pub(super) const OKEX_USDT_BILL_CODE: &str = "OKEX_USDT_BILL_V2";
+pub(super) const OKEX_USDT_BILL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000023");
 
 // Journal
 pub(super) const STABLESATS_JOURNAL_NAME: &str = "Stablesats";
@@ -63,8 +87,35 @@ pub(super) const OKEX_POSITION_ID: Uuid = uuid!("10000000-1000-0000-0000-0000000
 pub(super) const OKEX_ALLOCATION_CODE: &str = "OKEX_ALLOCATION";
 pub(super) const OKEX_ALLOCATION_ID: Uuid = uuid!("10000000-1000-0000-0000-000000000003");
 
+pub(super) const BITFINEX_POSITION_CODE: &str = "BITFINEX_POSITION";
+pub(super) const BITFINEX_POSITION_ID: Uuid = uuid!("10000000-1000-0000-0000-000000000005");
+
 pub(super) const BITFINEX_ALLOCATION_CODE: &str = "BITFINEX_ALLOCATION";
 pub(super) const BITFINEX_ALLOCATION_ID: uuid::Uuid = uuid!("10000000-1000-0000-0000-000000000004");
 
+pub(super) const OKEX_FUNDING_PAYMENTS_CODE: &str = "OKEX_FUNDING_PAYMENTS_V2";
+pub(super) const OKEX_FUNDING_PAYMENTS_ID: Uuid = uuid!("10000000-1000-0000-0000-000000000015");
+
+pub(super) const OKEX_TRADING_FEES_CODE: &str = "OKEX_TRADING_FEES_V2";
+pub(super) const OKEX_TRADING_FEES_ID: Uuid = uuid!("10000000-1000-0000-0000-000000000016");
+
+pub(super) const OKEX_BTC_MARGIN_CODE: &str = "OKEX_BTC_MARGIN_V2";
+pub(super) const OKEX_BTC_MARGIN_ID: Uuid = uuid!("10000000-1000-0000-0000-000000000017");
+
+pub(super) const OKEX_REALIZED_PNL_ACCOUNT_CODE: &str = "OKEX_REALIZED_PNL_V2";
+pub(super) const OKEX_REALIZED_PNL_ACCOUNT_ID: Uuid = uuid!("10000000-1000-0000-0000-000000000018");
+
+pub(super) const OKEX_UNREALIZED_PNL_CODE: &str = "OKEX_UNREALIZED_PNL_V2";
+pub(super) const OKEX_UNREALIZED_PNL_ID: Uuid = uuid!("10000000-1000-0000-0000-000000000019");
+
+pub(super) const OKEX_BTC_FUNDING_CODE: &str = "OKEX_BTC_FUNDING";
+pub(super) const OKEX_BTC_FUNDING_ID: Uuid = uuid!("10000000-1000-0000-0000-000000000011");
+
+pub(super) const OKEX_WITHDRAWAL_FEES_CODE: &str = "OKEX_WITHDRAWAL_FEES";
+pub(super) const OKEX_WITHDRAWAL_FEES_ID: Uuid = uuid!("10000000-1000-0000-0000-000000000013");
+
+pub(super) const OKEX_USDT_MARGIN_CODE: &str = "OKEX_USDT_MARGIN_V2";
+pub(super) const OKEX_USDT_MARGIN_ID: Uuid = uuid!("10000000-1000-0000-0000-000000000020");
+
 pub const SATS_PER_BTC: Decimal = dec!(100_000_000);
 pub const CENTS_PER_USD: Decimal = dec!(100);
@@ -7,4 +7,6 @@ pub enum LedgerError {
     SqlxLedger(#[from] sqlx_ledger::SqlxLedgerError),
     #[error("HedgingError - Sqlx: {0}")]
     Sqlx(#[from] sqlx::Error),
+    #[error("LedgerError - InvalidPageSize: {0}")]
+    InvalidPageSize(i64),
 }
@@ -2,17 +2,22 @@
 #![cfg_attr(feature = "fail-on-warnings", deny(clippy::all))]
 
 use rust_decimal::Decimal;
-use sqlx::{PgPool, Postgres, Transaction};
+use sqlx::{Acquire, PgPool, Postgres, Transaction};
 use tokio::sync::broadcast;
 use tracing::instrument;
 
 mod balances;
 pub mod constants;
 mod error;
+mod statements;
 mod templates;
 
+pub use balances::LiabilityAllocations;
 use constants::*;
 pub use error::*;
+pub use statements::{
+    AccountBalanceAt, StatementCursor, StatementEntry, StatementPage, StatementQuery,
+};
 pub use templates::*;
 
 use sqlx_ledger::{
@@ -28,10 +33,29 @@ pub use sqlx_ledger::{
 
 #[derive(Debug, Clone)]
 pub struct Ledger {
+    pool: PgPool,
     inner: SqlxLedger,
     events: EventSubscriber,
     usd: Currency,
     btc: Currency,
+    usdt: Currency,
+}
+
+/// Read-only access for reporting, does not create journals, accounts or templates
+/// nor subscribe to ledger events
+#[derive(Debug, Clone)]
+pub struct LedgerReader {
+    pool: PgPool,
+}
+
+impl LedgerReader {
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+
+    pub fn statements(&'_ self) -> statements::Statements<'_> {
+        statements::Statements { pool: &self.pool }
+    }
 }
 
 impl Ledger {
@@ -47,11 +71,20 @@ impl Ledger {
         Self::stablesats_liability_account(&inner).await?;
         Self::exchange_position_omnibus_account(&inner).await?;
         Self::okex_position_account(&inner).await?;
+        Self::bitfinex_position_account(&inner).await?;
         Self::quotes_omnibus_account(&inner).await?;
         Self::quotes_liabilities_account(&inner).await?;
         Self::quotes_assets_account(&inner).await?;
         Self::okex_allocation_account(&inner).await?;
         Self::bitfinex_allocation_account(&inner).await?;
+        Self::okex_funding_payments_account(&inner).await?;
+        Self::okex_trading_fees_account(&inner).await?;
+        Self::okex_btc_margin_account(&inner).await?;
+        Self::okex_realized_pnl_account(&inner).await?;
+        Self::okex_unrealized_pnl_account(&inner).await?;
+        Self::okex_btc_funding_account(&inner).await?;
+        Self::okex_withdrawal_fees_account(&inner).await?;
+        Self::okex_usdt_margin_account(&inner).await?;
 
         templates::UserBuysUsd::init(&inner).await?;
         templates::UserSellsUsd::init(&inner).await?;
@@ -62,12 +95,24 @@ impl Ledger {
         templates::BuyUsdQuoteAccepted::init(&inner).await?;
         templates::SellUsdQuoteAccepted::init(&inner).await?;
         templates::AdjustExchangeAllocation::init(&inner).await?;
+        templates::TransferQuoteLiability::init(&inner).await?;
+        templates::OkexFundingPayment::init(&inner).await?;
+        templates::OkexTradingFee::init(&inner).await?;
+        templates::OkexRealizedPnl::init(&inner).await?;
+        templates::AdjustOkexUnrealizedPnl::init(&inner).await?;
+        templates::OkexInternalTransfer::init(&inner).await?;
+        templates::OkexDepositInitiated::init(&inner).await?;
+        templates::OkexDepositSettled::init(&inner).await?;
+        templates::OkexWithdrawal::init(&inner).await?;
+        templates::OkexUsdtBill::init(&inner).await?;
 
         Ok(Self {
             events: inner.events(EventSubscriberOpts::default()).await?,
             inner,
+            pool: pool.clone(),
             usd: "USD".parse().unwrap(),
             btc: "BTC".parse().unwrap(),
+            usdt: "USDT".parse().unwrap(),
         })
     }
 
@@ -76,9 +121,14 @@ impl Ledger {
             inner: &self.inner,
             usd: self.usd,
             btc: self.btc,
+            usdt: self.usdt,
         }
     }
 
+    pub fn statements(&'_ self) -> statements::Statements<'_> {
+        statements::Statements { pool: &self.pool }
+    }
+
     #[instrument(name = "ledger.adjust_exchange_position", skip(self, tx))]
     async fn adjust_exchange_position(
         &self,
@@ -90,7 +140,7 @@ impl Ledger {
     ) -> Result<(), LedgerError> {
         let current_balance = self
             .balances()
-            .okex_position_account_balance()
+            .exchange_position_account_balance(exchange_position_id)
             .await?
             .map(|b| b.settled())
             .unwrap_or(Decimal::ZERO);
@@ -176,6 +226,25 @@ impl Ledger {
         Ok(())
     }
 
+    #[instrument(name = "ledger.adjust_bitfinex_position", skip(self,))]
+    pub async fn adjust_bitfinex_position(
+        &self,
+        tx: Transaction<'_, Postgres>,
+        usd_cents_amount: Decimal,
+        exchange_id: String,
+        instrument_id: String,
+    ) -> Result<(), LedgerError> {
+        self.adjust_exchange_position(
+            tx,
+            usd_cents_amount,
+            BITFINEX_POSITION_ID,
+            exchange_id,
+            instrument_id,
+        )
+        .await?;
+        Ok(())
+    }
+
     #[instrument(name = "ledger.adjust_exchange_allocation", skip(self, tx))]
     pub async fn adjust_exchange_allocation(
         &self,
@@ -248,25 +317,199 @@ impl Ledger {
     #[instrument(name = "ledger.buy_usd_quote_accepted", skip(self, tx))]
     pub async fn buy_usd_quote_accepted(
         &self,
-        tx: Transaction<'_, Postgres>,
+        mut tx: Transaction<'_, Postgres>,
         id: LedgerTxId,
         params: BuyUsdQuoteAcceptedParams,
     ) -> Result<(), LedgerError> {
+        let transfer_params = TransferQuoteLiabilityParams {
+            usd_cents_amount: params.usd_cents_amount,
+            meta: TransferQuoteLiabilityMeta {
+                timestamp: params.meta.timestamp,
+            },
+        };
         self.inner
-            .post_transaction_in_tx(tx, id, BUY_USD_QUOTE_ACCEPTED_CODE, Some(params))
+            .post_transaction_in_tx(
+                tx.begin().await?,
+                id,
+                BUY_USD_QUOTE_ACCEPTED_CODE,
+                Some(params),
+            )
+            .await?;
+        self.inner
+            .post_transaction_in_tx(
+                tx.begin().await?,
+                LedgerTxId::new(),
+                TRANSFER_QUOTE_LIABILITY_CODE,
+                Some(transfer_params),
+            )
             .await?;
+        tx.commit().await?;
         Ok(())
     }
 
     #[instrument(name = "ledger.sell_usd_quote_accepted", skip(self, tx))]
     pub async fn sell_usd_quote_accepted(
         &self,
-        tx: Transaction<'_, Postgres>,
+        mut tx: Transaction<'_, Postgres>,
         id: LedgerTxId,
         params: SellUsdQuoteAcceptedParams,
+    ) -> Result<(), LedgerError> {
+        let transfer_params = TransferQuoteLiabilityParams {
+            usd_cents_amount: -params.usd_cents_amount,
+            meta: TransferQuoteLiabilityMeta {
+                timestamp: params.meta.timestamp,
+            },
+        };
+        self.inner
+            .post_transaction_in_tx(
+                tx.begin().await?,
+                id,
+                SELL_USD_QUOTE_ACCEPTED_CODE,
+                Some(params),
+            )
+            .await?;
+        self.inner
+            .post_transaction_in_tx(
+                tx.begin().await?,
+                LedgerTxId::new(),
+                TRANSFER_QUOTE_LIABILITY_CODE,
+                Some(transfer_params),
+            )
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    #[instrument(name = "ledger.okex_funding_payment", skip(self, tx))]
+    pub async fn okex_funding_payment(
+        &self,
+        tx: Transaction<'_, Postgres>,
+        id: LedgerTxId,
+        params: OkexFundingPaymentParams,
+    ) -> Result<(), LedgerError> {
+        self.inner
+            .post_transaction_in_tx(tx, id, OKEX_FUNDING_PAYMENT_CODE, Some(params))
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(name = "ledger.okex_trading_fee", skip(self, tx))]
+    pub async fn okex_trading_fee(
+        &self,
+        tx: Transaction<'_, Postgres>,
+        id: LedgerTxId,
+        params: OkexTradingFeeParams,
+    ) -> Result<(), LedgerError> {
+        self.inner
+            .post_transaction_in_tx(tx, id, OKEX_TRADING_FEE_CODE, Some(params))
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(name = "ledger.okex_usdt_bill", skip(self, tx))]
+    pub async fn okex_usdt_bill(
+        &self,
+        tx: Transaction<'_, Postgres>,
+        id: LedgerTxId,
+        params: OkexUsdtBillParams,
+    ) -> Result<(), LedgerError> {
+        self.inner
+            .post_transaction_in_tx(tx, id, OKEX_USDT_BILL_CODE, Some(params))
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(name = "ledger.okex_realized_pnl", skip(self, tx))]
+    pub async fn okex_realized_pnl(
+        &self,
+        tx: Transaction<'_, Postgres>,
+        id: LedgerTxId,
+        params: OkexRealizedPnlParams,
+    ) -> Result<(), LedgerError> {
+        self.inner
+            .post_transaction_in_tx(tx, id, OKEX_REALIZED_PNL_CODE, Some(params))
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(name = "ledger.adjust_okex_unrealized_pnl", skip(self, tx))]
+    pub async fn adjust_okex_unrealized_pnl(
+        &self,
+        tx: Transaction<'_, Postgres>,
+        unrealized_pnl_btc: Decimal,
+        instrument_id: String,
+    ) -> Result<(), LedgerError> {
+        let current = self.balances().okex_unrealized_pnl_btc().await?;
+        let diff = unrealized_pnl_btc - current;
+        if diff == Decimal::ZERO {
+            return Ok(());
+        }
+        self.inner
+            .post_transaction_in_tx(
+                tx,
+                LedgerTxId::new(),
+                ADJUST_OKEX_UNREALIZED_PNL_CODE,
+                Some(AdjustOkexUnrealizedPnlParams {
+                    btc_amount: diff,
+                    meta: AdjustOkexUnrealizedPnlMeta {
+                        timestamp: chrono::Utc::now(),
+                        instrument_id,
+                        unrealized_pnl_btc,
+                    },
+                }),
+            )
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(name = "ledger.okex_internal_transfer", skip(self, tx))]
+    pub async fn okex_internal_transfer(
+        &self,
+        tx: Transaction<'_, Postgres>,
+        id: LedgerTxId,
+        params: OkexInternalTransferParams,
+    ) -> Result<(), LedgerError> {
+        self.inner
+            .post_transaction_in_tx(tx, id, OKEX_INTERNAL_TRANSFER_CODE, Some(params))
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(name = "ledger.okex_deposit_initiated", skip(self, tx))]
+    pub async fn okex_deposit_initiated(
+        &self,
+        tx: Transaction<'_, Postgres>,
+        id: LedgerTxId,
+        params: OkexDepositInitiatedParams,
     ) -> Result<(), LedgerError> {
         self.inner
-            .post_transaction_in_tx(tx, id, SELL_USD_QUOTE_ACCEPTED_CODE, Some(params))
+            .post_transaction_in_tx(tx, id, OKEX_DEPOSIT_INITIATED_CODE, Some(params))
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(name = "ledger.okex_deposit_settled", skip(self, tx))]
+    pub async fn okex_deposit_settled(
+        &self,
+        tx: Transaction<'_, Postgres>,
+        id: LedgerTxId,
+        params: OkexDepositSettledParams,
+    ) -> Result<(), LedgerError> {
+        self.inner
+            .post_transaction_in_tx(tx, id, OKEX_DEPOSIT_SETTLED_CODE, Some(params))
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(name = "ledger.okex_withdrawal", skip(self, tx))]
+    pub async fn okex_withdrawal(
+        &self,
+        tx: Transaction<'_, Postgres>,
+        id: LedgerTxId,
+        params: OkexWithdrawalParams,
+    ) -> Result<(), LedgerError> {
+        self.inner
+            .post_transaction_in_tx(tx, id, OKEX_WITHDRAWAL_CODE, Some(params))
             .await?;
         Ok(())
     }
@@ -280,6 +523,15 @@ impl Ledger {
             .await?)
     }
 
+    pub async fn bitfinex_usd_liability_balance_events(
+        &self,
+    ) -> Result<broadcast::Receiver<SqlxLedgerEvent>, LedgerError> {
+        Ok(self
+            .events
+            .account_balance(STABLESATS_JOURNAL_ID.into(), BITFINEX_ALLOCATION_ID.into())
+            .await?)
+    }
+
     pub async fn usd_omnibus_balance_events(
         &self,
     ) -> Result<broadcast::Receiver<SqlxLedgerEvent>, LedgerError> {
@@ -298,6 +550,18 @@ impl Ledger {
             .await?)
     }
 
+    pub async fn usd_bitfinex_position_balance_events(
+        &self,
+    ) -> Result<broadcast::Receiver<SqlxLedgerEvent>, LedgerError> {
+        Ok(self
+            .events
+            .account_balance(
+                EXCHANGE_POSITION_JOURNAL_ID.into(),
+                BITFINEX_POSITION_ID.into(),
+            )
+            .await?)
+    }
+
     #[instrument(name = "ledger.create_stablesats_journal", skip(ledger))]
     async fn create_stablesats_journal(ledger: &SqlxLedger) -> Result<(), LedgerError> {
         let new_journal = NewJournal::builder()
@@ -420,6 +684,22 @@ impl Ledger {
         }
     }
 
+    #[instrument(name = "ledger.bitfinex_position_account", skip_all)]
+    async fn bitfinex_position_account(ledger: &SqlxLedger) -> Result<(), LedgerError> {
+        let new_account = NewAccount::builder()
+            .code(BITFINEX_POSITION_CODE)
+            .id(BITFINEX_POSITION_ID)
+            .name(BITFINEX_POSITION_CODE)
+            .normal_balance_type(DebitOrCredit::Debit)
+            .description("Account for bitfinex position".to_string())
+            .build()
+            .expect("Couldn't create bitfinex position account");
+        match ledger.accounts().create(new_account).await {
+            Ok(_) | Err(SqlxLedgerError::DuplicateKey(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
     #[instrument(name = "ledger.okex_allocation_account", skip_all)]
     async fn okex_allocation_account(ledger: &SqlxLedger) -> Result<(), LedgerError> {
         let new_account = NewAccount::builder()
@@ -498,4 +778,130 @@ impl Ledger {
             Err(e) => Err(e.into()),
         }
     }
+
+    #[instrument(name = "ledger.okex_funding_payments_account", skip_all)]
+    async fn okex_funding_payments_account(ledger: &SqlxLedger) -> Result<(), LedgerError> {
+        let new_account = NewAccount::builder()
+            .code(OKEX_FUNDING_PAYMENTS_CODE)
+            .id(OKEX_FUNDING_PAYMENTS_ID)
+            .name(OKEX_FUNDING_PAYMENTS_CODE)
+            .normal_balance_type(DebitOrCredit::Credit)
+            .description("Account for funding payments on okex swaps".to_string())
+            .build()
+            .expect("Couldn't create okex funding payments account");
+        match ledger.accounts().create(new_account).await {
+            Ok(_) | Err(SqlxLedgerError::DuplicateKey(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    #[instrument(name = "ledger.okex_trading_fees_account", skip_all)]
+    async fn okex_trading_fees_account(ledger: &SqlxLedger) -> Result<(), LedgerError> {
+        let new_account = NewAccount::builder()
+            .code(OKEX_TRADING_FEES_CODE)
+            .id(OKEX_TRADING_FEES_ID)
+            .name(OKEX_TRADING_FEES_CODE)
+            .normal_balance_type(DebitOrCredit::Credit)
+            .description("Account for okex trading fees".to_string())
+            .build()
+            .expect("Couldn't create okex trading fees account");
+        match ledger.accounts().create(new_account).await {
+            Ok(_) | Err(SqlxLedgerError::DuplicateKey(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    #[instrument(name = "ledger.okex_btc_margin_account", skip_all)]
+    async fn okex_btc_margin_account(ledger: &SqlxLedger) -> Result<(), LedgerError> {
+        let new_account = NewAccount::builder()
+            .code(OKEX_BTC_MARGIN_CODE)
+            .id(OKEX_BTC_MARGIN_ID)
+            .name(OKEX_BTC_MARGIN_CODE)
+            .normal_balance_type(DebitOrCredit::Credit)
+            .description("Account for btc margin held in the okex trading account".to_string())
+            .build()
+            .expect("Couldn't create okex btc margin account");
+        match ledger.accounts().create(new_account).await {
+            Ok(_) | Err(SqlxLedgerError::DuplicateKey(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    #[instrument(name = "ledger.okex_usdt_margin_account", skip_all)]
+    async fn okex_usdt_margin_account(ledger: &SqlxLedger) -> Result<(), LedgerError> {
+        let new_account = NewAccount::builder()
+            .code(OKEX_USDT_MARGIN_CODE)
+            .id(OKEX_USDT_MARGIN_ID)
+            .name(OKEX_USDT_MARGIN_CODE)
+            .normal_balance_type(DebitOrCredit::Credit)
+            .description("Account for usdt margin held in the okex trading account".to_string())
+            .build()
+            .expect("Couldn't create okex usdt margin account");
+        match ledger.accounts().create(new_account).await {
+            Ok(_) | Err(SqlxLedgerError::DuplicateKey(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    #[instrument(name = "ledger.okex_realized_pnl_account", skip_all)]
+    async fn okex_realized_pnl_account(ledger: &SqlxLedger) -> Result<(), LedgerError> {
+        let new_account = NewAccount::builder()
+            .code(OKEX_REALIZED_PNL_ACCOUNT_CODE)
+            .id(OKEX_REALIZED_PNL_ACCOUNT_ID)
+            .name(OKEX_REALIZED_PNL_ACCOUNT_CODE)
+            .normal_balance_type(DebitOrCredit::Debit)
+            .description("Account for pnl realized by closing okex contracts".to_string())
+            .build()
+            .expect("Couldn't create okex realized pnl account");
+        match ledger.accounts().create(new_account).await {
+            Ok(_) | Err(SqlxLedgerError::DuplicateKey(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    #[instrument(name = "ledger.okex_unrealized_pnl_account", skip_all)]
+    async fn okex_unrealized_pnl_account(ledger: &SqlxLedger) -> Result<(), LedgerError> {
+        let new_account = NewAccount::builder()
+            .code(OKEX_UNREALIZED_PNL_CODE)
+            .id(OKEX_UNREALIZED_PNL_ID)
+            .name(OKEX_UNREALIZED_PNL_CODE)
+            .normal_balance_type(DebitOrCredit::Debit)
+            .description("Account for mark-to-market pnl of the open okex position".to_string())
+            .build()
+            .expect("Couldn't create okex unrealized pnl account");
+        match ledger.accounts().create(new_account).await {
+            Ok(_) | Err(SqlxLedgerError::DuplicateKey(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    #[instrument(name = "ledger.okex_btc_funding_account", skip_all)]
+    async fn okex_btc_funding_account(ledger: &SqlxLedger) -> Result<(), LedgerError> {
+        let new_account = NewAccount::builder()
+            .code(OKEX_BTC_FUNDING_CODE)
+            .id(OKEX_BTC_FUNDING_ID)
+            .name(OKEX_BTC_FUNDING_CODE)
+            .description("Account for btc held in the okex funding account".to_string())
+            .build()
+            .expect("Couldn't create okex btc funding account");
+        match ledger.accounts().create(new_account).await {
+            Ok(_) | Err(SqlxLedgerError::DuplicateKey(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    #[instrument(name = "ledger.okex_withdrawal_fees_account", skip_all)]
+    async fn okex_withdrawal_fees_account(ledger: &SqlxLedger) -> Result<(), LedgerError> {
+        let new_account = NewAccount::builder()
+            .code(OKEX_WITHDRAWAL_FEES_CODE)
+            .id(OKEX_WITHDRAWAL_FEES_ID)
+            .name(OKEX_WITHDRAWAL_FEES_CODE)
+            .description("Account for fees paid on okex withdrawals".to_string())
+            .build()
+            .expect("Couldn't create okex withdrawal fees account");
+        match ledger.accounts().create(new_account).await {
+            Ok(_) | Err(SqlxLedgerError::DuplicateKey(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
 }
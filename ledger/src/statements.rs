@@ -0,0 +1,171 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::PgPool;
+use tracing::instrument;
+
+use crate::LedgerError;
+
+pub struct Statements<'a> {
+    pub(super) pool: &'a PgPool,
+}
+
+/// Balance of an account as it stood at a point in time, signed by its normal balance type
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, sqlx::FromRow)]
+pub struct AccountBalanceAt {
+    pub journal: String,
+    pub account_id: uuid::Uuid,
+    pub account_code: String,
+    pub currency: String,
+    pub settled: Decimal,
+    pub pending: Decimal,
+    pub encumbered: Decimal,
+}
+
+/// Position after the last entry of a page, entries are ordered by effective date,
+/// creation time then id
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatementCursor {
+    pub effective: NaiveDate,
+    pub created_at: DateTime<Utc>,
+    pub entry_id: uuid::Uuid,
+}
+
+#[derive(Debug, Clone)]
+pub struct StatementQuery {
+    /// Inclusive effective date
+    pub from: NaiveDate,
+    /// Exclusive effective date
+    pub until: NaiveDate,
+    pub account_code: Option<String>,
+    pub after: Option<StatementCursor>,
+    pub limit: i64,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct StatementEntry {
+    pub entry_id: uuid::Uuid,
+    pub transaction_id: uuid::Uuid,
+    pub created_at: DateTime<Utc>,
+    pub effective: NaiveDate,
+    pub journal: String,
+    pub template_code: String,
+    pub account_code: String,
+    pub entry_type: String,
+    pub layer: String,
+    pub direction: String,
+    pub currency: String,
+    pub units: Decimal,
+    pub btc_tx_id: Option<String>,
+    pub usd_tx_id: Option<String>,
+    pub meta: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StatementPage {
+    pub entries: Vec<StatementEntry>,
+    /// Set when the page was full and more entries may follow
+    pub next: Option<StatementCursor>,
+}
+
+impl<'a> Statements<'a> {
+    /// Balances of every account that had an entry at or before `at`
+    #[instrument(name = "ledger.statements.balances_at", skip(self), err)]
+    pub async fn balances_at(
+        &self,
+        at: DateTime<Utc>,
+    ) -> Result<Vec<AccountBalanceAt>, LedgerError> {
+        let balances = sqlx::query_as::<_, AccountBalanceAt>(
+            r#"WITH balances AS (
+                 SELECT DISTINCT ON (journal_id, account_id, currency) *
+                 FROM sqlx_ledger_balances
+                 WHERE modified_at <= $1
+                 ORDER BY journal_id, account_id, currency, version DESC
+               )
+               SELECT j.name AS journal, a.id AS account_id, a.code AS account_code, b.currency,
+                 CASE WHEN a.normal_balance_type = 'credit'
+                   THEN b.settled_cr_balance - b.settled_dr_balance
+                   ELSE b.settled_dr_balance - b.settled_cr_balance END AS settled,
+                 CASE WHEN a.normal_balance_type = 'credit'
+                   THEN b.pending_cr_balance - b.pending_dr_balance
+                   ELSE b.pending_dr_balance - b.pending_cr_balance END AS pending,
+                 CASE WHEN a.normal_balance_type = 'credit'
+                   THEN b.encumbered_cr_balance - b.encumbered_dr_balance
+                   ELSE b.encumbered_dr_balance - b.encumbered_cr_balance END AS encumbered
+               FROM balances b
+               JOIN LATERAL (
+                 SELECT id, code, normal_balance_type FROM sqlx_ledger_accounts
+                 WHERE id = b.account_id ORDER BY version DESC LIMIT 1
+               ) a ON TRUE
+               JOIN LATERAL (
+                 SELECT name FROM sqlx_ledger_journals
+                 WHERE id = b.journal_id ORDER BY version DESC LIMIT 1
+               ) j ON TRUE
+               ORDER BY j.name, a.code, b.currency"#,
+        )
+        .bind(at)
+        .fetch_all(self.pool)
+        .await?;
+        Ok(balances)
+    }
+
+    /// One page of the entries effective in `[from, until)`, with the template and
+    /// transaction meta they were posted with
+    #[instrument(name = "ledger.statements.entries", skip(self), fields(n_entries), err)]
+    pub async fn entries(&self, query: &StatementQuery) -> Result<StatementPage, LedgerError> {
+        if query.limit <= 0 {
+            return Err(LedgerError::InvalidPageSize(query.limit));
+        }
+        let entries = sqlx::query_as::<_, StatementEntry>(
+            r#"SELECT e.id AS entry_id, e.transaction_id, e.created_at, t.effective,
+                 j.name AS journal, tt.code AS template_code, a.code AS account_code,
+                 e.entry_type, e.layer::TEXT AS layer, e.direction::TEXT AS direction,
+                 e.currency, e.units,
+                 t.metadata->>'btc_tx_id' AS btc_tx_id, t.metadata->>'usd_tx_id' AS usd_tx_id,
+                 t.metadata AS meta
+               FROM sqlx_ledger_entries e
+               JOIN LATERAL (
+                 SELECT effective, tx_template_id, metadata FROM sqlx_ledger_transactions
+                 WHERE id = e.transaction_id ORDER BY version DESC LIMIT 1
+               ) t ON TRUE
+               JOIN LATERAL (
+                 SELECT code FROM sqlx_ledger_tx_templates
+                 WHERE id = t.tx_template_id ORDER BY version DESC LIMIT 1
+               ) tt ON TRUE
+               JOIN LATERAL (
+                 SELECT code FROM sqlx_ledger_accounts
+                 WHERE id = e.account_id ORDER BY version DESC LIMIT 1
+               ) a ON TRUE
+               JOIN LATERAL (
+                 SELECT name FROM sqlx_ledger_journals
+                 WHERE id = e.journal_id ORDER BY version DESC LIMIT 1
+               ) j ON TRUE
+               WHERE t.effective >= $1 AND t.effective < $2
+                 AND ($3::VARCHAR IS NULL OR a.code = $3)
+                 AND ($4::DATE IS NULL OR (t.effective, e.created_at, e.id) > ($4, $5, $6))
+               ORDER BY t.effective, e.created_at, e.id
+               LIMIT $7"#,
+        )
+        .bind(query.from)
+        .bind(query.until)
+        .bind(query.account_code.as_deref())
+        .bind(query.after.map(|cursor| cursor.effective))
+        .bind(query.after.map(|cursor| cursor.created_at))
+        .bind(query.after.map(|cursor| cursor.entry_id))
+        .bind(query.limit)
+        .fetch_all(self.pool)
+        .await?;
+        tracing::Span::current().record("n_entries", entries.len());
+
+        let next = if entries.len() as i64 >= query.limit {
+            entries.last().map(|entry| StatementCursor {
+                effective: entry.effective,
+                created_at: entry.created_at,
+                entry_id: entry.entry_id,
+            })
+        } else {
+            None
+        };
+        Ok(StatementPage { entries, next })
+    }
+}
@@ -0,0 +1,125 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx_ledger::{tx_template::*, SqlxLedger, SqlxLedgerError};
+use tracing::instrument;
+
+use crate::{constants::*, error::*};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdjustOkexUnrealizedPnlMeta {
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub timestamp: DateTime<Utc>,
+    pub instrument_id: String,
+    pub unrealized_pnl_btc: Decimal,
+}
+
+#[derive(Debug, Clone)]
+pub struct AdjustOkexUnrealizedPnlParams {
+    /// Change in mark-to-market pnl since the last adjustment, negative when it decreased
+    pub btc_amount: Decimal,
+    pub meta: AdjustOkexUnrealizedPnlMeta,
+}
+
+impl AdjustOkexUnrealizedPnlParams {
+    pub fn defs() -> Vec<ParamDefinition> {
+        vec![
+            ParamDefinition::builder()
+                .name("btc_amount")
+                .r#type(ParamDataType::DECIMAL)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("pnl_direction")
+                .r#type(ParamDataType::STRING)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("margin_direction")
+                .r#type(ParamDataType::STRING)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("meta")
+                .r#type(ParamDataType::JSON)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("effective")
+                .r#type(ParamDataType::DATE)
+                .build()
+                .unwrap(),
+        ]
+    }
+}
+
+impl From<AdjustOkexUnrealizedPnlParams> for TxParams {
+    fn from(
+        AdjustOkexUnrealizedPnlParams { btc_amount, meta }: AdjustOkexUnrealizedPnlParams,
+    ) -> Self {
+        let effective = meta.timestamp.naive_utc().date();
+        let meta = serde_json::to_value(meta).expect("Couldn't serialize meta");
+        let (pnl_direction, margin_direction) = if btc_amount >= Decimal::ZERO {
+            ("DEBIT", "CREDIT")
+        } else {
+            ("CREDIT", "DEBIT")
+        };
+        let mut params = Self::default();
+        params.insert("btc_amount", btc_amount.abs());
+        params.insert("pnl_direction", pnl_direction);
+        params.insert("margin_direction", margin_direction);
+        params.insert("meta", meta);
+        params.insert("effective", effective);
+        params
+    }
+}
+
+pub struct AdjustOkexUnrealizedPnl {}
+
+impl AdjustOkexUnrealizedPnl {
+    #[instrument(name = "ledger.adjust_okex_unrealized_pnl.init", skip_all)]
+    pub async fn init(ledger: &SqlxLedger) -> Result<(), LedgerError> {
+        let tx_input = TxInput::builder()
+            .journal_id(format!("uuid('{STABLESATS_JOURNAL_ID}')"))
+            .effective("params.effective")
+            .metadata("params.meta")
+            .description("'Adjust okex unrealized pnl'")
+            .build()
+            .expect("Couldn't build TxInput");
+
+        let entries = vec![
+            EntryInput::builder()
+                .entry_type("'ADJUST_OKEX_UNREALIZED_PNL_PNL'")
+                .currency("'BTC'")
+                .account_id(format!("uuid('{OKEX_UNREALIZED_PNL_ID}')"))
+                .direction("params.pnl_direction")
+                .layer("SETTLED")
+                .units("params.btc_amount")
+                .build()
+                .expect("Couldn't build ADJUST_OKEX_UNREALIZED_PNL_PNL entry"),
+            EntryInput::builder()
+                .entry_type("'ADJUST_OKEX_UNREALIZED_PNL_MARGIN'")
+                .currency("'BTC'")
+                .account_id(format!("uuid('{OKEX_BTC_MARGIN_ID}')"))
+                .direction("params.margin_direction")
+                .layer("SETTLED")
+                .units("params.btc_amount")
+                .build()
+                .expect("Couldn't build ADJUST_OKEX_UNREALIZED_PNL_MARGIN entry"),
+        ];
+
+        let params = AdjustOkexUnrealizedPnlParams::defs();
+        let template = NewTxTemplate::builder()
+            .id(ADJUST_OKEX_UNREALIZED_PNL_ID)
+            .code(ADJUST_OKEX_UNREALIZED_PNL_CODE)
+            .tx_input(tx_input)
+            .entries(entries)
+            .params(params)
+            .build()
+            .expect("Couldn't build ADJUST_OKEX_UNREALIZED_PNL_CODE");
+        match ledger.tx_templates().create(template).await {
+            Ok(_) | Err(SqlxLedgerError::DuplicateKey(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
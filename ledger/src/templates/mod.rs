@@ -1,19 +1,39 @@
 mod adjust_exchange_allocation;
+mod adjust_okex_unrealized_pnl;
 mod buy_usd_quote_accepted;
 mod decrease_exchange_position;
 mod increase_exchange_position;
+mod okex_deposit_initiated;
+mod okex_deposit_settled;
+mod okex_funding_payment;
+mod okex_internal_transfer;
+mod okex_realized_pnl;
+mod okex_trading_fee;
+mod okex_usdt_bill;
+mod okex_withdrawal;
 mod revert_user_buys_usd;
 mod revert_user_sells_usd;
 mod sell_usd_quote_accepted;
+mod transfer_quote_liability;
 mod user_buys_usd;
 mod user_sells_usd;
 
 pub use adjust_exchange_allocation::*;
+pub use adjust_okex_unrealized_pnl::*;
 pub use buy_usd_quote_accepted::*;
 pub use decrease_exchange_position::*;
 pub use increase_exchange_position::*;
+pub use okex_deposit_initiated::*;
+pub use okex_deposit_settled::*;
+pub use okex_funding_payment::*;
+pub use okex_internal_transfer::*;
+pub use okex_realized_pnl::*;
+pub use okex_trading_fee::*;
+pub use okex_usdt_bill::*;
+pub use okex_withdrawal::*;
 pub use revert_user_buys_usd::*;
 pub use revert_user_sells_usd::*;
 pub use sell_usd_quote_accepted::*;
+pub use transfer_quote_liability::*;
 pub use user_buys_usd::*;
 pub use user_sells_usd::*;
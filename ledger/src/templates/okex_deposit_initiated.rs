@@ -0,0 +1,107 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx_ledger::{tx_template::*, SqlxLedger, SqlxLedgerError};
+use tracing::instrument;
+
+use crate::{constants::*, error::*};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OkexDepositMeta {
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub timestamp: DateTime<Utc>,
+    pub client_transfer_id: String,
+    pub tx_id: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OkexDepositInitiatedParams {
+    pub btc_amount: Decimal,
+    pub meta: OkexDepositMeta,
+}
+
+impl OkexDepositInitiatedParams {
+    pub fn defs() -> Vec<ParamDefinition> {
+        vec![
+            ParamDefinition::builder()
+                .name("btc_amount")
+                .r#type(ParamDataType::DECIMAL)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("meta")
+                .r#type(ParamDataType::JSON)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("effective")
+                .r#type(ParamDataType::DATE)
+                .build()
+                .unwrap(),
+        ]
+    }
+}
+
+impl From<OkexDepositInitiatedParams> for TxParams {
+    fn from(OkexDepositInitiatedParams { btc_amount, meta }: OkexDepositInitiatedParams) -> Self {
+        let effective = meta.timestamp.naive_utc().date();
+        let meta = serde_json::to_value(meta).expect("Couldn't serialize meta");
+        let mut params = Self::default();
+        params.insert("btc_amount", btc_amount);
+        params.insert("meta", meta);
+        params.insert("effective", effective);
+        params
+    }
+}
+
+/// The btc has left the wallet but okex has yet to credit it,
+/// so it sits on the pending layer of the funding account.
+pub struct OkexDepositInitiated {}
+
+impl OkexDepositInitiated {
+    #[instrument(name = "ledger.okex_deposit_initiated.init", skip_all)]
+    pub async fn init(ledger: &SqlxLedger) -> Result<(), LedgerError> {
+        let tx_input = TxInput::builder()
+            .journal_id(format!("uuid('{STABLESATS_JOURNAL_ID}')"))
+            .effective("params.effective")
+            .metadata("params.meta")
+            .description("'Okex deposit initiated'")
+            .build()
+            .expect("Couldn't build TxInput");
+
+        let entries = vec![
+            EntryInput::builder()
+                .entry_type("'OKEX_DEPOSIT_INITIATED_WALLET_DR'")
+                .currency("'BTC'")
+                .account_id(format!("uuid('{STABLESATS_BTC_WALLET_ID}')"))
+                .direction("DEBIT")
+                .layer("SETTLED")
+                .units("params.btc_amount")
+                .build()
+                .expect("Couldn't build OKEX_DEPOSIT_INITIATED_WALLET_DR entry"),
+            EntryInput::builder()
+                .entry_type("'OKEX_DEPOSIT_INITIATED_FUNDING_CR'")
+                .currency("'BTC'")
+                .account_id(format!("uuid('{OKEX_BTC_FUNDING_ID}')"))
+                .direction("CREDIT")
+                .layer("PENDING")
+                .units("params.btc_amount")
+                .build()
+                .expect("Couldn't build OKEX_DEPOSIT_INITIATED_FUNDING_CR entry"),
+        ];
+
+        let params = OkexDepositInitiatedParams::defs();
+        let template = NewTxTemplate::builder()
+            .id(OKEX_DEPOSIT_INITIATED_ID)
+            .code(OKEX_DEPOSIT_INITIATED_CODE)
+            .tx_input(tx_input)
+            .entries(entries)
+            .params(params)
+            .build()
+            .expect("Couldn't build OKEX_DEPOSIT_INITIATED_CODE");
+        match ledger.tx_templates().create(template).await {
+            Ok(_) | Err(SqlxLedgerError::DuplicateKey(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use rust_decimal::Decimal;
+use sqlx_ledger::{tx_template::*, SqlxLedger, SqlxLedgerError};
+use tracing::instrument;
+
+use super::OkexDepositMeta;
+use crate::{constants::*, error::*};
+
+#[derive(Debug, Clone)]
+pub struct OkexDepositSettledParams {
+    pub btc_amount: Decimal,
+    pub meta: OkexDepositMeta,
+}
+
+impl OkexDepositSettledParams {
+    pub fn defs() -> Vec<ParamDefinition> {
+        vec![
+            ParamDefinition::builder()
+                .name("btc_amount")
+                .r#type(ParamDataType::DECIMAL)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("meta")
+                .r#type(ParamDataType::JSON)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("effective")
+                .r#type(ParamDataType::DATE)
+                .build()
+                .unwrap(),
+        ]
+    }
+}
+
+impl From<OkexDepositSettledParams> for TxParams {
+    fn from(OkexDepositSettledParams { btc_amount, meta }: OkexDepositSettledParams) -> Self {
+        let effective = meta.timestamp.naive_utc().date();
+        let meta = serde_json::to_value(meta).expect("Couldn't serialize meta");
+        let mut params = Self::default();
+        params.insert("btc_amount", btc_amount);
+        params.insert("meta", meta);
+        params.insert("effective", effective);
+        params
+    }
+}
+
+/// Moves an initiated deposit from the pending to the settled layer of the funding account
+pub struct OkexDepositSettled {}
+
+impl OkexDepositSettled {
+    #[instrument(name = "ledger.okex_deposit_settled.init", skip_all)]
+    pub async fn init(ledger: &SqlxLedger) -> Result<(), LedgerError> {
+        let tx_input = TxInput::builder()
+            .journal_id(format!("uuid('{STABLESATS_JOURNAL_ID}')"))
+            .effective("params.effective")
+            .metadata("params.meta")
+            .description("'Okex deposit settled'")
+            .build()
+            .expect("Couldn't build TxInput");
+
+        let entries = vec![
+            EntryInput::builder()
+                .entry_type("'OKEX_DEPOSIT_SETTLED_PENDING_DR'")
+                .currency("'BTC'")
+                .account_id(format!("uuid('{OKEX_BTC_FUNDING_ID}')"))
+                .direction("DEBIT")
+                .layer("PENDING")
+                .units("params.btc_amount")
+                .build()
+                .expect("Couldn't build OKEX_DEPOSIT_SETTLED_PENDING_DR entry"),
+            EntryInput::builder()
+                .entry_type("'OKEX_DEPOSIT_SETTLED_FUNDING_CR'")
+                .currency("'BTC'")
+                .account_id(format!("uuid('{OKEX_BTC_FUNDING_ID}')"))
+                .direction("CREDIT")
+                .layer("SETTLED")
+                .units("params.btc_amount")
+                .build()
+                .expect("Couldn't build OKEX_DEPOSIT_SETTLED_FUNDING_CR entry"),
+        ];
+
+        let params = OkexDepositSettledParams::defs();
+        let template = NewTxTemplate::builder()
+            .id(OKEX_DEPOSIT_SETTLED_ID)
+            .code(OKEX_DEPOSIT_SETTLED_CODE)
+            .tx_input(tx_input)
+            .entries(entries)
+            .params(params)
+            .build()
+            .expect("Couldn't build OKEX_DEPOSIT_SETTLED_CODE");
+        match ledger.tx_templates().create(template).await {
+            Ok(_) | Err(SqlxLedgerError::DuplicateKey(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx_ledger::{tx_template::*, SqlxLedger, SqlxLedgerError};
+use tracing::instrument;
+
+use crate::{constants::*, error::*};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OkexFundingPaymentMeta {
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub timestamp: DateTime<Utc>,
+    pub bill_id: String,
+    pub instrument_id: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct OkexFundingPaymentParams {
+    /// Signed change of the okex btc balance, negative when funding was paid
+    pub btc_balance_change: Decimal,
+    pub meta: OkexFundingPaymentMeta,
+}
+
+impl OkexFundingPaymentParams {
+    pub fn defs() -> Vec<ParamDefinition> {
+        vec![
+            ParamDefinition::builder()
+                .name("btc_amount")
+                .r#type(ParamDataType::DECIMAL)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("cost_direction")
+                .r#type(ParamDataType::STRING)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("margin_direction")
+                .r#type(ParamDataType::STRING)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("meta")
+                .r#type(ParamDataType::JSON)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("effective")
+                .r#type(ParamDataType::DATE)
+                .build()
+                .unwrap(),
+        ]
+    }
+}
+
+impl From<OkexFundingPaymentParams> for TxParams {
+    fn from(
+        OkexFundingPaymentParams {
+            btc_balance_change,
+            meta,
+        }: OkexFundingPaymentParams,
+    ) -> Self {
+        let effective = meta.timestamp.naive_utc().date();
+        let meta = serde_json::to_value(meta).expect("Couldn't serialize meta");
+        let (cost_direction, margin_direction) = if btc_balance_change < Decimal::ZERO {
+            ("CREDIT", "DEBIT")
+        } else {
+            ("DEBIT", "CREDIT")
+        };
+        let mut params = Self::default();
+        params.insert("btc_amount", btc_balance_change.abs());
+        params.insert("cost_direction", cost_direction);
+        params.insert("margin_direction", margin_direction);
+        params.insert("meta", meta);
+        params.insert("effective", effective);
+        params
+    }
+}
+
+pub struct OkexFundingPayment {}
+
+impl OkexFundingPayment {
+    #[instrument(name = "ledger.okex_funding_payment.init", skip_all)]
+    pub async fn init(ledger: &SqlxLedger) -> Result<(), LedgerError> {
+        let tx_input = TxInput::builder()
+            .journal_id(format!("uuid('{STABLESATS_JOURNAL_ID}')"))
+            .effective("params.effective")
+            .metadata("params.meta")
+            .description("'Okex funding payment'")
+            .build()
+            .expect("Couldn't build TxInput");
+
+        let entries = vec![
+            EntryInput::builder()
+                .entry_type("'OKEX_FUNDING_PAYMENT_COST'")
+                .currency("'BTC'")
+                .account_id(format!("uuid('{OKEX_FUNDING_PAYMENTS_ID}')"))
+                .direction("params.cost_direction")
+                .layer("SETTLED")
+                .units("params.btc_amount")
+                .build()
+                .expect("Couldn't build OKEX_FUNDING_PAYMENT_COST entry"),
+            EntryInput::builder()
+                .entry_type("'OKEX_FUNDING_PAYMENT_MARGIN'")
+                .currency("'BTC'")
+                .account_id(format!("uuid('{OKEX_BTC_MARGIN_ID}')"))
+                .direction("params.margin_direction")
+                .layer("SETTLED")
+                .units("params.btc_amount")
+                .build()
+                .expect("Couldn't build OKEX_FUNDING_PAYMENT_MARGIN entry"),
+        ];
+
+        let params = OkexFundingPaymentParams::defs();
+        let template = NewTxTemplate::builder()
+            .id(OKEX_FUNDING_PAYMENT_ID)
+            .code(OKEX_FUNDING_PAYMENT_CODE)
+            .tx_input(tx_input)
+            .entries(entries)
+            .params(params)
+            .build()
+            .expect("Couldn't build OKEX_FUNDING_PAYMENT_CODE");
+        match ledger.tx_templates().create(template).await {
+            Ok(_) | Err(SqlxLedgerError::DuplicateKey(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx_ledger::{tx_template::*, SqlxLedger, SqlxLedgerError};
+use tracing::instrument;
+
+use crate::{constants::*, error::*};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OkexInternalTransferMeta {
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub timestamp: DateTime<Utc>,
+    pub client_transfer_id: String,
+    pub transfer_id: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OkexInternalTransferParams {
+    /// Signed change of the okex funding btc balance, negative when btc moved to trading
+    pub funding_btc_change: Decimal,
+    pub meta: OkexInternalTransferMeta,
+}
+
+impl OkexInternalTransferParams {
+    pub fn defs() -> Vec<ParamDefinition> {
+        vec![
+            ParamDefinition::builder()
+                .name("btc_amount")
+                .r#type(ParamDataType::DECIMAL)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("funding_direction")
+                .r#type(ParamDataType::STRING)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("trading_direction")
+                .r#type(ParamDataType::STRING)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("meta")
+                .r#type(ParamDataType::JSON)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("effective")
+                .r#type(ParamDataType::DATE)
+                .build()
+                .unwrap(),
+        ]
+    }
+}
+
+impl From<OkexInternalTransferParams> for TxParams {
+    fn from(
+        OkexInternalTransferParams {
+            funding_btc_change,
+            meta,
+        }: OkexInternalTransferParams,
+    ) -> Self {
+        let effective = meta.timestamp.naive_utc().date();
+        let meta = serde_json::to_value(meta).expect("Couldn't serialize meta");
+        let (funding_direction, trading_direction) = if funding_btc_change >= Decimal::ZERO {
+            ("CREDIT", "DEBIT")
+        } else {
+            ("DEBIT", "CREDIT")
+        };
+        let mut params = Self::default();
+        params.insert("btc_amount", funding_btc_change.abs());
+        params.insert("funding_direction", funding_direction);
+        params.insert("trading_direction", trading_direction);
+        params.insert("meta", meta);
+        params.insert("effective", effective);
+        params
+    }
+}
+
+pub struct OkexInternalTransfer {}
+
+impl OkexInternalTransfer {
+    #[instrument(name = "ledger.okex_internal_transfer.init", skip_all)]
+    pub async fn init(ledger: &SqlxLedger) -> Result<(), LedgerError> {
+        let tx_input = TxInput::builder()
+            .journal_id(format!("uuid('{STABLESATS_JOURNAL_ID}')"))
+            .effective("params.effective")
+            .metadata("params.meta")
+            .description("'Okex transfer between trading and funding'")
+            .build()
+            .expect("Couldn't build TxInput");
+
+        let entries = vec![
+            EntryInput::builder()
+                .entry_type("'OKEX_INTERNAL_TRANSFER_FUNDING'")
+                .currency("'BTC'")
+                .account_id(format!("uuid('{OKEX_BTC_FUNDING_ID}')"))
+                .direction("params.funding_direction")
+                .layer("SETTLED")
+                .units("params.btc_amount")
+                .build()
+                .expect("Couldn't build OKEX_INTERNAL_TRANSFER_FUNDING entry"),
+            EntryInput::builder()
+                .entry_type("'OKEX_INTERNAL_TRANSFER_TRADING'")
+                .currency("'BTC'")
+                .account_id(format!("uuid('{OKEX_BTC_MARGIN_ID}')"))
+                .direction("params.trading_direction")
+                .layer("SETTLED")
+                .units("params.btc_amount")
+                .build()
+                .expect("Couldn't build OKEX_INTERNAL_TRANSFER_TRADING entry"),
+        ];
+
+        let params = OkexInternalTransferParams::defs();
+        let template = NewTxTemplate::builder()
+            .id(OKEX_INTERNAL_TRANSFER_ID)
+            .code(OKEX_INTERNAL_TRANSFER_CODE)
+            .tx_input(tx_input)
+            .entries(entries)
+            .params(params)
+            .build()
+            .expect("Couldn't build OKEX_INTERNAL_TRANSFER_CODE");
+        match ledger.tx_templates().create(template).await {
+            Ok(_) | Err(SqlxLedgerError::DuplicateKey(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx_ledger::{tx_template::*, SqlxLedger, SqlxLedgerError};
+use tracing::instrument;
+
+use crate::{constants::*, error::*};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OkexRealizedPnlMeta {
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub timestamp: DateTime<Utc>,
+    pub bill_id: String,
+    pub order_id: String,
+    pub instrument_id: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct OkexRealizedPnlParams {
+    /// Pnl of the closed contracts in btc, negative for a loss
+    pub btc_amount: Decimal,
+    pub meta: OkexRealizedPnlMeta,
+}
+
+impl OkexRealizedPnlParams {
+    pub fn defs() -> Vec<ParamDefinition> {
+        vec![
+            ParamDefinition::builder()
+                .name("btc_amount")
+                .r#type(ParamDataType::DECIMAL)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("pnl_direction")
+                .r#type(ParamDataType::STRING)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("margin_direction")
+                .r#type(ParamDataType::STRING)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("meta")
+                .r#type(ParamDataType::JSON)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("effective")
+                .r#type(ParamDataType::DATE)
+                .build()
+                .unwrap(),
+        ]
+    }
+}
+
+impl From<OkexRealizedPnlParams> for TxParams {
+    fn from(OkexRealizedPnlParams { btc_amount, meta }: OkexRealizedPnlParams) -> Self {
+        let effective = meta.timestamp.naive_utc().date();
+        let meta = serde_json::to_value(meta).expect("Couldn't serialize meta");
+        let (pnl_direction, margin_direction) = if btc_amount >= Decimal::ZERO {
+            ("DEBIT", "CREDIT")
+        } else {
+            ("CREDIT", "DEBIT")
+        };
+        let mut params = Self::default();
+        params.insert("btc_amount", btc_amount.abs());
+        params.insert("pnl_direction", pnl_direction);
+        params.insert("margin_direction", margin_direction);
+        params.insert("meta", meta);
+        params.insert("effective", effective);
+        params
+    }
+}
+
+pub struct OkexRealizedPnl {}
+
+impl OkexRealizedPnl {
+    #[instrument(name = "ledger.okex_realized_pnl.init", skip_all)]
+    pub async fn init(ledger: &SqlxLedger) -> Result<(), LedgerError> {
+        let tx_input = TxInput::builder()
+            .journal_id(format!("uuid('{STABLESATS_JOURNAL_ID}')"))
+            .effective("params.effective")
+            .metadata("params.meta")
+            .description("'Okex realized pnl'")
+            .build()
+            .expect("Couldn't build TxInput");
+
+        let entries = vec![
+            EntryInput::builder()
+                .entry_type("'OKEX_REALIZED_PNL_PNL'")
+                .currency("'BTC'")
+                .account_id(format!("uuid('{OKEX_REALIZED_PNL_ACCOUNT_ID}')"))
+                .direction("params.pnl_direction")
+                .layer("SETTLED")
+                .units("params.btc_amount")
+                .build()
+                .expect("Couldn't build OKEX_REALIZED_PNL_PNL entry"),
+            EntryInput::builder()
+                .entry_type("'OKEX_REALIZED_PNL_MARGIN'")
+                .currency("'BTC'")
+                .account_id(format!("uuid('{OKEX_BTC_MARGIN_ID}')"))
+                .direction("params.margin_direction")
+                .layer("SETTLED")
+                .units("params.btc_amount")
+                .build()
+                .expect("Couldn't build OKEX_REALIZED_PNL_MARGIN entry"),
+        ];
+
+        let params = OkexRealizedPnlParams::defs();
+        let template = NewTxTemplate::builder()
+            .id(OKEX_REALIZED_PNL_ID)
+            .code(OKEX_REALIZED_PNL_CODE)
+            .tx_input(tx_input)
+            .entries(entries)
+            .params(params)
+            .build()
+            .expect("Couldn't build OKEX_REALIZED_PNL_CODE");
+        match ledger.tx_templates().create(template).await {
+            Ok(_) | Err(SqlxLedgerError::DuplicateKey(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
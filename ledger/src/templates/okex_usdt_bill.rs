@@ -0,0 +1,157 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx_ledger::{tx_template::*, SqlxLedger, SqlxLedgerError};
+use tracing::instrument;
+
+use crate::{constants::*, error::*};
+
+/// What a bill of the usdt margined swap paid for, each kind is booked
+/// against the same account as its btc counterpart
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OkexUsdtBillKind {
+    TradingFee,
+    FundingPayment,
+    RealizedPnl,
+}
+
+impl OkexUsdtBillKind {
+    fn account_id(&self) -> uuid::Uuid {
+        match self {
+            Self::TradingFee => OKEX_TRADING_FEES_ID,
+            Self::FundingPayment => OKEX_FUNDING_PAYMENTS_ID,
+            Self::RealizedPnl => OKEX_REALIZED_PNL_ACCOUNT_ID,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OkexUsdtBillMeta {
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub timestamp: DateTime<Utc>,
+    pub kind: OkexUsdtBillKind,
+    pub bill_id: String,
+    pub order_id: String,
+    pub instrument_id: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct OkexUsdtBillParams {
+    /// Signed change of the okex usdt balance
+    pub usdt_balance_change: Decimal,
+    pub meta: OkexUsdtBillMeta,
+}
+
+impl OkexUsdtBillParams {
+    pub fn defs() -> Vec<ParamDefinition> {
+        vec![
+            ParamDefinition::builder()
+                .name("usdt_amount")
+                .r#type(ParamDataType::DECIMAL)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("counterpart_account_id")
+                .r#type(ParamDataType::UUID)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("counterpart_direction")
+                .r#type(ParamDataType::STRING)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("margin_direction")
+                .r#type(ParamDataType::STRING)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("meta")
+                .r#type(ParamDataType::JSON)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("effective")
+                .r#type(ParamDataType::DATE)
+                .build()
+                .unwrap(),
+        ]
+    }
+}
+
+impl From<OkexUsdtBillParams> for TxParams {
+    fn from(
+        OkexUsdtBillParams {
+            usdt_balance_change,
+            meta,
+        }: OkexUsdtBillParams,
+    ) -> Self {
+        let effective = meta.timestamp.naive_utc().date();
+        let counterpart_account_id = meta.kind.account_id();
+        let meta = serde_json::to_value(meta).expect("Couldn't serialize meta");
+        let (counterpart_direction, margin_direction) = if usdt_balance_change < Decimal::ZERO {
+            ("CREDIT", "DEBIT")
+        } else {
+            ("DEBIT", "CREDIT")
+        };
+        let mut params = Self::default();
+        params.insert("usdt_amount", usdt_balance_change.abs());
+        params.insert("counterpart_account_id", counterpart_account_id);
+        params.insert("counterpart_direction", counterpart_direction);
+        params.insert("margin_direction", margin_direction);
+        params.insert("meta", meta);
+        params.insert("effective", effective);
+        params
+    }
+}
+
+pub struct OkexUsdtBill {}
+
+impl OkexUsdtBill {
+    #[instrument(name = "ledger.okex_usdt_bill.init", skip_all)]
+    pub async fn init(ledger: &SqlxLedger) -> Result<(), LedgerError> {
+        let tx_input = TxInput::builder()
+            .journal_id(format!("uuid('{STABLESATS_JOURNAL_ID}')"))
+            .effective("params.effective")
+            .metadata("params.meta")
+            .description("'Okex usdt bill'")
+            .build()
+            .expect("Couldn't build TxInput");
+
+        let entries = vec![
+            EntryInput::builder()
+                .entry_type("'OKEX_USDT_BILL_COUNTERPART'")
+                .currency("'USDT'")
+                .account_id("params.counterpart_account_id")
+                .direction("params.counterpart_direction")
+                .layer("SETTLED")
+                .units("params.usdt_amount")
+                .build()
+                .expect("Couldn't build OKEX_USDT_BILL_COUNTERPART entry"),
+            EntryInput::builder()
+                .entry_type("'OKEX_USDT_BILL_MARGIN'")
+                .currency("'USDT'")
+                .account_id(format!("uuid('{OKEX_USDT_MARGIN_ID}')"))
+                .direction("params.margin_direction")
+                .layer("SETTLED")
+                .units("params.usdt_amount")
+                .build()
+                .expect("Couldn't build OKEX_USDT_BILL_MARGIN entry"),
+        ];
+
+        let params = OkexUsdtBillParams::defs();
+        let template = NewTxTemplate::builder()
+            .id(OKEX_USDT_BILL_ID)
+            .code(OKEX_USDT_BILL_CODE)
+            .tx_input(tx_input)
+            .entries(entries)
+            .params(params)
+            .build()
+            .expect("Couldn't build OKEX_USDT_BILL_CODE");
+        match ledger.tx_templates().create(template).await {
+            Ok(_) | Err(SqlxLedgerError::DuplicateKey(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
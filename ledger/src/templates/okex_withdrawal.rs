@@ -0,0 +1,128 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx_ledger::{tx_template::*, SqlxLedger, SqlxLedgerError};
+use tracing::instrument;
+
+use crate::{constants::*, error::*};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OkexWithdrawalMeta {
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub timestamp: DateTime<Utc>,
+    pub client_transfer_id: String,
+    pub transfer_id: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OkexWithdrawalParams {
+    /// Amount arriving in the wallet, the fee is taken from the funding account on top of it
+    pub btc_amount: Decimal,
+    pub btc_fee: Decimal,
+    pub meta: OkexWithdrawalMeta,
+}
+
+impl OkexWithdrawalParams {
+    pub fn defs() -> Vec<ParamDefinition> {
+        vec![
+            ParamDefinition::builder()
+                .name("btc_amount")
+                .r#type(ParamDataType::DECIMAL)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("btc_fee")
+                .r#type(ParamDataType::DECIMAL)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("meta")
+                .r#type(ParamDataType::JSON)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("effective")
+                .r#type(ParamDataType::DATE)
+                .build()
+                .unwrap(),
+        ]
+    }
+}
+
+impl From<OkexWithdrawalParams> for TxParams {
+    fn from(
+        OkexWithdrawalParams {
+            btc_amount,
+            btc_fee,
+            meta,
+        }: OkexWithdrawalParams,
+    ) -> Self {
+        let effective = meta.timestamp.naive_utc().date();
+        let meta = serde_json::to_value(meta).expect("Couldn't serialize meta");
+        let mut params = Self::default();
+        params.insert("btc_amount", btc_amount);
+        params.insert("btc_fee", btc_fee);
+        params.insert("meta", meta);
+        params.insert("effective", effective);
+        params
+    }
+}
+
+pub struct OkexWithdrawal {}
+
+impl OkexWithdrawal {
+    #[instrument(name = "ledger.okex_withdrawal.init", skip_all)]
+    pub async fn init(ledger: &SqlxLedger) -> Result<(), LedgerError> {
+        let tx_input = TxInput::builder()
+            .journal_id(format!("uuid('{STABLESATS_JOURNAL_ID}')"))
+            .effective("params.effective")
+            .metadata("params.meta")
+            .description("'Okex withdrawal'")
+            .build()
+            .expect("Couldn't build TxInput");
+
+        let entries = vec![
+            EntryInput::builder()
+                .entry_type("'OKEX_WITHDRAWAL_FUNDING_DR'")
+                .currency("'BTC'")
+                .account_id(format!("uuid('{OKEX_BTC_FUNDING_ID}')"))
+                .direction("DEBIT")
+                .layer("SETTLED")
+                .units("params.btc_amount + params.btc_fee")
+                .build()
+                .expect("Couldn't build OKEX_WITHDRAWAL_FUNDING_DR entry"),
+            EntryInput::builder()
+                .entry_type("'OKEX_WITHDRAWAL_WALLET_CR'")
+                .currency("'BTC'")
+                .account_id(format!("uuid('{STABLESATS_BTC_WALLET_ID}')"))
+                .direction("CREDIT")
+                .layer("SETTLED")
+                .units("params.btc_amount")
+                .build()
+                .expect("Couldn't build OKEX_WITHDRAWAL_WALLET_CR entry"),
+            EntryInput::builder()
+                .entry_type("'OKEX_WITHDRAWAL_FEE_CR'")
+                .currency("'BTC'")
+                .account_id(format!("uuid('{OKEX_WITHDRAWAL_FEES_ID}')"))
+                .direction("CREDIT")
+                .layer("SETTLED")
+                .units("params.btc_fee")
+                .build()
+                .expect("Couldn't build OKEX_WITHDRAWAL_FEE_CR entry"),
+        ];
+
+        let params = OkexWithdrawalParams::defs();
+        let template = NewTxTemplate::builder()
+            .id(OKEX_WITHDRAWAL_ID)
+            .code(OKEX_WITHDRAWAL_CODE)
+            .tx_input(tx_input)
+            .entries(entries)
+            .params(params)
+            .build()
+            .expect("Couldn't build OKEX_WITHDRAWAL_CODE");
+        match ledger.tx_templates().create(template).await {
+            Ok(_) | Err(SqlxLedgerError::DuplicateKey(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx_ledger::{tx_template::*, SqlxLedger, SqlxLedgerError};
+use tracing::instrument;
+
+use crate::{constants::*, error::*};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferQuoteLiabilityMeta {
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TransferQuoteLiabilityParams {
+    pub usd_cents_amount: Decimal,
+    pub meta: TransferQuoteLiabilityMeta,
+}
+
+impl TransferQuoteLiabilityParams {
+    pub fn defs() -> Vec<ParamDefinition> {
+        vec![
+            ParamDefinition::builder()
+                .name("usd_amount")
+                .r#type(ParamDataType::DECIMAL)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("liability_direction")
+                .r#type(ParamDataType::STRING)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("quotes_direction")
+                .r#type(ParamDataType::STRING)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("meta")
+                .r#type(ParamDataType::JSON)
+                .build()
+                .unwrap(),
+            ParamDefinition::builder()
+                .name("effective")
+                .r#type(ParamDataType::DATE)
+                .build()
+                .unwrap(),
+        ]
+    }
+}
+
+impl From<TransferQuoteLiabilityParams> for TxParams {
+    fn from(
+        TransferQuoteLiabilityParams {
+            usd_cents_amount,
+            meta,
+        }: TransferQuoteLiabilityParams,
+    ) -> Self {
+        let effective = meta.timestamp.naive_utc().date();
+        let meta = serde_json::to_value(meta).expect("Couldn't serialize meta");
+        let (liability_direction, quotes_direction) = if usd_cents_amount >= Decimal::ZERO {
+            ("CREDIT", "DEBIT")
+        } else {
+            ("DEBIT", "CREDIT")
+        };
+        let mut params = Self::default();
+        params.insert("usd_amount", (usd_cents_amount / CENTS_PER_USD).abs());
+        params.insert("liability_direction", liability_direction);
+        params.insert("quotes_direction", quotes_direction);
+        params.insert("meta", meta);
+        params.insert("effective", effective);
+        params
+    }
+}
+
+pub struct TransferQuoteLiability {}
+
+impl TransferQuoteLiability {
+    #[instrument(name = "ledger.transfer_quote_liability.init", skip_all)]
+    pub async fn init(ledger: &SqlxLedger) -> Result<(), LedgerError> {
+        let tx_input = TxInput::builder()
+            .journal_id(format!("uuid('{STABLESATS_JOURNAL_ID}')"))
+            .effective("params.effective")
+            .metadata("params.meta")
+            .description("'Transfer quote liability for hedging'")
+            .build()
+            .expect("Couldn't build TxInput");
+
+        let entries = vec![
+            EntryInput::builder()
+                .entry_type("'TRANSFER_QUOTE_LIABILITY_QUOTES_LIABILITY'")
+                .currency("'USD'")
+                .account_id(format!("uuid('{QUOTES_LIABILITIES_ID}')"))
+                .direction("params.quotes_direction")
+                .layer("SETTLED")
+                .units("params.usd_amount")
+                .build()
+                .expect("Couldn't build TRANSFER_QUOTE_LIABILITY_QUOTES_LIABILITY entry"),
+            EntryInput::builder()
+                .entry_type("'TRANSFER_QUOTE_LIABILITY_STABLESATS_LIABILITY'")
+                .currency("'USD'")
+                .account_id(format!("uuid('{STABLESATS_LIABILITY_ID}')"))
+                .direction("params.liability_direction")
+                .layer("SETTLED")
+                .units("params.usd_amount")
+                .build()
+                .expect("Couldn't build TRANSFER_QUOTE_LIABILITY_STABLESATS_LIABILITY entry"),
+            EntryInput::builder()
+                .entry_type("'TRANSFER_QUOTE_LIABILITY_QUOTES_OMNIBUS'")
+                .currency("'USD'")
+                .account_id(format!("uuid('{QUOTES_OMNIBUS_ID}')"))
+                .direction("params.liability_direction")
+                .layer("SETTLED")
+                .units("params.usd_amount")
+                .build()
+                .expect("Couldn't build TRANSFER_QUOTE_LIABILITY_QUOTES_OMNIBUS entry"),
+            EntryInput::builder()
+                .entry_type("'TRANSFER_QUOTE_LIABILITY_STABLESATS_OMNIBUS'")
+                .currency("'USD'")
+                .account_id(format!("uuid('{STABLESATS_OMNIBUS_ID}')"))
+                .direction("params.quotes_direction")
+                .layer("SETTLED")
+                .units("params.usd_amount")
+                .build()
+                .expect("Couldn't build TRANSFER_QUOTE_LIABILITY_STABLESATS_OMNIBUS entry"),
+        ];
+
+        let params = TransferQuoteLiabilityParams::defs();
+        let template = NewTxTemplate::builder()
+            .id(TRANSFER_QUOTE_LIABILITY_ID)
+            .code(TRANSFER_QUOTE_LIABILITY_CODE)
+            .tx_input(tx_input)
+            .entries(entries)
+            .params(params)
+            .build()
+            .expect("Couldn't build TRANSFER_QUOTE_LIABILITY_CODE");
+        match ledger.tx_templates().create(template).await {
+            Ok(_) | Err(SqlxLedgerError::DuplicateKey(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
@@ -230,3 +230,518 @@ async fn exchange_allocation() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+#[serial]
+#[file_serial]
+async fn quotes_included_in_liability_allocation() -> anyhow::Result<()> {
+    let pool = init_pool().await?;
+    let ledger = Ledger::init(&pool).await?;
+    let initial_liabilities = ledger.balances().usd_liability_balances().await?;
+
+    ledger
+        .buy_usd_quote_accepted(
+            pool.begin().await?,
+            LedgerTxId::new(),
+            BuyUsdQuoteAcceptedParams {
+                satoshi_amount: dec!(1000000),
+                usd_cents_amount: dec!(500),
+                meta: BuyUsdQuoteAcceptedMeta {
+                    timestamp: chrono::Utc::now(),
+                },
+            },
+        )
+        .await?;
+    let after_buy = ledger.balances().usd_liability_balances().await?;
+    assert_eq!(
+        after_buy.unallocated_usd - initial_liabilities.unallocated_usd,
+        dec!(5)
+    );
+    assert_eq!(
+        Decimal::from(after_buy.total_liability)
+            - Decimal::from(initial_liabilities.total_liability),
+        dec!(500)
+    );
+
+    ledger
+        .adjust_exchange_allocation(
+            pool.begin().await?,
+            AdjustExchangeAllocationParams {
+                okex_allocation_adjustment_usd_cents_amount: dec!(500),
+                bitfinex_allocation_adjustment_usd_cents_amount: dec!(0),
+                meta: AdjustExchangeAllocationMeta {
+                    timestamp: chrono::Utc::now(),
+                },
+            },
+        )
+        .await?;
+    let after_allocation = ledger.balances().usd_liability_balances().await?;
+    assert_eq!(
+        after_allocation.unallocated_usd,
+        initial_liabilities.unallocated_usd
+    );
+    assert_eq!(
+        Decimal::from(after_allocation.okex_allocation)
+            - Decimal::from(initial_liabilities.okex_allocation),
+        dec!(500)
+    );
+
+    ledger
+        .sell_usd_quote_accepted(
+            pool.begin().await?,
+            LedgerTxId::new(),
+            SellUsdQuoteAcceptedParams {
+                satoshi_amount: dec!(1000000),
+                usd_cents_amount: dec!(500),
+                meta: SellUsdQuoteAcceptedMeta {
+                    timestamp: chrono::Utc::now(),
+                },
+            },
+        )
+        .await?;
+    ledger
+        .adjust_exchange_allocation(
+            pool.begin().await?,
+            AdjustExchangeAllocationParams {
+                okex_allocation_adjustment_usd_cents_amount: dec!(-500),
+                bitfinex_allocation_adjustment_usd_cents_amount: dec!(0),
+                meta: AdjustExchangeAllocationMeta {
+                    timestamp: chrono::Utc::now(),
+                },
+            },
+        )
+        .await?;
+    let final_liabilities = ledger.balances().usd_liability_balances().await?;
+    assert_eq!(initial_liabilities, final_liabilities);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+#[file_serial]
+async fn okex_funding_payments_and_trading_fees() -> anyhow::Result<()> {
+    let pool = init_pool().await?;
+    let ledger = Ledger::init(&pool).await?;
+    let initial_funding = ledger.balances().okex_funding_payments_btc().await?;
+    let initial_fees = ledger.balances().okex_trading_fees_btc().await?;
+
+    ledger
+        .okex_funding_payment(
+            pool.begin().await?,
+            LedgerTxId::new(),
+            OkexFundingPaymentParams {
+                btc_balance_change: dec!(-0.0002),
+                meta: OkexFundingPaymentMeta {
+                    timestamp: chrono::Utc::now(),
+                    bill_id: "1".to_string(),
+                    instrument_id: "BTC-USD-SWAP".to_string(),
+                },
+            },
+        )
+        .await?;
+    ledger
+        .okex_funding_payment(
+            pool.begin().await?,
+            LedgerTxId::new(),
+            OkexFundingPaymentParams {
+                btc_balance_change: dec!(0.00005),
+                meta: OkexFundingPaymentMeta {
+                    timestamp: chrono::Utc::now(),
+                    bill_id: "2".to_string(),
+                    instrument_id: "BTC-USD-SWAP".to_string(),
+                },
+            },
+        )
+        .await?;
+    let funding = ledger.balances().okex_funding_payments_btc().await?;
+    assert_eq!(funding - initial_funding, dec!(0.00015));
+
+    ledger
+        .okex_trading_fee(
+            pool.begin().await?,
+            LedgerTxId::new(),
+            OkexTradingFeeParams {
+                btc_balance_change: dec!(-0.00001),
+                meta: OkexTradingFeeMeta {
+                    timestamp: chrono::Utc::now(),
+                    bill_id: "3".to_string(),
+                    order_id: "4".to_string(),
+                    instrument_id: "BTC-USD-SWAP".to_string(),
+                },
+            },
+        )
+        .await?;
+    let fees = ledger.balances().okex_trading_fees_btc().await?;
+    assert_eq!(fees - initial_fees, dec!(0.00001));
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+#[file_serial]
+async fn okex_realized_and_unrealized_pnl() -> anyhow::Result<()> {
+    let pool = init_pool().await?;
+    let ledger = Ledger::init(&pool).await?;
+    let initial_realized = ledger.balances().okex_realized_pnl_btc().await?;
+    let initial_unrealized = ledger.balances().okex_unrealized_pnl_btc().await?;
+    let initial_margin = ledger.balances().okex_btc_margin().await?;
+
+    ledger
+        .okex_realized_pnl(
+            pool.begin().await?,
+            LedgerTxId::new(),
+            OkexRealizedPnlParams {
+                btc_amount: dec!(0.0003),
+                meta: OkexRealizedPnlMeta {
+                    timestamp: chrono::Utc::now(),
+                    bill_id: "5".to_string(),
+                    order_id: "6".to_string(),
+                    instrument_id: "BTC-USD-SWAP".to_string(),
+                },
+            },
+        )
+        .await?;
+    ledger
+        .okex_realized_pnl(
+            pool.begin().await?,
+            LedgerTxId::new(),
+            OkexRealizedPnlParams {
+                btc_amount: dec!(-0.0001),
+                meta: OkexRealizedPnlMeta {
+                    timestamp: chrono::Utc::now(),
+                    bill_id: "7".to_string(),
+                    order_id: "8".to_string(),
+                    instrument_id: "BTC-USD-SWAP".to_string(),
+                },
+            },
+        )
+        .await?;
+    let realized = ledger.balances().okex_realized_pnl_btc().await?;
+    assert_eq!(realized - initial_realized, dec!(0.0002));
+
+    ledger
+        .adjust_okex_unrealized_pnl(
+            pool.begin().await?,
+            dec!(0.0005),
+            "BTC-USD-SWAP".to_string(),
+        )
+        .await?;
+    assert_eq!(
+        ledger.balances().okex_unrealized_pnl_btc().await?,
+        dec!(0.0005)
+    );
+    ledger
+        .adjust_okex_unrealized_pnl(
+            pool.begin().await?,
+            dec!(-0.0002),
+            "BTC-USD-SWAP".to_string(),
+        )
+        .await?;
+    assert_eq!(
+        ledger.balances().okex_unrealized_pnl_btc().await?,
+        dec!(-0.0002)
+    );
+    ledger
+        .adjust_okex_unrealized_pnl(pool.begin().await?, dec!(0), "BTC-USD-SWAP".to_string())
+        .await?;
+    assert_eq!(ledger.balances().okex_unrealized_pnl_btc().await?, dec!(0));
+
+    let margin = ledger.balances().okex_btc_margin().await?;
+    assert_eq!(margin - initial_margin, dec!(0.0002) - initial_unrealized);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+#[file_serial]
+async fn okex_usdt_bills_are_booked_in_usdt() -> anyhow::Result<()> {
+    let pool = init_pool().await?;
+    let ledger = Ledger::init(&pool).await?;
+    let initial_margin = ledger.balances().okex_usdt_margin().await?;
+    let initial_btc_fees = ledger.balances().okex_trading_fees_btc().await?;
+    let meta = |kind| OkexUsdtBillMeta {
+        timestamp: chrono::Utc::now(),
+        kind,
+        bill_id: "5".to_string(),
+        order_id: "6".to_string(),
+        instrument_id: "BTC-USDT-SWAP".to_string(),
+    };
+
+    ledger
+        .okex_usdt_bill(
+            pool.begin().await?,
+            LedgerTxId::new(),
+            OkexUsdtBillParams {
+                usdt_balance_change: dec!(-1.5),
+                meta: meta(OkexUsdtBillKind::TradingFee),
+            },
+        )
+        .await?;
+    ledger
+        .okex_usdt_bill(
+            pool.begin().await?,
+            LedgerTxId::new(),
+            OkexUsdtBillParams {
+                usdt_balance_change: dec!(4),
+                meta: meta(OkexUsdtBillKind::RealizedPnl),
+            },
+        )
+        .await?;
+
+    let margin = ledger.balances().okex_usdt_margin().await?;
+    assert_eq!(margin - initial_margin, dec!(2.5));
+    assert_eq!(
+        ledger.balances().okex_trading_fees_btc().await?,
+        initial_btc_fees
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+#[file_serial]
+async fn okex_transfers_move_btc_between_wallet_funding_and_trading() -> anyhow::Result<()> {
+    let pool = init_pool().await?;
+    let ledger = Ledger::init(&pool).await?;
+    let initial_wallet = ledger.balances().stablesats_btc_assets().await?;
+    let initial_funding = ledger.balances().okex_btc_funding().await?;
+    let initial_in_flight = ledger.balances().okex_btc_deposits_in_flight().await?;
+    let initial_margin = ledger.balances().okex_btc_margin().await?;
+    let initial_fees = ledger.balances().okex_withdrawal_fees_btc().await?;
+
+    let deposit_meta = OkexDepositMeta {
+        timestamp: chrono::Utc::now(),
+        client_transfer_id: "deposit".to_string(),
+        tx_id: Some("txid".to_string()),
+    };
+    ledger
+        .okex_deposit_initiated(
+            pool.begin().await?,
+            LedgerTxId::new(),
+            OkexDepositInitiatedParams {
+                btc_amount: dec!(0.5),
+                meta: deposit_meta.clone(),
+            },
+        )
+        .await?;
+    assert_eq!(
+        ledger.balances().stablesats_btc_assets().await? - initial_wallet,
+        dec!(-0.5)
+    );
+    assert_eq!(
+        ledger.balances().okex_btc_deposits_in_flight().await? - initial_in_flight,
+        dec!(0.5)
+    );
+    assert_eq!(ledger.balances().okex_btc_funding().await?, initial_funding);
+
+    ledger
+        .okex_deposit_settled(
+            pool.begin().await?,
+            LedgerTxId::new(),
+            OkexDepositSettledParams {
+                btc_amount: dec!(0.5),
+                meta: deposit_meta,
+            },
+        )
+        .await?;
+    assert_eq!(
+        ledger.balances().okex_btc_deposits_in_flight().await?,
+        initial_in_flight
+    );
+    assert_eq!(
+        ledger.balances().okex_btc_funding().await? - initial_funding,
+        dec!(0.5)
+    );
+
+    ledger
+        .okex_internal_transfer(
+            pool.begin().await?,
+            LedgerTxId::new(),
+            OkexInternalTransferParams {
+                funding_btc_change: dec!(-0.3),
+                meta: OkexInternalTransferMeta {
+                    timestamp: chrono::Utc::now(),
+                    client_transfer_id: "funding-to-trading".to_string(),
+                    transfer_id: None,
+                },
+            },
+        )
+        .await?;
+    assert_eq!(
+        ledger.balances().okex_btc_margin().await? - initial_margin,
+        dec!(0.3)
+    );
+
+    ledger
+        .okex_withdrawal(
+            pool.begin().await?,
+            LedgerTxId::new(),
+            OkexWithdrawalParams {
+                btc_amount: dec!(0.1),
+                btc_fee: dec!(0.0002),
+                meta: OkexWithdrawalMeta {
+                    timestamp: chrono::Utc::now(),
+                    client_transfer_id: "withdraw".to_string(),
+                    transfer_id: Some("1".to_string()),
+                },
+            },
+        )
+        .await?;
+    assert_eq!(
+        ledger.balances().okex_btc_funding().await? - initial_funding,
+        dec!(0.0998)
+    );
+    assert_eq!(
+        ledger.balances().stablesats_btc_assets().await? - initial_wallet,
+        dec!(-0.4)
+    );
+    assert_eq!(
+        ledger.balances().okex_withdrawal_fees_btc().await? - initial_fees,
+        dec!(0.0002)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+#[file_serial]
+async fn statements_show_balances_at_a_point_in_time_and_entries() -> anyhow::Result<()> {
+    let pool = init_pool().await?;
+    let ledger = Ledger::init(&pool).await?;
+    let wallet_at = |balances: Vec<AccountBalanceAt>| {
+        balances
+            .into_iter()
+            .find(|b| b.account_code == "STABLESATS_BTC_WALLET" && b.currency == "BTC")
+            .map(|b| b.settled)
+            .unwrap_or(Decimal::ZERO)
+    };
+
+    let before = chrono::Utc::now();
+    let wallet_before = wallet_at(ledger.statements().balances_at(before).await?);
+    let btc_tx_id = uuid::Uuid::new_v4().to_string();
+    ledger
+        .user_buys_usd(
+            pool.begin().await?,
+            LedgerTxId::new(),
+            UserBuysUsdParams {
+                satoshi_amount: dec!(1000000),
+                usd_cents_amount: dec!(500),
+                meta: UserBuysUsdMeta {
+                    timestamp: chrono::Utc::now(),
+                    btc_tx_id: btc_tx_id.clone(),
+                    usd_tx_id: "usd_tx_id".to_string(),
+                },
+            },
+        )
+        .await?;
+    let after = chrono::Utc::now();
+
+    assert_eq!(
+        wallet_at(ledger.statements().balances_at(before).await?),
+        wallet_before
+    );
+    assert_eq!(
+        wallet_at(ledger.statements().balances_at(after).await?),
+        ledger.balances().stablesats_btc_assets().await?
+    );
+    assert_eq!(
+        wallet_at(ledger.statements().balances_at(after).await?) - wallet_before,
+        dec!(0.01)
+    );
+
+    let back_dated_btc_tx_id = uuid::Uuid::new_v4().to_string();
+    let back_dated = chrono::Utc::now() - chrono::Duration::days(10);
+    ledger
+        .user_buys_usd(
+            pool.begin().await?,
+            LedgerTxId::new(),
+            UserBuysUsdParams {
+                satoshi_amount: dec!(1000000),
+                usd_cents_amount: dec!(500),
+                meta: UserBuysUsdMeta {
+                    timestamp: back_dated,
+                    btc_tx_id: back_dated_btc_tx_id.clone(),
+                    usd_tx_id: "usd_tx_id".to_string(),
+                },
+            },
+        )
+        .await?;
+
+    let reader = LedgerReader::new(&pool);
+    let today = after.date_naive();
+    let mut query = StatementQuery {
+        from: today,
+        until: today + chrono::Duration::days(1),
+        account_code: None,
+        after: None,
+        limit: 1,
+    };
+    let mut entries = Vec::new();
+    loop {
+        let page = reader.statements().entries(&query).await?;
+        entries.extend(page.entries);
+        match page.next {
+            Some(next) => query.after = Some(next),
+            None => break,
+        }
+    }
+    let posted: Vec<_> = entries
+        .iter()
+        .filter(|e| e.btc_tx_id.as_deref() == Some(btc_tx_id.as_str()))
+        .collect();
+    assert_eq!(posted.len(), 4);
+    assert!(
+        posted
+            .iter()
+            .all(|e| e.template_code == "USER_BUYS_USD"
+                && e.usd_tx_id.as_deref() == Some("usd_tx_id"))
+    );
+    let wallet_entry = posted
+        .iter()
+        .find(|e| e.account_code == "STABLESATS_BTC_WALLET")
+        .context("No wallet entry")?;
+    assert_eq!(wallet_entry.units, dec!(0.01));
+    assert_eq!(wallet_entry.direction, "credit");
+    assert!(!entries
+        .iter()
+        .any(|e| e.btc_tx_id.as_deref() == Some(back_dated_btc_tx_id.as_str())));
+
+    let back_dated_query = StatementQuery {
+        from: back_dated.date_naive(),
+        until: back_dated.date_naive() + chrono::Duration::days(1),
+        account_code: None,
+        after: None,
+        limit: 100,
+    };
+    let page = reader.statements().entries(&back_dated_query).await?;
+    assert_eq!(
+        page.entries
+            .iter()
+            .filter(|e| e.btc_tx_id.as_deref() == Some(back_dated_btc_tx_id.as_str()))
+            .count(),
+        4
+    );
+
+    query.account_code = Some("STABLESATS_BTC_WALLET".to_string());
+    query.after = None;
+    query.limit = 100;
+    let page = reader.statements().entries(&query).await?;
+    assert!(page
+        .entries
+        .iter()
+        .all(|e| e.account_code == "STABLESATS_BTC_WALLET"));
+    assert!(page.next.is_none());
+
+    query.limit = 0;
+    assert!(matches!(
+        reader.statements().entries(&query).await,
+        Err(LedgerError::InvalidPageSize(0))
+    ));
+
+    Ok(())
+}
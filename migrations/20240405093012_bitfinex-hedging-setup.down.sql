@@ -0,0 +1,2 @@
+DROP TABLE bitfinex_transfers;
+DROP TABLE bitfinex_orders;
@@ -0,0 +1,7 @@
+DELETE FROM okex_transfers WHERE shadow = true;
+ALTER TABLE okex_transfers DROP CONSTRAINT okex_transfers_state_check;
+ALTER TABLE okex_transfers ADD CONSTRAINT okex_transfers_state_check CHECK (state in ('success', 'pending', 'failed', 'deleted'));
+ALTER TABLE okex_transfers DROP COLUMN shadow;
+
+DELETE FROM okex_orders WHERE shadow = true;
+ALTER TABLE okex_orders DROP COLUMN shadow;
@@ -0,0 +1,5 @@
+ALTER TABLE okex_orders ADD COLUMN shadow BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE okex_transfers ADD COLUMN shadow BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE okex_transfers DROP CONSTRAINT okex_transfers_state_check;
+ALTER TABLE okex_transfers ADD CONSTRAINT okex_transfers_state_check CHECK (state in ('success', 'pending', 'failed', 'deleted', 'shadow'));
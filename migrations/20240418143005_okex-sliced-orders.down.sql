@@ -0,0 +1,3 @@
+DROP INDEX idx_okex_orders_parent_client_order_id;
+ALTER TABLE okex_orders DROP COLUMN parent_client_order_id;
+ALTER TABLE okex_orders DROP COLUMN is_parent;
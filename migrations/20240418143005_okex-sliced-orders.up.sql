@@ -0,0 +1,3 @@
+ALTER TABLE okex_orders ADD COLUMN is_parent BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE okex_orders ADD COLUMN parent_client_order_id VARCHAR(32) REFERENCES okex_orders(client_order_id);
+CREATE INDEX idx_okex_orders_parent_client_order_id ON okex_orders (parent_client_order_id);
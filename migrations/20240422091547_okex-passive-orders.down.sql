@@ -0,0 +1,4 @@
+ALTER TABLE okex_orders DROP COLUMN replaces_client_order_id;
+ALTER TABLE okex_orders DROP COLUMN filled_size;
+ALTER TABLE okex_orders DROP COLUMN limit_price;
+ALTER TABLE okex_orders DROP COLUMN order_type;
@@ -0,0 +1,4 @@
+ALTER TABLE okex_orders ADD COLUMN order_type VARCHAR(16) NOT NULL DEFAULT 'market';
+ALTER TABLE okex_orders ADD COLUMN limit_price NUMERIC;
+ALTER TABLE okex_orders ADD COLUMN filled_size NUMERIC;
+ALTER TABLE okex_orders ADD COLUMN replaces_client_order_id VARCHAR(32) UNIQUE REFERENCES okex_orders(client_order_id);
@@ -0,0 +1 @@
+DROP TABLE okex_bills;
@@ -0,0 +1,10 @@
+CREATE TABLE okex_bills (
+  bill_id VARCHAR(32) PRIMARY KEY,
+  bill_type VARCHAR(20) NOT NULL CHECK (bill_type in ('trading-fee', 'funding-payment')),
+  instrument VARCHAR(32) NOT NULL,
+  order_id VARCHAR(32),
+  btc_amount NUMERIC NOT NULL,
+  ledger_tx_id UUID NOT NULL,
+  billed_at TIMESTAMP WITH TIME ZONE NOT NULL,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
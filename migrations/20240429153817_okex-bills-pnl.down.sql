@@ -0,0 +1,7 @@
+ALTER TABLE okex_bills DROP COLUMN pnl_ledger_tx_id;
+ALTER TABLE okex_bills DROP COLUMN realized_pnl_btc;
+DELETE FROM okex_bills WHERE ledger_tx_id IS NULL;
+ALTER TABLE okex_bills ALTER COLUMN ledger_tx_id SET NOT NULL;
+ALTER TABLE okex_bills DROP CONSTRAINT okex_bills_bill_type_check;
+UPDATE okex_bills SET bill_type = 'trading-fee' WHERE bill_type = 'trade';
+ALTER TABLE okex_bills ADD CONSTRAINT okex_bills_bill_type_check CHECK (bill_type in ('trading-fee', 'funding-payment'));
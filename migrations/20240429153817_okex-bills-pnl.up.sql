@@ -0,0 +1,6 @@
+ALTER TABLE okex_bills DROP CONSTRAINT okex_bills_bill_type_check;
+UPDATE okex_bills SET bill_type = 'trade' WHERE bill_type = 'trading-fee';
+ALTER TABLE okex_bills ADD CONSTRAINT okex_bills_bill_type_check CHECK (bill_type in ('trade', 'funding-payment'));
+ALTER TABLE okex_bills ALTER COLUMN ledger_tx_id DROP NOT NULL;
+ALTER TABLE okex_bills ADD COLUMN realized_pnl_btc NUMERIC NOT NULL DEFAULT 0;
+ALTER TABLE okex_bills ADD COLUMN pnl_ledger_tx_id UUID;
@@ -0,0 +1 @@
+DROP TABLE liability_watermark;
@@ -0,0 +1 @@
+DROP TABLE hedging_circuit_breaker_events;
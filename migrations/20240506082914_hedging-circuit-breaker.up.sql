@@ -0,0 +1,6 @@
+CREATE TABLE hedging_circuit_breaker_events (
+  id SERIAL PRIMARY KEY,
+  event_type VARCHAR(10) NOT NULL CHECK (event_type in ('tripped', 'reset')),
+  reason VARCHAR NOT NULL,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
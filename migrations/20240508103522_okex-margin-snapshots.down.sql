@@ -0,0 +1 @@
+DROP TABLE okex_margin_snapshots;
@@ -0,0 +1,11 @@
+CREATE TABLE okex_margin_snapshots (
+  id SERIAL PRIMARY KEY,
+  instrument VARCHAR(32) NOT NULL,
+  margin_ratio NUMERIC,
+  liquidation_price_usd_cents NUMERIC,
+  mark_price_usd_cents NUMERIC,
+  liquidation_distance NUMERIC,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX okex_margin_snapshots_created_at_idx ON okex_margin_snapshots (created_at);
@@ -0,0 +1,33 @@
+ALTER TABLE okex_transfers ADD COLUMN action VARCHAR(32);
+ALTER TABLE okex_transfers ADD COLUMN state VARCHAR(20);
+ALTER TABLE okex_transfers ADD COLUMN lost BOOLEAN NOT NULL DEFAULT FALSE;
+
+UPDATE okex_transfers SET action = CASE kind
+  WHEN 'trading_to_funding' THEN 'transfer-trading-to-funding'
+  WHEN 'funding_to_trading' THEN 'transfer-funding-to-trading'
+  ELSE kind::VARCHAR
+END;
+
+UPDATE okex_transfers SET state = CASE
+  WHEN shadow THEN 'shadow'
+  WHEN status = 'settled' THEN 'success'
+  WHEN status = 'failed' THEN 'failed'
+  WHEN status = 'lost' THEN 'deleted'
+  ELSE 'pending'
+END;
+
+ALTER TABLE okex_transfers ALTER COLUMN action SET NOT NULL;
+ALTER TABLE okex_transfers ALTER COLUMN state SET NOT NULL;
+ALTER TABLE okex_transfers ADD CONSTRAINT okex_transfers_action_check CHECK (action in ('transfer-trading-to-funding', 'transfer-funding-to-trading', 'deposit', 'withdraw'));
+ALTER TABLE okex_transfers ADD CONSTRAINT okex_transfers_state_check CHECK (state in ('success', 'pending', 'failed', 'deleted', 'shadow'));
+
+DROP INDEX okex_transfers_status_idx;
+ALTER TABLE okex_transfers DROP COLUMN kind;
+ALTER TABLE okex_transfers DROP COLUMN status;
+ALTER TABLE okex_transfers DROP COLUMN attempts;
+ALTER TABLE okex_transfers DROP COLUMN next_attempt_at;
+ALTER TABLE okex_transfers DROP COLUMN last_error;
+ALTER TABLE okex_transfers DROP COLUMN updated_at;
+
+DROP TYPE OkexTransferKind;
+DROP TYPE OkexTransferStatus;
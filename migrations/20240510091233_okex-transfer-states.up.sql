@@ -0,0 +1,32 @@
+CREATE TYPE OkexTransferKind AS ENUM ('trading_to_funding', 'funding_to_trading', 'deposit', 'withdraw');
+CREATE TYPE OkexTransferStatus AS ENUM ('reserved', 'submitted', 'confirming', 'settled', 'failed', 'lost');
+
+ALTER TABLE okex_transfers ADD COLUMN kind OkexTransferKind;
+ALTER TABLE okex_transfers ADD COLUMN status OkexTransferStatus;
+ALTER TABLE okex_transfers ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE okex_transfers ADD COLUMN next_attempt_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE okex_transfers ADD COLUMN last_error VARCHAR;
+ALTER TABLE okex_transfers ADD COLUMN updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW();
+
+UPDATE okex_transfers SET kind = CASE action
+  WHEN 'transfer-trading-to-funding' THEN 'trading_to_funding'::OkexTransferKind
+  WHEN 'transfer-funding-to-trading' THEN 'funding_to_trading'::OkexTransferKind
+  WHEN 'deposit' THEN 'deposit'::OkexTransferKind
+  ELSE 'withdraw'::OkexTransferKind
+END;
+
+UPDATE okex_transfers SET status = CASE
+  WHEN state IN ('success', 'shadow') THEN 'settled'::OkexTransferStatus
+  WHEN state = 'failed' THEN 'failed'::OkexTransferStatus
+  WHEN state = 'deleted' OR lost THEN 'lost'::OkexTransferStatus
+  WHEN action = 'deposit' THEN 'confirming'::OkexTransferStatus
+  ELSE 'submitted'::OkexTransferStatus
+END, attempts = 1, updated_at = created_at;
+
+ALTER TABLE okex_transfers ALTER COLUMN kind SET NOT NULL;
+ALTER TABLE okex_transfers ALTER COLUMN status SET NOT NULL;
+ALTER TABLE okex_transfers DROP COLUMN action;
+ALTER TABLE okex_transfers DROP COLUMN state;
+ALTER TABLE okex_transfers DROP COLUMN lost;
+
+CREATE INDEX okex_transfers_status_idx ON okex_transfers (status);
@@ -0,0 +1,2 @@
+DROP TABLE bria_payout_cursor;
+ALTER TABLE okex_transfers DROP COLUMN tx_id;
@@ -0,0 +1,7 @@
+ALTER TABLE okex_transfers ADD COLUMN tx_id VARCHAR(64);
+
+CREATE TABLE bria_payout_cursor (
+  id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+  sequence BIGINT NOT NULL,
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,6 @@
+DELETE FROM okex_transfers WHERE kind IN ('lightning_deposit', 'lightning_withdraw');
+
+ALTER TYPE OkexTransferKind RENAME TO OkexTransferKind_old;
+CREATE TYPE OkexTransferKind AS ENUM ('trading_to_funding', 'funding_to_trading', 'deposit', 'withdraw');
+ALTER TABLE okex_transfers ALTER COLUMN kind TYPE OkexTransferKind USING kind::VARCHAR::OkexTransferKind;
+DROP TYPE OkexTransferKind_old;
@@ -0,0 +1,2 @@
+ALTER TYPE OkexTransferKind ADD VALUE 'lightning_deposit';
+ALTER TYPE OkexTransferKind ADD VALUE 'lightning_withdraw';
@@ -0,0 +1 @@
+DROP TABLE hedging_process_control_events;
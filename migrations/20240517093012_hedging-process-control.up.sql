@@ -0,0 +1,7 @@
+CREATE TABLE hedging_process_control_events (
+  id SERIAL PRIMARY KEY,
+  process VARCHAR(10) NOT NULL CHECK (process in ('hedging', 'funding')),
+  event_type VARCHAR(10) NOT NULL CHECK (event_type in ('paused', 'resumed')),
+  reason VARCHAR NOT NULL,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,2 @@
+DROP TABLE hedging_decisions;
+DROP TABLE hedging_decision_configs;
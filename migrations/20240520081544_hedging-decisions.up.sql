@@ -0,0 +1,21 @@
+CREATE TABLE hedging_decision_configs (
+  version VARCHAR(16) PRIMARY KEY,
+  config JSONB NOT NULL,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE hedging_decisions (
+  id BIGSERIAL PRIMARY KEY,
+  correlation_id UUID NOT NULL,
+  exchange VARCHAR(16) NOT NULL,
+  process VARCHAR(10) NOT NULL CHECK (process in ('hedging', 'funding')),
+  evaluated_in VARCHAR(64) NOT NULL,
+  inputs JSONB NOT NULL,
+  config_version VARCHAR(16) NOT NULL REFERENCES hedging_decision_configs(version),
+  action VARCHAR NOT NULL,
+  action_required BOOLEAN NOT NULL,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_hedging_decisions_created_at ON hedging_decisions (created_at);
+CREATE INDEX idx_hedging_decisions_correlation_id ON hedging_decisions (correlation_id);
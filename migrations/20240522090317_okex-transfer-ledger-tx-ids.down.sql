@@ -0,0 +1,3 @@
+DROP INDEX okex_transfers_unposted_idx;
+ALTER TABLE okex_transfers DROP COLUMN ledger_tx_id;
+ALTER TABLE okex_transfers DROP COLUMN ledger_pending_tx_id;
@@ -0,0 +1,4 @@
+ALTER TABLE okex_transfers ADD COLUMN ledger_pending_tx_id UUID;
+ALTER TABLE okex_transfers ADD COLUMN ledger_tx_id UUID;
+
+CREATE INDEX okex_transfers_unposted_idx ON okex_transfers (updated_at) WHERE ledger_tx_id IS NULL AND shadow = false;
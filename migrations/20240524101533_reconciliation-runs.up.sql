@@ -0,0 +1,9 @@
+CREATE TABLE reconciliation_runs (
+  id BIGSERIAL PRIMARY KEY,
+  exchange VARCHAR(32) NOT NULL,
+  checks JSONB NOT NULL,
+  discrepancies VARCHAR[] NOT NULL,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX reconciliation_runs_exchange_idx ON reconciliation_runs (exchange, id);
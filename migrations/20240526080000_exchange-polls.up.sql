@@ -0,0 +1,4 @@
+CREATE TABLE exchange_polls (
+  exchange VARCHAR(32) PRIMARY KEY,
+  last_successful_poll_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
@@ -0,0 +1 @@
+DROP TABLE okex_instrument_selection;
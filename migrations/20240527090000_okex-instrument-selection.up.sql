@@ -0,0 +1,5 @@
+CREATE TABLE okex_instrument_selection (
+  id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+  active_instrument VARCHAR(32) NOT NULL,
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
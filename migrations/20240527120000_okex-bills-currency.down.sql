@@ -0,0 +1,4 @@
+DELETE FROM okex_bills WHERE currency != 'BTC';
+ALTER TABLE okex_bills RENAME COLUMN realized_pnl TO realized_pnl_btc;
+ALTER TABLE okex_bills RENAME COLUMN amount TO btc_amount;
+ALTER TABLE okex_bills DROP COLUMN currency;
@@ -0,0 +1,3 @@
+ALTER TABLE okex_bills ADD COLUMN currency VARCHAR(8) NOT NULL DEFAULT 'BTC';
+ALTER TABLE okex_bills RENAME COLUMN btc_amount TO amount;
+ALTER TABLE okex_bills RENAME COLUMN realized_pnl_btc TO realized_pnl;
@@ -0,0 +1,2 @@
+DROP INDEX okex_transfers_shadow_correlation_id_idx;
+DROP INDEX okex_orders_shadow_correlation_id_idx;
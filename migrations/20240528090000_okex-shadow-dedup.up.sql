@@ -0,0 +1,9 @@
+DELETE FROM okex_orders o USING okex_orders d
+  WHERE o.shadow AND d.shadow AND o.correlation_id = d.correlation_id
+    AND (o.created_at, o.client_order_id) > (d.created_at, d.client_order_id);
+CREATE UNIQUE INDEX okex_orders_shadow_correlation_id_idx ON okex_orders (correlation_id) WHERE shadow;
+
+DELETE FROM okex_transfers t USING okex_transfers d
+  WHERE t.shadow AND d.shadow AND t.correlation_id = d.correlation_id AND t.kind = d.kind
+    AND (t.created_at, t.client_transfer_id) > (d.created_at, d.client_transfer_id);
+CREATE UNIQUE INDEX okex_transfers_shadow_correlation_id_idx ON okex_transfers (correlation_id, kind) WHERE shadow;
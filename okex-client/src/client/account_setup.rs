@@ -0,0 +1,164 @@
+use rust_decimal::Decimal;
+use tracing::instrument;
+
+use std::collections::HashMap;
+
+use super::{okex_response::*, OkexClient};
+use crate::{OkexClientError, OkexInstrumentId, OkexMarginMode, OkexPositionMode};
+
+const EXPECTED_ACCOUNT_LEVEL: &str = "2";
+
+/// A setting of the okex account that differs from what hedging expects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OkexAccountChange {
+    AccountLevel {
+        current: String,
+    },
+    PositionMode {
+        current: String,
+    },
+    Leverage {
+        instrument_id: OkexInstrumentId,
+        current: Decimal,
+        expected: Decimal,
+    },
+}
+
+impl std::fmt::Display for OkexAccountChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OkexAccountChange::AccountLevel { current } => {
+                write!(f, "acct_lv: {current} -> {EXPECTED_ACCOUNT_LEVEL}")
+            }
+            OkexAccountChange::PositionMode { current } => {
+                write!(f, "pos_mode: {current} -> {}", OkexPositionMode::Net)
+            }
+            OkexAccountChange::Leverage {
+                instrument_id,
+                current,
+                expected,
+            } => write!(f, "{instrument_id} leverage: {current} -> {expected}"),
+        }
+    }
+}
+
+impl OkexClient {
+    pub(super) async fn account_configuration(
+        &self,
+    ) -> Result<OkexAccountConfigurationData, OkexClientError> {
+        let path = "/api/v5/account/config";
+        let headers = self.get_request_headers(path)?;
+
+        let response = self
+            .rate_limit_client(path)
+            .await
+            .get(self.url_for_path(path))
+            .headers(headers)
+            .send()
+            .await?;
+        Self::extract_response_data::<OkexAccountConfigurationData>(response).await
+    }
+
+    pub(super) fn check_account_configuration(
+        config_data: &OkexAccountConfigurationData,
+    ) -> Result<(), OkexClientError> {
+        if config_data.pos_mode != OkexPositionMode::Net.to_string() {
+            return Err(OkexClientError::MisconfiguredAccount(format!(
+                "Expected `{}`, got `{}`",
+                OkexPositionMode::Net,
+                config_data.pos_mode
+            )));
+        }
+
+        if config_data.acct_lv != EXPECTED_ACCOUNT_LEVEL {
+            return Err(OkexClientError::MisconfiguredAccount(format!(
+                "Expected `acct_lv: {EXPECTED_ACCOUNT_LEVEL}`, got `{}`",
+                config_data.acct_lv
+            )));
+        }
+        Ok(())
+    }
+
+    /// Everything that has to change before the account can be hedged on.
+    /// Leverage is compared for each of the given instruments.
+    #[instrument(name = "okex_client.account_changes", skip(self), err)]
+    pub async fn account_changes(
+        &self,
+        expected_leverage: Decimal,
+        instrument_ids: &[OkexInstrumentId],
+    ) -> Result<Vec<OkexAccountChange>, OkexClientError> {
+        let config_data = self.account_configuration().await?;
+        let mut changes = Vec::new();
+        if config_data.acct_lv != EXPECTED_ACCOUNT_LEVEL {
+            changes.push(OkexAccountChange::AccountLevel {
+                current: config_data.acct_lv,
+            });
+        }
+        if config_data.pos_mode != OkexPositionMode::Net.to_string() {
+            changes.push(OkexAccountChange::PositionMode {
+                current: config_data.pos_mode,
+            });
+        }
+        for instrument_id in instrument_ids {
+            let current = self
+                .for_instrument(*instrument_id)
+                .leverage_info()
+                .await?
+                .lever;
+            if current != expected_leverage {
+                changes.push(OkexAccountChange::Leverage {
+                    instrument_id: *instrument_id,
+                    current,
+                    expected: expected_leverage,
+                });
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Applies the changes in order. Okex refuses to switch the account level or
+    /// position mode while positions or orders are open.
+    #[instrument(name = "okex_client.apply_account_changes", skip(self), err)]
+    pub async fn apply_account_changes(
+        &self,
+        changes: &[OkexAccountChange],
+    ) -> Result<(), OkexClientError> {
+        for change in changes {
+            let (request_path, body) = match change {
+                OkexAccountChange::AccountLevel { .. } => (
+                    "/api/v5/account/set-account-level",
+                    HashMap::from([("acctLv", EXPECTED_ACCOUNT_LEVEL.to_string())]),
+                ),
+                OkexAccountChange::PositionMode { .. } => (
+                    "/api/v5/account/set-position-mode",
+                    HashMap::from([("posMode", OkexPositionMode::Net.to_string())]),
+                ),
+                OkexAccountChange::Leverage {
+                    instrument_id,
+                    expected,
+                    ..
+                } => (
+                    "/api/v5/account/set-leverage",
+                    HashMap::from([
+                        ("instId", instrument_id.to_string()),
+                        ("lever", expected.to_string()),
+                        ("mgnMode", OkexMarginMode::Cross.to_string()),
+                    ]),
+                ),
+            };
+            let request_body = serde_json::to_string(&body)?;
+            let headers = self.post_request_headers(request_path, &request_body)?;
+
+            let response = self
+                .rate_limit_client(request_path)
+                .await
+                .post(self.url_for_path(request_path))
+                .headers(headers)
+                .body(request_body)
+                .send()
+                .await?;
+            Self::extract_optional_response_data::<serde_json::Value>(response).await?;
+        }
+        Ok(())
+    }
+}
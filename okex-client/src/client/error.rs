@@ -24,12 +24,28 @@ pub enum OkexClientError {
     WithdrawalIdDoesNotExist,
     #[error("OkexClientError - NoLastPriceAvailable")]
     NoLastPriceAvailable,
+    #[error("OkexClientError - NoFundingRateAvailable")]
+    NoFundingRateAvailable,
+    #[error("OkexClientError - UnknownInstrument: {0}")]
+    UnknownInstrument(String),
     #[error("OkexClientError - NonParsablePositionData")]
     NonParsablePositionData,
     #[error("OkexClientError - DecimalConversion: {0}")]
     DecimalConversion(#[from] rust_decimal::Error),
+    #[error("OkexClientError - OrderTypeWithoutPrice: {0}")]
+    OrderTypeWithoutPrice(String),
     #[error("OkexClientError - MisconfiguredAccount: {0}")]
     MisconfiguredAccount(String),
+    #[error("OkexClientError - Websocket: {0}")]
+    Websocket(Box<tokio_tungstenite::tungstenite::Error>),
+    #[error("OkexClientError - WebsocketLogin: {0}")]
+    WebsocketLogin(String),
+}
+
+impl From<tokio_tungstenite::tungstenite::Error> for OkexClientError {
+    fn from(err: tokio_tungstenite::tungstenite::Error) -> Self {
+        OkexClientError::Websocket(Box::new(err))
+    }
 }
 
 impl From<(String, String)> for OkexClientError {
@@ -1,8 +1,10 @@
+mod account_setup;
 mod error;
 mod okex_response;
 mod primitives;
+mod private_ws;
 
-use chrono::{SecondsFormat, Utc};
+use chrono::{SecondsFormat, TimeZone, Utc};
 use data_encoding::BASE64;
 use reqwest::{
     header::{HeaderMap, HeaderValue, CONTENT_TYPE},
@@ -16,11 +18,13 @@ use tracing::instrument;
 
 use std::{collections::HashMap, time::Duration};
 
+pub use account_setup::*;
 pub use error::*;
 pub use okex_response::OrderDetails;
 pub use okex_response::TransferStateData;
 use okex_response::*;
 pub use primitives::*;
+pub use private_ws::*;
 
 use governor::{
     clock::DefaultClock, state::keyed::DefaultKeyedStateStore, Jitter, Quota, RateLimiter,
@@ -37,8 +41,9 @@ pub const OKEX_MINIMUM_WITHDRAWAL_FEE: Decimal = dec!(0.0002);
 pub const OKEX_MAXIMUM_WITHDRAWAL_FEE: Decimal = dec!(0.0004);
 pub const OKEX_MINIMUM_WITHDRAWAL_AMOUNT: Decimal = dec!(0.001);
 pub const OKEX_MAXIMUM_WITHDRAWAL_AMOUNT: Decimal = dec!(500);
+pub const OKEX_BILLS_PAGE_LIMIT: usize = 100;
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct OkexClientConfig {
     #[serde(default)]
     pub api_key: String,
@@ -48,48 +53,55 @@ pub struct OkexClientConfig {
     pub secret_key: String,
     #[serde(default)]
     pub simulated: bool,
+    #[serde(default = "default_api_url")]
+    pub api_url: String,
+    #[serde(default = "default_private_ws_url")]
+    pub private_ws_url: String,
+}
+
+impl Default for OkexClientConfig {
+    fn default() -> Self {
+        Self {
+            api_key: String::new(),
+            passphrase: String::new(),
+            secret_key: String::new(),
+            simulated: false,
+            api_url: default_api_url(),
+            private_ws_url: default_private_ws_url(),
+        }
+    }
+}
+
+fn default_api_url() -> String {
+    OKEX_API_URL.to_string()
+}
+
+fn default_private_ws_url() -> String {
+    OKEX_PRIVATE_WS_URL.to_string()
 }
 
 #[derive(Clone)]
 pub struct OkexClient {
     client: ReqwestClient,
     config: OkexClientConfig,
+    instrument_id: OkexInstrumentId,
 }
 
 impl OkexClient {
     pub async fn new(config: OkexClientConfig) -> Result<Self, OkexClientError> {
-        let client = Self {
+        let client = Self::new_unchecked(config)?;
+        let config_data = client.account_configuration().await?;
+        Self::check_account_configuration(&config_data)?;
+        Ok(client)
+    }
+
+    /// Skips verifying the account configuration, used to set up a fresh account
+    pub fn new_unchecked(config: OkexClientConfig) -> Result<Self, OkexClientError> {
+        Ok(Self {
             client: ReqwestClient::builder().use_rustls_tls().build()?,
             config,
-        };
-        let path = "/api/v5/account/config";
-        let config_url = Self::url_for_path(path);
-        let headers = client.get_request_headers(path)?;
-
-        let response = client
-            .rate_limit_client(path)
-            .await
-            .get(config_url)
-            .headers(headers)
-            .send()
-            .await?;
-        let config_data =
-            Self::extract_response_data::<OkexAccountConfigurationData>(response).await?;
-
-        if &config_data.pos_mode != "net_mode" {
-            return Err(OkexClientError::MisconfiguredAccount(format!(
-                "Expected `net_mode`, got `{}`",
-                config_data.pos_mode
-            )));
-        }
-
-        if &config_data.acct_lv != "2" {
-            return Err(OkexClientError::MisconfiguredAccount(format!(
-                "Expected `acct_lv: 2`, got `{}`",
-                config_data.acct_lv
-            )));
-        }
-        Ok(client)
+            instrument_id: OkexInstrumentId::BtcUsdSwap,
+        })
     }
 
     pub async fn check_leverage(&self, expected_leverage: Decimal) -> Result<(), OkexClientError> {
@@ -108,13 +120,27 @@ impl OkexClient {
         self.config.simulated
     }
 
+    /// A client sharing this one's credentials that trades the given swap.
+    /// Clients start out on the BTC-USD-SWAP.
+    pub fn for_instrument(&self, instrument_id: OkexInstrumentId) -> Self {
+        Self {
+            instrument_id,
+            ..self.clone()
+        }
+    }
+
+    pub fn instrument_id(&self) -> OkexInstrumentId {
+        self.instrument_id
+    }
+
     pub async fn leverage_info(&self) -> Result<OkexLeverageInfoData, OkexClientError> {
-        let path = "/api/v5/account/leverage-info?instId=BTC-USD-SWAP&mgnMode=cross";
-        let config_url = Self::url_for_path(path);
-        let headers = self.get_request_headers(path)?;
+        let static_path = "/api/v5/account/leverage-info";
+        let path = format!("{static_path}?instId={}&mgnMode=cross", self.instrument_id);
+        let config_url = self.url_for_path(&path);
+        let headers = self.get_request_headers(&path)?;
 
         let response = self
-            .rate_limit_client(path)
+            .rate_limit_client(static_path)
             .await
             .get(config_url)
             .headers(headers)
@@ -146,7 +172,7 @@ impl OkexClient {
         let response = self
             .rate_limit_client(request_path)
             .await
-            .get(Self::url_for_path(request_path))
+            .get(self.url_for_path(request_path))
             .headers(headers)
             .send()
             .await?;
@@ -179,7 +205,7 @@ impl OkexClient {
         let response = self
             .rate_limit_client(request_path)
             .await
-            .get(Self::url_for_path(request_path))
+            .get(self.url_for_path(request_path))
             .headers(headers)
             .send()
             .await?;
@@ -225,7 +251,7 @@ impl OkexClient {
         let response = self
             .rate_limit_client(request_path)
             .await
-            .post(Self::url_for_path(request_path))
+            .post(self.url_for_path(request_path))
             .headers(headers)
             .body(request_body)
             .send()
@@ -258,7 +284,7 @@ impl OkexClient {
         let response = self
             .rate_limit_client(request_path)
             .await
-            .post(Self::url_for_path(request_path))
+            .post(self.url_for_path(request_path))
             .headers(headers)
             .body(request_body)
             .send()
@@ -280,7 +306,7 @@ impl OkexClient {
         let response = self
             .rate_limit_client(request_path)
             .await
-            .get(Self::url_for_path(request_path))
+            .get(self.url_for_path(request_path))
             .headers(headers)
             .send()
             .await?;
@@ -296,34 +322,48 @@ impl OkexClient {
 
     #[instrument(name = "okex_client.trading_account_balance", skip(self), err)]
     pub async fn trading_account_balance(&self) -> Result<AvailableBalance, OkexClientError> {
-        let request_path = "/api/v5/account/balance?ccy=BTC";
+        Ok(self
+            .trading_account_balance_in(TradeCurrency::BTC)
+            .await?
+            .into())
+    }
 
-        let headers = self.get_request_headers(request_path)?;
+    /// The linear swap is margined in USDT, the inverse one in BTC
+    #[instrument(name = "okex_client.trading_account_balance_in", skip(self), err)]
+    pub async fn trading_account_balance_in(
+        &self,
+        ccy: TradeCurrency,
+    ) -> Result<CurrencyBalance, OkexClientError> {
+        let static_request_path = "/api/v5/account/balance?ccy=";
+        let request_path = format!("{static_request_path}{ccy}");
+
+        let headers = self.get_request_headers(&request_path)?;
 
         let response = self
-            .rate_limit_client(request_path)
+            .rate_limit_client(static_request_path)
             .await
-            .get(Self::url_for_path(request_path))
+            .get(self.url_for_path(&request_path))
             .headers(headers)
             .send()
             .await?;
 
         let trading_balance = Self::extract_response_data::<TradingBalanceData>(response).await?;
 
-        let mut free_amt_in_btc = Decimal::ZERO;
-        let mut used_amt_in_btc = Decimal::ZERO;
-        let mut total_amt_in_btc = Decimal::ZERO;
+        let mut free_amt = Decimal::ZERO;
+        let mut used_amt = Decimal::ZERO;
+        let mut total_amt = Decimal::ZERO;
 
         if !trading_balance.details.is_empty() {
-            free_amt_in_btc = trading_balance.details[0].avail_eq;
-            used_amt_in_btc = trading_balance.details[0].frozen_bal;
-            total_amt_in_btc = trading_balance.details[0].eq;
+            free_amt = trading_balance.details[0].avail_eq;
+            used_amt = trading_balance.details[0].frozen_bal;
+            total_amt = trading_balance.details[0].eq;
         }
 
-        Ok(AvailableBalance {
-            free_amt_in_btc,
-            used_amt_in_btc,
-            total_amt_in_btc,
+        Ok(CurrencyBalance {
+            ccy,
+            free_amt,
+            used_amt,
+            total_amt,
         })
     }
 
@@ -340,7 +380,7 @@ impl OkexClient {
         let response = self
             .rate_limit_client(static_request_path)
             .await
-            .get(Self::url_for_path(&request_path))
+            .get(self.url_for_path(&request_path))
             .headers(headers)
             .send()
             .await?;
@@ -366,7 +406,7 @@ impl OkexClient {
         let response = self
             .rate_limit_client(static_request_path)
             .await
-            .get(Self::url_for_path(&request_path))
+            .get(self.url_for_path(&request_path))
             .headers(headers)
             .send()
             .await?;
@@ -393,7 +433,7 @@ impl OkexClient {
         body.insert("amt".to_string(), amt.to_string());
         body.insert("dest".to_string(), "4".to_string());
         body.insert("fee".to_string(), fee.to_string());
-        body.insert("chain".to_string(), "BTC-Bitcoin".to_string());
+        body.insert("chain".to_string(), Chain::BITCOIN.to_string());
         body.insert("toAddr".to_string(), btc_address);
         body.insert("clientId".to_string(), client_id.0);
         let request_body = serde_json::to_string(&body)?;
@@ -404,7 +444,7 @@ impl OkexClient {
         let response = self
             .rate_limit_client(request_path)
             .await
-            .post(Self::url_for_path(request_path))
+            .post(self.url_for_path(request_path))
             .headers(headers)
             .body(request_body)
             .send()
@@ -417,40 +457,96 @@ impl OkexClient {
         })
     }
 
+    /// https://www.okx.com/docs-v5/en/#funding-account-rest-api-lightning-deposits
+    /// Invoice that credits the funding account once paid
+    #[instrument(name = "okex_client.lightning_deposit_invoice", skip(self), err)]
+    pub async fn lightning_deposit_invoice(
+        &self,
+        amt: Decimal,
+    ) -> Result<LightningInvoice, OkexClientError> {
+        let static_request_path = "/api/v5/asset/deposit-lightning?ccy=BTC&to=6&amt=";
+        let request_path = format!("{static_request_path}{amt}");
+        let headers = self.get_request_headers(&request_path)?;
+        let response = self
+            .rate_limit_client(static_request_path)
+            .await
+            .get(self.url_for_path(&request_path))
+            .headers(headers)
+            .send()
+            .await?;
+
+        let deposit_data = Self::extract_response_data::<LightningDepositData>(response).await?;
+
+        Ok(LightningInvoice {
+            value: deposit_data.invoice,
+        })
+    }
+
+    /// https://www.okx.com/docs-v5/en/#funding-account-rest-api-lightning-withdrawals
+    /// The amount is encoded in the invoice, okex doesn't accept a client id here
+    #[instrument(name = "okex_client.withdraw_lightning", skip(self, invoice), err)]
+    pub async fn withdraw_lightning(
+        &self,
+        invoice: String,
+        memo: String,
+    ) -> Result<WithdrawId, OkexClientError> {
+        let mut body: HashMap<String, String> = HashMap::new();
+        body.insert("ccy".to_string(), TradeCurrency::BTC.to_string());
+        body.insert("invoice".to_string(), invoice);
+        body.insert("memo".to_string(), memo);
+        let request_body = serde_json::to_string(&body)?;
+
+        let request_path = "/api/v5/asset/withdrawal-lightning";
+        let headers = self.post_request_headers(request_path, request_body.as_str())?;
+
+        let response = self
+            .rate_limit_client(request_path)
+            .await
+            .post(self.url_for_path(request_path))
+            .headers(headers)
+            .body(request_body)
+            .send()
+            .await?;
+
+        let withdraw_data = Self::extract_response_data::<LightningWithdrawData>(response).await?;
+
+        Ok(WithdrawId {
+            value: withdraw_data.wd_id,
+        })
+    }
+
     /// https://www.okx.com/docs-v5/en/#funding-account-rest-api-get-deposit-history
+    /// None until okex has seen the transaction
     #[instrument(
-        name = "okex_client.fetch_deposit",
+        name = "okex_client.fetch_deposit_by_tx_id",
         fields(deposit_found, okex_deposit_state),
         skip(self),
         err
     )]
-    pub async fn fetch_deposit(
+    pub async fn fetch_deposit_by_tx_id(
         &self,
-        depo_addr: String,
-        amt_in_btc: Decimal,
-    ) -> Result<DepositStatus, OkexClientError> {
-        // 1. Get all deposit history
-        let request_path = "/api/v5/asset/deposit-history";
-        let headers = self.get_request_headers(request_path)?;
+        tx_id: String,
+    ) -> Result<Option<DepositStatus>, OkexClientError> {
+        let static_request_path = "/api/v5/asset/deposit-history?ccy=BTC&txId=";
+        let request_path = format!("{static_request_path}{tx_id}");
+        let headers = self.get_request_headers(&request_path)?;
         let response = self
-            .rate_limit_client(request_path)
+            .rate_limit_client(static_request_path)
             .await
-            .get(Self::url_for_path(request_path))
+            .get(self.url_for_path(&request_path))
             .headers(headers)
             .send()
             .await?;
 
-        let history = Self::extract_response_data_array::<DepositHistoryData>(response).await?;
+        let deposit = Self::extract_response_data_array::<DepositHistoryData>(response)
+            .await?
+            .into_iter()
+            .find(|deposit_entry| deposit_entry.tx_id == tx_id);
+        tracing::Span::current().record("deposit_found", deposit.is_some());
 
-        // 2. Filter through results from above and find any entry that matches addr and amt_in_btc
-        let deposit = history.into_iter().find(|deposit_entry| {
-            deposit_entry.to == depo_addr && deposit_entry.amt == amt_in_btc.to_string()
-        });
-
-        if let Some(deposit_data) = deposit {
-            tracing::Span::current().record("deposit_found", true);
+        Ok(deposit.map(|deposit_data| {
             tracing::Span::current().record("okex_deposit_state", &deposit_data.state);
-            Ok(DepositStatus {
+            DepositStatus {
                 state: match &deposit_data.state[..] {
                     "0" => "pending".to_string(),  // waiting for confirmation
                     "1" => "pending".to_string(),  // deposit credited, cannot withdraw
@@ -461,13 +557,8 @@ impl OkexClient {
                     _ => "failed".to_string(),
                 },
                 transaction_id: deposit_data.tx_id,
-            })
-        } else {
-            Err(OkexClientError::UnexpectedResponse {
-                msg: format!("No deposit of {amt_in_btc} made to {depo_addr}"),
-                code: "0".to_string(),
-            })
-        }
+            }
+        }))
     }
 
     #[instrument(name = "okex_client.fetch_withdrawal_by_client_id", skip(self), err)]
@@ -477,11 +568,32 @@ impl OkexClient {
     ) -> Result<WithdrawalStatus, OkexClientError> {
         let static_request_path = "/api/v5/asset/withdrawal-history?ccy=BTC&clientId=";
         let request_path = format!("{}{}", static_request_path, client_id.0);
+        self.fetch_withdrawal(static_request_path, request_path)
+            .await
+    }
+
+    /// Lightning withdrawals carry no client id and can only be looked up by wdId
+    #[instrument(name = "okex_client.fetch_withdrawal_by_id", skip(self), err)]
+    pub async fn fetch_withdrawal_by_id(
+        &self,
+        withdraw_id: String,
+    ) -> Result<WithdrawalStatus, OkexClientError> {
+        let static_request_path = "/api/v5/asset/withdrawal-history?ccy=BTC&wdId=";
+        let request_path = format!("{static_request_path}{withdraw_id}");
+        self.fetch_withdrawal(static_request_path, request_path)
+            .await
+    }
+
+    async fn fetch_withdrawal(
+        &self,
+        static_request_path: &'static str,
+        request_path: String,
+    ) -> Result<WithdrawalStatus, OkexClientError> {
         let headers = self.get_request_headers(&request_path)?;
         let response = self
             .rate_limit_client(static_request_path)
             .await
-            .get(Self::url_for_path(&request_path))
+            .get(self.url_for_path(&request_path))
             .headers(headers)
             .send()
             .await?;
@@ -518,19 +630,52 @@ impl OkexClient {
         id: ClientOrderId,
         side: OkexOrderSide,
         contracts: &BtcUsdSwapContracts,
+    ) -> Result<OrderId, OkexClientError> {
+        self.submit_order(id, side, contracts, OkexOrderType::Market, None)
+            .await
+    }
+
+    #[instrument(name = "okex_client.place_limit_order", skip(self), err)]
+    pub async fn place_limit_order(
+        &self,
+        id: ClientOrderId,
+        side: OkexOrderSide,
+        contracts: &BtcUsdSwapContracts,
+        order_type: OkexOrderType,
+        price: Decimal,
+    ) -> Result<OrderId, OkexClientError> {
+        if !order_type.requires_price() {
+            return Err(OkexClientError::OrderTypeWithoutPrice(
+                order_type.to_string(),
+            ));
+        }
+        self.submit_order(id, side, contracts, order_type, Some(price))
+            .await
+    }
+
+    async fn submit_order(
+        &self,
+        id: ClientOrderId,
+        side: OkexOrderSide,
+        contracts: &BtcUsdSwapContracts,
+        order_type: OkexOrderType,
+        price: Option<Decimal>,
     ) -> Result<OrderId, OkexClientError> {
         let mut body: HashMap<String, String> = HashMap::new();
-        body.insert("ccy".to_string(), TradeCurrency::BTC.to_string());
-        body.insert("clOrdId".to_string(), id.0);
         body.insert(
-            "instId".to_string(),
-            OkexInstrumentId::BtcUsdSwap.to_string(),
+            "ccy".to_string(),
+            self.instrument_id.margin_currency().to_string(),
         );
+        body.insert("clOrdId".to_string(), id.0);
+        body.insert("instId".to_string(), self.instrument_id.to_string());
         body.insert("tdMode".to_string(), OkexMarginMode::Cross.to_string());
         body.insert("side".to_string(), side.to_string());
-        body.insert("ordType".to_string(), OkexOrderType::Market.to_string());
+        body.insert("ordType".to_string(), order_type.to_string());
         body.insert("posSide".to_string(), OkexPositionSide::Net.to_string());
         body.insert("sz".to_string(), contracts.0.to_string());
+        if let Some(price) = price {
+            body.insert("px".to_string(), price.to_string());
+        }
         let request_body = serde_json::to_string(&body)?;
 
         let request_path = "/api/v5/trade/order";
@@ -539,7 +684,7 @@ impl OkexClient {
         let response = self
             .rate_limit_client(request_path)
             .await
-            .post(Self::url_for_path(request_path))
+            .post(self.url_for_path(request_path))
             .headers(headers)
             .body(request_body)
             .send()
@@ -557,35 +702,153 @@ impl OkexClient {
         })
     }
 
+    #[instrument(name = "okex_client.amend_order_price", skip(self), err)]
+    pub async fn amend_order_price(
+        &self,
+        id: ClientOrderId,
+        new_price: Decimal,
+    ) -> Result<(), OkexClientError> {
+        let mut body: HashMap<String, String> = HashMap::new();
+        body.insert("instId".to_string(), self.instrument_id.to_string());
+        body.insert("clOrdId".to_string(), id.0);
+        body.insert("newPx".to_string(), new_price.to_string());
+        let request_body = serde_json::to_string(&body)?;
+
+        let request_path = "/api/v5/trade/amend-order";
+        let headers = self.post_request_headers(request_path, &request_body)?;
+
+        let response = self
+            .rate_limit_client(request_path)
+            .await
+            .post(self.url_for_path(request_path))
+            .headers(headers)
+            .body(request_body)
+            .send()
+            .await?;
+
+        let order_data = Self::extract_response_data::<OrderData>(response).await?;
+        if !order_data.s_code.is_empty() && order_data.s_code != "0" {
+            return Err(OkexClientError::from((order_data.s_msg, order_data.s_code)));
+        }
+        Ok(())
+    }
+
+    #[instrument(name = "okex_client.cancel_order", skip(self), err)]
+    pub async fn cancel_order(&self, id: ClientOrderId) -> Result<(), OkexClientError> {
+        let mut body: HashMap<String, String> = HashMap::new();
+        body.insert("instId".to_string(), self.instrument_id.to_string());
+        body.insert("clOrdId".to_string(), id.0);
+        let request_body = serde_json::to_string(&body)?;
+
+        let request_path = "/api/v5/trade/cancel-order";
+        let headers = self.post_request_headers(request_path, &request_body)?;
+
+        let response = self
+            .rate_limit_client(request_path)
+            .await
+            .post(self.url_for_path(request_path))
+            .headers(headers)
+            .body(request_body)
+            .send()
+            .await?;
+
+        match Self::extract_response_data::<OrderData>(response).await {
+            // Order was already filled, canceled or never reached the book
+            Err(OkexClientError::UnexpectedResponse { code, .. }) if code == "51400" => Ok(()),
+            Ok(order_data) if order_data.s_code == "51400" => Ok(()),
+            Ok(order_data) if !order_data.s_code.is_empty() && order_data.s_code != "0" => {
+                Err(OkexClientError::from((order_data.s_msg, order_data.s_code)))
+            }
+            res => res.map(|_| ()),
+        }
+    }
+
     #[instrument(name = "okex_client.order_details", skip(self), err)]
     pub async fn order_details(&self, id: ClientOrderId) -> Result<OrderDetails, OkexClientError> {
-        let static_request_path = "/api/v5/trade/order?instId=BTC-USD-SWAP&clOrdId=";
-        let request_path = format!("{}{}", static_request_path, id.0);
+        let static_request_path = "/api/v5/trade/order";
+        let request_path = format!(
+            "{}?instId={}&clOrdId={}",
+            static_request_path, self.instrument_id, id.0
+        );
         let headers = self.get_request_headers(&request_path)?;
 
         let response = self
             .rate_limit_client(static_request_path)
             .await
-            .get(Self::url_for_path(&request_path))
+            .get(self.url_for_path(&request_path))
             .headers(headers)
             .send()
             .await?;
 
-        let mut details = Self::extract_response_data::<OrderDetails>(response).await?;
-        if details.state == "filled" || details.state == "canceled" {
-            details.complete = true;
-        }
-        Ok(details)
+        let details = Self::extract_response_data::<OrderDetails>(response).await?;
+        Ok(details.with_completion())
+    }
+
+    /// Account bills of the last 7 days for the instrument, in its margin currency,
+    /// newest first. Pass the oldest bill_id of the previous page to page backwards.
+    #[instrument(name = "okex_client.account_bills", skip(self), err)]
+    pub async fn account_bills(
+        &self,
+        after_bill_id: Option<String>,
+    ) -> Result<Vec<AccountBill>, OkexClientError> {
+        let static_request_path = "/api/v5/account/bills";
+        let request_path = format!(
+            "{static_request_path}?instType=SWAP&instId={}&ccy={}&limit=100",
+            self.instrument_id,
+            self.instrument_id.margin_currency()
+        );
+        let request_path = match after_bill_id {
+            Some(after) => format!("{request_path}&after={after}"),
+            None => request_path,
+        };
+        let headers = self.get_request_headers(&request_path)?;
+
+        let response = self
+            .rate_limit_client(static_request_path)
+            .await
+            .get(self.url_for_path(&request_path))
+            .headers(headers)
+            .send()
+            .await?;
+
+        let bills = Self::extract_response_data_array::<AccountBillData>(response).await?;
+        bills
+            .into_iter()
+            .map(|bill| {
+                let millis =
+                    bill.ts
+                        .parse::<i64>()
+                        .map_err(|_| OkexClientError::UnexpectedResponse {
+                            msg: format!("Unparsable bill timestamp '{}'", bill.ts),
+                            code: "0".to_string(),
+                        })?;
+                Ok(AccountBill {
+                    bill_id: bill.bill_id,
+                    bill_type: OkexBillType::from(bill.bill_type),
+                    instrument_id: bill.inst_id,
+                    currency: bill.ccy,
+                    balance_change: bill.bal_chg,
+                    fee: bill.fee,
+                    pnl: bill.pnl,
+                    order_id: bill.ord_id,
+                    timestamp: Utc
+                        .timestamp_millis_opt(millis)
+                        .single()
+                        .unwrap_or_else(Utc::now),
+                })
+            })
+            .collect()
     }
 
     pub async fn get_last_price_in_usd_cents(&self) -> Result<LastPrice, OkexClientError> {
-        let request_path = "/api/v5/market/ticker?instId=BTC-USD-SWAP";
-        let headers = self.get_request_headers(request_path)?;
+        let static_request_path = "/api/v5/market/ticker";
+        let request_path = format!("{static_request_path}?instId={}", self.instrument_id);
+        let headers = self.get_request_headers(&request_path)?;
 
         let response = self
-            .rate_limit_client(request_path)
+            .rate_limit_client(static_request_path)
             .await
-            .get(Self::url_for_path(request_path))
+            .get(self.url_for_path(&request_path))
             .headers(headers)
             .send()
             .await?;
@@ -601,6 +864,38 @@ impl OkexClient {
         }
     }
 
+    /// Current funding rate of the swap. Shorts receive funding while it is positive.
+    pub async fn funding_rate(&self) -> Result<FundingRate, OkexClientError> {
+        let static_request_path = "/api/v5/public/funding-rate";
+        let request_path = format!("{static_request_path}?instId={}", self.instrument_id);
+        let headers = self.get_request_headers(&request_path)?;
+
+        let response = self
+            .rate_limit_client(static_request_path)
+            .await
+            .get(self.url_for_path(&request_path))
+            .headers(headers)
+            .send()
+            .await?;
+
+        match Self::extract_optional_response_data::<FundingRateData>(response).await? {
+            Some(FundingRateData {
+                inst_id,
+                funding_rate,
+                funding_time,
+            }) => Ok(FundingRate {
+                instrument_id: inst_id.parse()?,
+                rate: funding_rate,
+                funding_time: funding_time
+                    .parse::<i64>()
+                    .ok()
+                    .and_then(|millis| Utc.timestamp_millis_opt(millis).single())
+                    .unwrap_or_else(Utc::now),
+            }),
+            None => Err(OkexClientError::NoFundingRateAvailable),
+        }
+    }
+
     #[instrument(
         name = "okex_client.get_position_in_signed_usd_cents",
         skip_all,
@@ -608,13 +903,14 @@ impl OkexClient {
         err
     )]
     pub async fn get_position_in_signed_usd_cents(&self) -> Result<PositionSize, OkexClientError> {
-        let request_path = "/api/v5/account/positions?instId=BTC-USD-SWAP";
-        let headers = self.get_request_headers(request_path)?;
+        let static_request_path = "/api/v5/account/positions";
+        let request_path = format!("{static_request_path}?instId={}", self.instrument_id);
+        let headers = self.get_request_headers(&request_path)?;
 
         let response = self
-            .rate_limit_client(request_path)
+            .rate_limit_client(static_request_path)
             .await
-            .get(Self::url_for_path(request_path))
+            .get(self.url_for_path(&request_path))
             .headers(headers)
             .send()
             .await?;
@@ -623,6 +919,7 @@ impl OkexClient {
             notional_usd,
             pos,
             last,
+            upl,
             ..
         }) = Self::extract_optional_response_data::<PositionData>(response).await?
         {
@@ -637,55 +934,79 @@ impl OkexClient {
             //  Else: raise an error
             // Position responses without data:
             //  No position on account: successful api call, but no data
-            let d_result = pos.parse::<Decimal>();
-            let n_result = notional_usd.parse::<Decimal>();
-            let l_result = last.parse::<Decimal>();
-
-            match (d_result, n_result, l_result) {
-                (Ok(direction), Ok(notional_usd), Ok(last)) => Ok(PositionSize {
-                    instrument_id: OkexInstrumentId::BtcUsdSwap,
-                    usd_cents: notional_usd
-                        * Decimal::ONE_HUNDRED
-                        * if direction > Decimal::ZERO {
-                            Decimal::ONE
-                        } else {
-                            Decimal::NEGATIVE_ONE
-                        },
-                    last_price_in_usd_cents: last * Decimal::ONE_HUNDRED,
-                }),
-                (Ok(direction), _, _) => {
-                    if direction.is_zero() {
-                        Ok(PositionSize {
-                            instrument_id: OkexInstrumentId::BtcUsdSwap,
-                            usd_cents: Decimal::ZERO,
-                            last_price_in_usd_cents: Decimal::ZERO,
-                        })
-                    } else {
-                        Err(OkexClientError::NonParsablePositionData)
-                    }
-                }
-                _ => Err(OkexClientError::NonParsablePositionData),
-            }
+            position_size(self.instrument_id, &pos, &notional_usd, &last, &upl)
         } else {
             Ok(PositionSize {
-                instrument_id: OkexInstrumentId::BtcUsdSwap,
+                instrument_id: self.instrument_id,
                 usd_cents: Decimal::ZERO,
                 last_price_in_usd_cents: Decimal::ZERO,
+                unrealized_pnl_btc: Decimal::ZERO,
             })
         }
     }
 
+    #[instrument(
+        name = "okex_client.get_position_risk",
+        skip_all,
+        fields(margin_ratio, liquidation_price, mark_price),
+        err
+    )]
+    pub async fn get_position_risk(&self) -> Result<PositionRisk, OkexClientError> {
+        let static_request_path = "/api/v5/account/positions";
+        let request_path = format!("{static_request_path}?instId={}", self.instrument_id);
+        let headers = self.get_request_headers(&request_path)?;
+
+        let response = self
+            .rate_limit_client(static_request_path)
+            .await
+            .get(self.url_for_path(&request_path))
+            .headers(headers)
+            .send()
+            .await?;
+
+        let mut risk = PositionRisk {
+            instrument_id: self.instrument_id,
+            margin_ratio: None,
+            liquidation_price_in_usd_cents: None,
+            mark_price_in_usd_cents: None,
+        };
+        if let Some(PositionData {
+            mgn_ratio,
+            liq_px,
+            mark_px,
+            ..
+        }) = Self::extract_optional_response_data::<PositionData>(response).await?
+        {
+            let span = tracing::Span::current();
+            span.record("margin_ratio", tracing::field::display(&mgn_ratio));
+            span.record("liquidation_price", tracing::field::display(&liq_px));
+            span.record("mark_price", tracing::field::display(&mark_px));
+
+            risk.margin_ratio = mgn_ratio.parse().ok();
+            risk.liquidation_price_in_usd_cents = liq_px
+                .parse::<Decimal>()
+                .ok()
+                .filter(|price| price > &Decimal::ZERO)
+                .map(|price| price * Decimal::ONE_HUNDRED);
+            risk.mark_price_in_usd_cents = mark_px
+                .parse::<Decimal>()
+                .ok()
+                .map(|price| price * Decimal::ONE_HUNDRED);
+        }
+        Ok(risk)
+    }
+
     #[instrument(name = "okex_client.close_positions", skip(self), err)]
     pub async fn close_positions(&self, id: ClientOrderId) -> Result<(), OkexClientError> {
         let mut body: HashMap<String, String> = HashMap::new();
-        body.insert(
-            "instId".to_string(),
-            OkexInstrumentId::BtcUsdSwap.to_string(),
-        );
+        body.insert("instId".to_string(), self.instrument_id.to_string());
         body.insert("clOrdId".to_string(), id.0);
         body.insert("mgnMode".to_string(), OkexMarginMode::Cross.to_string());
         body.insert("posSide".to_string(), OkexPositionSide::Net.to_string());
-        body.insert("ccy".to_string(), TradeCurrency::BTC.to_string());
+        body.insert(
+            "ccy".to_string(),
+            self.instrument_id.margin_currency().to_string(),
+        );
         body.insert("autoCxl".to_string(), "false".to_string());
         let request_body = serde_json::to_string(&body)?;
 
@@ -695,7 +1016,7 @@ impl OkexClient {
         let response = self
             .rate_limit_client(request_path)
             .await
-            .post(Self::url_for_path(request_path))
+            .post(self.url_for_path(request_path))
             .headers(headers)
             .body(request_body)
             .send()
@@ -760,8 +1081,8 @@ impl OkexClient {
         BASE64.encode(signature.as_ref())
     }
 
-    fn url_for_path(path: &str) -> String {
-        format!("{OKEX_API_URL}{path}")
+    fn url_for_path(&self, path: &str) -> String {
+        format!("{}{path}", self.config.api_url.trim_end_matches('/'))
     }
 
     fn post_request_headers(
@@ -814,3 +1135,49 @@ impl OkexClient {
         Ok(headers)
     }
 }
+
+/// Linear swaps report their unrealized pnl in USDT, it is converted to BTC at the last price
+fn position_size(
+    instrument_id: OkexInstrumentId,
+    pos: &str,
+    notional_usd: &str,
+    last: &str,
+    upl: &str,
+) -> Result<PositionSize, OkexClientError> {
+    let d_result = pos.parse::<Decimal>();
+    let n_result = notional_usd.parse::<Decimal>();
+    let l_result = last.parse::<Decimal>();
+    let upl = upl.parse::<Decimal>().unwrap_or(Decimal::ZERO);
+
+    match (d_result, n_result, l_result) {
+        (Ok(direction), Ok(notional_usd), Ok(last)) => Ok(PositionSize {
+            instrument_id,
+            usd_cents: notional_usd
+                * Decimal::ONE_HUNDRED
+                * if direction > Decimal::ZERO {
+                    Decimal::ONE
+                } else {
+                    Decimal::NEGATIVE_ONE
+                },
+            last_price_in_usd_cents: last * Decimal::ONE_HUNDRED,
+            unrealized_pnl_btc: if instrument_id.is_inverse() || last.is_zero() {
+                upl
+            } else {
+                upl / last
+            },
+        }),
+        (Ok(direction), _, _) => {
+            if direction.is_zero() {
+                Ok(PositionSize {
+                    instrument_id,
+                    usd_cents: Decimal::ZERO,
+                    last_price_in_usd_cents: Decimal::ZERO,
+                    unrealized_pnl_btc: Decimal::ZERO,
+                })
+            } else {
+                Err(OkexClientError::NonParsablePositionData)
+            }
+        }
+        _ => Err(OkexClientError::NonParsablePositionData),
+    }
+}
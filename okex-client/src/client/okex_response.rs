@@ -119,6 +119,18 @@ pub struct WithdrawData {
     pub chain: String,
 }
 
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LightningWithdrawData {
+    pub wd_id: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LightningDepositData {
+    pub invoice: String,
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct DepositHistoryData {
@@ -159,19 +171,63 @@ pub struct OrderData {
     pub s_msg: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct OrderDetails {
     pub cl_ord_id: ClientOrderId,
     pub ord_id: String,
+    #[serde(deserialize_with = "empty_as_zero")]
     pub avg_px: Decimal,
+    #[serde(deserialize_with = "empty_as_zero")]
     pub fee: Decimal,
     pub sz: Decimal,
+    #[serde(default, deserialize_with = "empty_as_zero")]
+    pub acc_fill_sz: Decimal,
+    #[serde(default, deserialize_with = "empty_as_zero")]
+    pub px: Decimal,
+    #[serde(default)]
+    pub ord_type: String,
     pub state: String,
     #[serde(skip)]
     pub complete: bool,
 }
 
+impl OrderDetails {
+    pub(super) fn with_completion(mut self) -> Self {
+        if self.state == "filled" || self.state == "canceled" || self.state == "mmp_canceled" {
+            self.complete = true;
+        }
+        self
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountBillData {
+    pub bill_id: String,
+    #[serde(rename = "type")]
+    pub bill_type: String,
+    pub inst_id: String,
+    pub ccy: String,
+    #[serde(deserialize_with = "empty_as_zero")]
+    pub bal_chg: Decimal,
+    #[serde(deserialize_with = "empty_as_zero")]
+    pub fee: Decimal,
+    #[serde(default, deserialize_with = "empty_as_zero")]
+    pub pnl: Decimal,
+    pub ord_id: String,
+    pub ts: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FundingRateData {
+    pub inst_id: String,
+    #[serde(deserialize_with = "empty_as_zero")]
+    pub funding_rate: Decimal,
+    pub funding_time: String,
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct LastPriceData {
@@ -258,6 +314,67 @@ pub struct OkexLeverageInfoData {
     pub lever: Decimal,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct PrivateWsEvent {
+    pub event: String,
+    #[serde(default)]
+    pub code: String,
+    #[serde(default)]
+    pub msg: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PrivateWsPush {
+    pub arg: PrivateWsChannelArg,
+    pub data: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PrivateWsChannelArg {
+    pub channel: String,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PositionChannelData {
+    pub inst_id: String,
+    pub pos: String,
+    pub notional_usd: String,
+    pub last: String,
+    pub upl: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountChannelData {
+    pub details: Vec<AccountChannelDetails>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountChannelDetails {
+    pub ccy: String,
+    #[serde(deserialize_with = "empty_as_zero")]
+    pub avail_eq: Decimal,
+    #[serde(deserialize_with = "empty_as_zero")]
+    pub eq: Decimal,
+    #[serde(deserialize_with = "empty_as_zero")]
+    pub frozen_bal: Decimal,
+}
+
+/// Okex sends empty strings for numeric fields that have no value yet
+/// (eg. the average price of a resting order without fills)
+fn empty_as_zero<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    if s.is_empty() {
+        return Ok(Decimal::ZERO);
+    }
+    s.parse::<Decimal>().map_err(serde::de::Error::custom)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -305,4 +422,23 @@ mod tests {
         }
         panic!()
     }
+
+    #[test]
+    fn resting_order_details() {
+        let json = r#"{"clOrdId":"abc","ordId":"123","avgPx":"","fee":"0","sz":"10","accFillSz":"3","px":"43000.1","ordType":"post_only","state":"partially_filled"}"#;
+        let details: OrderDetails = serde_json::from_str(json).unwrap();
+        assert_eq!(details.avg_px, Decimal::ZERO);
+        assert_eq!(details.acc_fill_sz, Decimal::from(3));
+        assert_eq!(details.px, "43000.1".parse::<Decimal>().unwrap());
+        assert_eq!(details.ord_type, "post_only");
+    }
+
+    #[test]
+    fn funding_fee_bill() {
+        let json = r#"{"billId":"623950854533513219","type":"8","subType":"173","instId":"BTC-USD-SWAP","balChg":"-0.00001234","fee":"0","ordId":"","ts":"1695871200000","ccy":"BTC"}"#;
+        let bill: AccountBillData = serde_json::from_str(json).unwrap();
+        assert_eq!(bill.bill_type, "8");
+        assert_eq!(bill.bal_chg, "-0.00001234".parse::<Decimal>().unwrap());
+        assert_eq!(bill.fee, Decimal::ZERO);
+    }
 }
@@ -1,7 +1,10 @@
+use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::Deserialize;
 use std::{fmt::Display, str::FromStr};
 
+use crate::OkexClientError;
+
 #[derive(serde::Deserialize, Debug, Clone)]
 #[serde(transparent)]
 pub struct ClientOrderId(pub(super) String);
@@ -98,6 +101,7 @@ impl Display for OnchainFees {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Chain {
     BITCOIN,
+    LIGHTNING,
     UNSUPPORTED,
 }
 
@@ -105,6 +109,7 @@ impl Display for Chain {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match *self {
             Chain::BITCOIN => write!(f, "BTC-Bitcoin"),
+            Chain::LIGHTNING => write!(f, "BTC-Lightning"),
             Chain::UNSUPPORTED => write!(f, "UNSUPPORTED"),
         }
     }
@@ -116,6 +121,7 @@ impl FromStr for Chain {
     fn from_str(s: &str) -> Result<Chain, String> {
         match s {
             "BTC-Bitcoin" => Ok(Chain::BITCOIN),
+            "BTC-Lightning" => Ok(Chain::LIGHTNING),
             _ => Ok(Chain::UNSUPPORTED),
         }
     }
@@ -153,7 +159,7 @@ pub struct TransferId {
     pub value: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AvailableBalance {
     pub free_amt_in_btc: Decimal,
     pub used_amt_in_btc: Decimal,
@@ -170,6 +176,25 @@ impl Display for AvailableBalance {
     }
 }
 
+/// Trading account balance of one currency, amounts are in that currency
+#[derive(Debug, Clone)]
+pub struct CurrencyBalance {
+    pub ccy: TradeCurrency,
+    pub free_amt: Decimal,
+    pub used_amt: Decimal,
+    pub total_amt: Decimal,
+}
+
+impl From<CurrencyBalance> for AvailableBalance {
+    fn from(balance: CurrencyBalance) -> Self {
+        Self {
+            free_amt_in_btc: balance.free_amt,
+            used_amt_in_btc: balance.used_amt,
+            total_amt_in_btc: balance.total_amt,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct TransferState {
     pub state: String,
@@ -182,6 +207,11 @@ pub struct WithdrawId {
     pub value: String,
 }
 
+#[derive(Debug)]
+pub struct LightningInvoice {
+    pub value: String,
+}
+
 #[derive(Debug)]
 pub struct DepositStatus {
     pub state: String,
@@ -200,31 +230,126 @@ pub struct OrderId {
     pub value: String,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OkexBillType {
+    Trade,
+    FundingFee,
+    Other(String),
+}
+
+impl From<String> for OkexBillType {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "2" => OkexBillType::Trade,
+            "8" => OkexBillType::FundingFee,
+            _ => OkexBillType::Other(s),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AccountBill {
+    pub bill_id: String,
+    pub bill_type: OkexBillType,
+    pub instrument_id: String,
+    pub currency: String,
+    pub balance_change: Decimal,
+    pub fee: Decimal,
+    pub pnl: Decimal,
+    pub order_id: String,
+    pub timestamp: DateTime<Utc>,
+}
+
 #[derive(Debug)]
 pub struct LastPrice {
     pub usd_cents: Decimal,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PositionSize {
     pub instrument_id: OkexInstrumentId,
     pub usd_cents: Decimal,
     pub last_price_in_usd_cents: Decimal,
+    pub unrealized_pnl_btc: Decimal,
 }
 
-#[derive(Debug, Clone)]
+/// Risk metrics okex reports for the open position. Empty while flat.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PositionRisk {
+    pub instrument_id: OkexInstrumentId,
+    /// Okex liquidates once the margin ratio falls to 1
+    pub margin_ratio: Option<Decimal>,
+    pub liquidation_price_in_usd_cents: Option<Decimal>,
+    pub mark_price_in_usd_cents: Option<Decimal>,
+}
+
+impl PositionRisk {
+    /// How far the mark price can move before the position is liquidated,
+    /// as a fraction of the mark price
+    pub fn liquidation_distance(&self) -> Option<Decimal> {
+        match (
+            self.liquidation_price_in_usd_cents,
+            self.mark_price_in_usd_cents,
+        ) {
+            (Some(liquidation), Some(mark)) if !mark.is_zero() => {
+                Some((liquidation - mark).abs() / mark)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum OkexInstrumentId {
+    /// Inverse swap, $100 per contract and margined in BTC
+    #[serde(rename = "BTC-USD-SWAP")]
     BtcUsdSwap,
+    /// Linear swap, 0.01 BTC per contract and margined in USDT
+    #[serde(rename = "BTC-USDT-SWAP")]
+    BtcUsdtSwap,
+}
+
+impl OkexInstrumentId {
+    pub fn is_inverse(&self) -> bool {
+        matches!(self, OkexInstrumentId::BtcUsdSwap)
+    }
+
+    pub fn margin_currency(&self) -> TradeCurrency {
+        match *self {
+            OkexInstrumentId::BtcUsdSwap => TradeCurrency::BTC,
+            OkexInstrumentId::BtcUsdtSwap => TradeCurrency::USDT,
+        }
+    }
 }
 
 impl Display for OkexInstrumentId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match *self {
             OkexInstrumentId::BtcUsdSwap => write!(f, "BTC-USD-SWAP"),
+            OkexInstrumentId::BtcUsdtSwap => write!(f, "BTC-USDT-SWAP"),
+        }
+    }
+}
+
+impl FromStr for OkexInstrumentId {
+    type Err = OkexClientError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "BTC-USD-SWAP" => Ok(OkexInstrumentId::BtcUsdSwap),
+            "BTC-USDT-SWAP" => Ok(OkexInstrumentId::BtcUsdtSwap),
+            _ => Err(OkexClientError::UnknownInstrument(s.to_string())),
         }
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct FundingRate {
+    pub instrument_id: OkexInstrumentId,
+    pub rate: Decimal,
+    pub funding_time: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone)]
 pub enum OkexMarginMode {
     Cross,
@@ -283,6 +408,7 @@ impl Display for OkexOrderSide {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OkexOrderType {
     Market,
     Limit,
@@ -292,6 +418,18 @@ pub enum OkexOrderType {
     OptimalLimitIoc,
 }
 
+impl OkexOrderType {
+    pub fn requires_price(&self) -> bool {
+        matches!(
+            self,
+            OkexOrderType::Limit
+                | OkexOrderType::PostOnly
+                | OkexOrderType::Fok
+                | OkexOrderType::Ioc
+        )
+    }
+}
+
 impl Display for OkexOrderType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match *self {
@@ -309,6 +447,7 @@ impl Display for OkexOrderType {
 pub enum TradeCurrency {
     BTC,
     USD,
+    USDT,
 }
 
 impl Display for TradeCurrency {
@@ -316,6 +455,7 @@ impl Display for TradeCurrency {
         match *self {
             TradeCurrency::BTC => write!(f, "BTC"),
             TradeCurrency::USD => write!(f, "USD"),
+            TradeCurrency::USDT => write!(f, "USDT"),
         }
     }
 }
@@ -0,0 +1,268 @@
+use chrono::Utc;
+use futures::{SinkExt, Stream, StreamExt};
+use rust_decimal::Decimal;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+use std::{pin::Pin, time::Duration};
+
+use super::{okex_response::*, position_size, OkexClient};
+use crate::{AvailableBalance, OkexClientError, OkexInstrumentId, OrderDetails, PositionSize};
+
+pub const OKEX_PRIVATE_WS_URL: &str = "wss://ws.okx.com:8443/ws/v5/private";
+const OKEX_SIMULATED_PRIVATE_WS_URL: &str = "wss://wspap.okx.com:8443/ws/v5/private?brokerId=9999";
+// Okex drops connections that stay silent for 30 seconds
+const PING_INTERVAL: Duration = Duration::from_secs(20);
+
+#[derive(Debug, Clone)]
+pub enum OkexPrivateEvent {
+    Position(PositionSize),
+    Order(OrderDetails),
+    TradingBalance(AvailableBalance),
+    Heartbeat,
+}
+
+pub type OkexPrivateStream = Pin<Box<dyn Stream<Item = OkexPrivateEvent> + Send>>;
+
+impl OkexClient {
+    /// Logs into the private websocket and subscribes to the position of the client's swap,
+    /// its orders and the BTC trading balance. The stream ends when the connection drops.
+    pub async fn subscribe_private_channels(&self) -> Result<OkexPrivateStream, OkexClientError> {
+        let (ws_stream, _) = connect_async(self.private_ws_url()).await?;
+        let (mut sender, mut receiver) = ws_stream.split();
+
+        sender.send(Message::Text(self.login_message())).await?;
+        loop {
+            let msg = receiver.next().await.ok_or_else(|| {
+                OkexClientError::WebsocketLogin("connection closed before login".to_string())
+            })??;
+            if let Ok(PrivateWsEvent { event, code, msg }) =
+                serde_json::from_str::<PrivateWsEvent>(msg.to_text().unwrap_or_default())
+            {
+                match event.as_str() {
+                    "login" if code == "0" => break,
+                    "login" | "error" => {
+                        return Err(OkexClientError::WebsocketLogin(format!("{code} - {msg}")))
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        let subscribe_args = serde_json::json!({
+            "op": "subscribe",
+            "args": [
+                {
+                    "channel": "positions",
+                    "instType": "SWAP",
+                    "instId": self.instrument_id.to_string()
+                },
+                {
+                    "channel": "orders",
+                    "instType": "SWAP",
+                    "instId": self.instrument_id.to_string()
+                },
+                {
+                    "channel": "account",
+                    "ccy": "BTC"
+                }
+            ]
+        })
+        .to_string();
+        sender.send(Message::Text(subscribe_args)).await?;
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(PING_INTERVAL).await;
+                if sender
+                    .send(Message::Text("ping".to_string()))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let instrument_id = self.instrument_id;
+        Ok(Box::pin(
+            receiver
+                .take_while(|message| futures::future::ready(message.is_ok()))
+                .filter_map(move |message| async move {
+                    message
+                        .ok()
+                        .and_then(|msg| msg.into_text().ok())
+                        .map(|text| {
+                            futures::stream::iter(parse_private_message(instrument_id, &text))
+                        })
+                })
+                .flatten(),
+        ))
+    }
+
+    fn private_ws_url(&self) -> String {
+        if self.config.simulated && self.config.private_ws_url == OKEX_PRIVATE_WS_URL {
+            OKEX_SIMULATED_PRIVATE_WS_URL.to_string()
+        } else {
+            self.config.private_ws_url.clone()
+        }
+    }
+
+    fn login_message(&self) -> String {
+        let timestamp = Utc::now().timestamp().to_string();
+        let sign = self.sign_okex_request(format!("{timestamp}GET/users/self/verify"));
+        serde_json::json!({
+            "op": "login",
+            "args": [
+                {
+                    "apiKey": self.config.api_key,
+                    "passphrase": self.config.passphrase,
+                    "timestamp": timestamp,
+                    "sign": sign
+                }
+            ]
+        })
+        .to_string()
+    }
+}
+
+fn parse_private_message(instrument_id: OkexInstrumentId, text: &str) -> Vec<OkexPrivateEvent> {
+    if text == "pong" {
+        return vec![OkexPrivateEvent::Heartbeat];
+    }
+    let Ok(PrivateWsPush { arg, data }) = serde_json::from_str::<PrivateWsPush>(text) else {
+        return Vec::new();
+    };
+    match arg.channel.as_str() {
+        "positions" => {
+            let positions: Vec<PositionChannelData> = data
+                .into_iter()
+                .filter_map(|entry| serde_json::from_value(entry).ok())
+                .collect();
+            if positions.is_empty() {
+                return vec![OkexPrivateEvent::Position(PositionSize {
+                    instrument_id,
+                    usd_cents: Decimal::ZERO,
+                    last_price_in_usd_cents: Decimal::ZERO,
+                    unrealized_pnl_btc: Decimal::ZERO,
+                })];
+            }
+            positions
+                .into_iter()
+                .filter(|position| position.inst_id == instrument_id.to_string())
+                .filter_map(|position| {
+                    position_size(
+                        instrument_id,
+                        &position.pos,
+                        &position.notional_usd,
+                        &position.last,
+                        &position.upl,
+                    )
+                    .ok()
+                })
+                .map(OkexPrivateEvent::Position)
+                .collect()
+        }
+        "orders" => data
+            .into_iter()
+            .filter_map(|entry| serde_json::from_value::<OrderDetails>(entry).ok())
+            .map(|details| OkexPrivateEvent::Order(details.with_completion()))
+            .collect(),
+        "account" => data
+            .into_iter()
+            .filter_map(|entry| serde_json::from_value::<AccountChannelData>(entry).ok())
+            .map(|account| {
+                let balance = account
+                    .details
+                    .into_iter()
+                    .find(|details| details.ccy == "BTC")
+                    .map(|details| AvailableBalance {
+                        free_amt_in_btc: details.avail_eq,
+                        used_amt_in_btc: details.frozen_bal,
+                        total_amt_in_btc: details.eq,
+                    })
+                    .unwrap_or(AvailableBalance {
+                        free_amt_in_btc: Decimal::ZERO,
+                        used_amt_in_btc: Decimal::ZERO,
+                        total_amt_in_btc: Decimal::ZERO,
+                    });
+                OkexPrivateEvent::TradingBalance(balance)
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn position_push() {
+        let text = r#"{"arg":{"channel":"positions","instType":"SWAP","instId":"BTC-USD-SWAP","uid":"1"},"data":[{"instId":"BTC-USD-SWAP","pos":"-40","notionalUsd":"4000","last":"32000","upl":"0.025","mgnMode":"cross"}]}"#;
+        let events = parse_private_message(OkexInstrumentId::BtcUsdSwap, text);
+        let [OkexPrivateEvent::Position(position)] = &events[..] else {
+            panic!("expected a single position event, got {events:?}");
+        };
+        assert_eq!(position.usd_cents, dec!(-400_000));
+        assert_eq!(position.last_price_in_usd_cents, dec!(3_200_000));
+        assert_eq!(position.unrealized_pnl_btc, dec!(0.025));
+    }
+
+    #[test]
+    fn linear_position_push() {
+        let text = r#"{"arg":{"channel":"positions","instType":"SWAP","instId":"BTC-USDT-SWAP"},"data":[{"instId":"BTC-USDT-SWAP","pos":"-10","notionalUsd":"4000","last":"40000","upl":"100","mgnMode":"cross"}]}"#;
+        assert!(parse_private_message(OkexInstrumentId::BtcUsdSwap, text).is_empty());
+        let events = parse_private_message(OkexInstrumentId::BtcUsdtSwap, text);
+        let [OkexPrivateEvent::Position(position)] = &events[..] else {
+            panic!("expected a single position event, got {events:?}");
+        };
+        assert_eq!(position.instrument_id, OkexInstrumentId::BtcUsdtSwap);
+        assert_eq!(position.usd_cents, dec!(-400_000));
+        assert_eq!(position.unrealized_pnl_btc, dec!(0.0025));
+    }
+
+    #[test]
+    fn empty_position_push() {
+        let text = r#"{"arg":{"channel":"positions","instType":"SWAP"},"data":[]}"#;
+        let events = parse_private_message(OkexInstrumentId::BtcUsdSwap, text);
+        let [OkexPrivateEvent::Position(position)] = &events[..] else {
+            panic!("expected a single position event, got {events:?}");
+        };
+        assert_eq!(position.usd_cents, Decimal::ZERO);
+    }
+
+    #[test]
+    fn order_push() {
+        let text = r#"{"arg":{"channel":"orders","instType":"SWAP"},"data":[{"clOrdId":"abc","ordId":"123","avgPx":"43000","fee":"-0.00001","sz":"10","accFillSz":"10","px":"","ordType":"market","state":"filled","instId":"BTC-USD-SWAP"}]}"#;
+        let events = parse_private_message(OkexInstrumentId::BtcUsdSwap, text);
+        let [OkexPrivateEvent::Order(details)] = &events[..] else {
+            panic!("expected a single order event, got {events:?}");
+        };
+        assert!(details.complete);
+        assert_eq!(details.acc_fill_sz, dec!(10));
+    }
+
+    #[test]
+    fn account_push() {
+        let text = r#"{"arg":{"channel":"account","ccy":"BTC"},"data":[{"totalEq":"41624","details":[{"ccy":"BTC","availEq":"0.9","eq":"1.02","frozenBal":"0.12","upl":""}]}]}"#;
+        let events = parse_private_message(OkexInstrumentId::BtcUsdSwap, text);
+        let [OkexPrivateEvent::TradingBalance(balance)] = &events[..] else {
+            panic!("expected a single balance event, got {events:?}");
+        };
+        assert_eq!(balance.total_amt_in_btc, dec!(1.02));
+        assert_eq!(balance.used_amt_in_btc, dec!(0.12));
+    }
+
+    #[test]
+    fn acknowledgements_are_ignored() {
+        let text =
+            r#"{"event":"subscribe","arg":{"channel":"account","ccy":"BTC"},"connId":"a4d3ae55"}"#;
+        assert!(parse_private_message(OkexInstrumentId::BtcUsdSwap, text).is_empty());
+        assert!(matches!(
+            parse_private_message(OkexInstrumentId::BtcUsdSwap, "pong")[..],
+            [OkexPrivateEvent::Heartbeat]
+        ));
+    }
+}
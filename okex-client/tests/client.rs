@@ -16,6 +16,7 @@ async fn configured_okex_client() -> anyhow::Result<OkexClient> {
         passphrase,
         secret_key,
         simulated: true,
+        ..Default::default()
     })
     .await?;
 
@@ -58,6 +59,7 @@ async fn client_is_missing_header() -> anyhow::Result<()> {
         passphrase: "".to_string(),
         secret_key: "".to_string(),
         simulated: true,
+        ..Default::default()
     })
     .await;
 
@@ -115,16 +117,12 @@ async fn unknown_client_order_id() -> anyhow::Result<()> {
 #[tokio::test]
 #[ignore = "only works against real okex client"]
 async fn deposit_status() -> anyhow::Result<()> {
-    if let (Ok(deposit_addr), Ok(deposit_amount)) = (
-        env::var("OKEX_DEPOSIT_ADDRESS"),
-        env::var("OKEX_DEPOSIT_AMOUNT"),
-    ) {
-        let amt = Decimal::from_str_exact(&deposit_amount)?;
+    if let Ok(tx_id) = env::var("OKEX_DEPOSIT_TX_ID") {
         let client = configured_okex_client().await?;
 
-        let deposit = client.fetch_deposit(deposit_addr, amt).await?;
+        let deposit = client.fetch_deposit_by_tx_id(tx_id).await?;
 
-        assert_eq!(deposit.state, "2".to_string());
+        assert_eq!(deposit.map(|d| d.state), Some("success".to_string()));
     }
     Ok(())
 }
@@ -226,6 +224,55 @@ async fn open_close_position() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+#[serial]
+#[ignore = "avoid rate limit"]
+async fn passive_order_amend_and_cancel() -> anyhow::Result<()> {
+    let client = configured_okex_client().await?;
+    let last_price = client.get_last_price_in_usd_cents().await?;
+    let far_below_market = (last_price.usd_cents / dec!(200)).round_dp(1);
+
+    let id = ClientOrderId::new();
+    client
+        .place_limit_order(
+            id.clone(),
+            OkexOrderSide::Buy,
+            &BtcUsdSwapContracts::from(1),
+            OkexOrderType::PostOnly,
+            far_below_market,
+        )
+        .await?;
+    let details = client.order_details(id.clone()).await?;
+    assert_eq!(details.state, "live");
+    assert!(!details.complete);
+
+    client
+        .amend_order_price(id.clone(), far_below_market - dec!(1))
+        .await?;
+    client.cancel_order(id.clone()).await?;
+    let details = client.order_details(id).await?;
+    assert!(details.complete);
+    assert_eq!(details.acc_fill_sz, dec!(0));
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+#[ignore = "avoid rate limit"]
+async fn account_bills() -> anyhow::Result<()> {
+    let client = configured_okex_client().await?;
+
+    let bills = client.account_bills(None).await?;
+    assert!(bills.len() <= OKEX_BILLS_PAGE_LIMIT);
+    if let Some(oldest) = bills.last() {
+        let older = client.account_bills(Some(oldest.bill_id.clone())).await?;
+        assert!(older.iter().all(|bill| bill.bill_id != oldest.bill_id));
+    }
+
+    Ok(())
+}
+
 #[tokio::test]
 #[serial]
 #[ignore = "avoid rate limit"]
@@ -0,0 +1,334 @@
+use futures::StreamExt;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use okex_client::*;
+use okex_mock::OkexMock;
+
+async fn mocked_okex_client() -> anyhow::Result<(OkexMock, OkexClient)> {
+    let mock = OkexMock::start().await;
+    let client = OkexClient::new(OkexClientConfig {
+        api_key: "mock".to_string(),
+        passphrase: "mock".to_string(),
+        secret_key: "mock".to_string(),
+        simulated: false,
+        api_url: mock.url(),
+        private_ws_url: mock.private_ws_url(),
+    })
+    .await?;
+    Ok((mock, client))
+}
+
+#[tokio::test]
+async fn mock_rejects_missing_header() -> anyhow::Result<()> {
+    let mock = OkexMock::start().await;
+    let client = OkexClient::new(OkexClientConfig {
+        api_url: mock.url(),
+        ..Default::default()
+    })
+    .await;
+
+    if let Err(OkexClientError::UnexpectedResponse { msg, .. }) = client {
+        assert!(msg.contains("header"));
+    } else {
+        panic!("expected the missing header to be rejected")
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn mock_hedge_cycle() -> anyhow::Result<()> {
+    let (mock, client) = mocked_okex_client().await?;
+    mock.account().trading_btc = dec!(1);
+    client.check_leverage(dec!(4)).await?;
+
+    let open_id = ClientOrderId::new();
+    client
+        .place_order(
+            open_id.clone(),
+            OkexOrderSide::Sell,
+            &BtcUsdSwapContracts::from(40),
+        )
+        .await?;
+    let details = client.order_details(open_id).await?;
+    assert!(details.complete);
+    assert_eq!(details.avg_px, dec!(40_000));
+
+    mock.set_last_price(dec!(32_000));
+    let position = client.get_position_in_signed_usd_cents().await?;
+    assert_eq!(position.usd_cents, dec!(-400_000));
+    assert_eq!(position.last_price_in_usd_cents, dec!(3_200_000));
+    assert_eq!(position.unrealized_pnl_btc, dec!(0.025));
+
+    let passive_id = ClientOrderId::new();
+    client
+        .place_limit_order(
+            passive_id.clone(),
+            OkexOrderSide::Buy,
+            &BtcUsdSwapContracts::from(10),
+            OkexOrderType::PostOnly,
+            dec!(31_900),
+        )
+        .await?;
+    client
+        .amend_order_price(passive_id.clone(), dec!(31_950))
+        .await?;
+    assert!(!client.order_details(passive_id.clone()).await?.complete);
+    mock.set_last_price(dec!(31_950));
+    let details = client.order_details(passive_id.clone()).await?;
+    assert_eq!(details.state, "filled");
+    assert_eq!(details.acc_fill_sz, dec!(10));
+    client.cancel_order(passive_id).await?;
+    assert_eq!(mock.position_contracts(), -30);
+
+    client.close_positions(ClientOrderId::new()).await?;
+    client.close_positions(ClientOrderId::new()).await?;
+    let position = client.get_position_in_signed_usd_cents().await?;
+    assert_eq!(position.usd_cents, Decimal::ZERO);
+
+    let bills = client.account_bills(None).await?;
+    assert_eq!(bills.len(), 3);
+    assert!(bills
+        .iter()
+        .all(|bill| bill.bill_type == OkexBillType::Trade
+            && bill.currency == "BTC"
+            && bill.fee < Decimal::ZERO));
+    let realized: Decimal = bills.iter().map(|bill| bill.pnl).sum();
+    assert!(realized > Decimal::ZERO);
+    let older = client.account_bills(Some(bills[1].bill_id.clone())).await?;
+    assert_eq!(older.len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn mock_funding_cycle() -> anyhow::Result<()> {
+    let (mock, client) = mocked_okex_client().await?;
+    assert!(!client.get_funding_deposit_address().await?.value.is_empty());
+    assert!(client
+        .fetch_deposit_by_tx_id("unknown-tx".to_string())
+        .await?
+        .is_none());
+    let tx_id = mock.credit_deposit(dec!(1.5));
+    let deposit = client
+        .fetch_deposit_by_tx_id(tx_id.clone())
+        .await?
+        .expect("deposit not found");
+    assert_eq!(deposit.state, "success");
+    assert_eq!(deposit.transaction_id, tx_id);
+
+    let to_trading = ClientTransferId::new();
+    client
+        .transfer_funding_to_trading(to_trading.clone(), dec!(1))
+        .await?;
+    let state = client.transfer_state_by_client_id(to_trading).await?;
+    assert_eq!(state.state, "success");
+    assert_eq!(
+        client.trading_account_balance().await?.total_amt_in_btc,
+        dec!(1)
+    );
+
+    let to_funding = ClientTransferId::new();
+    let transfer_id = client
+        .transfer_trading_to_funding(to_funding, dec!(0.25))
+        .await?;
+    assert_eq!(client.transfer_state(transfer_id).await?.state, "success");
+    assert_eq!(
+        client.funding_account_balance().await?.total_amt_in_btc,
+        dec!(0.75)
+    );
+
+    let fees = client.get_onchain_fees().await?;
+    let withdrawal = ClientTransferId::new();
+    client
+        .withdraw_btc_onchain(
+            withdrawal.clone(),
+            dec!(0.5),
+            fees.min_fee,
+            "bcrt1qwithdrawal".to_string(),
+        )
+        .await?;
+    let status = client.fetch_withdrawal_by_client_id(withdrawal).await?;
+    assert_eq!(status.state, "success");
+    assert!(matches!(
+        client
+            .fetch_withdrawal_by_client_id(ClientTransferId::new())
+            .await,
+        Err(OkexClientError::ParameterClientIdNotFound)
+    ));
+    assert_eq!(
+        client.funding_account_balance().await?.total_amt_in_btc,
+        dec!(0.75) - dec!(0.5) - fees.min_fee
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn mock_private_channels() -> anyhow::Result<()> {
+    let (mock, client) = mocked_okex_client().await?;
+    mock.account().trading_btc = dec!(1);
+    let mut stream = client.subscribe_private_channels().await?;
+
+    let mut initial_position = None;
+    while initial_position.is_none() {
+        if let Some(OkexPrivateEvent::Position(position)) = stream.next().await {
+            initial_position = Some(position);
+        }
+    }
+    assert_eq!(initial_position.unwrap().usd_cents, Decimal::ZERO);
+
+    let id = ClientOrderId::new();
+    client
+        .place_order(
+            id.clone(),
+            OkexOrderSide::Sell,
+            &BtcUsdSwapContracts::from(5),
+        )
+        .await?;
+
+    let (mut order, mut position, mut balance) = (None, None, None);
+    while order.is_none() || position.is_none() || balance.is_none() {
+        match stream.next().await.expect("stream ended") {
+            OkexPrivateEvent::Order(details) => order = Some(details),
+            OkexPrivateEvent::Position(size) => position = Some(size),
+            OkexPrivateEvent::TradingBalance(available) => balance = Some(available),
+            OkexPrivateEvent::Heartbeat => (),
+        }
+    }
+    let order = order.unwrap();
+    assert_eq!(String::from(order.cl_ord_id), String::from(id));
+    assert!(order.complete);
+    assert_eq!(position.unwrap().usd_cents, dec!(-50_000));
+    assert!(balance.unwrap().total_amt_in_btc < dec!(1));
+    Ok(())
+}
+
+#[tokio::test]
+async fn mock_funding_rates() -> anyhow::Result<()> {
+    let (mock, client) = mocked_okex_client().await?;
+    mock.account()
+        .set_funding_rate("BTC-USDT-SWAP", dec!(0.0003));
+
+    let inverse = client.funding_rate().await?;
+    assert_eq!(inverse.instrument_id, OkexInstrumentId::BtcUsdSwap);
+    assert_eq!(inverse.rate, dec!(0.0001));
+
+    let linear = client
+        .for_instrument(OkexInstrumentId::BtcUsdtSwap)
+        .funding_rate()
+        .await?;
+    assert_eq!(linear.instrument_id, OkexInstrumentId::BtcUsdtSwap);
+    assert_eq!(linear.rate, dec!(0.0003));
+    Ok(())
+}
+
+#[tokio::test]
+async fn mock_account_setup() -> anyhow::Result<()> {
+    let mock = OkexMock::start().await;
+    {
+        let mut account = mock.account();
+        account.acct_lv = "1".to_string();
+        account.pos_mode = "long_short_mode".to_string();
+        account.leverage = dec!(3);
+    }
+    let config = OkexClientConfig {
+        api_key: "mock".to_string(),
+        passphrase: "mock".to_string(),
+        secret_key: "mock".to_string(),
+        simulated: false,
+        api_url: mock.url(),
+        private_ws_url: mock.private_ws_url(),
+    };
+    assert!(matches!(
+        OkexClient::new(config.clone()).await,
+        Err(OkexClientError::MisconfiguredAccount(_))
+    ));
+
+    let client = OkexClient::new_unchecked(config.clone())?;
+    let changes = client
+        .account_changes(dec!(4), &[OkexInstrumentId::BtcUsdSwap])
+        .await?;
+    assert_eq!(
+        changes,
+        vec![
+            OkexAccountChange::AccountLevel {
+                current: "1".to_string()
+            },
+            OkexAccountChange::PositionMode {
+                current: "long_short_mode".to_string()
+            },
+            OkexAccountChange::Leverage {
+                instrument_id: OkexInstrumentId::BtcUsdSwap,
+                current: dec!(3),
+                expected: dec!(4)
+            },
+        ]
+    );
+    client.apply_account_changes(&changes).await?;
+
+    let client = OkexClient::new(config).await?;
+    assert!(client
+        .account_changes(dec!(4), &[OkexInstrumentId::BtcUsdSwap])
+        .await?
+        .is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn mock_position_risk() -> anyhow::Result<()> {
+    let (mock, client) = mocked_okex_client().await?;
+    let risk = client.get_position_risk().await?;
+    assert_eq!(risk.liquidation_distance(), None);
+    assert_eq!(risk.margin_ratio, None);
+
+    mock.account().trading_btc = dec!(0.05);
+    client
+        .place_order(
+            ClientOrderId::new(),
+            OkexOrderSide::Sell,
+            &BtcUsdSwapContracts::from(40),
+        )
+        .await?;
+    let risk = client.get_position_risk().await?;
+    let liquidation_price = risk.liquidation_price_in_usd_cents.unwrap();
+    assert!(liquidation_price > dec!(4_000_000));
+    assert_eq!(risk.mark_price_in_usd_cents, Some(dec!(4_000_000)));
+    assert!(risk.margin_ratio.unwrap() > Decimal::ONE);
+
+    mock.set_last_price(dec!(70_000));
+    let closer = client.get_position_risk().await?;
+    assert!(closer.liquidation_distance() < risk.liquidation_distance());
+    Ok(())
+}
+
+#[tokio::test]
+async fn mock_lightning_funding() -> anyhow::Result<()> {
+    let (mock, client) = mocked_okex_client().await?;
+    let invoice = client.lightning_deposit_invoice(dec!(0.02)).await?;
+    let tx_id = mock
+        .pay_lightning_invoice(&invoice.value)
+        .expect("invoice not issued by okex");
+    let deposit = client
+        .fetch_deposit_by_tx_id(tx_id)
+        .await?
+        .expect("deposit not found");
+    assert_eq!(deposit.state, "success");
+    assert_eq!(
+        client.funding_account_balance().await?.total_amt_in_btc,
+        dec!(0.02)
+    );
+
+    let withdrawal = client
+        .withdraw_lightning("lnlocal:0.015:1".to_string(), "withdrawal".to_string())
+        .await?;
+    let status = client.fetch_withdrawal_by_id(withdrawal.value).await?;
+    assert_eq!(status.state, "success");
+    assert!(client
+        .withdraw_lightning("lnlocal:1:2".to_string(), "withdrawal".to_string())
+        .await
+        .is_err());
+    assert_eq!(
+        client.funding_account_balance().await?.total_amt_in_btc,
+        dec!(0.005)
+    );
+    Ok(())
+}
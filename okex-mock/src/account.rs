@@ -0,0 +1,826 @@
+use chrono::Utc;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use std::collections::HashMap;
+
+pub const CONTRACT_SIZE_USD: Decimal = dec!(100);
+pub const TAKER_FEE_RATE: Decimal = dec!(0.0005);
+pub const MAKER_FEE_RATE: Decimal = dec!(0.0002);
+pub const DEPOSIT_ADDRESS: &str = "tb1qmockokexdepositaddress000000000000000";
+pub const DEFAULT_FUNDING_RATE: Decimal = dec!(0.0001);
+pub const MAINTENANCE_MARGIN_RATE: Decimal = dec!(0.004);
+pub const ONCHAIN: &str = "BTC-Bitcoin";
+pub const LIGHTNING: &str = "BTC-Lightning";
+const BTC_DECIMALS: u32 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl Side {
+    fn sign(&self) -> i64 {
+        match self {
+            Side::Buy => 1,
+            Side::Sell => -1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderState {
+    Live,
+    Filled,
+    Canceled,
+}
+
+impl std::fmt::Display for OrderState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderState::Live => write!(f, "live"),
+            OrderState::Filled => write!(f, "filled"),
+            OrderState::Canceled => write!(f, "canceled"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MockOrder {
+    pub ord_id: String,
+    pub cl_ord_id: String,
+    pub side: Side,
+    pub ord_type: String,
+    pub sz: u32,
+    pub px: Decimal,
+    pub state: OrderState,
+    pub avg_px: Decimal,
+    pub fee: Decimal,
+    pub revision: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct MockBill {
+    pub bill_id: u64,
+    pub bill_type: &'static str,
+    pub bal_chg: Decimal,
+    pub fee: Decimal,
+    pub pnl: Decimal,
+    pub ord_id: String,
+    pub ts: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct MockTransfer {
+    pub trans_id: String,
+    pub client_id: String,
+    pub from: String,
+    pub to: String,
+    pub amt: Decimal,
+}
+
+#[derive(Debug, Clone)]
+pub struct MockDeposit {
+    pub dep_id: String,
+    pub chain: &'static str,
+    pub to: String,
+    pub amt: Decimal,
+    pub tx_id: String,
+    pub ts: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct MockWithdrawal {
+    pub wd_id: String,
+    pub chain: &'static str,
+    pub client_id: String,
+    pub to: String,
+    pub amt: Decimal,
+    pub tx_id: String,
+    pub ts: i64,
+}
+
+/// A rejection in okex terms, ie. the `sCode` / `code` and message okex would return
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rejection {
+    pub code: &'static str,
+    pub msg: &'static str,
+}
+
+const DUPLICATED_CLIENT_ORDER_ID: Rejection = Rejection {
+    code: "51016",
+    msg: "Duplicated clOrdId",
+};
+const ORDER_DOES_NOT_EXIST: Rejection = Rejection {
+    code: "51603",
+    msg: "Order does not exist",
+};
+const ORDER_ALREADY_COMPLETED: Rejection = Rejection {
+    code: "51400",
+    msg: "Cancellation failed as the order has been filled, canceled or does not exist",
+};
+const AMEND_FAILED: Rejection = Rejection {
+    code: "51503",
+    msg: "Order modification failed as the order has been filled, canceled or does not exist",
+};
+const POSITION_DOES_NOT_EXIST: Rejection = Rejection {
+    code: "51023",
+    msg: "Position does not exist",
+};
+const INSUFFICIENT_BALANCE: Rejection = Rejection {
+    code: "58350",
+    msg: "Insufficient balance",
+};
+const UNSUPPORTED_ACCOUNT: Rejection = Rejection {
+    code: "58123",
+    msg: "Parameter from or to is invalid",
+};
+const INVALID_INVOICE: Rejection = Rejection {
+    code: "58358",
+    msg: "Invalid invoice",
+};
+const POSITIONS_OPEN: Rejection = Rejection {
+    code: "59000",
+    msg: "Settings failed. Close any open positions or orders before modifying settings.",
+};
+
+/// In memory state of a single okex account trading BTC-USD-SWAP in net mode.
+/// Market orders fill at the last price, limit orders rest until the last
+/// price crosses them.
+#[derive(Debug)]
+pub struct MockAccount {
+    pub acct_lv: String,
+    pub pos_mode: String,
+    pub last_price: Decimal,
+    pub leverage: Decimal,
+    pub position_contracts: i64,
+    pub avg_px: Decimal,
+    pub trading_btc: Decimal,
+    /// USDT margin for the linear swap, held aside from the btc equity
+    pub trading_usdt: Decimal,
+    pub funding_btc: Decimal,
+    funding_rates: HashMap<String, Decimal>,
+    orders: HashMap<String, MockOrder>,
+    bills: Vec<MockBill>,
+    transfers: Vec<MockTransfer>,
+    deposits: Vec<MockDeposit>,
+    withdrawals: Vec<MockWithdrawal>,
+    lightning_invoices: HashMap<String, Decimal>,
+    next_id: u64,
+    revision: u64,
+}
+
+impl Default for MockAccount {
+    fn default() -> Self {
+        Self {
+            acct_lv: "2".to_string(),
+            pos_mode: "net_mode".to_string(),
+            last_price: dec!(40_000),
+            leverage: dec!(4),
+            position_contracts: 0,
+            avg_px: Decimal::ZERO,
+            trading_btc: Decimal::ZERO,
+            trading_usdt: Decimal::ZERO,
+            funding_btc: Decimal::ZERO,
+            funding_rates: HashMap::new(),
+            orders: HashMap::new(),
+            bills: Vec::new(),
+            transfers: Vec::new(),
+            deposits: Vec::new(),
+            withdrawals: Vec::new(),
+            lightning_invoices: HashMap::new(),
+            next_id: 1,
+            revision: 0,
+        }
+    }
+}
+
+impl MockAccount {
+    /// Increases on every change to the account, used to push updates over the websocket
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn orders_since(&self, revision: u64) -> Vec<&MockOrder> {
+        let mut orders: Vec<_> = self
+            .orders
+            .values()
+            .filter(|order| order.revision > revision)
+            .collect();
+        orders.sort_by_key(|order| order.revision);
+        orders
+    }
+
+    /// Instruments without an explicit rate pay 0.01% per funding period
+    pub fn funding_rate(&self, instrument_id: &str) -> Decimal {
+        self.funding_rates
+            .get(instrument_id)
+            .copied()
+            .unwrap_or(DEFAULT_FUNDING_RATE)
+    }
+
+    pub fn set_funding_rate(&mut self, instrument_id: &str, rate: Decimal) {
+        self.funding_rates.insert(instrument_id.to_string(), rate);
+    }
+
+    /// Like okex the account level and position mode only change while flat
+    pub fn set_account_level(&mut self, acct_lv: &str) -> Result<(), Rejection> {
+        self.ensure_flat()?;
+        self.acct_lv = acct_lv.to_string();
+        Ok(())
+    }
+
+    pub fn set_position_mode(&mut self, pos_mode: &str) -> Result<(), Rejection> {
+        self.ensure_flat()?;
+        self.pos_mode = pos_mode.to_string();
+        Ok(())
+    }
+
+    fn ensure_flat(&self) -> Result<(), Rejection> {
+        let resting_orders = self
+            .orders
+            .values()
+            .any(|order| order.state == OrderState::Live);
+        if self.position_contracts != 0 || resting_orders {
+            return Err(POSITIONS_OPEN);
+        }
+        Ok(())
+    }
+
+    pub fn set_last_price(&mut self, price: Decimal) {
+        self.last_price = price;
+        self.touch();
+        let crossed: Vec<String> = self
+            .orders
+            .values()
+            .filter(|order| order.state == OrderState::Live && self.crosses(order.side, order.px))
+            .map(|order| order.cl_ord_id.clone())
+            .collect();
+        for cl_ord_id in crossed {
+            let (side, sz, px) = {
+                let order = &self.orders[&cl_ord_id];
+                (order.side, order.sz, order.px)
+            };
+            self.fill(&cl_ord_id, side, sz, px, MAKER_FEE_RATE);
+        }
+    }
+
+    pub fn place_order(
+        &mut self,
+        cl_ord_id: String,
+        side: Side,
+        ord_type: String,
+        sz: u32,
+        px: Option<Decimal>,
+    ) -> Result<String, Rejection> {
+        if self.orders.contains_key(&cl_ord_id) {
+            return Err(DUPLICATED_CLIENT_ORDER_ID);
+        }
+        let ord_id = self.next_id().to_string();
+        let px = px.unwrap_or(Decimal::ZERO);
+        let revision = self.touch();
+        self.orders.insert(
+            cl_ord_id.clone(),
+            MockOrder {
+                ord_id: ord_id.clone(),
+                cl_ord_id: cl_ord_id.clone(),
+                side,
+                ord_type: ord_type.clone(),
+                sz,
+                px,
+                state: OrderState::Live,
+                avg_px: Decimal::ZERO,
+                fee: Decimal::ZERO,
+                revision,
+            },
+        );
+        match ord_type.as_str() {
+            "market" => self.fill(&cl_ord_id, side, sz, self.last_price, TAKER_FEE_RATE),
+            // okex cancels post only orders that would take liquidity
+            "post_only" if self.crosses(side, px) => {
+                self.set_state(&cl_ord_id, OrderState::Canceled)
+            }
+            _ if self.crosses(side, px) => {
+                self.fill(&cl_ord_id, side, sz, self.last_price, TAKER_FEE_RATE)
+            }
+            _ => (),
+        }
+        Ok(ord_id)
+    }
+
+    pub fn amend_order(&mut self, cl_ord_id: &str, new_px: Decimal) -> Result<(), Rejection> {
+        let revision = self.touch();
+        let (side, sz, post_only) = match self.orders.get_mut(cl_ord_id) {
+            Some(order) if order.state == OrderState::Live => {
+                order.px = new_px;
+                order.revision = revision;
+                (order.side, order.sz, order.ord_type == "post_only")
+            }
+            _ => return Err(AMEND_FAILED),
+        };
+        if self.crosses(side, new_px) {
+            if post_only {
+                self.set_state(cl_ord_id, OrderState::Canceled);
+            } else {
+                self.fill(cl_ord_id, side, sz, self.last_price, TAKER_FEE_RATE);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn cancel_order(&mut self, cl_ord_id: &str) -> Result<(), Rejection> {
+        match self.orders.get(cl_ord_id) {
+            Some(order) if order.state == OrderState::Live => {
+                self.set_state(cl_ord_id, OrderState::Canceled);
+                Ok(())
+            }
+            _ => Err(ORDER_ALREADY_COMPLETED),
+        }
+    }
+
+    pub fn order(&self, cl_ord_id: &str) -> Result<&MockOrder, Rejection> {
+        self.orders.get(cl_ord_id).ok_or(ORDER_DOES_NOT_EXIST)
+    }
+
+    pub fn close_position(&mut self, cl_ord_id: Option<String>) -> Result<(), Rejection> {
+        if self.position_contracts == 0 {
+            return Err(POSITION_DOES_NOT_EXIST);
+        }
+        let side = if self.position_contracts > 0 {
+            Side::Sell
+        } else {
+            Side::Buy
+        };
+        let sz = u32::try_from(self.position_contracts.unsigned_abs()).unwrap_or(u32::MAX);
+        let cl_ord_id = cl_ord_id.unwrap_or_else(|| format!("close-{}", self.next_id));
+        self.place_order(cl_ord_id, side, "market".to_string(), sz, None)
+            .map(|_| ())
+    }
+
+    pub fn transfer(
+        &mut self,
+        client_id: String,
+        from: String,
+        to: String,
+        amt: Decimal,
+    ) -> Result<String, Rejection> {
+        let (source, destination) = match (from.as_str(), to.as_str()) {
+            ("6", "18") => (&mut self.funding_btc, &mut self.trading_btc),
+            ("18", "6") => (&mut self.trading_btc, &mut self.funding_btc),
+            _ => return Err(UNSUPPORTED_ACCOUNT),
+        };
+        if *source < amt {
+            return Err(INSUFFICIENT_BALANCE);
+        }
+        *source -= amt;
+        *destination += amt;
+        self.touch();
+        let trans_id = self.next_id().to_string();
+        self.transfers.push(MockTransfer {
+            trans_id: trans_id.clone(),
+            client_id,
+            from,
+            to,
+            amt,
+        });
+        Ok(trans_id)
+    }
+
+    pub fn transfer_by(
+        &self,
+        trans_id: Option<&str>,
+        client_id: Option<&str>,
+    ) -> Option<&MockTransfer> {
+        self.transfers.iter().find(|transfer| {
+            trans_id.is_some_and(|id| transfer.trans_id == id)
+                || client_id.is_some_and(|id| transfer.client_id == id)
+        })
+    }
+
+    pub fn withdraw(
+        &mut self,
+        client_id: String,
+        to: String,
+        amt: Decimal,
+        fee: Decimal,
+    ) -> Result<String, Rejection> {
+        if self.funding_btc < amt + fee {
+            return Err(INSUFFICIENT_BALANCE);
+        }
+        self.funding_btc -= amt + fee;
+        self.touch();
+        let wd_id = self.next_id().to_string();
+        self.withdrawals.push(MockWithdrawal {
+            tx_id: format!("mock-withdrawal-tx-{wd_id}"),
+            wd_id: wd_id.clone(),
+            chain: ONCHAIN,
+            client_id,
+            to,
+            amt,
+            ts: Utc::now().timestamp_millis(),
+        });
+        Ok(wd_id)
+    }
+
+    /// Pays out a lightning invoice from the funding account, the amount is
+    /// read from the invoice (see `lightning_invoice_amount`)
+    pub fn withdraw_lightning(&mut self, invoice: String) -> Result<String, Rejection> {
+        let amt = lightning_invoice_amount(&invoice).ok_or(INVALID_INVOICE)?;
+        if self.funding_btc < amt {
+            return Err(INSUFFICIENT_BALANCE);
+        }
+        self.funding_btc -= amt;
+        self.touch();
+        let wd_id = self.next_id().to_string();
+        self.withdrawals.push(MockWithdrawal {
+            tx_id: format!("mock-ln-withdrawal-{wd_id}"),
+            wd_id: wd_id.clone(),
+            chain: LIGHTNING,
+            client_id: String::new(),
+            to: invoice,
+            amt,
+            ts: Utc::now().timestamp_millis(),
+        });
+        Ok(wd_id)
+    }
+
+    pub fn withdrawal_by_client_id(&self, client_id: &str) -> Option<&MockWithdrawal> {
+        self.withdrawals
+            .iter()
+            .find(|withdrawal| !client_id.is_empty() && withdrawal.client_id == client_id)
+    }
+
+    pub fn withdrawal_by_id(&self, wd_id: &str) -> Option<&MockWithdrawal> {
+        self.withdrawals
+            .iter()
+            .find(|withdrawal| withdrawal.wd_id == wd_id)
+    }
+
+    /// Issues an invoice that credits the funding account once paid
+    pub fn lightning_invoice(&mut self, amt: Decimal) -> String {
+        let invoice = format!("lnmock:{amt}:{}", self.next_id());
+        self.lightning_invoices.insert(invoice.clone(), amt);
+        invoice
+    }
+
+    /// Settles an invoice issued by `lightning_invoice` and returns the deposit tx id
+    pub fn pay_lightning_invoice(&mut self, invoice: &str) -> Result<String, Rejection> {
+        let amt = self
+            .lightning_invoices
+            .remove(invoice)
+            .ok_or(INVALID_INVOICE)?;
+        self.funding_btc += amt;
+        self.touch();
+        let dep_id = self.next_id().to_string();
+        self.deposits.push(MockDeposit {
+            tx_id: format!("mock-ln-deposit-{dep_id}"),
+            dep_id,
+            chain: LIGHTNING,
+            to: invoice.to_string(),
+            amt,
+            ts: Utc::now().timestamp_millis(),
+        });
+        Ok(self.deposits.last().expect("just pushed").tx_id.clone())
+    }
+
+    /// Credits an onchain deposit to the funding account
+    pub fn credit_deposit(&mut self, amt: Decimal) -> String {
+        self.funding_btc += amt;
+        self.touch();
+        let dep_id = self.next_id().to_string();
+        self.deposits.push(MockDeposit {
+            tx_id: format!("mock-deposit-tx-{dep_id}"),
+            dep_id,
+            chain: ONCHAIN,
+            to: DEPOSIT_ADDRESS.to_string(),
+            amt,
+            ts: Utc::now().timestamp_millis(),
+        });
+        self.deposits.last().expect("just pushed").tx_id.clone()
+    }
+
+    pub fn deposits(&self) -> &[MockDeposit] {
+        &self.deposits
+    }
+
+    /// Charges (or pays out when negative) a funding fee on the open position
+    pub fn charge_funding_fee(&mut self, amt: Decimal) {
+        self.trading_btc -= amt;
+        self.touch();
+        self.push_bill("8", -amt, Decimal::ZERO, Decimal::ZERO, String::new());
+    }
+
+    /// Bills newest first, optionally only those older than `after`
+    pub fn bills(&self, after: Option<u64>, limit: usize) -> Vec<&MockBill> {
+        self.bills
+            .iter()
+            .rev()
+            .filter(|bill| after.is_none_or(|after| bill.bill_id < after))
+            .take(limit)
+            .collect()
+    }
+
+    pub fn unrealized_pnl(&self) -> Decimal {
+        if self.position_contracts == 0 {
+            return Decimal::ZERO;
+        }
+        (Decimal::from(self.position_contracts)
+            * CONTRACT_SIZE_USD
+            * (Decimal::ONE / self.avg_px - Decimal::ONE / self.last_price))
+            .round_dp(BTC_DECIMALS)
+    }
+
+    pub fn notional_usd(&self) -> Decimal {
+        Decimal::from(self.position_contracts.unsigned_abs()) * CONTRACT_SIZE_USD
+    }
+
+    pub fn initial_margin(&self) -> Decimal {
+        (self.notional_usd() / self.last_price / self.leverage).round_dp(BTC_DECIMALS)
+    }
+
+    pub fn trading_equity(&self) -> Decimal {
+        self.trading_btc + self.unrealized_pnl()
+    }
+
+    pub fn maintenance_margin(&self) -> Decimal {
+        (self.notional_usd() / self.last_price * MAINTENANCE_MARGIN_RATE).round_dp(BTC_DECIMALS)
+    }
+
+    pub fn margin_ratio(&self) -> Option<Decimal> {
+        let maintenance_margin = self.maintenance_margin();
+        (!maintenance_margin.is_zero())
+            .then(|| (self.trading_equity() / maintenance_margin).round_dp(4))
+    }
+
+    /// The price at which equity drops to the maintenance margin.
+    /// None when the collateral covers any move against the position.
+    pub fn liquidation_price(&self) -> Option<Decimal> {
+        if self.position_contracts == 0 {
+            return None;
+        }
+        let side = Decimal::from(self.position_contracts.signum());
+        let notional = self.notional_usd();
+        let denominator = self.trading_btc + side * notional / self.avg_px;
+        if denominator.is_zero() {
+            return None;
+        }
+        let price = notional * (side + MAINTENANCE_MARGIN_RATE) / denominator;
+        (price > Decimal::ZERO).then(|| price.round_dp(1))
+    }
+
+    fn crosses(&self, side: Side, px: Decimal) -> bool {
+        match side {
+            Side::Buy => px >= self.last_price,
+            Side::Sell => px <= self.last_price,
+        }
+    }
+
+    fn fill(&mut self, cl_ord_id: &str, side: Side, sz: u32, px: Decimal, fee_rate: Decimal) {
+        let fee = (Decimal::from(sz) * CONTRACT_SIZE_USD / px * fee_rate).round_dp(BTC_DECIMALS);
+        let pnl = self.apply_fill(side.sign() * i64::from(sz), px);
+        self.trading_btc += pnl - fee;
+        let revision = self.touch();
+        let ord_id = {
+            let order = self.orders.get_mut(cl_ord_id).expect("order exists");
+            order.state = OrderState::Filled;
+            order.revision = revision;
+            order.avg_px = px;
+            order.fee = -fee;
+            order.ord_id.clone()
+        };
+        self.push_bill("2", pnl - fee, -fee, pnl, ord_id);
+    }
+
+    /// Updates the net position and returns the realized pnl in btc
+    fn apply_fill(&mut self, signed_sz: i64, px: Decimal) -> Decimal {
+        let current = self.position_contracts;
+        let mut realized = Decimal::ZERO;
+        if current == 0 || current.signum() == signed_sz.signum() {
+            let total = Decimal::from(current.abs() + signed_sz.abs());
+            let held_btc = if current == 0 {
+                Decimal::ZERO
+            } else {
+                Decimal::from(current.abs()) / self.avg_px
+            };
+            self.avg_px = total / (held_btc + Decimal::from(signed_sz.abs()) / px);
+        } else {
+            let closed = current.abs().min(signed_sz.abs());
+            realized = (Decimal::from(current.signum() * closed)
+                * CONTRACT_SIZE_USD
+                * (Decimal::ONE / self.avg_px - Decimal::ONE / px))
+                .round_dp(BTC_DECIMALS);
+            if signed_sz.abs() > current.abs() {
+                self.avg_px = px;
+            }
+        }
+        self.position_contracts = current + signed_sz;
+        if self.position_contracts == 0 {
+            self.avg_px = Decimal::ZERO;
+        }
+        realized
+    }
+
+    fn set_state(&mut self, cl_ord_id: &str, state: OrderState) {
+        let revision = self.touch();
+        if let Some(order) = self.orders.get_mut(cl_ord_id) {
+            order.state = state;
+            order.revision = revision;
+        }
+    }
+
+    fn touch(&mut self) -> u64 {
+        self.revision += 1;
+        self.revision
+    }
+
+    fn push_bill(
+        &mut self,
+        bill_type: &'static str,
+        bal_chg: Decimal,
+        fee: Decimal,
+        pnl: Decimal,
+        ord_id: String,
+    ) {
+        let bill_id = self.next_id();
+        self.bills.push(MockBill {
+            bill_id,
+            bill_type,
+            bal_chg,
+            fee,
+            pnl,
+            ord_id,
+            ts: Utc::now().timestamp_millis(),
+        });
+    }
+
+    fn next_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+}
+
+/// Stands in for bolt11 decoding: mock invoices are written as
+/// `ln<tag>:<amount in btc>:<nonce>`
+pub fn lightning_invoice_amount(invoice: &str) -> Option<Decimal> {
+    let mut parts = invoice.split(':');
+    if !parts.next()?.starts_with("ln") {
+        return None;
+    }
+    parts
+        .next()?
+        .parse()
+        .ok()
+        .filter(|amt: &Decimal| amt > &Decimal::ZERO)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn market(account: &mut MockAccount, id: &str, side: Side, sz: u32) {
+        account
+            .place_order(id.to_string(), side, "market".to_string(), sz, None)
+            .unwrap();
+    }
+
+    #[test]
+    fn market_orders_fill_and_realize_pnl() {
+        let mut account = MockAccount {
+            trading_btc: dec!(1),
+            ..Default::default()
+        };
+        market(&mut account, "open", Side::Sell, 40);
+        assert_eq!(account.position_contracts, -40);
+        assert_eq!(account.notional_usd(), dec!(4000));
+        assert_eq!(account.trading_btc, dec!(0.99995));
+
+        account.set_last_price(dec!(32_000));
+        assert_eq!(account.unrealized_pnl(), dec!(0.025));
+
+        account.close_position(Some("close".to_string())).unwrap();
+        assert_eq!(account.position_contracts, 0);
+        assert_eq!(account.unrealized_pnl(), Decimal::ZERO);
+        assert_eq!(account.order("close").unwrap().state, OrderState::Filled);
+        let bills = account.bills(None, 100);
+        assert_eq!(bills.len(), 2);
+        assert_eq!(bills[0].pnl, dec!(0.025));
+        assert_eq!(
+            account.trading_btc,
+            dec!(0.99995) + dec!(0.025) - dec!(0.0000625)
+        );
+    }
+
+    #[test]
+    fn resting_orders_fill_when_price_crosses() {
+        let mut account = MockAccount::default();
+        account
+            .place_order(
+                "passive".to_string(),
+                Side::Sell,
+                "post_only".to_string(),
+                10,
+                Some(dec!(40_100)),
+            )
+            .unwrap();
+        assert_eq!(account.order("passive").unwrap().state, OrderState::Live);
+        account.amend_order("passive", dec!(40_050)).unwrap();
+        account.set_last_price(dec!(40_060));
+        let order = account.order("passive").unwrap();
+        assert_eq!(order.state, OrderState::Filled);
+        assert_eq!(order.avg_px, dec!(40_050));
+        assert_eq!(account.position_contracts, -10);
+        assert_eq!(
+            account.cancel_order("passive"),
+            Err(ORDER_ALREADY_COMPLETED)
+        );
+    }
+
+    #[test]
+    fn crossing_post_only_orders_are_canceled() {
+        let mut account = MockAccount::default();
+        account
+            .place_order(
+                "taker".to_string(),
+                Side::Buy,
+                "post_only".to_string(),
+                10,
+                Some(dec!(40_100)),
+            )
+            .unwrap();
+        assert_eq!(account.order("taker").unwrap().state, OrderState::Canceled);
+        assert_eq!(account.position_contracts, 0);
+    }
+
+    #[test]
+    fn funds_move_between_accounts() {
+        let mut account = MockAccount::default();
+        account.credit_deposit(dec!(1));
+        account
+            .transfer(
+                "t1".to_string(),
+                "6".to_string(),
+                "18".to_string(),
+                dec!(0.4),
+            )
+            .unwrap();
+        assert_eq!(account.funding_btc, dec!(0.6));
+        assert_eq!(account.trading_btc, dec!(0.4));
+        assert_eq!(
+            account.transfer("t2".to_string(), "18".to_string(), "6".to_string(), dec!(1)),
+            Err(INSUFFICIENT_BALANCE)
+        );
+        account
+            .withdraw(
+                "w1".to_string(),
+                "addr".to_string(),
+                dec!(0.5),
+                dec!(0.0002),
+            )
+            .unwrap();
+        assert_eq!(account.funding_btc, dec!(0.0998));
+        assert!(account.withdrawal_by_client_id("w1").is_some());
+        assert!(account.transfer_by(None, Some("t1")).is_some());
+    }
+
+    #[test]
+    fn lightning_invoices_move_funds() {
+        let mut account = MockAccount::default();
+        let invoice = account.lightning_invoice(dec!(0.01));
+        account.pay_lightning_invoice(&invoice).unwrap();
+        assert_eq!(account.funding_btc, dec!(0.01));
+        assert_eq!(
+            account.pay_lightning_invoice(&invoice),
+            Err(INVALID_INVOICE)
+        );
+
+        let wd_id = account
+            .withdraw_lightning("lnlocal:0.004:1".to_string())
+            .unwrap();
+        assert_eq!(account.funding_btc, dec!(0.006));
+        assert_eq!(account.withdrawal_by_id(&wd_id).unwrap().chain, LIGHTNING);
+        assert_eq!(
+            account.withdraw_lightning("bc1q".to_string()),
+            Err(INVALID_INVOICE)
+        );
+    }
+
+    #[test]
+    fn settings_only_change_while_flat() {
+        let mut account = MockAccount {
+            trading_btc: dec!(1),
+            ..Default::default()
+        };
+        market(&mut account, "open", Side::Sell, 40);
+        assert_eq!(
+            account.set_position_mode("long_short_mode"),
+            Err(POSITIONS_OPEN)
+        );
+        account.close_position(None).unwrap();
+        account.set_position_mode("long_short_mode").unwrap();
+        account.set_account_level("3").unwrap();
+        assert_eq!(account.pos_mode, "long_short_mode");
+        assert_eq!(account.acct_lv, "3");
+    }
+}
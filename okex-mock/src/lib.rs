@@ -0,0 +1,69 @@
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![cfg_attr(feature = "fail-on-warnings", deny(clippy::all))]
+
+//! An in memory stand-in for the okex REST and private websocket api used by `okex-client`,
+//! so that hedging and funding cycles can be exercised without credentials.
+
+mod account;
+mod routes;
+mod ws;
+
+use rust_decimal::Decimal;
+
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+pub use account::*;
+
+type SharedAccount = Arc<Mutex<MockAccount>>;
+
+#[derive(Clone)]
+pub struct OkexMock {
+    url: String,
+    account: SharedAccount,
+}
+
+impl OkexMock {
+    /// Serves a fresh account on an ephemeral local port
+    pub async fn start() -> Self {
+        let account = SharedAccount::default();
+        let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+            .serve(routes::router(Arc::clone(&account)).into_make_service());
+        let url = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+        Self { url, account }
+    }
+
+    /// Base url to configure the okex client with
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    pub fn private_ws_url(&self) -> String {
+        format!("{}/ws/v5/private", self.url.replacen("http", "ws", 1))
+    }
+
+    pub fn account(&self) -> MutexGuard<'_, MockAccount> {
+        self.account.lock().expect("mock account lock poisoned")
+    }
+
+    pub fn set_last_price(&self, usd_per_btc: Decimal) {
+        self.account().set_last_price(usd_per_btc);
+    }
+
+    /// Credits an onchain deposit to the funding account and returns its tx id
+    pub fn credit_deposit(&self, btc: Decimal) -> String {
+        self.account().credit_deposit(btc)
+    }
+
+    /// Pays an invoice issued through the deposit-lightning endpoint and returns its tx id
+    pub fn pay_lightning_invoice(&self, invoice: &str) -> Option<String> {
+        self.account().pay_lightning_invoice(invoice).ok()
+    }
+
+    pub fn position_contracts(&self) -> i64 {
+        self.account().position_contracts
+    }
+}
@@ -0,0 +1,603 @@
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::Utc;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+
+use std::collections::HashMap;
+
+use crate::{account::*, ws, SharedAccount};
+
+type Params = HashMap<String, String>;
+
+pub(crate) const INSTRUMENT_ID: &str = "BTC-USD-SWAP";
+
+pub(crate) fn router(account: SharedAccount) -> Router {
+    Router::new()
+        .route("/api/v5/account/config", get(account_config))
+        .route("/api/v5/account/leverage-info", get(leverage_info))
+        .route("/api/v5/account/set-account-level", post(set_account_level))
+        .route("/api/v5/account/set-position-mode", post(set_position_mode))
+        .route("/api/v5/account/set-leverage", post(set_leverage))
+        .route("/api/v5/account/balance", get(trading_balance))
+        .route("/api/v5/account/positions", get(positions))
+        .route("/api/v5/account/bills", get(bills))
+        .route("/api/v5/asset/balances", get(funding_balance))
+        .route("/api/v5/asset/deposit-address", get(deposit_address))
+        .route("/api/v5/asset/currencies", get(currencies))
+        .route("/api/v5/asset/transfer", post(transfer))
+        .route("/api/v5/asset/transfer-state", get(transfer_state))
+        .route("/api/v5/asset/withdrawal", post(withdrawal))
+        .route(
+            "/api/v5/asset/withdrawal-lightning",
+            post(withdrawal_lightning),
+        )
+        .route("/api/v5/asset/withdrawal-history", get(withdrawal_history))
+        .route("/api/v5/asset/deposit-lightning", get(deposit_lightning))
+        .route("/api/v5/asset/deposit-history", get(deposit_history))
+        .route("/api/v5/market/ticker", get(ticker))
+        .route("/api/v5/public/funding-rate", get(funding_rate))
+        .route("/api/v5/trade/order", get(order_details).post(place_order))
+        .route("/api/v5/trade/amend-order", post(amend_order))
+        .route("/api/v5/trade/cancel-order", post(cancel_order))
+        .route("/api/v5/trade/close-position", post(close_position))
+        .route("/ws/v5/private", get(ws::private_channels))
+        .with_state(account)
+}
+
+fn ok(data: Vec<Value>) -> Json<Value> {
+    Json(json!({ "code": "0", "msg": "", "data": data }))
+}
+
+fn rejected(Rejection { code, msg }: Rejection) -> Json<Value> {
+    Json(json!({ "code": code, "msg": msg, "data": [] }))
+}
+
+fn order_result(cl_ord_id: &str, result: Result<String, Rejection>) -> Json<Value> {
+    let (code, ord_id, s_code, s_msg) = match result {
+        Ok(ord_id) => ("0", ord_id, "0", ""),
+        Err(Rejection { code, msg }) => ("1", String::new(), code, msg),
+    };
+    Json(json!({
+        "code": code,
+        "msg": "",
+        "data": [{ "clOrdId": cl_ord_id, "ordId": ord_id, "tag": "", "sCode": s_code, "sMsg": s_msg }]
+    }))
+}
+
+/// Okex answers with `50103` before anything else when the api key header is missing
+fn authenticated(headers: &HeaderMap) -> Result<(), Json<Value>> {
+    match headers.get("OK-ACCESS-KEY") {
+        Some(key) if !key.is_empty() => Ok(()),
+        _ => Err(Json(json!({
+            "code": "50103",
+            "msg": "Request header \"OK-ACCESS-KEY\" can not be empty.",
+            "data": []
+        }))),
+    }
+}
+
+fn param<'a>(body: &'a Params, key: &str) -> &'a str {
+    body.get(key).map(String::as_str).unwrap_or_default()
+}
+
+fn decimal_param(body: &Params, key: &str) -> Decimal {
+    param(body, key).parse().unwrap_or_default()
+}
+
+async fn account_config(State(account): State<SharedAccount>, headers: HeaderMap) -> Json<Value> {
+    if let Err(e) = authenticated(&headers) {
+        return e;
+    }
+    let account = account.lock().expect("mock account lock poisoned");
+    ok(vec![json!({
+        "acctLv": account.acct_lv,
+        "autoLoan": false,
+        "ctIsoMode": "automatic",
+        "greeksType": "PA",
+        "level": "Lv1",
+        "levelTmp": "",
+        "mgnIsoMode": "automatic",
+        "posMode": account.pos_mode,
+        "uid": "mock"
+    })])
+}
+
+async fn set_account_level(
+    State(account): State<SharedAccount>,
+    Json(body): Json<Params>,
+) -> Json<Value> {
+    let mut account = account.lock().expect("mock account lock poisoned");
+    let acct_lv = param(&body, "acctLv");
+    match account.set_account_level(acct_lv) {
+        Ok(()) => ok(vec![json!({ "acctLv": acct_lv })]),
+        Err(rejection) => rejected(rejection),
+    }
+}
+
+async fn set_position_mode(
+    State(account): State<SharedAccount>,
+    Json(body): Json<Params>,
+) -> Json<Value> {
+    let mut account = account.lock().expect("mock account lock poisoned");
+    let pos_mode = param(&body, "posMode");
+    match account.set_position_mode(pos_mode) {
+        Ok(()) => ok(vec![json!({ "posMode": pos_mode })]),
+        Err(rejection) => rejected(rejection),
+    }
+}
+
+async fn set_leverage(
+    State(account): State<SharedAccount>,
+    Json(body): Json<Params>,
+) -> Json<Value> {
+    let mut account = account.lock().expect("mock account lock poisoned");
+    account.leverage = decimal_param(&body, "lever");
+    ok(vec![json!({
+        "instId": param(&body, "instId"),
+        "lever": account.leverage.to_string(),
+        "mgnMode": param(&body, "mgnMode"),
+        "posSide": ""
+    })])
+}
+
+async fn leverage_info(State(account): State<SharedAccount>) -> Json<Value> {
+    let account = account.lock().expect("mock account lock poisoned");
+    ok(vec![json!({
+        "instId": INSTRUMENT_ID,
+        "mgnMode": "cross",
+        "posSide": "net",
+        "lever": account.leverage.to_string()
+    })])
+}
+
+async fn trading_balance(
+    State(account): State<SharedAccount>,
+    Query(params): Query<Params>,
+) -> Json<Value> {
+    let account = account.lock().expect("mock account lock poisoned");
+    let mut balance = trading_balance_json(&account);
+    if params.get("ccy").map(String::as_str) == Some("USDT") {
+        let usdt = account.trading_usdt.to_string();
+        let details = &mut balance["details"][0];
+        for field in ["availBal", "availEq", "cashBal", "eq", "eqUsd"] {
+            details[field] = json!(usdt);
+        }
+        details["ccy"] = json!("USDT");
+        details["frozenBal"] = json!("0");
+        details["upl"] = json!("0");
+    }
+    ok(vec![balance])
+}
+
+pub(crate) fn trading_balance_json(account: &MockAccount) -> Value {
+    let eq = account.trading_equity();
+    let imr = account.initial_margin();
+    json!({
+        "adjEq": "",
+        "details": [{
+            "availBal": account.trading_btc.to_string(),
+            "availEq": (eq - imr).to_string(),
+            "cashBal": account.trading_btc.to_string(),
+            "ccy": "BTC",
+            "crossLiab": "",
+            "disEq": "",
+            "eq": eq.to_string(),
+            "eqUsd": (eq * account.last_price).to_string(),
+            "frozenBal": imr.to_string(),
+            "interest": "",
+            "isoEq": "0",
+            "isoLiab": "",
+            "isoUpl": "0",
+            "liab": "",
+            "maxLoan": "",
+            "mgnRatio": "",
+            "notionalLever": "",
+            "ordFrozen": "0",
+            "twap": "0",
+            "uTime": "",
+            "upl": account.unrealized_pnl().to_string(),
+            "uplLiab": "",
+            "stgyEq": "0",
+            "spotInUseAmt": ""
+        }],
+        "imr": "",
+        "isoEq": "0",
+        "mgnRatio": account
+            .margin_ratio()
+            .map(|ratio| ratio.to_string())
+            .unwrap_or_default(),
+        "mmr": account.maintenance_margin().to_string(),
+        "notionalUsd": account.notional_usd().to_string(),
+        "ordFroz": "",
+        "totalEq": (eq * account.last_price).to_string(),
+        "uTime": ""
+    })
+}
+
+async fn positions(State(account): State<SharedAccount>) -> Json<Value> {
+    let account = account.lock().expect("mock account lock poisoned");
+    ok(position_json(&account).into_iter().collect())
+}
+
+/// Okex omits the position entirely when there is none
+pub(crate) fn position_json(account: &MockAccount) -> Option<Value> {
+    if account.position_contracts == 0 {
+        return None;
+    }
+    Some(json!({
+        "adl": "1",
+        "availPos": "",
+        "avgPx": account.avg_px.to_string(),
+        "cTime": "",
+        "ccy": "BTC",
+        "deltaBS": "",
+        "deltaPA": "",
+        "gammaBS": "",
+        "gammaPA": "",
+        "imr": account.initial_margin().to_string(),
+        "instId": INSTRUMENT_ID,
+        "instType": "SWAP",
+        "interest": "",
+        "usdPx": "",
+        "last": account.last_price.to_string(),
+        "lever": account.leverage.to_string(),
+        "liab": "",
+        "liabCcy": "",
+        "liqPx": account
+            .liquidation_price()
+            .map(|price| price.to_string())
+            .unwrap_or_default(),
+        "markPx": account.last_price.to_string(),
+        "margin": "",
+        "mgnMode": "cross",
+        "mgnRatio": account
+            .margin_ratio()
+            .map(|ratio| ratio.to_string())
+            .unwrap_or_default(),
+        "mmr": account.maintenance_margin().to_string(),
+        "notionalUsd": account.notional_usd().to_string(),
+        "optVal": "",
+        "pos": account.position_contracts.to_string(),
+        "posCcy": "",
+        "posId": "1",
+        "posSide": "net",
+        "thetaBS": "",
+        "thetaPA": "",
+        "tradeId": "",
+        "uTime": "",
+        "upl": account.unrealized_pnl().to_string(),
+        "uplRatio": "",
+        "vegaBS": "",
+        "vegaPA": ""
+    }))
+}
+
+async fn bills(State(account): State<SharedAccount>, Query(params): Query<Params>) -> Json<Value> {
+    let account = account.lock().expect("mock account lock poisoned");
+    let after = params.get("after").and_then(|after| after.parse().ok());
+    let limit = params
+        .get("limit")
+        .and_then(|limit| limit.parse().ok())
+        .unwrap_or(100);
+    ok(account
+        .bills(after, limit)
+        .into_iter()
+        .map(|bill| {
+            json!({
+                "billId": bill.bill_id.to_string(),
+                "type": bill.bill_type,
+                "subType": "",
+                "instId": INSTRUMENT_ID,
+                "balChg": bill.bal_chg.to_string(),
+                "fee": bill.fee.to_string(),
+                "pnl": bill.pnl.to_string(),
+                "ordId": bill.ord_id,
+                "ts": bill.ts.to_string(),
+                "ccy": "BTC"
+            })
+        })
+        .collect())
+}
+
+async fn funding_balance(State(account): State<SharedAccount>) -> Json<Value> {
+    let account = account.lock().expect("mock account lock poisoned");
+    ok(vec![json!({
+        "availBal": account.funding_btc.to_string(),
+        "bal": account.funding_btc.to_string(),
+        "ccy": "BTC",
+        "frozenBal": "0"
+    })])
+}
+
+async fn deposit_address() -> Json<Value> {
+    ok(vec![json!({
+        "chain": "BTC-Bitcoin",
+        "ctAddr": "",
+        "ccy": "BTC",
+        "to": "6",
+        "addr": DEPOSIT_ADDRESS,
+        "selected": true
+    })])
+}
+
+async fn currencies() -> Json<Value> {
+    ok(vec![json!({
+        "ccy": "BTC",
+        "chain": "BTC-Bitcoin",
+        "minFee": "0.0002",
+        "maxFee": "0.0004",
+        "minWd": "0.001",
+        "maxWd": "500"
+    })])
+}
+
+async fn transfer(State(account): State<SharedAccount>, Json(body): Json<Params>) -> Json<Value> {
+    let mut account = account.lock().expect("mock account lock poisoned");
+    let amt = decimal_param(&body, "amt");
+    match account.transfer(
+        param(&body, "clientId").to_string(),
+        param(&body, "from").to_string(),
+        param(&body, "to").to_string(),
+        amt,
+    ) {
+        Ok(trans_id) => ok(vec![json!({
+            "transId": trans_id,
+            "ccy": "BTC",
+            "clientId": param(&body, "clientId"),
+            "from": param(&body, "from"),
+            "amt": amt.to_string(),
+            "to": param(&body, "to")
+        })]),
+        Err(rejection) => rejected(rejection),
+    }
+}
+
+async fn transfer_state(
+    State(account): State<SharedAccount>,
+    Query(params): Query<Params>,
+) -> Json<Value> {
+    let account = account.lock().expect("mock account lock poisoned");
+    let transfer = account.transfer_by(
+        params.get("transId").map(String::as_str),
+        params.get("clientId").map(String::as_str),
+    );
+    match transfer {
+        Some(transfer) => ok(vec![json!({
+            "amt": transfer.amt.to_string(),
+            "ccy": "BTC",
+            "clientId": transfer.client_id,
+            "from": transfer.from,
+            "state": "success",
+            "subAcct": "",
+            "to": transfer.to,
+            "transId": transfer.trans_id
+        })]),
+        None => rejected(Rejection {
+            code: "58129",
+            msg: "Parameter clientId or transId error",
+        }),
+    }
+}
+
+async fn withdrawal(State(account): State<SharedAccount>, Json(body): Json<Params>) -> Json<Value> {
+    let mut account = account.lock().expect("mock account lock poisoned");
+    let amt = decimal_param(&body, "amt");
+    match account.withdraw(
+        param(&body, "clientId").to_string(),
+        param(&body, "toAddr").to_string(),
+        amt,
+        decimal_param(&body, "fee"),
+    ) {
+        Ok(wd_id) => ok(vec![json!({
+            "amt": amt.to_string(),
+            "wdId": wd_id,
+            "ccy": "BTC",
+            "clientId": param(&body, "clientId"),
+            "chain": "BTC-Bitcoin"
+        })]),
+        Err(rejection) => rejected(rejection),
+    }
+}
+
+async fn withdrawal_lightning(
+    State(account): State<SharedAccount>,
+    Json(body): Json<Params>,
+) -> Json<Value> {
+    let mut account = account.lock().expect("mock account lock poisoned");
+    match account.withdraw_lightning(param(&body, "invoice").to_string()) {
+        Ok(wd_id) => ok(vec![json!({
+            "wdId": wd_id,
+            "cTime": Utc::now().timestamp_millis().to_string()
+        })]),
+        Err(rejection) => rejected(rejection),
+    }
+}
+
+async fn withdrawal_history(
+    State(account): State<SharedAccount>,
+    Query(params): Query<Params>,
+) -> Json<Value> {
+    let account = account.lock().expect("mock account lock poisoned");
+    let withdrawal = match params.get("wdId") {
+        Some(wd_id) => account.withdrawal_by_id(wd_id),
+        None => account.withdrawal_by_client_id(param(&params, "clientId")),
+    };
+    ok(withdrawal
+        .map(|withdrawal| {
+            json!({
+                "ccy": "BTC",
+                "chain": withdrawal.chain,
+                "amt": withdrawal.amt.to_string(),
+                "ts": withdrawal.ts.to_string(),
+                "from": "",
+                "to": withdrawal.to,
+                "txId": withdrawal.tx_id,
+                "state": "2",
+                "wdId": withdrawal.wd_id,
+                "clientId": withdrawal.client_id
+            })
+        })
+        .into_iter()
+        .collect())
+}
+
+async fn deposit_lightning(
+    State(account): State<SharedAccount>,
+    Query(params): Query<Params>,
+) -> Json<Value> {
+    let mut account = account.lock().expect("mock account lock poisoned");
+    let invoice = account.lightning_invoice(decimal_param(&params, "amt"));
+    ok(vec![json!({
+        "invoice": invoice,
+        "cTime": Utc::now().timestamp_millis().to_string()
+    })])
+}
+
+async fn deposit_history(
+    State(account): State<SharedAccount>,
+    Query(params): Query<Params>,
+) -> Json<Value> {
+    let account = account.lock().expect("mock account lock poisoned");
+    let tx_id = params.get("txId");
+    ok(account
+        .deposits()
+        .iter()
+        .rev()
+        .filter(|deposit| tx_id.is_none_or(|tx_id| &deposit.tx_id == tx_id))
+        .map(|deposit| {
+            json!({
+                "actualDepBlkConfirm": "6",
+                "amt": deposit.amt.to_string(),
+                "ccy": "BTC",
+                "chain": deposit.chain,
+                "depId": deposit.dep_id,
+                "from": "",
+                "state": "2",
+                "to": deposit.to,
+                "ts": deposit.ts.to_string(),
+                "txId": deposit.tx_id
+            })
+        })
+        .collect())
+}
+
+async fn ticker(State(account): State<SharedAccount>) -> Json<Value> {
+    let account = account.lock().expect("mock account lock poisoned");
+    ok(vec![json!({
+        "instType": "SWAP",
+        "instId": INSTRUMENT_ID,
+        "last": account.last_price.to_string(),
+        "lastSz": "1",
+        "askPx": account.last_price.to_string(),
+        "askSz": "1",
+        "bidPx": account.last_price.to_string(),
+        "bidSz": "1"
+    })])
+}
+
+async fn funding_rate(
+    State(account): State<SharedAccount>,
+    Query(params): Query<Params>,
+) -> Json<Value> {
+    let account = account.lock().expect("mock account lock poisoned");
+    let instrument_id = param(&params, "instId");
+    ok(vec![json!({
+        "instType": "SWAP",
+        "instId": instrument_id,
+        "fundingRate": account.funding_rate(instrument_id).to_string(),
+        "nextFundingRate": "",
+        "fundingTime": "1700000000000"
+    })])
+}
+
+async fn order_details(
+    State(account): State<SharedAccount>,
+    Query(params): Query<Params>,
+) -> Json<Value> {
+    let account = account.lock().expect("mock account lock poisoned");
+    match account.order(param(&params, "clOrdId")) {
+        Ok(order) => ok(vec![order_json(order)]),
+        Err(rejection) => rejected(rejection),
+    }
+}
+
+pub(crate) fn order_json(order: &MockOrder) -> Value {
+    let filled = if order.state == OrderState::Filled {
+        order.sz
+    } else {
+        0
+    };
+    json!({
+        "instId": INSTRUMENT_ID,
+        "clOrdId": order.cl_ord_id,
+        "ordId": order.ord_id,
+        "avgPx": if filled > 0 { order.avg_px.to_string() } else { String::new() },
+        "fee": order.fee.to_string(),
+        "sz": order.sz.to_string(),
+        "accFillSz": filled.to_string(),
+        "px": if order.px.is_zero() { String::new() } else { order.px.to_string() },
+        "ordType": order.ord_type,
+        "state": order.state.to_string()
+    })
+}
+
+async fn place_order(
+    State(account): State<SharedAccount>,
+    Json(body): Json<Params>,
+) -> Json<Value> {
+    let mut account = account.lock().expect("mock account lock poisoned");
+    let side = if param(&body, "side") == "buy" {
+        Side::Buy
+    } else {
+        Side::Sell
+    };
+    let px = body.get("px").and_then(|px| px.parse().ok());
+    let result = account.place_order(
+        param(&body, "clOrdId").to_string(),
+        side,
+        param(&body, "ordType").to_string(),
+        param(&body, "sz").parse().unwrap_or_default(),
+        px,
+    );
+    order_result(param(&body, "clOrdId"), result)
+}
+
+async fn amend_order(
+    State(account): State<SharedAccount>,
+    Json(body): Json<Params>,
+) -> Json<Value> {
+    let mut account = account.lock().expect("mock account lock poisoned");
+    let cl_ord_id = param(&body, "clOrdId");
+    let result = account
+        .amend_order(cl_ord_id, decimal_param(&body, "newPx"))
+        .and_then(|_| account.order(cl_ord_id).map(|order| order.ord_id.clone()));
+    order_result(cl_ord_id, result)
+}
+
+async fn cancel_order(
+    State(account): State<SharedAccount>,
+    Json(body): Json<Params>,
+) -> Json<Value> {
+    let mut account = account.lock().expect("mock account lock poisoned");
+    let cl_ord_id = param(&body, "clOrdId");
+    let result = account
+        .cancel_order(cl_ord_id)
+        .and_then(|_| account.order(cl_ord_id).map(|order| order.ord_id.clone()));
+    order_result(cl_ord_id, result)
+}
+
+async fn close_position(
+    State(account): State<SharedAccount>,
+    Json(body): Json<Params>,
+) -> Json<Value> {
+    let mut account = account.lock().expect("mock account lock poisoned");
+    match account.close_position(body.get("clOrdId").cloned()) {
+        Ok(()) => ok(vec![json!({ "instId": INSTRUMENT_ID, "posSide": "net" })]),
+        Err(rejection) => rejected(rejection),
+    }
+}
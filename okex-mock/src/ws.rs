@@ -0,0 +1,112 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::Response,
+};
+use serde_json::{json, Value};
+
+use std::time::Duration;
+
+use crate::{routes::*, SharedAccount};
+
+const PUSH_INTERVAL: Duration = Duration::from_millis(100);
+
+pub(crate) async fn private_channels(
+    ws: WebSocketUpgrade,
+    State(account): State<SharedAccount>,
+) -> Response {
+    ws.on_upgrade(move |socket| serve(socket, account))
+}
+
+/// Answers login and subscribe requests, then pushes the position, the
+/// trading balance and any changed orders whenever the account changes.
+async fn serve(mut socket: WebSocket, account: SharedAccount) {
+    let mut logged_in = false;
+    let mut subscribed = false;
+    let mut pushed_revision = 0;
+    let mut ticker = tokio::time::interval(PUSH_INTERVAL);
+    loop {
+        let outgoing = tokio::select! {
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Text(text))) => {
+                    if text == "ping" {
+                        vec!["pong".to_string()]
+                    } else {
+                        let request: Value = serde_json::from_str(&text).unwrap_or_default();
+                        match request["op"].as_str() {
+                            Some("login") => {
+                                logged_in = request["args"][0]["apiKey"]
+                                    .as_str()
+                                    .is_some_and(|key| !key.is_empty());
+                                let code = if logged_in { "0" } else { "60005" };
+                                vec![json!({ "event": "login", "code": code, "msg": "" }).to_string()]
+                            }
+                            Some("subscribe") if logged_in => {
+                                subscribed = true;
+                                let mut acks: Vec<String> = request["args"]
+                                    .as_array()
+                                    .into_iter()
+                                    .flatten()
+                                    .map(|arg| json!({ "event": "subscribe", "arg": arg }).to_string())
+                                    .collect();
+                                let account = account.lock().expect("mock account lock poisoned");
+                                acks.extend(snapshot(&account, 0));
+                                pushed_revision = account.revision();
+                                acks
+                            }
+                            _ => vec![json!({ "event": "error", "code": "60011", "msg": "Please log in" }).to_string()],
+                        }
+                    }
+                }
+                Some(Ok(_)) => Vec::new(),
+                _ => break,
+            },
+            _ = ticker.tick(), if subscribed => {
+                let account = account.lock().expect("mock account lock poisoned");
+                if account.revision() == pushed_revision {
+                    continue;
+                }
+                let pushes = snapshot(&account, pushed_revision);
+                pushed_revision = account.revision();
+                pushes
+            }
+        };
+        for msg in outgoing {
+            if socket.send(Message::Text(msg)).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+fn snapshot(account: &crate::MockAccount, since_revision: u64) -> Vec<String> {
+    let mut pushes = vec![
+        json!({
+            "arg": { "channel": "positions", "instType": "SWAP", "instId": INSTRUMENT_ID },
+            "data": position_json(account).into_iter().collect::<Vec<_>>()
+        })
+        .to_string(),
+        json!({
+            "arg": { "channel": "account", "ccy": "BTC" },
+            "data": [trading_balance_json(account)]
+        })
+        .to_string(),
+    ];
+    let orders: Vec<_> = account
+        .orders_since(since_revision)
+        .into_iter()
+        .map(order_json)
+        .collect();
+    if !orders.is_empty() {
+        pushes.push(
+            json!({
+                "arg": { "channel": "orders", "instType": "SWAP", "instId": INSTRUMENT_ID },
+                "data": orders
+            })
+            .to_string(),
+        );
+    }
+    pushes
+}
@@ -1 +1,2 @@
 pub const OKEX_EXCHANGE_ID: &str = "okex";
+pub const BITFINEX_EXCHANGE_ID: &str = "bitfinex";
@@ -9,7 +9,8 @@ use shared::sqlxmq::JobExecutor;
 use std::time::Duration;
 
 use crate::{
-    error::UserTradesError, galoy_transactions::GaloyTransactions, user_trades::UserTrades,
+    error::UserTradesError, galoy_transactions::GaloyTransactions,
+    liability_watermark::LiabilityWatermarks, user_trades::UserTrades,
 };
 
 // retired: uuid!("10000000-0000-0000-0000-000000000001");
@@ -75,12 +76,14 @@ async fn poll_galoy_transactions(
         .expect("couldn't build JobExecutor")
         .execute(|_| async move {
             let galoy_transactions = GaloyTransactions::new(pool.clone());
+            let liability_watermarks = LiabilityWatermarks::new(pool.clone());
             poll_galoy_transactions::execute(
                 &pool,
                 &user_trades,
                 &galoy_transactions,
                 &galoy,
                 &ledger,
+                &liability_watermarks,
             )
             .await
         })
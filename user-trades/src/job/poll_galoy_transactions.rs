@@ -5,7 +5,10 @@ use std::collections::BTreeMap;
 
 use galoy_client::{GaloyClient, SettlementCurrency, TxCursor};
 
-use crate::{error::UserTradesError, galoy_transactions::*, user_trades::*};
+use crate::{
+    error::UserTradesError, galoy_transactions::*, liability_watermark::LiabilityWatermarks,
+    user_trades::*,
+};
 
 #[instrument(
     name = "user_trades.job.poll_galoy_transactions",
@@ -18,7 +21,8 @@ use crate::{error::UserTradesError, galoy_transactions::*, user_trades::*};
         has_more,
         n_bad_trades,
         unpaired_reimport_n_txs,
-        unpaired_reimport_cursor
+        unpaired_reimport_cursor,
+        n_unaccounted_trades
     )
 )]
 pub(super) async fn execute(
@@ -27,15 +31,41 @@ pub(super) async fn execute(
     galoy_transactions: &GaloyTransactions,
     galoy: &GaloyClient,
     ledger: &ledger::Ledger,
+    liability_watermarks: &LiabilityWatermarks,
 ) -> Result<bool, UserTradesError> {
     let has_more = import_galoy_transactions(galoy_transactions, galoy.clone()).await?;
     reimport_unpaired_galoy_transactions(galoy_transactions, galoy.clone()).await?;
     update_user_trades(galoy_transactions, user_trades).await?;
     update_ledger(pool, user_trades, ledger).await?;
+    update_liability_watermark(
+        galoy_transactions,
+        user_trades,
+        liability_watermarks,
+        has_more,
+    )
+    .await?;
 
     Ok(has_more)
 }
 
+async fn update_liability_watermark(
+    galoy_transactions: &GaloyTransactions,
+    user_trades: &UserTrades,
+    liability_watermarks: &LiabilityWatermarks,
+    has_more: bool,
+) -> Result<(), UserTradesError> {
+    let n_unaccounted_trades = user_trades.n_unaccounted_trades().await?;
+    tracing::Span::current().record(
+        "n_unaccounted_trades",
+        &tracing::field::display(n_unaccounted_trades),
+    );
+    if has_more || n_unaccounted_trades > 0 {
+        return Ok(());
+    }
+    let cursor = galoy_transactions.get_latest_cursor().await?;
+    liability_watermarks.record(cursor.map(|c| c.0)).await
+}
+
 async fn import_galoy_transactions(
     galoy_transactions: &GaloyTransactions,
     galoy: GaloyClient,
@@ -0,0 +1,73 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use tracing::instrument;
+
+use crate::error::UserTradesError;
+
+/// The ledger has accounted for every user trade derived from galoy
+/// transactions up to `galoy_cursor` as of `caught_up_at`.
+#[derive(Debug, Clone)]
+pub struct LiabilityWatermark {
+    pub galoy_cursor: Option<String>,
+    pub caught_up_at: DateTime<Utc>,
+}
+
+impl LiabilityWatermark {
+    pub fn age(&self) -> chrono::Duration {
+        Utc::now() - self.caught_up_at
+    }
+
+    pub fn is_within(&self, tolerance: chrono::Duration) -> bool {
+        self.age() <= tolerance
+    }
+}
+
+#[derive(Clone)]
+pub struct LiabilityWatermarks {
+    pool: PgPool,
+}
+
+impl LiabilityWatermarks {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    #[instrument(name = "user_trades.liability_watermark.record", skip(self))]
+    pub async fn record(&self, galoy_cursor: Option<String>) -> Result<(), UserTradesError> {
+        sqlx::query!(
+            r#"INSERT INTO liability_watermark (galoy_cursor, caught_up_at)
+               VALUES ($1, NOW())
+               ON CONFLICT (id) DO UPDATE
+               SET galoy_cursor = EXCLUDED.galoy_cursor, caught_up_at = EXCLUDED.caught_up_at, updated_at = NOW()"#,
+            galoy_cursor
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn current(&self) -> Result<Option<LiabilityWatermark>, UserTradesError> {
+        let row = sqlx::query!(r#"SELECT galoy_cursor, caught_up_at FROM liability_watermark"#)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.map(|row| LiabilityWatermark {
+            galoy_cursor: row.galoy_cursor,
+            caught_up_at: row.caught_up_at,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn watermark_age_against_tolerance() {
+        let watermark = LiabilityWatermark {
+            galoy_cursor: None,
+            caught_up_at: Utc::now() - chrono::Duration::seconds(30),
+        };
+        assert!(watermark.is_within(chrono::Duration::seconds(60)));
+        assert!(!watermark.is_within(chrono::Duration::seconds(10)));
+    }
+}
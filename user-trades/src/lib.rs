@@ -5,12 +5,14 @@ mod app;
 mod error;
 mod galoy_transactions;
 pub mod job;
+mod liability_watermark;
 pub mod user_trades;
 
 use galoy_client::GaloyClientConfig;
 
 pub use app::*;
 pub use error::*;
+pub use liability_watermark::*;
 
 pub async fn run(
     pool: sqlx::PgPool,
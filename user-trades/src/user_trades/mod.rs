@@ -57,11 +57,13 @@ pub struct PairedTradesLookup {
 }
 
 #[derive(Clone)]
-pub struct UserTrades {}
+pub struct UserTrades {
+    pool: PgPool,
+}
 
 impl UserTrades {
-    pub fn new(_pool: PgPool) -> Self {
-        Self {}
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
     }
 
     pub async fn persist_all<'a>(
@@ -152,6 +154,15 @@ impl UserTrades {
         })
     }
 
+    pub async fn n_unaccounted_trades(&self) -> Result<i64, UserTradesError> {
+        let res = sqlx::query!(
+            r#"SELECT COUNT(*) as "count!" FROM user_trades WHERE ledger_tx_id IS NULL"#
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(res.count)
+    }
+
     #[instrument(name = "user_trades.find_unaccounted_trade", skip_all)]
     pub async fn find_unaccounted_trade(
         &self,
@@ -16,6 +16,7 @@ fn galoy_client_configuration() -> GaloyClientConfig {
         api,
         phone_number,
         auth_code: code,
+        ..Default::default()
     };
 
     config
@@ -47,3 +48,24 @@ async fn publishes_liability() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+#[serial]
+async fn records_liability_watermark() -> anyhow::Result<()> {
+    let pg_host = std::env::var("PG_HOST").unwrap_or("localhost".to_string());
+    let pg_con = format!("postgres://user:password@{pg_host}:5432/pg",);
+    let pool = sqlx::PgPool::connect(&pg_con).await?;
+    let watermarks = LiabilityWatermarks::new(pool);
+
+    watermarks.record(Some("cursor-1".to_string())).await?;
+    let first = watermarks.current().await?.expect("watermark recorded");
+    assert_eq!(first.galoy_cursor, Some("cursor-1".to_string()));
+    assert!(first.is_within(chrono::Duration::seconds(60)));
+
+    watermarks.record(Some("cursor-2".to_string())).await?;
+    let second = watermarks.current().await?.expect("watermark recorded");
+    assert_eq!(second.galoy_cursor, Some("cursor-2".to_string()));
+    assert!(second.caught_up_at >= first.caught_up_at);
+
+    Ok(())
+}